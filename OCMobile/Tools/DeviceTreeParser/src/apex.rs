@@ -0,0 +1,168 @@
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::makefile::{split_assignment, statements};
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Soong module types that define an APEX, and whether they wrap a prebuilt
+const APEX_MODULE_TYPES: [(&str, bool); 4] =
+    [("apex", false), ("override_apex", false), ("prebuilt_apex", true), ("apex_set", true)];
+
+// APEX modules of the tree: .apex/.capex prebuilts, apex modules in
+// Android.bp files and the APEX entries of device.mk's PRODUCT_PACKAGES,
+// merged by name
+#[derive(Debug, Default)]
+pub struct ApexInfo {
+    pub modules: Vec<ApexModule>,
+}
+
+#[derive(Debug)]
+pub struct ApexModule {
+    pub name: String,
+    pub prebuilt: bool,
+    // .apex/.capex files and Android.bp files defining it
+    pub files: Vec<String>,
+    // Shipped compressed (.capex)
+    pub compressed: bool,
+    // Listed in PRODUCT_PACKAGES
+    pub packaged: bool,
+}
+
+impl ApexInfo {
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    pub fn prebuilt_count(&self) -> usize {
+        self.modules.iter().filter(|module| module.prebuilt).count()
+    }
+
+    // Modules the tree defines that PRODUCT_PACKAGES never installs
+    pub fn unpackaged(&self) -> Vec<&ApexModule> {
+        self.modules.iter().filter(|module| !module.packaged && !module.files.is_empty()).collect()
+    }
+
+    fn module_mut(&mut self, name: &str) -> &mut ApexModule {
+        let index = match self.modules.iter().position(|module| module.name == name) {
+            Some(index) => index,
+            None => {
+                self.modules.push(ApexModule {
+                    name: name.to_string(),
+                    prebuilt: false,
+                    files: Vec::new(),
+                    compressed: false,
+                    packaged: false,
+                });
+                self.modules.len() - 1
+            }
+        };
+        &mut self.modules[index]
+    }
+}
+
+pub fn scan_apex(
+    tree_path: &Path,
+    device_mk: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> ApexInfo {
+    let mut apex = ApexInfo::default();
+
+    let mut prebuilts: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| apex_file_name(name).is_some(), &mut prebuilts);
+    prebuilts.sort();
+    for file in prebuilts {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let file_name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let Some((name, compressed)) = apex_file_name(&file_name) else {
+            continue;
+        };
+        let module = apex.module_mut(name);
+        module.prebuilt = true;
+        module.compressed |= compressed;
+        module.files.push(relative);
+    }
+
+    let mut blueprints: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name == "Android.bp", &mut blueprints);
+    blueprints.sort();
+    for file in blueprints {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let content = match fsread::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) => {
+                warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+                continue;
+            }
+        };
+        for (name, prebuilt) in blueprint_apexes(&content) {
+            let module = apex.module_mut(&name);
+            module.prebuilt |= prebuilt;
+            if !module.files.contains(&relative) {
+                module.files.push(relative.clone());
+            }
+        }
+    }
+
+    // Unreadable device.mk is already reported by the driver scan
+    if let Some(device_mk) = device_mk
+        && walk_options.includes_file(device_mk)
+        && let Ok(content) = fsread::read_to_string(device_mk)
+    {
+        for (_, statement) in statements(&content) {
+            let Some(("PRODUCT_PACKAGES", value)) = split_assignment(&statement) else {
+                continue;
+            };
+            for package in value.split_whitespace() {
+                // Names the tree defines, plus ones spelled like an APEX
+                let known = apex.modules.iter().any(|module| module.name == package);
+                if known || looks_like_apex(package) {
+                    apex.module_mut(package).packaged = true;
+                }
+            }
+        }
+    }
+
+    apex.modules.sort_by(|a, b| a.name.cmp(&b.name));
+    apex
+}
+
+// "com.android.foo.apex" -> ("com.android.foo", false), ".capex" -> compressed
+fn apex_file_name(name: &str) -> Option<(&str, bool)> {
+    if let Some(stem) = name.strip_suffix(".capex") {
+        return (!stem.is_empty()).then_some((stem, true));
+    }
+    name.strip_suffix(".apex").filter(|stem| !stem.is_empty()).map(|stem| (stem, false))
+}
+
+// PRODUCT_PACKAGES entries not defined in the tree: APEX names are reverse
+// domains ("com.android.vndk.current", "com.qti.camera.apex")
+fn looks_like_apex(package: &str) -> bool {
+    let lower = package.to_lowercase();
+    (lower.starts_with("com.") && lower.split(['.', '_', '-']).any(|part| part == "apex" || part == "capex"))
+        || lower.starts_with("com.android.vndk.")
+}
+
+// (name, prebuilt) of the apex/prebuilt_apex/apex_set modules in a blueprint
+fn blueprint_apexes(content: &str) -> Vec<(String, bool)> {
+    let mut apexes = Vec::new();
+    let mut current: Option<bool> = None;
+    for line in content.lines().map(str::trim) {
+        if let Some(module_type) = line.strip_suffix('{').map(str::trim)
+            && !module_type.contains(':')
+        {
+            current = APEX_MODULE_TYPES.iter().find(|(t, _)| *t == module_type).map(|(_, prebuilt)| *prebuilt);
+            continue;
+        }
+        if let Some(prebuilt) = current
+            && let Some(value) = line.strip_prefix("name:")
+        {
+            let name = value.trim().trim_end_matches(',').trim_matches('"');
+            if !name.is_empty() {
+                apexes.push((name.to_string(), prebuilt));
+            }
+            current = None;
+        }
+    }
+    apexes
+}
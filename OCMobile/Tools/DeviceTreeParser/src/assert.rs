@@ -0,0 +1,201 @@
+// Tiny expression language for --assert, evaluated against a HardwareReport.
+//
+//   <path> exists                e.g. drivers.WiFi Driver exists
+//   <path> == <value>            e.g. structure_valid == true
+//   <path> != <value>            e.g. device_info.vendor != unknown
+//   count(<path>) <op> <number>  e.g. count(drivers.Kernel Modules) >= 2
+//
+// Paths are resolved by HardwareReport::get (see query.rs for the grammar).
+//
+// <op> is one of ==, !=, >, >=, <, <=. "exists" holds for true booleans and
+// non-empty strings, lists and maps.
+
+use crate::HardwareReport;
+use crate::query::Value;
+
+#[derive(Debug)]
+pub struct Assertion {
+    pub source: String,
+    operand: Operand,
+    check: Check,
+}
+
+#[derive(Debug)]
+enum Operand {
+    Path(String),
+    Count(String),
+}
+
+#[derive(Debug)]
+enum Check {
+    Exists,
+    Compare(Op, String),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+// Operators must be surrounded by spaces, so category names containing
+// symbols are never split in the middle
+const OPERATORS: [(&str, Op); 6] = [
+    (" == ", Op::Eq),
+    (" != ", Op::Ne),
+    (" >= ", Op::Ge),
+    (" <= ", Op::Le),
+    (" > ", Op::Gt),
+    (" < ", Op::Lt),
+];
+
+pub fn parse(expr: &str) -> Result<Assertion, String> {
+    let trimmed = expr.trim();
+
+    let (lhs, check) = if let Some(lhs) = trimmed.strip_suffix(" exists") {
+        (lhs, Check::Exists)
+    } else {
+        let (pos, token, op) = OPERATORS
+            .iter()
+            .filter_map(|(token, op)| trimmed.rfind(token).map(|pos| (pos, *token, *op)))
+            .max_by_key(|(pos, _, _)| *pos)
+            .ok_or_else(|| "expected 'exists' or a comparison (==, !=, >, >=, <, <=)".to_string())?;
+
+        let value = trimmed[pos + token.len()..].trim().trim_matches('"').to_string();
+        if value.is_empty() {
+            return Err("missing value after operator".to_string());
+        }
+        (&trimmed[..pos], Check::Compare(op, value))
+    };
+
+    let lhs = lhs.trim();
+    let operand = if let Some(inner) = lhs.strip_prefix("count(").and_then(|s| s.strip_suffix(')')) {
+        Operand::Count(inner.trim().to_string())
+    } else {
+        Operand::Path(lhs.to_string())
+    };
+
+    let path = match &operand {
+        Operand::Path(path) | Operand::Count(path) => path,
+    };
+    if path.is_empty() {
+        return Err("missing field path".to_string());
+    }
+
+    if let (Operand::Count(_), Check::Compare(_, value)) = (&operand, &check)
+        && value.parse::<usize>().is_err()
+    {
+        return Err(format!("count() must be compared against a number, got '{}'", value));
+    }
+
+    Ok(Assertion {
+        source: trimmed.to_string(),
+        operand,
+        check,
+    })
+}
+
+impl Assertion {
+    // Ok(()) when the assertion holds, Err(reason) otherwise
+    pub fn evaluate(&self, report: &HardwareReport) -> Result<(), String> {
+        let path = match &self.operand {
+            Operand::Path(path) | Operand::Count(path) => path,
+        };
+        let value = report.get(path);
+
+        match (&self.operand, &self.check) {
+            (Operand::Path(_), Check::Exists) => match value {
+                Some(v) if v.is_present() => Ok(()),
+                Some(v) => Err(format!("{} is {}", path, v.describe())),
+                None => Err(format!("{} not found", path)),
+            },
+            (Operand::Count(_), Check::Exists) => {
+                let count = value.and_then(|v| v.count()).unwrap_or(0);
+                if count > 0 { Ok(()) } else { Err(format!("{} is empty", path)) }
+            }
+            (Operand::Path(_), Check::Compare(op, expected)) => {
+                let value = value.ok_or_else(|| format!("{} not found", path))?;
+                let actual = match &value {
+                    Value::Bool(b) => b.to_string(),
+                    Value::Str(s) => s.to_string(),
+                    _ => return Err(format!("{} is a collection, use count() or exists", path)),
+                };
+                let holds = match op {
+                    Op::Eq => actual == *expected,
+                    Op::Ne => actual != *expected,
+                    _ => return Err("ordering comparisons need count()".to_string()),
+                };
+                if holds { Ok(()) } else { Err(format!("got {}", value.describe())) }
+            }
+            (Operand::Count(_), Check::Compare(op, expected)) => {
+                let count = match value {
+                    Some(v) => v.count().ok_or_else(|| format!("{} is not a collection", path))?,
+                    None => 0,
+                };
+                let expected: usize = expected.parse().unwrap_or(0);
+                let holds = match op {
+                    Op::Eq => count == expected,
+                    Op::Ne => count != expected,
+                    Op::Gt => count > expected,
+                    Op::Ge => count >= expected,
+                    Op::Lt => count < expected,
+                    Op::Le => count <= expected,
+                };
+                if holds { Ok(()) } else { Err(format!("count is {}", count)) }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::category;
+
+    #[test]
+    fn paths_keep_their_spaces() {
+        let assertion = parse("count(drivers.WiFi Driver) >= 2").unwrap();
+        assert!(matches!(&assertion.operand, Operand::Count(path) if path == "drivers.WiFi Driver"));
+        assert!(matches!(&assertion.check, Check::Compare(Op::Ge, value) if value == "2"));
+
+        let hal = "drivers.HAL (Hardware Abstraction Layer)";
+        let assertion = parse(&format!("{} exists", hal)).unwrap();
+        assert!(matches!(&assertion.operand, Operand::Path(path) if path == hal));
+        assert!(matches!(assertion.check, Check::Exists));
+    }
+
+    #[test]
+    fn malformed_assertions_are_rejected() {
+        let error = |expr: &str| parse(expr).unwrap_err();
+        assert_eq!(error("drivers.WiFi Driver"), "expected 'exists' or a comparison (==, !=, >, >=, <, <=)");
+        assert_eq!(error("structure_valid == "), "expected 'exists' or a comparison (==, !=, >, >=, <, <=)");
+        assert_eq!(error("count(drivers.WiFi Driver) > two"), "count() must be compared against a number, got 'two'");
+        assert_eq!(error(" == true"), "expected 'exists' or a comparison (==, !=, >, >=, <, <=)");
+    }
+
+    #[test]
+    fn assertions_evaluate_against_the_report() {
+        let mut report = HardwareReport::default();
+        report.drivers.insert(category::WIFI.to_string(), vec!["qcwcn".to_string(), "wlan.ko".to_string()]);
+        report.drivers.insert(category::GPU.to_string(), vec!["msmnile".to_string()]);
+        report.key_files.insert("BoardConfig.mk".to_string(), true);
+        report.key_files.insert("system.prop".to_string(), false);
+        let evaluate = |expr: &str| parse(expr).unwrap().evaluate(&report);
+
+        assert_eq!(evaluate("count(drivers.WiFi Driver) >= 2"), Ok(()));
+        assert_eq!(evaluate("count(drivers.WiFi Driver) > 2"), Err("count is 2".to_string()));
+        assert_eq!(evaluate("drivers.GPU/Platform exists"), Ok(()));
+        assert_eq!(evaluate("key_files.BoardConfig.mk == true"), Ok(()));
+        assert_eq!(evaluate("key_files.system.prop == true"), Err("got false".to_string()));
+        assert_eq!(evaluate("drivers.Modem Driver exists"), Err("drivers.Modem Driver not found".to_string()));
+        assert_eq!(evaluate("count(drivers.Modem Driver) == 0"), Ok(()));
+        assert_eq!(
+            evaluate("drivers.GPU/Platform == msmnile"),
+            Err("drivers.GPU/Platform is a collection, use count() or exists".to_string())
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use std::path::{Path, PathBuf};
+use crate::dts::{DtsNode, DtsSources};
+use crate::walk::{self, WalkOptions};
+
+// Qualcomm has renamed its charger and fuel gauge bindings with nearly every
+// PMIC generation; downstream and upstream names are both in use
+const BINDINGS: [(&str, SupplyKind, &str); 18] = [
+    ("qcom,qpnp-charger", SupplyKind::Charger, "QPNP charger (PM8941 era)"),
+    ("qcom,qpnp-linear-charger", SupplyKind::Charger, "QPNP linear charger (PM8916)"),
+    ("qcom,qpnp-smbcharger", SupplyKind::Charger, "SMBCHG (PMI8994/PMI8996)"),
+    ("qcom,qpnp-smb2", SupplyKind::Charger, "SMB2 (PMI8998/PM660)"),
+    ("qcom,qpnp-smb5", SupplyKind::Charger, "SMB5 (PM8150B/PM7250B)"),
+    ("qcom,smb138x-charger", SupplyKind::Charger, "SMB138x parallel charger"),
+    ("qcom,smb1355", SupplyKind::Charger, "SMB1355 parallel charger"),
+    ("qcom,smb1390-charger-psy", SupplyKind::Charger, "SMB1390 charge pump"),
+    ("qcom,pm8941-charger", SupplyKind::Charger, "SMBB (upstream)"),
+    ("qcom,pmi8998-charger", SupplyKind::Charger, "SMB2 (upstream)"),
+    ("qcom,pm8150b-charger", SupplyKind::Charger, "SMB5 (upstream)"),
+    ("qcom,qpnp-vm-bms", SupplyKind::FuelGauge, "Voltage-mode BMS (PM8916)"),
+    ("qcom,qpnp-bms", SupplyKind::FuelGauge, "BMS (PM8941 era)"),
+    ("qcom,qpnp-fg", SupplyKind::FuelGauge, "FG (PMI8994/PMI8996)"),
+    ("qcom,fg-gen3", SupplyKind::FuelGauge, "FG Gen3 (PMI8998/PM660)"),
+    ("qcom,fg-gen4", SupplyKind::FuelGauge, "FG Gen4 (PM8150B)"),
+    ("qcom,qpnp-qg", SupplyKind::FuelGauge, "QGauge (PM632/PM7250B)"),
+    ("qcom,pmi8998-fg", SupplyKind::FuelGauge, "FG Gen3 (upstream)"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SupplyKind {
+    Charger,
+    FuelGauge,
+}
+
+// Charger/fuel gauge controllers, battery profiles described in the DTS and
+// any battery data files shipped with the tree
+#[derive(Debug, Default)]
+pub struct BatteryInfo {
+    pub controllers: Vec<PowerSupplyController>,
+    pub profiles: Vec<BatteryProfile>,
+    pub profile_files: Vec<BatteryProfileFile>,
+}
+
+#[derive(Debug)]
+pub struct PowerSupplyController {
+    pub node: String,
+    pub kind: SupplyKind,
+    // The compatible that identified it
+    pub binding: String,
+    // Which generation the binding belongs to, for known Qualcomm bindings
+    pub description: Option<&'static str>,
+    pub locations: Vec<String>,
+}
+
+// A qcom,battery-data child or an upstream simple-battery node
+#[derive(Debug)]
+pub struct BatteryProfile {
+    pub node: String,
+    pub battery_type: Option<String>,
+    pub capacity_mah: Option<u64>,
+    pub chemistry: Option<String>,
+    pub max_microvolt: Option<u64>,
+    pub locations: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct BatteryProfileFile {
+    pub file: String,
+    // From the file name, e.g. "batterydata-ascent-3450mah.dtsi"
+    pub capacity_mah: Option<u64>,
+}
+
+impl SupplyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SupplyKind::Charger => "charger",
+            SupplyKind::FuelGauge => "fuel_gauge",
+        }
+    }
+}
+
+impl BatteryInfo {
+    pub fn is_empty(&self) -> bool {
+        self.controllers.is_empty() && self.profiles.is_empty() && self.profile_files.is_empty()
+    }
+}
+
+pub fn scan_battery(tree_path: &Path, sources: &DtsSources, walk_options: &WalkOptions) -> BatteryInfo {
+    let mut battery = BatteryInfo::default();
+
+    for node in sources.nodes.values() {
+        if let Some(controller) = controller(node) {
+            battery.controllers.push(controller);
+        } else if is_profile(node) {
+            battery.profiles.push(BatteryProfile {
+                node: node.path.clone(),
+                battery_type: node.string("qcom,battery-type").map(|s| s.to_string()),
+                capacity_mah: node
+                    .cells("qcom,nom-batt-capacity-mah")
+                    .first()
+                    .copied()
+                    .or_else(|| node.cells("charge-full-design-microamp-hours").first().map(|uah| uah / 1000)),
+                chemistry: node.string("device-chemistry").map(|s| s.to_string()),
+                max_microvolt: node
+                    .cells("qcom,max-voltage-uv")
+                    .first()
+                    .copied()
+                    .or_else(|| node.cells("voltage-max-design-microvolt").first().copied()),
+                locations: node.locations.clone(),
+            });
+        }
+    }
+
+    battery.profile_files = find_profile_files(tree_path, walk_options);
+    battery
+}
+
+fn controller(node: &DtsNode) -> Option<PowerSupplyController> {
+    let compatible = node.strings("compatible");
+
+    for binding in &compatible {
+        if let Some((_, kind, description)) = BINDINGS.iter().find(|(name, _, _)| binding.starts_with(name)) {
+            return Some(PowerSupplyController {
+                node: node.path.clone(),
+                kind: *kind,
+                binding: binding.to_string(),
+                description: Some(description),
+                locations: node.locations.clone(),
+            });
+        }
+    }
+
+    // Other vendors' chargers (bq25890, sgm4154x, ...) and gauges (bq27z561,
+    // max1720x, ...) only share naming habits
+    compatible.iter().find_map(|binding| {
+        let part = binding.split_once(',').map_or(*binding, |(_, part)| part);
+        let words: Vec<&str> = part.split(['-', '_']).collect();
+        let kind = if part.contains("charger") || words.contains(&"chg") {
+            SupplyKind::Charger
+        } else if part.replace(['-', '_'], "").contains("fuelgauge") || words.contains(&"fg") {
+            SupplyKind::FuelGauge
+        } else {
+            return None;
+        };
+        Some(PowerSupplyController {
+            node: node.path.clone(),
+            kind,
+            binding: binding.to_string(),
+            description: None,
+            locations: node.locations.clone(),
+        })
+    })
+}
+
+fn is_profile(node: &DtsNode) -> bool {
+    node.is_compatible_with("simple-battery") || node.properties.contains_key("qcom,battery-type")
+}
+
+fn find_profile_files(tree_path: &Path, walk_options: &WalkOptions) -> Vec<BatteryProfileFile> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| {
+            let lower = name.to_lowercase().replace(['-', '_'], "");
+            lower.contains("batterydata") || lower.contains("batteryprofile") || lower.contains("battprofile")
+        },
+        &mut files,
+    );
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|file| BatteryProfileFile {
+            capacity_mah: file.file_name().and_then(|name| capacity_from_name(&name.to_string_lossy())),
+            file: file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string(),
+        })
+        .collect()
+}
+
+// The digits right before "mah", e.g. "..._4000mAh.dtsi" -> 4000
+fn capacity_from_name(name: &str) -> Option<u64> {
+    let lower = name.to_lowercase();
+    let end = lower.find("mah")?;
+    let digits = lower[..end].chars().rev().take_while(|c| c.is_ascii_digit()).count();
+    lower[end - digits..end].parse().ok()
+}
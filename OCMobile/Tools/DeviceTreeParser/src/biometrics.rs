@@ -0,0 +1,212 @@
+use std::path::{Path, PathBuf};
+use crate::dts::{DtsNode, DtsSources};
+use crate::fsread;
+use crate::makefile::{split_assignment, statements};
+use crate::walk::{self, WalkOptions};
+
+// Compatible fragments of fingerprint sensors: generic names, FPC, Goodix,
+// Egis, Silead and Chipone parts
+const FINGERPRINT_MARKERS: [&str; 7] =
+    ["fingerprint", "fpc,", "fpc1", "goodix,fp", "egistec", "silead,fp", "chipone,fp"];
+
+// Words (split on anything but letters and digits) that mark an under-display
+// sensor: Xiaomi/Goodix "fod", AOSP "udfps", and the optical/ultrasonic parts
+const UNDER_DISPLAY_WORDS: [&str; 5] = ["fod", "udfps", "optical", "ultrasonic", "underscreen"];
+
+// Vendors whose sensors are always capacitive
+const CAPACITIVE_VENDORS: [&str; 1] = ["fpc"];
+
+// Build files that mention fingerprint without being config
+const SKIPPED_EXTENSIONS: [&str; 4] = [".mk", ".bp", ".dts", ".dtsi"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SensorKind {
+    Capacitive,
+    UnderDisplay,
+    #[default]
+    Unknown,
+}
+
+impl SensorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SensorKind::Capacitive => "capacitive",
+            SensorKind::UnderDisplay => "under-display",
+            SensorKind::Unknown => "unknown",
+        }
+    }
+}
+
+// Fingerprint sensors from the DTS, the biometrics HAL packages from
+// device.mk and any fingerprint config shipped with the tree
+#[derive(Debug, Default)]
+pub struct Biometrics {
+    pub sensors: Vec<FingerprintSensor>,
+    // Every fingerprint/biometrics PRODUCT_PACKAGES entry
+    pub hal_packages: Vec<String>,
+    pub config_files: Vec<String>,
+    // device.mk statements and file names that point at an under-display
+    // sensor (TARGET_HAS_FOD, udfps overlays, ...)
+    pub under_display_hints: Vec<Hint>,
+}
+
+#[derive(Debug, Default)]
+pub struct FingerprintSensor {
+    pub node: String,
+    pub vendor: String,
+    pub compatible: Vec<String>,
+    pub kind: SensorKind,
+    // HAL packages that belong to this sensor (see correlate)
+    pub hal_packages: Vec<String>,
+    pub locations: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Hint {
+    pub text: String,
+    pub location: String,
+}
+
+impl Biometrics {
+    pub fn is_empty(&self) -> bool {
+        self.sensors.is_empty() && self.hal_packages.is_empty() && self.config_files.is_empty()
+    }
+
+    // Fingerprint HAL packages; the face HAL and other biometrics don't count
+    pub fn fingerprint_hals(&self) -> Vec<&str> {
+        self.hal_packages
+            .iter()
+            .filter(|package| package.to_lowercase().contains("fingerprint"))
+            .map(|package| package.as_str())
+            .collect()
+    }
+}
+
+pub fn scan_biometrics(
+    tree_path: &Path,
+    sources: &DtsSources,
+    device_mk: Option<&Path>,
+    walk_options: &WalkOptions,
+) -> Biometrics {
+    let mut biometrics = Biometrics::default();
+
+    for node in sources.nodes.values() {
+        let compatible: Vec<String> = node.strings("compatible").into_iter().map(|s| s.to_string()).collect();
+        if !is_fingerprint(node, &compatible) {
+            continue;
+        }
+        let vendor = compatible
+            .iter()
+            .find_map(|c| c.split_once(',').map(|(vendor, _)| vendor.to_string()))
+            .unwrap_or_else(|| "unknown".to_string());
+        let under_display = compatible.iter().any(|c| c.ends_with(",screen") || mentions_under_display(c))
+            || mentions_under_display(node.name());
+        let kind = if under_display {
+            SensorKind::UnderDisplay
+        } else if CAPACITIVE_VENDORS.contains(&vendor.as_str()) {
+            SensorKind::Capacitive
+        } else {
+            SensorKind::Unknown
+        };
+        biometrics.sensors.push(FingerprintSensor {
+            node: node.path.clone(),
+            vendor,
+            compatible,
+            kind,
+            locations: node.locations.clone(),
+            ..Default::default()
+        });
+    }
+
+    // Unreadable device.mk is already reported by the driver scan
+    if let Some(device_mk) = device_mk
+        && walk_options.includes_file(device_mk)
+        && let Ok(content) = fsread::read_to_string(device_mk)
+    {
+        let file_name = device_mk.file_name().unwrap_or_default().to_string_lossy().to_string();
+        for (line, statement) in statements(&content) {
+            if mentions_under_display(&statement) {
+                biometrics.under_display_hints.push(Hint {
+                    text: statement.trim().to_string(),
+                    location: format!("{}:{}", file_name, line),
+                });
+            }
+            let Some(("PRODUCT_PACKAGES", value)) = split_assignment(&statement) else {
+                continue;
+            };
+            for package in value.split_whitespace() {
+                let lower = package.to_lowercase();
+                if (lower.contains("fingerprint") || lower.contains("biometrics"))
+                    && !biometrics.hal_packages.iter().any(|p| p == package)
+                {
+                    biometrics.hal_packages.push(package.to_string());
+                }
+            }
+        }
+    }
+
+    biometrics.config_files = find_config_files(tree_path, walk_options);
+    for file in &biometrics.config_files {
+        let name = file.rsplit('/').next().unwrap_or(file);
+        if mentions_under_display(name) {
+            biometrics.under_display_hints.push(Hint { text: name.to_string(), location: file.clone() });
+        }
+    }
+
+    correlate(&mut biometrics);
+    biometrics
+}
+
+fn is_fingerprint(node: &DtsNode, compatible: &[String]) -> bool {
+    !compatible.is_empty()
+        && (node.name().contains("fingerprint")
+            || node.name().starts_with("fpc")
+            || compatible.iter().any(|c| FINGERPRINT_MARKERS.iter().any(|marker| c.contains(marker))))
+}
+
+fn mentions_under_display(text: &str) -> bool {
+    text.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|word| UNDER_DISPLAY_WORDS.contains(&word))
+}
+
+// A HAL package belongs to a sensor when it names the sensor's vendor
+// ("vendor.goodix.hardware.biometrics.fingerprint@2.1"). With a single
+// sensor, a generic fingerprint HAL can only be driving that one. A sensor
+// the DTS doesn't classify is taken as under-display when the tree has
+// under-display hints and it is the only sensor.
+fn correlate(biometrics: &mut Biometrics) {
+    let single = biometrics.sensors.len() == 1;
+    let fingerprint_hals: Vec<String> = biometrics.fingerprint_hals().into_iter().map(str::to_string).collect();
+    for sensor in &mut biometrics.sensors {
+        for package in &fingerprint_hals {
+            if package.to_lowercase().contains(&sensor.vendor.to_lowercase()) || single {
+                sensor.hal_packages.push(package.clone());
+            }
+        }
+        if sensor.kind == SensorKind::Unknown && single && !biometrics.under_display_hints.is_empty() {
+            sensor.kind = SensorKind::UnderDisplay;
+        }
+    }
+}
+
+fn find_config_files(tree_path: &Path, walk_options: &WalkOptions) -> Vec<String> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| {
+            let lower = name.to_lowercase();
+            (lower.contains("fingerprint") || lower.contains("fpc") || lower.contains("udfps"))
+                && !SKIPPED_EXTENSIONS.iter().any(|ext| lower.ends_with(ext))
+        },
+        &mut files,
+    );
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|file| file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string())
+        .collect()
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::fsread;
+use crate::makefile::{self, AssignOp, statements};
+use crate::walk::WalkOptions;
+
+// Makefile variables describing slots and where recovery lives
+const BOOT_VARIABLES: [&str; 7] = [
+    "AB_OTA_UPDATER",
+    "AB_OTA_PARTITIONS",
+    "BOARD_USES_RECOVERY_AS_BOOT",
+    "BOARD_MOVE_RECOVERY_RESOURCES_TO_VENDOR_BOOT",
+    "BOARD_INCLUDE_RECOVERY_RAMDISK_IN_VENDOR_BOOT",
+    "BOARD_RECOVERYIMAGE_PARTITION_SIZE",
+    "TARGET_NO_RECOVERY",
+];
+
+// A/B slot setup from BoardConfig.mk/device.mk, i.e. what fastboot flashes
+// per slot and whether recovery has a partition of its own
+#[derive(Debug, Default)]
+pub struct BootInfo {
+    // AB_OTA_PARTITIONS, accumulated over every += in both makefiles
+    pub ab_partitions: Vec<String>,
+    pub ab_updater: bool,
+    pub recovery: Option<RecoveryPartition>,
+    // Every matched variable as last assigned (AB_OTA_PARTITIONS joined)
+    pub vars: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryPartition {
+    // BOARD_RECOVERYIMAGE_PARTITION_SIZE
+    Dedicated,
+    // BOARD_USES_RECOVERY_AS_BOOT: the recovery ramdisk is in boot.img
+    InBoot,
+    // The recovery ramdisk is in vendor_boot.img
+    InVendorBoot,
+    // TARGET_NO_RECOVERY
+    None,
+}
+
+impl RecoveryPartition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecoveryPartition::Dedicated => "dedicated partition",
+            RecoveryPartition::InBoot => "in boot (recovery-as-boot)",
+            RecoveryPartition::InVendorBoot => "in vendor_boot",
+            RecoveryPartition::None => "none",
+        }
+    }
+}
+
+impl BootInfo {
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    pub fn is_ab(&self) -> bool {
+        self.ab_updater || !self.ab_partitions.is_empty()
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        if self.is_ab() { "A/B" } else { "A-only" }
+    }
+}
+
+pub fn scan_boot(makefiles: &[&Path], walk_options: &WalkOptions) -> BootInfo {
+    let mut boot = BootInfo::default();
+
+    // Unreadable makefiles are already reported by the driver scan
+    for makefile in makefiles {
+        if !walk_options.includes_file(makefile) {
+            continue;
+        }
+        let Ok(content) = fsread::read_to_string(makefile) else {
+            continue;
+        };
+
+        for (_, statement) in statements(&content) {
+            let Some(makefile::Assignment { name, op, value }) = makefile::parse_assignment(&statement) else {
+                continue;
+            };
+            if !BOOT_VARIABLES.contains(&name) {
+                continue;
+            }
+
+            if name == "AB_OTA_PARTITIONS" {
+                // := starts the list over, += appends to it
+                if op != AssignOp::Append {
+                    boot.ab_partitions.clear();
+                }
+                for partition in value.split_whitespace() {
+                    if !boot.ab_partitions.iter().any(|p| p == partition) {
+                        boot.ab_partitions.push(partition.to_string());
+                    }
+                }
+                boot.vars.insert(name.to_string(), boot.ab_partitions.join(" "));
+            } else {
+                boot.vars.insert(name.to_string(), value.trim_matches('"').to_string());
+            }
+        }
+    }
+
+    let enabled = |name: &str| boot.vars.get(name).is_some_and(|value| value == "true");
+    boot.ab_updater = enabled("AB_OTA_UPDATER");
+    boot.recovery = if enabled("BOARD_USES_RECOVERY_AS_BOOT") {
+        Some(RecoveryPartition::InBoot)
+    } else if enabled("BOARD_MOVE_RECOVERY_RESOURCES_TO_VENDOR_BOOT")
+        || enabled("BOARD_INCLUDE_RECOVERY_RAMDISK_IN_VENDOR_BOOT")
+    {
+        Some(RecoveryPartition::InVendorBoot)
+    } else if enabled("TARGET_NO_RECOVERY") {
+        Some(RecoveryPartition::None)
+    } else if boot.vars.contains_key("BOARD_RECOVERYIMAGE_PARTITION_SIZE") {
+        Some(RecoveryPartition::Dedicated)
+    } else {
+        None
+    };
+
+    boot
+}
@@ -0,0 +1,271 @@
+use std::path::{Path, PathBuf};
+use crate::dts::{DtsNode, DtsSources};
+use crate::fsread;
+use crate::makefile::{split_assignment, statements};
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Image sensor part prefixes: Sony IMX, OmniVision OV, Samsung S5K,
+// GalaxyCore GC, SK hynix Hi and onsemi AR. A model is a prefix followed by
+// a digit ("imx586", "ov13b10", "hi846"), except S5K whose models may
+// continue with letters ("s5kgm1").
+const SENSOR_PREFIXES: [&str; 6] = ["imx", "ov", "s5k", "gc", "hi", "ar"];
+
+// Compatibles of Qualcomm sensor nodes, which name the model in a property
+// rather than in the compatible
+const QCOM_SENSOR_COMPATIBLES: [&str; 3] = ["qcom,cam-sensor", "qcom,camera", "qcom,msm-cam-sensor"];
+
+// Image sensors from the DTS, the camera config files and sensor module
+// libraries shipped with the tree, and the camera packages from device.mk.
+// Sensors are merged by model, so one entry shows where a sensor is
+// described and what in the tree supports it.
+#[derive(Debug, Default)]
+pub struct CameraInfo {
+    pub sensors: Vec<CameraSensor>,
+    // Sensor nodes whose model couldn't be told (qcom,cam-sensor without a
+    // sensor-name)
+    pub unnamed_nodes: Vec<String>,
+    pub hal_packages: Vec<String>,
+    pub config_files: Vec<CameraConfig>,
+    // com.qti.sensormodule.*.bin, libmmcamera_*.so, ...
+    pub sensor_libs: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct CameraSensor {
+    pub model: String,
+    // DTS nodes describing it
+    pub nodes: Vec<String>,
+    // Sensor libs and config files naming it
+    pub files: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct CameraConfig {
+    pub file: String,
+    // camera_config.xml <SensorName>s, or models named by override settings
+    pub sensors: Vec<String>,
+    // name=value lines of camxoverridesettings.txt
+    pub settings: Vec<(String, String)>,
+}
+
+impl CameraInfo {
+    pub fn is_empty(&self) -> bool {
+        self.sensors.is_empty()
+            && self.unnamed_nodes.is_empty()
+            && self.hal_packages.is_empty()
+            && self.config_files.is_empty()
+            && self.sensor_libs.is_empty()
+    }
+
+    // Sensors in the DTS with no lib or config in the tree to drive them;
+    // with CAMX they need a sensor module from the vendor blobs
+    pub fn unsupported_sensors(&self) -> Vec<&CameraSensor> {
+        self.sensors.iter().filter(|sensor| !sensor.nodes.is_empty() && sensor.files.is_empty()).collect()
+    }
+
+    fn sensor_mut(&mut self, model: &str) -> &mut CameraSensor {
+        let index = match self.sensors.iter().position(|sensor| sensor.model == model) {
+            Some(index) => index,
+            None => {
+                self.sensors.push(CameraSensor { model: model.to_string(), nodes: Vec::new(), files: Vec::new() });
+                self.sensors.len() - 1
+            }
+        };
+        &mut self.sensors[index]
+    }
+}
+
+pub fn scan_camera(
+    tree_path: &Path,
+    sources: &DtsSources,
+    device_mk: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> CameraInfo {
+    let mut camera = CameraInfo::default();
+
+    for node in sources.nodes.values() {
+        if !is_sensor_node(node) {
+            continue;
+        }
+        let models = node_models(node);
+        if models.is_empty() {
+            camera.unnamed_nodes.push(node.path.clone());
+        }
+        for model in models {
+            let sensor = camera.sensor_mut(&model);
+            if !sensor.nodes.contains(&node.path) {
+                sensor.nodes.push(node.path.clone());
+            }
+        }
+    }
+
+    // Unreadable device.mk is already reported by the driver scan
+    if let Some(device_mk) = device_mk
+        && walk_options.includes_file(device_mk)
+        && let Ok(content) = fsread::read_to_string(device_mk)
+    {
+        for (_, statement) in statements(&content) {
+            let Some(("PRODUCT_PACKAGES", value)) = split_assignment(&statement) else {
+                continue;
+            };
+            for package in value.split_whitespace() {
+                if package.to_lowercase().contains("camera") && !camera.hal_packages.iter().any(|p| p == package) {
+                    camera.hal_packages.push(package.to_string());
+                }
+            }
+        }
+    }
+
+    for file in find_camera_files(tree_path, walk_options) {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let name = file.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+
+        let models = if name == "camera_config.xml" || name == "camxoverridesettings.txt" {
+            let content = match fsread::read_to_string(&file) {
+                Ok(content) => content,
+                Err(e) => {
+                    warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+                    continue;
+                }
+            };
+            let config = if name == "camera_config.xml" {
+                CameraConfig { file: relative.clone(), sensors: config_sensor_names(&content), settings: Vec::new() }
+            } else {
+                let settings = override_settings(&content);
+                let sensors =
+                    settings.iter().flat_map(|(name, value)| sensor_models(&format!("{} {}", name, value))).collect();
+                CameraConfig { file: relative.clone(), sensors: dedup(sensors), settings }
+            };
+            let models = config.sensors.clone();
+            camera.config_files.push(config);
+            models
+        } else {
+            camera.sensor_libs.push(relative.clone());
+            sensor_models(&name)
+        };
+
+        for model in models {
+            let sensor = camera.sensor_mut(&model);
+            if !sensor.files.contains(&relative) {
+                sensor.files.push(relative.clone());
+            }
+        }
+    }
+
+    camera.sensors.sort_by(|a, b| a.model.cmp(&b.model));
+    camera
+}
+
+fn is_sensor_node(node: &DtsNode) -> bool {
+    let compatible = node.strings("compatible");
+    compatible.iter().any(|c| QCOM_SENSOR_COMPATIBLES.contains(c))
+        || compatible.iter().any(|c| {
+            // HiSilicon SoCs and PMICs (hi3660, hi6421) look like SK hynix parts
+            let (vendor, part) = c.split_once(',').unwrap_or(("", c));
+            vendor != "hisilicon" && !sensor_models(part).is_empty()
+        })
+}
+
+// From sensor-name style properties first, then the compatibles
+fn node_models(node: &DtsNode) -> Vec<String> {
+    for property in ["sensor-name", "qcom,sensor-name", "sensor-model"] {
+        if let Some(name) = node.string(property) {
+            let models = sensor_models(name);
+            if !models.is_empty() {
+                return models;
+            }
+        }
+    }
+    let compatible = node.strings("compatible");
+    dedup(
+        compatible
+            .iter()
+            .filter(|c| !QCOM_SENSOR_COMPATIBLES.contains(c))
+            .flat_map(|c| sensor_models(c.split_once(',').map_or(*c, |(_, part)| part)))
+            .collect(),
+    )
+}
+
+// Sensor models among the words of `text`, lowercased
+fn sensor_models(text: &str) -> Vec<String> {
+    let lower = text.to_lowercase();
+    let models = lower
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| {
+            SENSOR_PREFIXES.iter().any(|prefix| {
+                word.strip_prefix(prefix).is_some_and(|rest| {
+                    match rest.chars().next() {
+                        Some(first) if *prefix == "s5k" => first.is_ascii_alphanumeric(),
+                        Some(first) => first.is_ascii_digit(),
+                        None => false,
+                    }
+                })
+            })
+        })
+        .map(|word| word.to_string())
+        .collect();
+    dedup(models)
+}
+
+fn find_camera_files(tree_path: &Path, walk_options: &WalkOptions) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| {
+            let lower = name.to_lowercase();
+            lower == "camera_config.xml"
+                || lower == "camxoverridesettings.txt"
+                || lower.starts_with("com.qti.sensormodule.")
+                || lower.starts_with("com.qti.sensor.")
+                || lower.starts_with("libmmcamera_")
+                || (lower.contains("sensor")
+                    && lower.contains("cam")
+                    && (lower.ends_with(".so") || lower.ends_with(".bin")))
+        },
+        &mut files,
+    );
+    files.sort();
+    files
+}
+
+// <SensorName>imx298</SensorName> entries of a mm-camera camera_config.xml
+fn config_sensor_names(content: &str) -> Vec<String> {
+    let names = content
+        .split("<SensorName>")
+        .skip(1)
+        .filter_map(|rest| rest.split_once("</SensorName>"))
+        .map(|(name, _)| name.trim().to_lowercase())
+        .filter(|name| !name.is_empty())
+        .collect();
+    dedup(names)
+}
+
+// name=value lines; later ones win, as in CAMX
+fn override_settings(content: &str) -> Vec<(String, String)> {
+    let mut settings: Vec<(String, String)> = Vec::new();
+    for line in content.lines().map(str::trim).filter(|line| !line.starts_with(['#', ';'])) {
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (name, value) = (name.trim(), value.trim());
+        match settings.iter_mut().find(|(existing, _)| existing == name) {
+            Some(setting) => setting.1 = value.to_string(),
+            None => settings.push((name.to_string(), value.to_string())),
+        }
+    }
+    settings
+}
+
+fn dedup(mut items: Vec<String>) -> Vec<String> {
+    let mut seen: Vec<String> = Vec::new();
+    items.retain(|item| {
+        let new = !seen.contains(item);
+        seen.push(item.clone());
+        new
+    });
+    items
+}
@@ -0,0 +1,163 @@
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use xml::reader::{EventReader, XmlEvent};
+use crate::fsread;
+use crate::walk::{self, EntryKind, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// apns-conf.xml can list thousands of APNs; only keep this many per list
+pub const MAX_ENTRIES: usize = 50;
+
+#[derive(Debug, Default)]
+pub struct CarrierConfig {
+    pub apn_files: Vec<String>,
+    pub apns: Vec<String>,
+    pub apns_omitted: usize,
+    pub config_files: Vec<String>,
+    pub carrier_ids: Vec<String>,
+    pub carrier_ids_omitted: usize,
+    pub carrier_list_blobs: Vec<String>,
+}
+
+impl CarrierConfig {
+    pub fn is_empty(&self) -> bool {
+        self.apn_files.is_empty() && self.config_files.is_empty() && self.carrier_list_blobs.is_empty()
+    }
+}
+
+pub fn scan_carrier_config(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> CarrierConfig {
+    let mut files = Vec::new();
+    find_carrier_files(tree_path, &mut files, walk_options, 0);
+    files.sort();
+
+    let mut config = CarrierConfig::default();
+    let mut apns = Vec::new();
+    let mut carrier_ids = Vec::new();
+
+    for file in files {
+        let file_name = file.file_name().unwrap().to_string_lossy().to_string();
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+
+        let parsed = if file_name == "carrier_list.pb" {
+            config.carrier_list_blobs.push(relative.clone());
+            Ok(())
+        } else if file_name.starts_with("apns") {
+            config.apn_files.push(relative.clone());
+            parse_apns_xml(&file, &mut apns)
+        } else {
+            if let Some(id) = carrier_id_from_file_name(&file_name) {
+                carrier_ids.push(id);
+            }
+            config.config_files.push(relative.clone());
+            parse_carrier_config_xml(&file, &mut carrier_ids)
+        };
+
+        if let Err(e) = parsed {
+            warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+        }
+    }
+
+    carrier_ids.sort();
+    carrier_ids.dedup();
+
+    config.apns_omitted = apns.len().saturating_sub(MAX_ENTRIES);
+    apns.truncate(MAX_ENTRIES);
+    config.apns = apns;
+
+    config.carrier_ids_omitted = carrier_ids.len().saturating_sub(MAX_ENTRIES);
+    carrier_ids.truncate(MAX_ENTRIES);
+    config.carrier_ids = carrier_ids;
+
+    config
+}
+
+fn find_carrier_files(path: &Path, found: &mut Vec<PathBuf>, walk_options: &WalkOptions, depth: usize) {
+    if !walk_options.within_depth(depth) {
+        return;
+    }
+
+    for (entry_path, kind) in walk::read_dir(path, walk_options) {
+        match kind {
+            EntryKind::File => {
+                let file_name = entry_path.file_name().unwrap().to_string_lossy();
+                let is_apn = file_name.starts_with("apns") && file_name.ends_with(".xml");
+                let is_carrier_config = file_name.starts_with("carrier_config") && file_name.ends_with(".xml");
+                if is_apn || is_carrier_config || file_name == "carrier_list.pb" {
+                    found.push(entry_path);
+                }
+            }
+            EntryKind::Dir => find_carrier_files(&entry_path, found, walk_options, depth + 1),
+            EntryKind::Skip => {}
+        }
+    }
+}
+
+fn parse_apns_xml(path: &Path, apns: &mut Vec<String>) -> io::Result<()> {
+    // <apn carrier="T-Mobile US" mcc="310" mnc="260" apn="fast.t-mobile.com" ... />
+    let file = fsread::open(path)?;
+
+    for event in EventReader::new(BufReader::new(file)) {
+        match event {
+            Ok(XmlEvent::StartElement { name, attributes, .. }) if name.local_name == "apn" => {
+                let attr = |key: &str| {
+                    attributes
+                        .iter()
+                        .find(|a| a.name.local_name == key)
+                        .map(|a| a.value.as_str())
+                        .unwrap_or("")
+                };
+
+                let carrier = attr("carrier");
+                let apn = attr("apn");
+                let mut entry = if carrier.is_empty() { apn.to_string() } else { format!("{} ({})", carrier, apn) };
+                if !attr("mcc").is_empty() {
+                    entry.push_str(&format!(" [{}{}]", attr("mcc"), attr("mnc")));
+                }
+                apns.push(entry);
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_carrier_config_xml(path: &Path, carrier_ids: &mut Vec<String>) -> io::Result<()> {
+    // <carrier_config carrier_id="1" /> or <carrier_config mcc="310" mnc="260">
+    let file = fsread::open(path)?;
+
+    for event in EventReader::new(BufReader::new(file)) {
+        match event {
+            Ok(XmlEvent::StartElement { name, attributes, .. }) if name.local_name == "carrier_config" => {
+                let attr = |key: &str| attributes.iter().find(|a| a.name.local_name == key).map(|a| a.value.clone());
+
+                if let Some(id) = attr("carrier_id").or_else(|| attr("cid")) {
+                    carrier_ids.push(format!("carrierid {}", id));
+                } else if let (Some(mcc), Some(mnc)) = (attr("mcc"), attr("mnc")) {
+                    carrier_ids.push(format!("mccmnc {}{}", mcc, mnc));
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    Ok(())
+}
+
+fn carrier_id_from_file_name(file_name: &str) -> Option<String> {
+    // carrier_config_carrierid_1187_Verizon.xml / carrier_config_mccmnc_310260.xml
+    let stem = file_name.strip_suffix(".xml")?;
+
+    if let Some(rest) = stem.strip_prefix("carrier_config_carrierid_") {
+        return Some(match rest.split_once('_') {
+            Some((id, name)) => format!("carrierid {} ({})", id, name),
+            None => format!("carrierid {}", rest),
+        });
+    }
+    if let Some(rest) = stem.strip_prefix("carrier_config_mccmnc_") {
+        return Some(format!("mccmnc {}", rest));
+    }
+    None
+}
@@ -0,0 +1,189 @@
+// Driver categories: the one place their names are spelled, which part of
+// the driver scan fills each one, --category-map renames and the
+// --filter-source restriction.
+//
+// A new category is added here (a constant, an entry in its source's list)
+// and is then picked up by the attempted/not-scanned bookkeeping, the JSON
+// driver_categories table, --format summary-json and --sort source/file.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+use crate::fsread;
+
+pub const DEVICE_TREE_BINDINGS: &str = "Device Tree Bindings";
+pub const KERNEL_MODULES: &str = "Kernel Modules";
+pub const WIFI: &str = "WiFi Driver";
+pub const BLUETOOTH: &str = "Bluetooth Driver";
+pub const GPU: &str = "GPU/Platform";
+// android.hardware.* PRODUCT_PACKAGES
+pub const HAL: &str = "HAL (Hardware Abstraction Layer)";
+pub const AUDIO: &str = "Audio Driver";
+pub const CAMERA: &str = "Camera Driver";
+pub const PREBUILT_MODULES: &str = "Prebuilt Kernel Modules";
+
+// Categories each part of the driver scan can produce
+pub const DTS: [&str; 1] = [DEVICE_TREE_BINDINGS];
+pub const BOARD_CONFIG: [&str; 4] = [KERNEL_MODULES, WIFI, BLUETOOTH, GPU];
+pub const DEVICE_MK: [&str; 3] = [HAL, AUDIO, CAMERA];
+pub const PREBUILT: [&str; 1] = [PREBUILT_MODULES];
+
+pub fn all() -> Vec<&'static str> {
+    [&DTS[..], &BOARD_CONFIG, &DEVICE_MK, &PREBUILT].concat()
+}
+
+// Where list_device_drivers finds a category, and the file it reads (None
+// when entries come from several files)
+pub fn source(category: &str) -> (&'static str, Option<&'static str>) {
+    if DTS.contains(&category) {
+        ("dts", None)
+    } else if PREBUILT.contains(&category) {
+        ("prebuilt", None)
+    } else if DEVICE_MK.contains(&category) {
+        ("device_mk", Some("device.mk"))
+    } else {
+        ("board_config", Some("BoardConfig.mk"))
+    }
+}
+
+// --category-map: category -> name it's reported under. Several categories
+// mapped to one name are merged into it.
+#[derive(Debug, Clone, Default)]
+pub struct CategoryMap {
+    renames: BTreeMap<String, String>,
+    // --filter-source: the sources (as source() names them) whose
+    // categories are reported; empty for all
+    sources: Vec<&'static str>,
+}
+
+impl CategoryMap {
+    // One "Category = New Name" per line; blank lines and '#' comments are
+    // skipped. Every left-hand side must be a known category, so a typo
+    // fails instead of silently mapping nothing.
+    pub fn load(path: &Path) -> Result<CategoryMap, String> {
+        let content = fsread::read_to_string(path).map_err(|e| e.to_string())?;
+        let known = all();
+        let mut map = CategoryMap::default();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((from, to)) = line.split_once('=').map(|(from, to)| (from.trim(), to.trim())) else {
+                return Err(format!("line {}: expected 'Category = New Name'", index + 1));
+            };
+            if !known.contains(&from) {
+                return Err(format!("line {}: unknown category '{}' (known: {})", index + 1, from, known.join(", ")));
+            }
+            if to.is_empty() {
+                return Err(format!("line {}: no new name for '{}'", index + 1, from));
+            }
+            map.renames.insert(from.to_string(), to.to_string());
+        }
+        Ok(map)
+    }
+
+    pub fn filter_sources(&mut self, sources: Vec<&'static str>) {
+        self.sources = sources;
+    }
+
+    // Whether --filter-source keeps a category (by its built-in name)
+    pub fn includes(&self, category: &str) -> bool {
+        self.sources.is_empty() || self.sources.contains(&source(category).0)
+    }
+
+    // The name a category is reported under
+    pub fn name<'a>(&'a self, category: &'a str) -> &'a str {
+        self.renames.get(category).map_or(category, |name| name.as_str())
+    }
+
+    // The first known category reported under a name, for looking up its
+    // source; the name itself when nothing maps to it
+    pub fn original<'a>(&self, name: &'a str) -> &'a str {
+        all().into_iter().find(|category| self.name(category) == name).unwrap_or(name)
+    }
+
+    // The name a category is reported under, unless other categories are
+    // reported under it too
+    pub fn unmerged<'a>(&'a self, category: &'a str) -> Option<&'a str> {
+        let name = self.name(category);
+        (all().into_iter().filter(|other| self.name(other) == name).count() == 1).then_some(name)
+    }
+
+    // Every known category --filter-source keeps, under its reported name,
+    // merged ones once
+    pub fn known(&self) -> Vec<&str> {
+        let mut known: Vec<&str> = Vec::new();
+        for category in all().into_iter().filter(|category| self.includes(category)) {
+            let name = self.name(category);
+            if !known.contains(&name) {
+                known.push(name);
+            }
+        }
+        known
+    }
+
+    // Drops the categories --filter-source leaves out, then renames (and
+    // merges) the rest. Every category is looked up by its built-in name, so
+    // "A = B" and "B = A" swap the two instead of merging them; merged
+    // entries follow in category name order.
+    pub fn apply(
+        &self,
+        drivers: &mut HashMap<String, Vec<String>>,
+        attempted: &mut BTreeSet<String>,
+        truncated: &mut BTreeSet<String>,
+    ) {
+        let mut categories: Vec<(String, Vec<String>)> =
+            drivers.drain().filter(|(category, _)| self.includes(category)).collect();
+        categories.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (category, entries) in categories {
+            drivers.entry(self.name(&category).to_string()).or_default().extend(entries);
+        }
+        for categories in [attempted, truncated] {
+            let kept = categories.iter().filter(|category| self.includes(category));
+            *categories = kept.map(|category| self.name(category).to_string()).collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(renames: &[(&str, &str)]) -> CategoryMap {
+        let renames = renames.iter().map(|(from, to)| (from.to_string(), to.to_string())).collect();
+        CategoryMap { renames, ..Default::default() }
+    }
+
+    #[test]
+    fn swapped_categories_keep_their_entries_apart() {
+        let mut drivers = HashMap::from([
+            (AUDIO.to_string(), vec!["audio.primary.msmnile".to_string()]),
+            (CAMERA.to_string(), vec!["camera.msmnile".to_string()]),
+        ]);
+        let mut attempted = BTreeSet::from([AUDIO.to_string(), CAMERA.to_string()]);
+        let mut truncated = BTreeSet::from([AUDIO.to_string()]);
+        map(&[(AUDIO, CAMERA), (CAMERA, AUDIO)]).apply(&mut drivers, &mut attempted, &mut truncated);
+
+        assert_eq!(drivers[AUDIO], ["camera.msmnile"]);
+        assert_eq!(drivers[CAMERA], ["audio.primary.msmnile"]);
+        assert_eq!(attempted, BTreeSet::from([AUDIO.to_string(), CAMERA.to_string()]));
+        assert_eq!(truncated, BTreeSet::from([CAMERA.to_string()]));
+    }
+
+    #[test]
+    fn categories_mapped_to_one_name_are_merged() {
+        let mut drivers = HashMap::from([
+            (KERNEL_MODULES.to_string(), vec!["wlan.ko".to_string()]),
+            (PREBUILT_MODULES.to_string(), vec!["touch.ko".to_string()]),
+        ]);
+        let (mut attempted, mut truncated) = (BTreeSet::new(), BTreeSet::new());
+        map(&[(KERNEL_MODULES, "Modules"), (PREBUILT_MODULES, "Modules")]).apply(
+            &mut drivers,
+            &mut attempted,
+            &mut truncated,
+        );
+
+        assert_eq!(drivers.len(), 1);
+        assert_eq!(drivers["Modules"], ["wlan.ko", "touch.ko"]);
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use crate::fsread;
+use crate::makefile::{self, split_assignment, statements};
+use crate::warning::{Warning, WarningKind};
+
+// $(VAR)s that point at the device tree itself
+const TREE_VARIABLES: [&str; 2] = ["LOCAL_PATH", "DEVICE_PATH"];
+
+// Build system variables for partition roots, as used in destinations
+const PARTITION_VARIABLES: [(&str, &str); 7] = [
+    ("TARGET_COPY_OUT_SYSTEM", "system"),
+    ("TARGET_COPY_OUT_SYSTEM_EXT", "system_ext"),
+    ("TARGET_COPY_OUT_PRODUCT", "product"),
+    ("TARGET_COPY_OUT_VENDOR", "vendor"),
+    ("TARGET_COPY_OUT_VENDOR_DLKM", "vendor_dlkm"),
+    ("TARGET_COPY_OUT_ODM", "odm"),
+    ("TARGET_COPY_OUT_RECOVERY", "recovery"),
+];
+
+// PRODUCT_COPY_FILES src:dst pairs, i.e. which files land where on the device
+#[derive(Debug, Default)]
+pub struct CopiedFiles {
+    pub entries: Vec<CopyEntry>,
+}
+
+#[derive(Debug)]
+pub struct CopyEntry {
+    // Relative to the tree when it comes from $(LOCAL_PATH)/$(DEVICE_PATH),
+    // otherwise as written (a path in the platform source)
+    pub source: String,
+    pub destination: String,
+    pub in_tree: bool,
+    pub location: String,
+}
+
+impl CopiedFiles {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Destination partition (first path component) -> entries
+    pub fn by_partition(&self) -> BTreeMap<&str, Vec<&CopyEntry>> {
+        let mut partitions: BTreeMap<&str, Vec<&CopyEntry>> = BTreeMap::new();
+        for entry in &self.entries {
+            partitions.entry(entry.partition()).or_default().push(entry);
+        }
+        partitions
+    }
+}
+
+impl CopyEntry {
+    pub fn partition(&self) -> &str {
+        self.destination.split('/').find(|part| !part.is_empty()).unwrap_or("/")
+    }
+}
+
+pub fn scan_copy_files(tree_path: &Path, device_mk: Option<&Path>, warnings: &mut Vec<Warning>) -> CopiedFiles {
+    let mut copied = CopiedFiles::default();
+    let Some(device_mk) = device_mk else {
+        return copied;
+    };
+    let file_name = device_mk.file_name().map_or("device.mk".into(), |n| n.to_string_lossy());
+
+    // Unreadable device.mk is already reported by the driver scan
+    let Ok(content) = fsread::read_to_string(device_mk) else {
+        return copied;
+    };
+
+    let mut variables: HashMap<String, String> = HashMap::new();
+    for (line_number, statement) in statements(&content) {
+        let Some((name, value)) = split_assignment(&statement) else {
+            continue;
+        };
+
+        if name != "PRODUCT_COPY_FILES" {
+            variables.insert(name.to_string(), value.to_string());
+            continue;
+        }
+
+        for pair in value.split_whitespace() {
+            // $(call find-copy-subdir-files, ...) and friends can't be expanded here
+            if pair.starts_with("$(call") || pair.starts_with("$(foreach") {
+                continue;
+            }
+            let mut parts = pair.splitn(3, ':');
+            let (Some(raw_source), Some(raw_destination)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+
+            let (source, in_tree) = match tree_relative(raw_source) {
+                Some(relative) => (expand(relative, &variables), true),
+                None => (expand(raw_source, &variables), false),
+            };
+            let destination = expand(raw_destination, &variables);
+            let location = format!("{}:{}", file_name, line_number);
+            if in_tree && !tree_path.join(&source).is_file() {
+                warnings.push(Warning::new(
+                    WarningKind::MissingCopySource,
+                    &location,
+                    format!("PRODUCT_COPY_FILES source '{}' not found in the tree", source),
+                ));
+            }
+
+            copied.entries.push(CopyEntry {
+                source,
+                destination,
+                in_tree,
+                location,
+            });
+        }
+    }
+
+    copied
+}
+
+// "$(LOCAL_PATH)/configs/foo.xml" -> "configs/foo.xml"
+fn tree_relative(source: &str) -> Option<&str> {
+    TREE_VARIABLES.iter().find_map(|variable| {
+        source.strip_prefix(&format!("$({})", variable))
+            .or_else(|| source.strip_prefix(&format!("${{{}}}", variable)))
+            .map(|rest| rest.trim_start_matches('/'))
+    })
+}
+
+// Substitutes $(VAR)/${VAR} from the makefile's own assignments and the
+// partition variables; anything else is left as written
+fn expand(value: &str, variables: &HashMap<String, String>) -> String {
+    makefile::expand(value, &|name| {
+        partition(name).or_else(|| variables.get(name).map(|value| makefile::expand(value, &partition)))
+    })
+}
+
+// TARGET_COPY_OUT_VENDOR -> vendor
+fn partition(name: &str) -> Option<String> {
+    PARTITION_VARIABLES.iter().find(|(variable, _)| *variable == name).map(|(_, partition)| partition.to_string())
+}
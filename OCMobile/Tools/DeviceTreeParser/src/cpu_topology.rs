@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+const CPUSET_ROOT: &str = "/dev/cpuset";
+
+// Cpuset groups written by init scripts and the task profiles that move
+// tasks into them. Which cores each group may use gives away the SoC's
+// cluster layout: background work stays on the little cores, top-app gets
+// everything.
+#[derive(Debug, Default)]
+pub struct CpuTopology {
+    // Group name ("" for the root cpuset) -> group
+    pub groups: BTreeMap<String, CpusetGroup>,
+    pub profiles: Vec<TaskProfile>,
+    // Files that contributed writes or profiles
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct CpusetGroup {
+    // The last value written to <group>/cpus, as written ("0-3,6")
+    pub cpus: Option<String>,
+    pub cores: Vec<u32>,
+    // Task profiles that join this group
+    pub profiles: Vec<String>,
+    pub locations: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct TaskProfile {
+    pub name: String,
+    // Path of the cpuset the profile joins, when it joins one
+    pub cpuset: Option<String>,
+    pub file: String,
+}
+
+impl CpuTopology {
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty() && self.profiles.is_empty()
+    }
+
+    // Runs of consecutive cores that belong to exactly the same groups, each
+    // with those groups; on most SoCs these line up with the clusters
+    pub fn clusters(&self) -> Vec<(Vec<u32>, Vec<&str>)> {
+        let all: BTreeSet<u32> = self.groups.values().flat_map(|group| group.cores.iter().copied()).collect();
+        let mut clusters: Vec<(Vec<u32>, Vec<&str>)> = Vec::new();
+        for core in all {
+            let members: Vec<&str> = self
+                .groups
+                .iter()
+                .filter(|(name, group)| !name.is_empty() && group.cores.contains(&core))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            match clusters.last_mut() {
+                Some((cores, groups)) if *groups == members && cores.last() == Some(&(core - 1)) => cores.push(core),
+                _ => clusters.push((vec![core], members)),
+            }
+        }
+        clusters
+    }
+}
+
+pub fn scan_cpu_topology(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> CpuTopology {
+    // ueventd*.rc use a different syntax; init.*.sh scripts often do the
+    // same writes with echo
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| {
+            (name.ends_with(".rc") && !name.starts_with("ueventd"))
+                || name.ends_with(".sh")
+                || name == "task_profiles.json"
+        },
+        &mut files,
+    );
+    files.sort();
+
+    let mut topology = CpuTopology::default();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let content = match fsread::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) => {
+                warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+                continue;
+            }
+        };
+
+        let contributed = if relative.ends_with(".json") {
+            match parse_task_profiles(&content, &relative, &mut topology) {
+                Ok(contributed) => contributed,
+                Err(e) => {
+                    warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e));
+                    continue;
+                }
+            }
+        } else {
+            parse_cpuset_writes(&content, &relative, &mut topology)
+        };
+        if contributed {
+            topology.files.push(relative);
+        }
+    }
+
+    // Merge: record on each group which profiles put tasks there
+    for profile in &topology.profiles {
+        if let Some(cpuset) = &profile.cpuset {
+            let group = topology.groups.entry(cpuset.clone()).or_default();
+            if !group.profiles.contains(&profile.name) {
+                group.profiles.push(profile.name.clone());
+            }
+        }
+    }
+
+    topology
+}
+
+// "write /dev/cpuset/top-app/cpus 0-7" in .rc files,
+// "echo 0-7 > /dev/cpuset/top-app/cpus" in shell scripts
+fn parse_cpuset_writes(content: &str, file: &str, topology: &mut CpuTopology) -> bool {
+    let mut contributed = false;
+    for (index, line) in content.lines().enumerate() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let (target, value) = match tokens.as_slice() {
+            ["write", target, value, ..] => (*target, *value),
+            ["echo", value, ">", target, ..] => (*target, value.trim_matches('"')),
+            _ => continue,
+        };
+        let Some(group) = target.strip_prefix(CPUSET_ROOT).and_then(|rest| rest.strip_suffix("/cpus")) else {
+            continue;
+        };
+
+        let group = topology.groups.entry(group.trim_start_matches('/').to_string()).or_default();
+        group.cpus = Some(value.to_string());
+        group.cores = parse_cpu_list(value);
+        group.locations.push(format!("{}:{}", file, index + 1));
+        contributed = true;
+    }
+    contributed
+}
+
+// {"Profiles": [{"Name": "HighPerformance", "Actions": [{"Name": "JoinCgroup",
+//   "Params": {"Controller": "cpuset", "Path": "foreground"}}]}],
+//  "AggregateProfiles": [{"Name": "SCHED_SP_FOREGROUND", "Profiles": [...]}]}
+fn parse_task_profiles(content: &str, file: &str, topology: &mut CpuTopology) -> Result<bool, String> {
+    let parsed = json::parse(content).map_err(|e| format!("invalid JSON: {}", e))?;
+    let before = topology.profiles.len();
+
+    let mut cpusets: BTreeMap<String, Option<String>> = BTreeMap::new();
+    for profile in parsed["Profiles"].members() {
+        let Some(name) = profile["Name"].as_str() else {
+            continue;
+        };
+        let cpuset = profile["Actions"]
+            .members()
+            .find(|action| {
+                action["Name"].as_str() == Some("JoinCgroup") && action["Params"]["Controller"].as_str() == Some("cpuset")
+            })
+            .and_then(|action| action["Params"]["Path"].as_str())
+            .map(|path| path.to_string());
+        cpusets.insert(name.to_string(), cpuset.clone());
+        topology.profiles.push(TaskProfile { name: name.to_string(), cpuset, file: file.to_string() });
+    }
+
+    // An aggregate joins whatever cpuset one of its member profiles joins
+    for aggregate in parsed["AggregateProfiles"].members() {
+        let Some(name) = aggregate["Name"].as_str() else {
+            continue;
+        };
+        let cpuset = aggregate["Profiles"]
+            .members()
+            .filter_map(|member| member.as_str())
+            .find_map(|member| cpusets.get(member).cloned().flatten());
+        topology.profiles.push(TaskProfile { name: name.to_string(), cpuset, file: file.to_string() });
+    }
+
+    Ok(topology.profiles.len() > before)
+}
+
+// "0-3,6" -> [0, 1, 2, 3, 6]
+fn parse_cpu_list(list: &str) -> Vec<u32> {
+    let mut cores = Vec::new();
+    for part in list.split(',') {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.trim().parse::<u32>(), end.trim().parse::<u32>()) {
+                    cores.extend(start..=end);
+                }
+            }
+            None => cores.extend(part.trim().parse::<u32>().ok()),
+        }
+    }
+    cores.sort();
+    cores.dedup();
+    cores
+}
@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::makefile::{self, statements};
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Widevine/OEMCrypto libraries. liboemcrypto is the TEE-backed OEMCrypto an
+// L1 device ships; the rest are the Widevine HAL's own plugin libraries.
+const OEMCRYPTO_LIB: &str = "liboemcrypto.so";
+const WIDEVINE_LIBS: [&str; 3] = ["libwvhidl.so", "libwvaidl.so", "libwvdrmengine.so"];
+
+// Extensions of trusted app images: Qualcomm split/whole images, Trustonic
+// trustlets and generic .ta files
+const TRUSTED_APP_EXTENSIONS: [&str; 5] = ["mdt", "mbn", "tlbin", "ta", "elf"];
+
+// Widevine setup from the tree: DRM HAL packages, BOARD_WIDEVINE_* flags and
+// the OEMCrypto/Widevine blobs, whether shipped in the tree or listed in
+// proprietary-files*.txt. The security level a device gets is decided at
+// runtime (keybox provisioning included), so level() only says what the
+// tree points to and how directly.
+#[derive(Debug, Default)]
+pub struct DrmInfo {
+    // DRM PRODUCT_PACKAGES entries (android.hardware.drm*, Widevine and
+    // ClearKey services)
+    pub hal_packages: Vec<String>,
+    pub board_flags: Vec<Evidence>,
+    pub libraries: Vec<Evidence>,
+    // Widevine trusted app images (widevine.mdt, widevine.mbn, ...)
+    pub trusted_apps: Vec<Evidence>,
+}
+
+#[derive(Debug)]
+pub struct Evidence {
+    // Library or flag name, e.g. "liboemcrypto.so", "BOARD_WIDEVINE_OEMCRYPTO_LEVEL"
+    pub name: String,
+    // Flag value; the blob's path for libraries and trusted apps
+    pub value: String,
+    pub location: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidevineLevel {
+    // Set by BOARD_WIDEVINE_OEMCRYPTO_LEVEL
+    Configured(u8),
+    // Inferred from which blobs and packages are present
+    Likely(u8),
+    Unknown,
+}
+
+impl WidevineLevel {
+    pub fn describe(&self) -> String {
+        match self {
+            WidevineLevel::Configured(level) => format!("L{} (set in BoardConfig)", level),
+            WidevineLevel::Likely(level) => format!("L{} likely", level),
+            WidevineLevel::Unknown => "unknown".to_string(),
+        }
+    }
+
+    pub fn number(&self) -> Option<u8> {
+        match self {
+            WidevineLevel::Configured(level) | WidevineLevel::Likely(level) => Some(*level),
+            WidevineLevel::Unknown => None,
+        }
+    }
+}
+
+impl DrmInfo {
+    pub fn is_empty(&self) -> bool {
+        self.hal_packages.is_empty()
+            && self.board_flags.is_empty()
+            && self.libraries.is_empty()
+            && self.trusted_apps.is_empty()
+    }
+
+    pub fn widevine_hals(&self) -> Vec<&str> {
+        self.hal_packages
+            .iter()
+            .filter(|package| package.to_lowercase().contains("widevine"))
+            .map(|package| package.as_str())
+            .collect()
+    }
+
+    // Blobs shipped in the tree itself, as opposed to listed for extraction
+    pub fn files(&self) -> Vec<String> {
+        self.libraries
+            .iter()
+            .chain(&self.trusted_apps)
+            .filter(|blob| blob.location == blob.value)
+            .map(|blob| blob.value.clone())
+            .collect()
+    }
+
+    pub fn has_oemcrypto(&self) -> bool {
+        self.libraries.iter().any(|library| library.name == OEMCRYPTO_LIB)
+    }
+
+    // BOARD_WIDEVINE_OEMCRYPTO_LEVEL when set; else L1 when TEE-backed
+    // OEMCrypto (liboemcrypto or the Widevine trusted app) is there, L3 when
+    // there's Widevine without it
+    pub fn level(&self) -> WidevineLevel {
+        let configured = self
+            .board_flags
+            .iter()
+            .rev()
+            .find(|flag| flag.name == "BOARD_WIDEVINE_OEMCRYPTO_LEVEL")
+            .and_then(|flag| flag.value.parse().ok());
+        if let Some(level) = configured {
+            WidevineLevel::Configured(level)
+        } else if self.has_oemcrypto() || !self.trusted_apps.is_empty() {
+            WidevineLevel::Likely(1)
+        } else if !self.widevine_hals().is_empty() || !self.libraries.is_empty() {
+            WidevineLevel::Likely(3)
+        } else {
+            WidevineLevel::Unknown
+        }
+    }
+
+    // Why level() says what it says
+    pub fn level_reason(&self) -> String {
+        match self.level() {
+            WidevineLevel::Configured(_) => "BOARD_WIDEVINE_OEMCRYPTO_LEVEL is set".to_string(),
+            WidevineLevel::Likely(1) => {
+                let mut found = Vec::new();
+                if self.has_oemcrypto() {
+                    found.push(OEMCRYPTO_LIB);
+                }
+                if !self.trusted_apps.is_empty() {
+                    found.push("the Widevine trusted app");
+                }
+                format!("{} present; keybox provisioning can't be checked from the tree", found.join(" and "))
+            }
+            WidevineLevel::Likely(_) => {
+                format!("Widevine without {} or a trusted app, so software-only", OEMCRYPTO_LIB)
+            }
+            WidevineLevel::Unknown => "no Widevine HAL or blobs found".to_string(),
+        }
+    }
+}
+
+pub fn scan_drm(
+    tree_path: &Path,
+    device_mk: Option<&Path>,
+    board_config: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> DrmInfo {
+    let mut drm = DrmInfo::default();
+
+    // Unreadable makefiles are already reported by the driver scan
+    for makefile in [device_mk, board_config].into_iter().flatten() {
+        if !walk_options.includes_file(makefile) {
+            continue;
+        }
+        let Ok(content) = fsread::read_to_string(makefile) else {
+            continue;
+        };
+        let file_name = makefile.file_name().unwrap_or_default().to_string_lossy().to_string();
+        for (line, statement) in statements(&content) {
+            let location = format!("{}:{}", file_name, line);
+            let Some(makefile::Assignment { name, value, .. }) = makefile::parse_assignment(&statement) else {
+                continue;
+            };
+            if name.starts_with("BOARD_WIDEVINE_") {
+                drm.board_flags.push(Evidence {
+                    name: name.to_string(),
+                    value: value.trim().to_string(),
+                    location,
+                });
+            } else if name == "PRODUCT_PACKAGES" {
+                for package in value.split_whitespace() {
+                    let lower = package.to_lowercase();
+                    let is_drm = ["hardware.drm", "widevine", "clearkey"].iter().any(|k| lower.contains(k));
+                    if is_drm && !drm.hal_packages.iter().any(|p| p == package) {
+                        drm.hal_packages.push(package.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| is_library(name) || is_trusted_app(name) || is_proprietary_list(name),
+        &mut files,
+    );
+    files.sort();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if is_proprietary_list(&name) {
+            match fsread::read_to_string(&file) {
+                Ok(content) => parse_proprietary_list(&content, &relative, &mut drm),
+                Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+            }
+        } else {
+            add_blob(&mut drm, &name, &relative, &relative);
+        }
+    }
+
+    drm
+}
+
+// "vendor/lib64/liboemcrypto.so", "-vendor/lib/libwvhidl.so|<sha1>" or
+// "src:dst" lines; '#' comments and [sections] are skipped
+fn parse_proprietary_list(content: &str, file: &str, drm: &mut DrmInfo) {
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            continue;
+        }
+        let path = line.trim_start_matches('-').split(['|', ';']).next().unwrap_or_default();
+        let path = path.rsplit(':').next().unwrap_or(path);
+        let name = path.rsplit('/').next().unwrap_or(path);
+        if is_library(name) || is_trusted_app(name) {
+            add_blob(drm, name, path, &format!("{}:{}", file, index + 1));
+        }
+    }
+}
+
+fn add_blob(drm: &mut DrmInfo, name: &str, path: &str, location: &str) {
+    let evidence = Evidence { name: name.to_string(), value: path.to_string(), location: location.to_string() };
+    if is_library(name) {
+        drm.libraries.push(evidence);
+    } else {
+        drm.trusted_apps.push(evidence);
+    }
+}
+
+fn is_library(name: &str) -> bool {
+    name == OEMCRYPTO_LIB || WIDEVINE_LIBS.contains(&name)
+}
+
+// widevine.mdt/.b00-.b07/.mbn and the like
+fn is_trusted_app(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    let Some((stem, extension)) = lower.rsplit_once('.') else {
+        return false;
+    };
+    let split_image = extension.len() == 3
+        && extension.starts_with('b')
+        && extension[1..].chars().all(|c| c.is_ascii_digit());
+    stem.contains("widevine") && (TRUSTED_APP_EXTENSIONS.contains(&extension) || split_image)
+}
+
+fn is_proprietary_list(name: &str) -> bool {
+    name.starts_with("proprietary-files") && name.ends_with(".txt")
+}
@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::makefile;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Compiled device tree blobs and overlays shipped in the tree, plus the
+// mkdtboimg configs and BoardConfig.mk variables that assemble dtbo.img
+#[derive(Debug, Default)]
+pub struct DtbOverlays {
+    // .dtb/.dtbo files and dtbo.img, relative to the tree root
+    pub prebuilts: Vec<String>,
+    pub config_files: Vec<String>,
+    pub overlays: Vec<Overlay>,
+    // BOARD_*DTB* variables, e.g. BOARD_KERNEL_SEPARATED_DTBO -> true
+    pub board_vars: BTreeMap<String, String>,
+    pub build_mode: Option<DtboBuildMode>,
+}
+
+#[derive(Debug)]
+pub struct Overlay {
+    pub file: String,
+    // Position in dtbo.img, i.e. the order of the config entries
+    pub index: usize,
+    pub id: Option<String>,
+    pub rev: Option<String>,
+    pub location: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DtboBuildMode {
+    // BOARD_KERNEL_SEPARATED_DTBO: the kernel build produces dtbo.img
+    Source,
+    // BOARD_PREBUILT_DTBOIMAGE or a dtbo.img checked into the tree
+    Prebuilt,
+}
+
+impl DtboBuildMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DtboBuildMode::Source => "source",
+            DtboBuildMode::Prebuilt => "prebuilt",
+        }
+    }
+}
+
+impl DtbOverlays {
+    pub fn is_empty(&self) -> bool {
+        self.prebuilts.is_empty() && self.overlays.is_empty() && self.board_vars.is_empty()
+    }
+}
+
+pub fn scan_dtb_overlays(
+    tree_path: &Path,
+    board_config: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> DtbOverlays {
+    let mut found = DtbOverlays::default();
+
+    if let Some(board_config) = board_config
+        && walk_options.includes_file(board_config)
+        && let Ok(content) = fsread::read_to_string(board_config)
+    {
+        for (_, statement) in makefile::statements(&content) {
+            if let Some((name, value)) = makefile::split_assignment(&statement)
+                && name.starts_with("BOARD_")
+                && name.contains("DTB")
+                && !value.is_empty()
+            {
+                found.board_vars.insert(name.to_string(), value.to_string());
+            }
+        }
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| name.ends_with(".dtb") || name.ends_with(".dtbo") || name == "dtbo.img" || name.ends_with(".cfg"),
+        &mut files,
+    );
+    files.sort();
+
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        if !relative.ends_with(".cfg") {
+            found.prebuilts.push(relative);
+            continue;
+        }
+
+        match fsread::read_to_string(&file) {
+            Ok(content) => {
+                // Other .cfg files (wifi, audio, ...) simply list no overlays
+                let before = found.overlays.len();
+                parse_dtboimg_cfg(&content, &relative, &mut found.overlays);
+                if found.overlays.len() > before {
+                    found.config_files.push(relative);
+                }
+            }
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+        }
+    }
+
+    let var_is = |name: &str, value: &str| found.board_vars.get(name).is_some_and(|v| v == value);
+    found.build_mode = if found.board_vars.contains_key("BOARD_PREBUILT_DTBOIMAGE") {
+        Some(DtboBuildMode::Prebuilt)
+    } else if var_is("BOARD_KERNEL_SEPARATED_DTBO", "true") {
+        Some(DtboBuildMode::Source)
+    } else if found.prebuilts.iter().any(|p| p.ends_with("dtbo.img")) {
+        Some(DtboBuildMode::Prebuilt)
+    } else {
+        None
+    };
+
+    found
+}
+
+fn parse_dtboimg_cfg(content: &str, file: &str, overlays: &mut Vec<Overlay>) {
+    // mkdtboimg.py cfg_create format:
+    //   <global options, indented>
+    //   board1v1.dtbo [id=0x1 rev=0x0]
+    //     id=0x2
+    //     custom0=0xabc
+    // Entries start at column 0; indented lines set options for the entry
+    // above them (or global ones before the first entry).
+    let first = overlays.len();
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let indented = line.starts_with([' ', '\t']);
+        let mut tokens = trimmed.split_whitespace();
+        let options: Vec<&str> = if indented {
+            tokens.collect()
+        } else {
+            let entry = tokens.next().unwrap_or("");
+            if !entry.ends_with(".dtb") && !entry.ends_with(".dtbo") {
+                // Not a dtbo config after all
+                overlays.truncate(first);
+                return;
+            }
+            overlays.push(Overlay {
+                file: entry.to_string(),
+                index: overlays.len() - first,
+                id: None,
+                rev: None,
+                location: format!("{}:{}", file, index + 1),
+            });
+            tokens.collect()
+        };
+
+        if let Some(overlay) = overlays[first..].last_mut() {
+            for option in options {
+                match option.split_once('=') {
+                    Some(("id", value)) => overlay.id = Some(value.to_string()),
+                    Some(("rev", value)) => overlay.rev = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
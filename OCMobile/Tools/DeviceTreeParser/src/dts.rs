@@ -0,0 +1,796 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+use crate::fsread;
+use crate::walk::{self, EntryKind, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+#[derive(Debug, Default)]
+pub struct DtsSources {
+    // Relative path (from the tree root) of every .dts/.dtsi found
+    pub files: Vec<String>,
+    // File -> files it includes; unresolved includes keep their raw name
+    pub includes: BTreeMap<String, Vec<String>>,
+    // Every node across all sources, keyed by full path ("/soc/dsi@ae94000").
+    // Nodes reopened in several files (or through &label references) are
+    // merged, later definitions overriding earlier properties.
+    pub nodes: BTreeMap<String, DtsNode>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct DtsNode {
+    pub path: String,
+    pub labels: Vec<String>,
+    // Property name -> raw value with whitespace collapsed, e.g. "<1080>".
+    // Boolean properties have an empty value.
+    pub properties: BTreeMap<String, String>,
+    // Property name -> "file:line" of the definition that won
+    pub property_locations: BTreeMap<String, String>,
+    // "file:line" of every block that contributed to this node
+    pub locations: Vec<String>,
+}
+
+// One /aliases property: serial0 = &uart2; or serial0 = "/soc/serial@a90000";
+#[derive(Debug)]
+pub struct Alias<'a> {
+    pub name: &'a str,
+    // The value as written
+    pub value: &'a str,
+    // Path of the node it names, when that node is in the sources
+    pub target: Option<&'a str>,
+    pub location: Option<&'a str>,
+}
+
+impl DtsNode {
+    // Last path component, e.g. "qcom,mdss_dsi_panel@0"
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or("/")
+    }
+
+    // First string of a string(-list) property: "a", "b" -> a
+    pub fn string(&self, property: &str) -> Option<&str> {
+        let value = self.properties.get(property)?;
+        let rest = value.strip_prefix('"')?;
+        rest.split('"').next()
+    }
+
+    // Every string of a string-list property
+    pub fn strings(&self, property: &str) -> Vec<&str> {
+        match self.properties.get(property) {
+            Some(value) => value.split('"').skip(1).step_by(2).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // Numeric cells of a <...> property; phandles and macros are skipped
+    pub fn cells(&self, property: &str) -> Vec<u64> {
+        let value = match self.properties.get(property) {
+            Some(value) => value,
+            None => return Vec::new(),
+        };
+
+        value
+            .split(['<', '>', ' ', ','])
+            .filter_map(|cell| match cell.strip_prefix("0x").or_else(|| cell.strip_prefix("0X")) {
+                Some(hex) => u64::from_str_radix(hex, 16).ok(),
+                None => cell.parse().ok(),
+            })
+            .collect()
+    }
+
+    // Number of cells in a <...> property, counting macros, phandles and
+    // parenthesized expressions as one cell each: <0 (0x100 + 4) &gpio> -> 3
+    pub fn cell_count(&self, property: &str) -> Option<usize> {
+        let value = self.properties.get(property)?;
+        let mut count = 0;
+        let mut depth = 0;
+        let mut in_cell = false;
+        let mut in_brackets = false;
+
+        for c in value.chars() {
+            match c {
+                '<' if depth == 0 => in_brackets = true,
+                '>' if depth == 0 => {
+                    in_brackets = false;
+                    in_cell = false;
+                }
+                _ if !in_brackets => {}
+                '(' => {
+                    if depth == 0 && !in_cell {
+                        count += 1;
+                        in_cell = true;
+                    }
+                    depth += 1;
+                }
+                ')' => depth -= 1,
+                c if c.is_whitespace() && depth == 0 => in_cell = false,
+                _ => {
+                    if !in_cell {
+                        count += 1;
+                        in_cell = true;
+                    }
+                }
+            }
+        }
+
+        Some(count)
+    }
+
+    // Labels referenced by a <...> property: <&pm8150_l5 &gpio 5> -> [pm8150_l5, gpio].
+    // Path references keep their braces: <&{/soc/foo}> -> [{/soc/foo}]
+    pub fn phandles(&self, property: &str) -> Vec<&str> {
+        match self.properties.get(property) {
+            Some(value) => value
+                .split(['<', '>', ' ', ','])
+                .filter_map(|cell| cell.strip_prefix('&'))
+                .filter(|label| !label.is_empty())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn is_compatible_with(&self, pattern: &str) -> bool {
+        self.strings("compatible").iter().any(|c| c.contains(pattern))
+    }
+
+    // A property as it reads in the source: the text of a single string
+    // ("console=ttyMSM0" -> console=ttyMSM0), anything else raw
+    pub fn display_value(&self, property: &str) -> Option<&str> {
+        let value = self.properties.get(property)?;
+        match value.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+            Some(text) if !text.contains('"') => Some(text),
+            _ => Some(value),
+        }
+    }
+}
+
+impl DtsSources {
+    // The node a phandle reference (as returned by DtsNode::phandles) points at
+    pub fn resolve_phandle(&self, reference: &str) -> Option<&DtsNode> {
+        if let Some(path) = reference.strip_prefix('{').and_then(|r| r.strip_suffix('}')) {
+            return self.nodes.get(path);
+        }
+        self.nodes.values().find(|node| node.labels.iter().any(|label| label == reference))
+    }
+
+    // The node a path-valued property names: "/soc/serial@a90000", an alias
+    // ("serial0", or "serial0/child"), or a &label / &{/path} reference
+    pub fn resolve_path(&self, path: &str) -> Option<&DtsNode> {
+        let path = path.trim().trim_matches('"');
+        if let Some(reference) = path.strip_prefix('&') {
+            return self.resolve_phandle(reference);
+        }
+        if path.starts_with('/') {
+            return self.nodes.get(path);
+        }
+        let (alias, rest) = path.split_once('/').map_or((path, ""), |(alias, rest)| (alias, rest));
+        // An alias is itself a path or a reference, never another alias
+        let value = self.nodes.get("/aliases")?.properties.get(alias)?.trim_matches('"');
+        if !value.starts_with(['/', '&']) {
+            return None;
+        }
+        let target = self.resolve_path(value)?;
+        if rest.is_empty() { Some(target) } else { self.nodes.get(&format!("{}/{}", target.path, rest)) }
+    }
+
+    // /aliases entries, each resolved to the node it names
+    pub fn aliases(&self) -> Vec<Alias<'_>> {
+        let Some(node) = self.nodes.get("/aliases") else {
+            return Vec::new();
+        };
+        node.properties
+            .iter()
+            .map(|(name, value)| Alias {
+                name,
+                value,
+                target: self.resolve_path(value).map(|target| target.path.as_str()),
+                location: node.property_locations.get(name).map(String::as_str),
+            })
+            .collect()
+    }
+
+    pub fn chosen(&self) -> Option<&DtsNode> {
+        self.nodes.get("/chosen")
+    }
+
+    // /chosen's stdout-path split into the console node's path (when it
+    // resolves) and the options after ':' ("serial0:115200n8" -> 115200n8)
+    pub fn stdout_path(&self) -> Option<(Option<&str>, Option<&str>)> {
+        let value = self.chosen()?.display_value("stdout-path")?;
+        let (path, options) = match value.split_once(':') {
+            Some((path, options)) => (path, Some(options)),
+            None => (value, None),
+        };
+        Some((self.resolve_path(path).map(|node| node.path.as_str()), options))
+    }
+
+    // Nodes below `node` (children, grandchildren, ...)
+    pub fn descendants<'a>(&'a self, node: &DtsNode) -> impl Iterator<Item = &'a DtsNode> + 'a {
+        let prefix = if node.path == "/" { "/".to_string() } else { format!("{}/", node.path) };
+        self.nodes
+            .range(prefix.clone()..)
+            .take_while(move |(path, _)| path.starts_with(&prefix))
+            .filter(|(path, _)| path.as_str() != "/")
+            .map(|(_, node)| node)
+    }
+}
+
+impl DtsSources {
+    // .dtsi files that no other source includes (potential dead files)
+    pub fn unused_includes(&self) -> Vec<&String> {
+        let included: BTreeSet<&String> = self.includes.values().flatten().collect();
+
+        self.files
+            .iter()
+            .filter(|f| f.ends_with(".dtsi") && !included.contains(f))
+            .collect()
+    }
+
+    // Every include chain starting at a file nobody includes, e.g.
+    // [foo.dts, bar.dtsi, baz.dtsi]
+    pub fn include_chains(&self) -> Vec<Vec<String>> {
+        let included: BTreeSet<&String> = self.includes.values().flatten().collect();
+        let mut chains = Vec::new();
+
+        for file in &self.files {
+            if included.contains(file) || !self.includes.contains_key(file) {
+                continue;
+            }
+            let mut chain = vec![file.clone()];
+            self.walk_chain(&mut chain, &mut chains);
+        }
+
+        chains
+    }
+
+    fn walk_chain(&self, chain: &mut Vec<String>, chains: &mut Vec<Vec<String>>) {
+        let current = chain.last().unwrap().clone();
+
+        match self.includes.get(&current) {
+            Some(children) if !children.is_empty() => {
+                for child in children {
+                    if chain.contains(child) {
+                        // Include cycle, stop here
+                        let mut cyclic = chain.clone();
+                        cyclic.push(format!("{} (cycle)", child));
+                        chains.push(cyclic);
+                        continue;
+                    }
+                    chain.push(child.clone());
+                    self.walk_chain(chain, chains);
+                    chain.pop();
+                }
+            }
+            _ => chains.push(chain.clone()),
+        }
+    }
+}
+
+pub fn collect_sources(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> DtsSources {
+    let mut paths = Vec::new();
+    find_dts_files(tree_path, &mut paths, walk_options, 0);
+    build_sources(tree_path, paths, warnings)
+}
+
+// A single .dts/.dtsi plus everything it includes (resolved relative to the
+// including file), with paths relative to the file's own directory
+pub fn collect_file(file_path: &Path, warnings: &mut Vec<Warning>) -> DtsSources {
+    let file_path = normalize_path(file_path);
+    let base = file_path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let mut paths = vec![file_path];
+    let mut next = 0;
+    while next < paths.len() {
+        let current = paths[next].clone();
+        next += 1;
+
+        let content = match fsread::read_to_string(&current) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        for name in parse_includes(&content) {
+            let candidate = normalize_path(&current.parent().unwrap_or(Path::new("")).join(name));
+            if candidate.is_file() && !paths.contains(&candidate) {
+                paths.push(candidate);
+            }
+        }
+    }
+
+    build_sources(&base, paths, warnings)
+}
+
+// --validate-dts: every reg must be a whole number of (address, size)
+// tuples as declared by the parent's #address-cells/#size-cells (2 and 1
+// when the parent doesn't say)
+pub fn validate_cells(sources: &DtsSources) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    for node in sources.nodes.values() {
+        let Some(reg_cells) = node.cell_count("reg") else {
+            continue;
+        };
+        let parent_path = match node.path.rsplit_once('/') {
+            Some(("", _)) => "/",
+            Some((parent, _)) => parent,
+            None => continue,
+        };
+        if parent_path == node.path {
+            continue;
+        }
+        let parent = sources.nodes.get(parent_path);
+        let declared = |property: &str, default: u64| {
+            parent.and_then(|p| p.cells(property).first().copied()).unwrap_or(default)
+        };
+        let address_cells = declared("#address-cells", 2);
+        let size_cells = declared("#size-cells", 1);
+        let tuple = (address_cells + size_cells) as usize;
+
+        if tuple == 0 || (reg_cells > 0 && reg_cells % tuple == 0) {
+            continue;
+        }
+
+        let location = node.property_locations.get("reg").cloned().unwrap_or_else(|| node.path.clone());
+        warnings.push(Warning::new(
+            WarningKind::CellMismatch,
+            location,
+            format!(
+                "{}: reg has {} cell(s), expected a multiple of {} (#address-cells = <{}>, #size-cells = <{}> in {})",
+                node.path, reg_cells, tuple, address_cells, size_cells, parent_path
+            ),
+        ));
+    }
+
+    warnings
+}
+
+fn build_sources(tree_path: &Path, mut paths: Vec<PathBuf>, warnings: &mut Vec<Warning>) -> DtsSources {
+    paths.sort();
+
+    let files: Vec<String> = paths.iter().map(|p| relative_path(tree_path, p)).collect();
+    let mut includes = BTreeMap::new();
+    let mut parsed = BTreeMap::new();
+
+    for (path, file) in paths.iter().zip(&files) {
+        let content = match fsread::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                warnings.push(Warning::new(WarningKind::UnreadableFile, file, e.to_string()));
+                continue;
+            }
+        };
+
+        let resolved: Vec<String> = parse_includes(&content)
+            .into_iter()
+            .map(|name| resolve_include(tree_path, path, &name, &files))
+            .collect();
+
+        for include in &resolved {
+            if let Some(name) = include.strip_suffix(" (unresolved)") {
+                warnings.push(Warning::new(
+                    WarningKind::UnresolvedInclude,
+                    file,
+                    format!("cannot find included file '{}'", name),
+                ));
+            }
+        }
+
+        if !resolved.is_empty() {
+            includes.insert(file.clone(), resolved);
+        }
+        parsed.insert(file.clone(), parse_nodes(&content, file, warnings));
+    }
+
+    let mut sources = DtsSources { files, includes, nodes: BTreeMap::new() };
+
+    // Merge in include order so a board .dts overrides the SoC .dtsi it
+    // pulls in, the same way dtc would see them
+    let mut raw_nodes = Vec::new();
+    for file in sources.include_order() {
+        if let Some(nodes) = parsed.remove(&file) {
+            raw_nodes.extend(nodes);
+        }
+    }
+    sources.nodes = merge_nodes(raw_nodes);
+
+    sources
+}
+
+impl DtsSources {
+    // Files ordered so that each comes after everything it includes
+    fn include_order(&self) -> Vec<String> {
+        let mut order = Vec::new();
+        let mut visited = BTreeSet::new();
+        for file in &self.files {
+            self.visit_includes(file, &mut visited, &mut order);
+        }
+        order
+    }
+
+    fn visit_includes(&self, file: &String, visited: &mut BTreeSet<String>, order: &mut Vec<String>) {
+        if !visited.insert(file.clone()) {
+            return;
+        }
+        for child in self.includes.get(file).into_iter().flatten() {
+            if self.files.contains(child) {
+                self.visit_includes(child, visited, order);
+            }
+        }
+        order.push(file.clone());
+    }
+}
+
+// Parses the node blocks of one source into a flat list. Blocks opened
+// through a reference (&label { ... }) get a path starting with "&label"
+// which merge_nodes resolves once every file's labels are known.
+fn parse_nodes(content: &str, file: &str, warnings: &mut Vec<Warning>) -> Vec<DtsNode> {
+    let content = strip_comments_and_directives(content);
+    let mut nodes: Vec<DtsNode> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut buffer = String::new();
+    let mut buffer_line = 0;
+    let mut line = 1;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut in_path_reference = false;
+
+    for c in content.chars() {
+        if c == '\n' {
+            line += 1;
+        }
+
+        if in_string {
+            buffer.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                buffer.push(c);
+            }
+            // &{/full/path} { ... }
+            '{' if buffer.trim_end().ends_with('&') => {
+                in_path_reference = true;
+                buffer.push(c);
+            }
+            '}' if in_path_reference => {
+                in_path_reference = false;
+                buffer.push(c);
+            }
+            '{' => {
+                let (labels, name) = split_labels(buffer.trim());
+                let path = match stack.last() {
+                    Some(&parent) => child_path(&nodes[parent].path, name),
+                    None if name.starts_with('&') => name.to_string(),
+                    None => child_path("/", name.trim_start_matches('/')),
+                };
+                nodes.push(DtsNode {
+                    path,
+                    labels,
+                    locations: vec![format!("{}:{}", file, buffer_line)],
+                    ..Default::default()
+                });
+                stack.push(nodes.len() - 1);
+                buffer.clear();
+            }
+            '}' => {
+                if stack.pop().is_none() {
+                    warnings.push(Warning::new(
+                        WarningKind::MalformedDts,
+                        format!("{}:{}", file, line),
+                        "'}' without a matching '{'",
+                    ));
+                }
+                buffer.clear();
+            }
+            ';' => {
+                let statement = buffer.trim();
+                // Directives like /dts-v1/ and /delete-node/ aren't properties
+                if !statement.is_empty()
+                    && !statement.starts_with('/')
+                    && let Some(&current) = stack.last()
+                {
+                    let (name, value) = statement.split_once('=').unwrap_or((statement, ""));
+                    let (_, name) = split_labels(name.trim());
+                    let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+                    nodes[current].properties.insert(name.to_string(), value);
+                    nodes[current]
+                        .property_locations
+                        .insert(name.to_string(), format!("{}:{}", file, buffer_line));
+                }
+                buffer.clear();
+            }
+            _ => {
+                if buffer.trim().is_empty() {
+                    buffer_line = line;
+                }
+                buffer.push(c);
+            }
+        }
+    }
+
+    if in_string {
+        warnings.push(Warning::new(WarningKind::MalformedDts, file, "unterminated string"));
+    }
+    if let Some(&unclosed) = stack.first() {
+        warnings.push(Warning::new(
+            WarningKind::MalformedDts,
+            nodes[unclosed].locations[0].clone(),
+            format!("block for '{}' is never closed", nodes[unclosed].path),
+        ));
+    }
+
+    nodes
+}
+
+// "lbl1: lbl2: name" -> ([lbl1, lbl2], name). Also drops keywords such as
+// /omit-if-no-ref/ in front of a node name.
+fn split_labels(header: &str) -> (Vec<String>, &str) {
+    let mut labels = Vec::new();
+    let mut rest = header;
+
+    loop {
+        rest = rest.trim_start();
+        if rest.starts_with('/')
+            && rest.len() > 1
+            && let Some(end) = rest[1..].find('/')
+        {
+            rest = &rest[end + 2..];
+            continue;
+        }
+        // Nothing before a reference is a label
+        if rest.starts_with('&') {
+            break;
+        }
+        match rest.split_once(':') {
+            Some((label, remainder)) if !label.is_empty() && !label.contains(char::is_whitespace) => {
+                labels.push(label.to_string());
+                rest = remainder;
+            }
+            _ => break,
+        }
+    }
+
+    (labels, rest.trim())
+}
+
+fn child_path(parent: &str, name: &str) -> String {
+    if name.is_empty() {
+        parent.to_string()
+    } else if parent == "/" {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+// Comments and preprocessor lines are replaced with blank space so line
+// numbers stay correct. Properties like #address-cells are kept.
+fn strip_comments_and_directives(content: &str) -> String {
+    const DIRECTIVES: [&str; 10] =
+        ["#include", "#define", "#undef", "#if", "#ifdef", "#ifndef", "#elif", "#else", "#endif", "#pragma"];
+
+    let mut without_comments = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            if c == '"' {
+                in_string = false;
+            }
+            without_comments.push(c);
+        } else if c == '"' {
+            in_string = true;
+            without_comments.push(c);
+        } else if c == '/' && chars.peek() == Some(&'/') {
+            for next in chars.by_ref() {
+                if next == '\n' {
+                    without_comments.push('\n');
+                    break;
+                }
+            }
+        } else if c == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut previous = ' ';
+            for next in chars.by_ref() {
+                if next == '\n' {
+                    without_comments.push('\n');
+                }
+                if previous == '*' && next == '/' {
+                    break;
+                }
+                previous = next;
+            }
+        } else {
+            without_comments.push(c);
+        }
+    }
+
+    let mut stripped = String::with_capacity(without_comments.len());
+    let mut continued = false;
+    for line in without_comments.lines() {
+        let trimmed = line.trim_start();
+        let is_directive = continued
+            || DIRECTIVES
+                .iter()
+                .any(|d| trimmed.strip_prefix(d).is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace)));
+
+        if is_directive {
+            continued = line.trim_end().ends_with('\\');
+        } else {
+            stripped.push_str(line);
+        }
+        stripped.push('\n');
+    }
+
+    stripped
+}
+
+// Resolves &label paths and merges blocks describing the same node
+fn merge_nodes(raw_nodes: Vec<DtsNode>) -> BTreeMap<String, DtsNode> {
+    let mut label_paths: BTreeMap<String, String> = BTreeMap::new();
+    for node in &raw_nodes {
+        for label in &node.labels {
+            label_paths.insert(label.clone(), node.path.clone());
+        }
+    }
+
+    let mut merged: BTreeMap<String, DtsNode> = BTreeMap::new();
+    for node in raw_nodes {
+        let path = resolve_reference(&node.path, &label_paths);
+        let entry = merged.entry(path.clone()).or_insert_with(|| DtsNode {
+            path,
+            ..Default::default()
+        });
+
+        for label in node.labels {
+            if !entry.labels.contains(&label) {
+                entry.labels.push(label);
+            }
+        }
+        entry.properties.extend(node.properties);
+        entry.property_locations.extend(node.property_locations);
+        entry.locations.extend(node.locations);
+    }
+
+    merged
+}
+
+fn resolve_reference(path: &str, label_paths: &BTreeMap<String, String>) -> String {
+    let mut path = path.to_string();
+
+    // Labels can point into other reference blocks; the bound only guards
+    // against labels that refer to each other
+    for _ in 0..16 {
+        let rest = match path.strip_prefix('&') {
+            Some(rest) => rest,
+            None => return path,
+        };
+
+        // &{/soc/dsi@ae94000}/child
+        if let Some(full) = rest.strip_prefix('{') {
+            return match full.split_once('}') {
+                Some((target, child)) => format!("{}{}", target, child),
+                None => full.to_string(),
+            };
+        }
+
+        let (label, child) = match rest.find('/') {
+            Some(pos) => rest.split_at(pos),
+            None => (rest, ""),
+        };
+        match label_paths.get(label) {
+            Some(target) if target != &path => path = format!("{}{}", target, child),
+            _ => return path,
+        }
+    }
+
+    path
+}
+
+fn find_dts_files(path: &Path, found: &mut Vec<PathBuf>, walk_options: &WalkOptions, depth: usize) {
+    if !walk_options.within_depth(depth) {
+        return;
+    }
+
+    for (entry_path, kind) in walk::read_dir(path, walk_options) {
+        match kind {
+            EntryKind::File => {
+                let file_name = entry_path.file_name().unwrap().to_string_lossy();
+                if file_name.ends_with(".dts") || file_name.ends_with(".dtsi") {
+                    found.push(entry_path);
+                }
+            }
+            EntryKind::Dir => find_dts_files(&entry_path, found, walk_options, depth + 1),
+            EntryKind::Skip => {}
+        }
+    }
+}
+
+fn parse_includes(content: &str) -> Vec<String> {
+    // Handles both the C preprocessor form (#include "foo.dtsi") and the
+    // dtc form (/include/ "foo.dtsi"). Header includes (.h) are skipped.
+    let mut includes = Vec::new();
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let rest = if let Some(rest) = trimmed.strip_prefix("#include") {
+            rest
+        } else if let Some(rest) = trimmed.strip_prefix("/include/") {
+            rest
+        } else {
+            continue;
+        };
+
+        let rest = rest.trim();
+        let name = match rest.chars().next() {
+            Some('"') => rest[1..].split('"').next(),
+            Some('<') => rest[1..].split('>').next(),
+            _ => None,
+        };
+
+        if let Some(name) = name
+            && (name.ends_with(".dts") || name.ends_with(".dtsi"))
+        {
+            includes.push(name.to_string());
+        }
+    }
+
+    includes
+}
+
+fn resolve_include(tree_path: &Path, including_file: &Path, name: &str, files: &[String]) -> String {
+    // Relative to the including file first, like the preprocessor does
+    if let Some(parent) = including_file.parent() {
+        let candidate = normalize_path(&parent.join(name));
+        if candidate.is_file() {
+            return relative_path(&normalize_path(tree_path), &candidate);
+        }
+    }
+
+    // Otherwise fall back to a unique match anywhere in the tree, which
+    // covers includes resolved through the kernel's -I search paths
+    let base_name = Path::new(name).file_name().map(|n| n.to_string_lossy().to_string());
+    let matches: Vec<&String> = files
+        .iter()
+        .filter(|f| Path::new(f.as_str()).file_name().map(|n| n.to_string_lossy().to_string()) == base_name)
+        .collect();
+
+    if matches.len() == 1 {
+        return matches[0].clone();
+    }
+
+    format!("{} (unresolved)", name)
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                if matches!(normalized.components().next_back(), None | Some(Component::ParentDir)) {
+                    normalized.push("..");
+                } else {
+                    normalized.pop();
+                }
+            }
+            Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn relative_path(tree_path: &Path, path: &Path) -> String {
+    path.strip_prefix(tree_path)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .to_string()
+}
@@ -0,0 +1,85 @@
+// --output-encoding: how export files are written. Every export is rendered
+// as UTF-8 text first and converted here on its way to disk; stdout is
+// always plain UTF-8.
+//
+// Exports are written to a temporary file next to the destination and
+// renamed over it once complete, so an interrupted or failed write never
+// leaves a truncated file where a reader expects a report.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use crate::OutputEncoding;
+
+pub const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+pub const UTF16LE_BOM: [u8; 2] = [0xFF, 0xFE];
+
+pub fn encode(text: &str, encoding: OutputEncoding) -> Vec<u8> {
+    match encoding {
+        OutputEncoding::Utf8 => text.as_bytes().to_vec(),
+        OutputEncoding::Utf8Bom => [&UTF8_BOM[..], text.as_bytes()].concat(),
+        OutputEncoding::Utf16le => {
+            let units = text.encode_utf16().flat_map(u16::to_le_bytes);
+            UTF16LE_BOM.into_iter().chain(units).collect()
+        }
+    }
+}
+
+// The encoding an XML declaration has to name. escape_xml only produces
+// ASCII entities, so escaped text is valid in either.
+pub fn xml_name(encoding: OutputEncoding) -> &'static str {
+    match encoding {
+        OutputEncoding::Utf8 | OutputEncoding::Utf8Bom => "UTF-8",
+        OutputEncoding::Utf16le => "UTF-16",
+    }
+}
+
+pub fn write(path: &Path, text: &str, encoding: OutputEncoding) -> io::Result<()> {
+    let temp = temp_path(path);
+    let written = write_synced(&temp, &encode(text, encoding)).and_then(|()| fs::rename(&temp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+// ".report.plist.<pid>.tmp" in the destination's directory; a rename only
+// replaces atomically within one filesystem
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    #[test]
+    fn each_encoding_writes_its_bom() {
+        // "é" is two bytes in UTF-8 and one unit in UTF-16
+        let text = "<a>é</a>";
+        assert_eq!(encode(text, OutputEncoding::Utf8), text.as_bytes());
+        assert_eq!(encode(text, OutputEncoding::Utf8Bom), b"\xEF\xBB\xBF<a>\xC3\xA9</a>");
+        assert_eq!(encode(text, OutputEncoding::Utf16le), b"\xFF\xFE<\0a\0>\0\xE9\0<\0/\0a\0>\0");
+    }
+
+    // The bytes on disk are the encoded ones, and the temporary file is gone
+    #[test]
+    fn write_puts_the_bom_on_disk() {
+        let dir = Scratch::new("encoding", &[]);
+        let path = dir.path().join("report.plist");
+        write(&path, "<plist/>", OutputEncoding::Utf8Bom).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), [&UTF8_BOM[..], b"<plist/>"].concat());
+
+        write(&path, "<plist/>", OutputEncoding::Utf16le).unwrap();
+        assert!(fs::read(&path).unwrap().starts_with(&UTF16LE_BOM));
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
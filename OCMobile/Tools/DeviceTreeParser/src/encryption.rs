@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::fsread;
+use crate::fstab::{self, FstabEntry};
+use crate::makefile::{split_assignment, statements};
+use crate::walk::WalkOptions;
+use crate::warning::{Warning, WarningKind};
+
+// BoardConfig.mk variables about the metadata partition and hardware
+// (ICE) disk encryption, plus the recovery ones TWRP trees set
+const ENCRYPTION_VARIABLES: [&str; 5] = [
+    "BOARD_USES_METADATA_PARTITION",
+    "TARGET_HW_DISK_ENCRYPTION",
+    "TARGET_CRYPTFS_HW_PATH",
+    "TW_INCLUDE_CRYPTO",
+    "TW_INCLUDE_CRYPTO_FBE",
+];
+
+// Algorithms a bare fileencryption (or one naming only contents) falls back to
+const FBE_DEFAULT_CONTENTS: &str = "aes-256-xts";
+const FBE_DEFAULT_FILENAMES: &str = "aes-256-cts";
+
+// How /data is encrypted, from the fstab fs_mgr flags and BoardConfig.mk
+#[derive(Debug, Default)]
+pub struct EncryptionInfo {
+    pub fstab_files: Vec<String>,
+    // The /data entry of each fstab
+    pub data: Vec<DataEncryption>,
+    // Locations of /metadata entries
+    pub metadata_mounts: Vec<String>,
+    pub vars: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+pub struct DataEncryption {
+    pub location: String,
+    pub fs_type: String,
+    pub mode: EncryptionMode,
+    // FBE contents/filenames algorithms, defaults filled in
+    pub contents: Option<String>,
+    pub filenames: Option<String>,
+    // v1/v2, when fileencryption= names one
+    pub policy: Option<String>,
+    // inlinecrypt_optimized, wrappedkey_v0, ...
+    pub options: Vec<String>,
+    // metadata_encryption= cipher and the keydirectory it uses
+    pub metadata_cipher: Option<String>,
+    pub key_directory: Option<String>,
+    // The encryption flags as written
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncryptionMode {
+    // fileencryption=
+    Fbe,
+    // forceencrypt=/encryptable=
+    Fde,
+    // forcefdeorfbe=: FDE, switchable to FBE
+    FdeOrFbe,
+    None,
+}
+
+impl EncryptionMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EncryptionMode::Fbe => "FBE",
+            EncryptionMode::Fde => "FDE",
+            EncryptionMode::FdeOrFbe => "FDE or FBE",
+            EncryptionMode::None => "none",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            EncryptionMode::Fbe => "file-based encryption",
+            EncryptionMode::Fde => "full-disk encryption",
+            EncryptionMode::FdeOrFbe => "full-disk encryption, convertible to file-based",
+            EncryptionMode::None => "/data is not encrypted",
+        }
+    }
+}
+
+impl DataEncryption {
+    fn is_recovery(&self) -> bool {
+        self.location.split('/').any(|part| part.starts_with("recovery"))
+    }
+}
+
+impl EncryptionInfo {
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty() && self.metadata_mounts.is_empty() && self.vars.is_empty()
+    }
+
+    // The mode of the first /data entry outside a recovery directory (TWRP
+    // trees carry their own fstab), or of the first one; None without any
+    pub fn mode(&self) -> Option<EncryptionMode> {
+        self.data.iter().find(|data| !data.is_recovery()).or(self.data.first()).map(|data| data.mode)
+    }
+
+    // Whether the device's (non-recovery) fstabs disagree about how /data
+    // is encrypted
+    pub fn modes_differ(&self) -> bool {
+        self.data.iter().filter(|data| !data.is_recovery()).any(|data| Some(data.mode) != self.mode())
+    }
+
+    pub fn uses_metadata_partition(&self) -> bool {
+        self.vars.get("BOARD_USES_METADATA_PARTITION").is_some_and(|value| value == "true")
+    }
+}
+
+pub fn scan_encryption(
+    tree_path: &Path,
+    board_config: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> EncryptionInfo {
+    let mut encryption = EncryptionInfo::default();
+
+    for file in fstab::find_fstabs(tree_path, walk_options) {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let content = match fsread::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) => {
+                warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+                continue;
+            }
+        };
+        for entry in fstab::parse(&content, &relative) {
+            match entry.mount_point.as_str() {
+                "/data" => encryption.data.push(data_encryption(&entry)),
+                "/metadata" => encryption.metadata_mounts.push(entry.location.clone()),
+                _ => {}
+            }
+        }
+        encryption.fstab_files.push(relative);
+    }
+
+    // Unreadable BoardConfig.mk is already reported by the driver scan
+    if let Some(board_config) = board_config
+        && walk_options.includes_file(board_config)
+        && let Ok(content) = fsread::read_to_string(board_config)
+    {
+        for (_, statement) in statements(&content) {
+            if let Some((name, value)) = split_assignment(&statement)
+                && ENCRYPTION_VARIABLES.contains(&name)
+            {
+                encryption.vars.insert(name.to_string(), value.trim_matches('"').to_string());
+            }
+        }
+    }
+
+    encryption
+}
+
+fn data_encryption(entry: &FstabEntry) -> DataEncryption {
+    let mut data = DataEncryption {
+        location: entry.location.clone(),
+        fs_type: entry.fs_type.clone(),
+        mode: EncryptionMode::None,
+        contents: None,
+        filenames: None,
+        policy: None,
+        options: Vec::new(),
+        metadata_cipher: None,
+        key_directory: None,
+        flags: Vec::new(),
+    };
+
+    for flag in &entry.fs_mgr_flags {
+        let (name, value) = flag.split_once('=').unwrap_or((flag, ""));
+        match name {
+            // fileencryption=<contents>:<filenames>:<v1|v2>[+<option>...]
+            "fileencryption" => {
+                data.mode = EncryptionMode::Fbe;
+                let mut parts = value.split(':');
+                let contents = parts.next().filter(|part| !part.is_empty()).unwrap_or(FBE_DEFAULT_CONTENTS);
+                let filenames = parts.next().filter(|part| !part.is_empty()).unwrap_or(FBE_DEFAULT_FILENAMES);
+                data.contents = Some(contents.to_string());
+                data.filenames = Some(filenames.to_string());
+                for option in parts.flat_map(|part| part.split('+')).filter(|option| !option.is_empty()) {
+                    if option == "v1" || option == "v2" {
+                        data.policy = Some(option.to_string());
+                    } else {
+                        data.options.push(option.to_string());
+                    }
+                }
+            }
+            "forceencrypt" | "encryptable" if data.mode != EncryptionMode::Fbe => data.mode = EncryptionMode::Fde,
+            "forcefdeorfbe" => data.mode = EncryptionMode::FdeOrFbe,
+            // metadata_encryption=<cipher>[:wrappedkey_v0]
+            "metadata_encryption" => {
+                let (cipher, option) = value.split_once(':').unwrap_or((value, ""));
+                data.metadata_cipher = Some(cipher.to_string());
+                if !option.is_empty() && !data.options.iter().any(|o| o == option) {
+                    data.options.push(option.to_string());
+                }
+            }
+            "keydirectory" => data.key_directory = Some(value.to_string()),
+            "inlinecrypt" | "wrappedkey_v0" => {
+                if !data.options.iter().any(|o| o == name) {
+                    data.options.push(name.to_string());
+                }
+            }
+            "forceencrypt" | "encryptable" => {}
+            _ => continue,
+        }
+        data.flags.push(flag.clone());
+    }
+
+    data
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+use json::JsonValue;
+
+// Everything that ends a run with a failing exit code. Text mode prints
+// the Display message; --format json prints to_json() on stderr instead.
+#[derive(Debug)]
+pub enum AnalyzeError {
+    PathNotFound(String),
+    NotADirectory(String),
+    InvalidDtb { path: String, message: String },
+    InvalidAssertion { expr: String, message: String },
+    InvalidReport { path: String, message: String },
+    InvalidCategoryMap { path: String, message: String },
+    NotAGitRepository(String),
+    Git(String),
+    Template(String),
+    Stdin(String),
+    // An --export-* file that couldn't be written; what is "plist", "TOML", ...
+    Export { what: &'static str, path: String, message: String },
+    AssertionsFailed,
+    WarningsAsErrors(usize),
+    TooManyWarnings { count: usize, threshold: usize },
+    MissingFiles(Vec<String>),
+    SelftestFailed(usize),
+}
+
+impl AnalyzeError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AnalyzeError::PathNotFound(_) => "path_not_found",
+            AnalyzeError::NotADirectory(_) => "not_a_directory",
+            AnalyzeError::InvalidDtb { .. } => "invalid_dtb",
+            AnalyzeError::InvalidAssertion { .. } => "invalid_assertion",
+            AnalyzeError::InvalidReport { .. } => "invalid_report",
+            AnalyzeError::InvalidCategoryMap { .. } => "invalid_category_map",
+            AnalyzeError::NotAGitRepository(_) => "not_a_git_repository",
+            AnalyzeError::Git(_) => "git_failed",
+            AnalyzeError::Template(_) => "template_failed",
+            AnalyzeError::Stdin(_) => "stdin_failed",
+            AnalyzeError::Export { .. } => "export_failed",
+            AnalyzeError::AssertionsFailed => "assertions_failed",
+            AnalyzeError::WarningsAsErrors(_) => "warnings_as_errors",
+            AnalyzeError::TooManyWarnings { .. } => "too_many_warnings",
+            AnalyzeError::MissingFiles(_) => "missing_files",
+            AnalyzeError::SelftestFailed(_) => "selftest_failed",
+        }
+    }
+
+    // The path the error is about, where there is one
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            AnalyzeError::PathNotFound(path)
+            | AnalyzeError::NotADirectory(path)
+            | AnalyzeError::NotAGitRepository(path)
+            | AnalyzeError::InvalidDtb { path, .. }
+            | AnalyzeError::InvalidReport { path, .. }
+            | AnalyzeError::InvalidCategoryMap { path, .. }
+            | AnalyzeError::Export { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    // Malformed command-line input exits with 2, like clap's own errors
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AnalyzeError::InvalidAssertion { .. } => 2,
+            _ => 1,
+        }
+    }
+
+    // Checks the tree failed, as opposed to the analysis itself going wrong.
+    // --exit-zero still reports these but exits 0.
+    pub fn is_check_failure(&self) -> bool {
+        matches!(
+            self,
+            AnalyzeError::AssertionsFailed
+                | AnalyzeError::WarningsAsErrors(_)
+                | AnalyzeError::TooManyWarnings { .. }
+                | AnalyzeError::MissingFiles(_)
+        )
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut object = JsonValue::new_object();
+        object["error"] = self.to_string().into();
+        object["kind"] = self.kind().into();
+        if let Some(path) = self.path() {
+            object["path"] = path.into();
+        }
+        if let AnalyzeError::MissingFiles(files) = self {
+            object["files"] = files.clone().into();
+        }
+        if let AnalyzeError::TooManyWarnings { count, threshold } = self {
+            object["count"] = (*count).into();
+            object["threshold"] = (*threshold).into();
+        }
+        object
+    }
+}
+
+impl fmt::Display for AnalyzeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalyzeError::PathNotFound(path) => write!(f, "Path '{}' does not exist", path),
+            AnalyzeError::NotADirectory(path) => write!(f, "Path '{}' is not a directory or a .dts/.dtsi file", path),
+            AnalyzeError::InvalidDtb { path, message } => {
+                write!(f, "'{}' is not a valid device tree blob: {}", path, message)
+            }
+            AnalyzeError::InvalidAssertion { expr, message } => write!(f, "Invalid assertion '{}': {}", expr, message),
+            AnalyzeError::InvalidReport { path, message } => write!(f, "Cannot load report '{}': {}", path, message),
+            AnalyzeError::InvalidCategoryMap { path, message } => {
+                write!(f, "Invalid category map '{}': {}", path, message)
+            }
+            AnalyzeError::NotAGitRepository(path) => {
+                write!(f, "'{}' is not inside a git repository (--since needs one)", path)
+            }
+            AnalyzeError::Git(message) | AnalyzeError::Template(message) => write!(f, "{}", message),
+            AnalyzeError::Stdin(message) => write!(f, "Failed to read the file listing from stdin: {}", message),
+            AnalyzeError::Export { what, path, message } => {
+                write!(f, "Failed to export {} to '{}': {}", what, path, message)
+            }
+            AnalyzeError::AssertionsFailed => write!(f, "One or more assertions failed"),
+            AnalyzeError::WarningsAsErrors(count) => {
+                write!(f, "{} warning(s) treated as errors (--warnings-as-errors)", count)
+            }
+            AnalyzeError::TooManyWarnings { count, threshold } => {
+                write!(f, "{} warning(s), more than the {} allowed (--fail-on-warning-count)", count, threshold)
+            }
+            AnalyzeError::MissingFiles(files) => write!(f, "Required file(s) missing: {}", files.join(", ")),
+            AnalyzeError::SelftestFailed(count) => write!(f, "{} self-test check(s) failed", count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_errors_carry_kind_and_path() {
+        let json = AnalyzeError::PathNotFound("/no/such/tree".to_string()).to_json();
+        assert_eq!(
+            json.dump(),
+            r#"{"error":"Path '/no/such/tree' does not exist","kind":"path_not_found","path":"/no/such/tree"}"#
+        );
+
+        let json = AnalyzeError::NotADirectory("/tree/BoardConfig.mk".to_string()).to_json();
+        assert_eq!(json.entries().map(|(key, _)| key).collect::<Vec<_>>(), ["error", "kind", "path"]);
+        assert_eq!(json["error"], "Path '/tree/BoardConfig.mk' is not a directory or a .dts/.dtsi file");
+        assert_eq!(json["kind"], "not_a_directory");
+        assert_eq!(json["path"], "/tree/BoardConfig.mk");
+
+        // No path to report
+        let json = AnalyzeError::AssertionsFailed.to_json();
+        assert_eq!(json.dump(), r#"{"error":"One or more assertions failed","kind":"assertions_failed"}"#);
+    }
+
+    #[test]
+    fn json_errors_carry_their_details() {
+        let json = AnalyzeError::MissingFiles(vec!["a.mk".to_string(), "b.mk".to_string()]).to_json();
+        assert_eq!(json["kind"], "missing_files");
+        assert_eq!(json["files"].members().map(|f| f.as_str().unwrap()).collect::<Vec<_>>(), ["a.mk", "b.mk"]);
+
+        let json = AnalyzeError::TooManyWarnings { count: 7, threshold: 3 }.to_json();
+        assert_eq!(json["kind"], "too_many_warnings");
+        assert_eq!((json["count"].as_usize(), json["threshold"].as_usize()), (Some(7), Some(3)));
+    }
+
+    #[test]
+    fn malformed_input_exits_with_2() {
+        let invalid = AnalyzeError::InvalidAssertion { expr: "x >".to_string(), message: "bad".to_string() };
+        assert_eq!(invalid.exit_code(), 2);
+        assert_eq!(AnalyzeError::NotADirectory("x".to_string()).exit_code(), 1);
+    }
+}
@@ -0,0 +1,388 @@
+// --dtb: decodes a flattened device tree blob (the binary .dtb/.dtbo format
+// dtc writes) into the same node map the DTS parser builds, so the binding
+// and node tree views work on compiled blobs too.
+//
+// Layout, all integers big-endian:
+//
+//   header     magic, totalsize, off_dt_struct, off_dt_strings,
+//              off_mem_rsvmap, version, last_comp_version, boot_cpuid_phys,
+//              size_dt_strings, size_dt_struct (v17)
+//   structure  FDT_BEGIN_NODE <name> / FDT_PROP <len> <nameoff> <value> /
+//              FDT_END_NODE / FDT_NOP tokens up to FDT_END, 4-byte aligned
+//   strings    NUL-terminated property names, addressed by nameoff
+//
+// Values carry no type in the blob, so they're rendered the way dtc -O dts
+// guesses: printable NUL-terminated strings, else cells when the length is
+// a multiple of 4, else bytes. Labels come from /__symbols__ (blobs built
+// with dtc -@), and single-cell interrupt-parent and *-supply properties are
+// turned back into <&label> references so phandles resolve as in source.
+
+use std::collections::BTreeMap;
+use crate::dts::{DtsNode, DtsSources};
+
+const FDT_MAGIC: u32 = 0xd00dfeed;
+// Android dtbo.img/dtb.img table: several blobs behind its own header
+const DT_TABLE_MAGIC: u32 = 0xd7b7ab1e;
+
+// v16 lacks size_dt_struct; v17 is what every current dtc writes
+const MIN_VERSION: u32 = 16;
+const MAX_COMP_VERSION: u32 = 17;
+const V16_HEADER_SIZE: usize = 36;
+
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_NOP: u32 = 0x4;
+const FDT_END: u32 = 0x9;
+
+pub fn decode(data: &[u8], file: &str) -> Result<DtsSources, String> {
+    let header = |field: usize| be32(data, field * 4);
+    let magic = header(0).ok_or("file is too short to be a device tree blob")?;
+    if magic == DT_TABLE_MAGIC {
+        return Err("this is an Android dtbo.img/dtb.img table, not a single blob; extract an entry first".to_string());
+    }
+    if magic != FDT_MAGIC {
+        return Err(format!("bad magic 0x{:08x} (expected 0x{:08x})", magic, FDT_MAGIC));
+    }
+    if data.len() < V16_HEADER_SIZE {
+        return Err(format!("truncated header ({} bytes)", data.len()));
+    }
+
+    let total_size = header(1).unwrap() as usize;
+    let struct_offset = header(2).unwrap() as usize;
+    let strings_offset = header(3).unwrap() as usize;
+    let version = header(5).unwrap();
+    let last_comp_version = header(6).unwrap();
+    let strings_size = header(8).unwrap() as usize;
+
+    if version < MIN_VERSION {
+        return Err(format!("unsupported version {} (need {} or later)", version, MIN_VERSION));
+    }
+    if last_comp_version > MAX_COMP_VERSION {
+        return Err(format!("version {} blob needs a reader for version {} or later", version, last_comp_version));
+    }
+    if total_size > data.len() {
+        return Err(format!("truncated: the header says {} bytes, the file has {}", total_size, data.len()));
+    }
+    let struct_size = if version >= 17 {
+        header(9).ok_or("truncated v17 header")? as usize
+    } else {
+        total_size.saturating_sub(struct_offset)
+    };
+
+    let block = |name: &str, offset: usize, size: usize| {
+        offset
+            .checked_add(size)
+            .filter(|end| *end <= total_size)
+            .map(|end| &data[offset..end])
+            .ok_or(format!("{} block (offset 0x{:x}, {} bytes) runs past the end of the blob", name, offset, size))
+    };
+    let structure = block("structure", struct_offset, struct_size)?;
+    let strings = block("strings", strings_offset, strings_size)?;
+
+    let mut nodes = walk_structure(structure, strings, file)?;
+    resolve_labels(&mut nodes);
+
+    Ok(DtsSources { files: vec![file.to_string()], includes: BTreeMap::new(), nodes })
+}
+
+// The structure block's tokens, as nodes keyed by full path
+fn walk_structure(structure: &[u8], strings: &[u8], file: &str) -> Result<BTreeMap<String, DtsNode>, String> {
+    let mut nodes: BTreeMap<String, DtsNode> = BTreeMap::new();
+    // Paths of the open nodes, innermost last
+    let mut open: Vec<String> = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        // Errors point at the token being decoded
+        let start = offset;
+        let at = |message: String| format!("structure block offset 0x{:x}: {}", start, message);
+        let token = be32(structure, offset).ok_or_else(|| at("ends before FDT_END".to_string()))?;
+        offset += 4;
+
+        match token {
+            FDT_BEGIN_NODE => {
+                let name = c_string(structure, offset).ok_or_else(|| at("unterminated node name".to_string()))?;
+                offset = align(offset + name.len() + 1);
+                let path = match open.last() {
+                    None if nodes.contains_key("/") => return Err(at("second root node".to_string())),
+                    None => "/".to_string(),
+                    Some(parent) if parent == "/" => format!("/{}", name),
+                    Some(parent) => format!("{}/{}", parent, name),
+                };
+                let location = format!("{}:0x{:x}", file, start);
+                nodes.entry(path.clone()).or_insert_with(|| DtsNode { path: path.clone(), ..Default::default() });
+                nodes.get_mut(&path).unwrap().locations.push(location);
+                open.push(path);
+            }
+            FDT_END_NODE => {
+                open.pop().ok_or_else(|| at("FDT_END_NODE without an open node".to_string()))?;
+            }
+            FDT_PROP => {
+                let (Some(len), Some(name_offset)) = (be32(structure, offset), be32(structure, offset + 4)) else {
+                    return Err(at("truncated property header".to_string()));
+                };
+                offset += 8;
+                let value = offset
+                    .checked_add(len as usize)
+                    .and_then(|end| structure.get(offset..end))
+                    .ok_or_else(|| at(format!("property value of {} bytes runs past the block", len)))?;
+                offset = align(offset + value.len());
+                let name = c_string(strings, name_offset as usize).ok_or_else(|| {
+                    at(format!("property name offset 0x{:x} is outside the strings block", name_offset))
+                })?;
+                let path = open.last().ok_or_else(|| at(format!("property '{}' outside any node", name)))?;
+                let node = nodes.get_mut(path).unwrap();
+                node.properties.insert(name.to_string(), render(value));
+                node.property_locations.insert(name.to_string(), format!("{}:0x{:x}", file, start));
+            }
+            FDT_NOP => {}
+            FDT_END => {
+                if !open.is_empty() {
+                    return Err(at(format!("FDT_END with {} node(s) still open", open.len())));
+                }
+                return Ok(nodes);
+            }
+            other => return Err(at(format!("unknown token 0x{:08x}", other))),
+        }
+    }
+}
+
+// Labels from /__symbols__ (label = "/path"), then single-cell phandle
+// properties rewritten to <&label>, or <&{/path}> for unlabelled targets
+fn resolve_labels(nodes: &mut BTreeMap<String, DtsNode>) {
+    let symbols: Vec<(String, String)> = match nodes.get("/__symbols__") {
+        Some(symbols) => symbols
+            .properties
+            .keys()
+            .filter_map(|label| Some((label.clone(), symbols.string(label)?.to_string())))
+            .collect(),
+        None => Vec::new(),
+    };
+    for (label, path) in symbols {
+        if let Some(node) = nodes.get_mut(&path) {
+            node.labels.push(label);
+        }
+    }
+
+    let mut references: BTreeMap<u64, String> = BTreeMap::new();
+    for node in nodes.values() {
+        let phandle = node.cells("phandle").first().or(node.cells("linux,phandle").first()).copied();
+        if let Some(phandle) = phandle {
+            let reference = match node.labels.first() {
+                Some(label) => label.clone(),
+                None => format!("{{{}}}", node.path),
+            };
+            references.insert(phandle, reference);
+        }
+    }
+
+    for node in nodes.values_mut() {
+        for (name, value) in node.properties.iter_mut() {
+            if name != "interrupt-parent" && !name.ends_with("-supply") {
+                continue;
+            }
+            let phandle = value
+                .strip_prefix("<0x")
+                .and_then(|rest| rest.strip_suffix('>'))
+                .and_then(|hex| u64::from_str_radix(hex, 16).ok());
+            if let Some(reference) = phandle.and_then(|phandle| references.get(&phandle)) {
+                *value = format!("<&{}>", reference);
+            }
+        }
+    }
+}
+
+// A property value as DTS source: "a", "b" / <0x1 0x2> / [01 02 03];
+// empty for boolean properties
+fn render(value: &[u8]) -> String {
+    if value.is_empty() {
+        return String::new();
+    }
+    if let Some(strings) = as_strings(value) {
+        return strings.iter().map(|s| format!("\"{}\"", s)).collect::<Vec<_>>().join(", ");
+    }
+    if value.len().is_multiple_of(4) {
+        let cells: Vec<String> = value
+            .chunks(4)
+            .map(|cell| format!("0x{:x}", u32::from_be_bytes([cell[0], cell[1], cell[2], cell[3]])))
+            .collect();
+        return format!("<{}>", cells.join(" "));
+    }
+    let bytes: Vec<String> = value.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("[{}]", bytes.join(" "))
+}
+
+// NUL-terminated, non-empty printable strings. Quotes and backslashes would
+// need escaping in DTS, so values containing them are shown as cells/bytes.
+fn as_strings(value: &[u8]) -> Option<Vec<&str>> {
+    let body = value.strip_suffix(&[0])?;
+    body.split(|byte| *byte == 0)
+        .map(|part| {
+            let printable = |byte: &u8| (byte.is_ascii_graphic() || *byte == b' ') && !matches!(byte, b'"' | b'\\');
+            if part.is_empty() || !part.iter().all(printable) {
+                return None;
+            }
+            std::str::from_utf8(part).ok()
+        })
+        .collect()
+}
+
+fn be32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn c_string(data: &[u8], offset: usize) -> Option<&str> {
+    let rest = data.get(offset..)?;
+    let end = rest.iter().position(|byte| *byte == 0)?;
+    std::str::from_utf8(&rest[..end]).ok()
+}
+
+fn align(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRINGS: &[u8] = b"compatible\0";
+    // A v17 header and an empty memory reservation map come first
+    const STRUCT_OFFSET: usize = 40 + 16;
+
+    fn token(structure: &mut Vec<u8>, token: u32) {
+        structure.extend(token.to_be_bytes());
+    }
+
+    fn begin_node(structure: &mut Vec<u8>, name: &str) {
+        token(structure, FDT_BEGIN_NODE);
+        structure.extend(name.as_bytes());
+        structure.push(0);
+        structure.resize(align(structure.len()), 0);
+    }
+
+    fn compatible(structure: &mut Vec<u8>, value: &str) {
+        token(structure, FDT_PROP);
+        token(structure, value.len() as u32 + 1);
+        token(structure, 0);
+        structure.extend(value.as_bytes());
+        structure.push(0);
+        structure.resize(align(structure.len()), 0);
+    }
+
+    // / { compatible = "acme,widget"; soc { }; };
+    fn structure() -> Vec<u8> {
+        let mut structure = Vec::new();
+        begin_node(&mut structure, "");
+        compatible(&mut structure, "acme,widget");
+        begin_node(&mut structure, "soc");
+        token(&mut structure, FDT_END_NODE);
+        token(&mut structure, FDT_END_NODE);
+        token(&mut structure, FDT_END);
+        structure
+    }
+
+    fn blob(structure: &[u8]) -> Vec<u8> {
+        let strings_offset = STRUCT_OFFSET + structure.len();
+        let total_size = strings_offset + STRINGS.len();
+        let header = [
+            FDT_MAGIC,
+            total_size as u32,
+            STRUCT_OFFSET as u32,
+            strings_offset as u32,
+            40,
+            17,
+            16,
+            0,
+            STRINGS.len() as u32,
+            structure.len() as u32,
+        ];
+        let mut blob: Vec<u8> = header.iter().flat_map(|field| field.to_be_bytes()).collect();
+        blob.resize(STRUCT_OFFSET, 0);
+        blob.extend(structure);
+        blob.extend(STRINGS);
+        blob
+    }
+
+    fn set_header(blob: &mut [u8], field: usize, value: u32) {
+        blob[field * 4..field * 4 + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn error(blob: &[u8]) -> String {
+        decode(blob, "board.dtb").unwrap_err()
+    }
+
+    #[test]
+    fn a_well_formed_blob_decodes() {
+        let sources = decode(&blob(&structure()), "board.dtb").unwrap();
+        assert_eq!(sources.nodes.keys().collect::<Vec<_>>(), ["/", "/soc"]);
+        assert_eq!(sources.nodes["/"].properties["compatible"], "\"acme,widget\"");
+        assert_eq!(sources.nodes["/soc"].locations, ["board.dtb:0x20"]);
+    }
+
+    #[test]
+    fn bad_magic() {
+        let mut blob = blob(&structure());
+        set_header(&mut blob, 0, 0x12345678);
+        assert_eq!(error(&blob), "bad magic 0x12345678 (expected 0xd00dfeed)");
+        set_header(&mut blob, 0, DT_TABLE_MAGIC);
+        assert!(error(&blob).starts_with("this is an Android dtbo.img/dtb.img table"));
+        assert_eq!(error(&[0xd0, 0x0d]), "file is too short to be a device tree blob");
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut blob = blob(&structure());
+        set_header(&mut blob, 5, 15);
+        assert_eq!(error(&blob), "unsupported version 15 (need 16 or later)");
+        set_header(&mut blob, 5, 18);
+        set_header(&mut blob, 6, 18);
+        assert_eq!(error(&blob), "version 18 blob needs a reader for version 18 or later");
+    }
+
+    #[test]
+    fn header_pointing_past_the_end() {
+        let mut blob = blob(&structure());
+        let len = blob.len();
+        set_header(&mut blob, 1, len as u32 + 64);
+        assert_eq!(error(&blob), format!("truncated: the header says {} bytes, the file has {}", len + 64, len));
+
+        set_header(&mut blob, 1, len as u32);
+        set_header(&mut blob, 9, 4096);
+        assert_eq!(error(&blob), "structure block (offset 0x38, 4096 bytes) runs past the end of the blob");
+        assert_eq!(error(&blob[..20]), "truncated header (20 bytes)");
+    }
+
+    #[test]
+    fn structure_truncated_mid_token() {
+        // The root's FDT_PROP token at 0x8 without its length and name offset
+        let structure = structure();
+        assert_eq!(error(&blob(&structure[..12])), "structure block offset 0x8: truncated property header");
+        assert_eq!(error(&blob(&structure[..8])), "structure block offset 0x8: ends before FDT_END");
+        assert_eq!(error(&blob(&structure[..4])), "structure block offset 0x0: unterminated node name");
+
+        let mut long_value = structure.clone();
+        long_value[12..16].copy_from_slice(&64u32.to_be_bytes());
+        assert_eq!(
+            error(&blob(&long_value)),
+            "structure block offset 0x8: property value of 64 bytes runs past the block"
+        );
+    }
+
+    #[test]
+    fn unbalanced_nodes() {
+        // Without the final FDT_END, then with one END_NODE too many or too few
+        let mut structure = structure();
+        let end = structure.len() - 4;
+        structure.truncate(end);
+        let mut extra_end = structure.clone();
+        token(&mut extra_end, FDT_END_NODE);
+        token(&mut extra_end, FDT_END);
+        assert_eq!(error(&blob(&extra_end)), "structure block offset 0x30: FDT_END_NODE without an open node");
+
+        let mut unclosed = structure[..end - 4].to_vec();
+        token(&mut unclosed, FDT_END);
+        assert_eq!(error(&blob(&unclosed)), "structure block offset 0x2c: FDT_END with 1 node(s) still open");
+    }
+}
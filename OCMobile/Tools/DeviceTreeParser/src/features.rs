@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+use xml::reader::{EventReader, XmlEvent};
+use crate::copy_files::CopiedFiles;
+use crate::fsread;
+use crate::hal::Hal;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Feature name (below android.hardware.) -> HAL family expected to back it.
+// Features not listed need no HAL (screen.portrait, touchscreen, ...) or
+// are usually backed by a vendor blob (telephony, location.gps).
+const FEATURE_HALS: [(&str, &str); 11] = [
+    ("audio", "audio"),
+    ("bluetooth", "bluetooth"),
+    ("camera", "camera.provider"),
+    ("consumerir", "ir"),
+    ("fingerprint", "biometrics.fingerprint"),
+    ("biometrics.face", "biometrics.face"),
+    ("nfc", "nfc"),
+    ("sensor", "sensors"),
+    ("usb", "usb"),
+    ("vibrator", "vibrator"),
+    ("wifi", "wifi"),
+];
+
+// <feature name=...> declarations from permission XML (etc/permissions,
+// etc/sysconfig), i.e. what the device advertises to PackageManager
+#[derive(Debug, Default)]
+pub struct DeclaredFeatures {
+    pub features: Vec<Feature>,
+    // In-tree XML files that declare features
+    pub files: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Feature {
+    pub name: String,
+    pub version: Option<String>,
+    // <unavailable-feature>: removes a feature another file declared
+    pub unavailable: bool,
+    // Permission files copied from the platform source can't be read here,
+    // so the feature is the file name (android.hardware.nfc.xml)
+    pub from_file_name: bool,
+    pub location: String,
+}
+
+impl DeclaredFeatures {
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    // Declared features, without the ones an <unavailable-feature> removes
+    pub fn available(&self) -> Vec<&Feature> {
+        self.features
+            .iter()
+            .filter(|feature| !feature.unavailable)
+            .filter(|feature| {
+                !self.features.iter().any(|other| other.unavailable && other.name == feature.name)
+            })
+            .collect()
+    }
+
+    // Available features whose HAL isn't in PRODUCT_PACKAGES, with the
+    // HAL family they'd need
+    pub fn missing_hals<'a>(&'a self, hals: &[Hal]) -> Vec<(&'a str, &'static str)> {
+        let mut missing = Vec::new();
+        for feature in self.available() {
+            let Some(family) = hal_family(&feature.name) else {
+                continue;
+            };
+            if !hals.iter().any(|hal| hal.family == family) && !missing.iter().any(|(_, f)| *f == family) {
+                missing.push((feature.name.as_str(), family));
+            }
+        }
+        missing
+    }
+}
+
+// android.hardware.camera.autofocus -> camera.provider
+pub fn hal_family(feature: &str) -> Option<&'static str> {
+    let name = feature.strip_prefix("android.hardware.")?;
+    FEATURE_HALS
+        .iter()
+        .find(|(prefix, _)| name == *prefix || name.starts_with(&format!("{}.", prefix)))
+        .map(|(_, family)| *family)
+}
+
+pub fn scan_features(
+    tree_path: &Path,
+    copied: &CopiedFiles,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> DeclaredFeatures {
+    let mut declared = DeclaredFeatures::default();
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name.ends_with(".xml"), &mut files);
+    files.sort();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let content = match fsread::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) => {
+                warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+                continue;
+            }
+        };
+        // Most XML in a tree is something else; skip it unparsed
+        if !content.contains("<permissions") {
+            continue;
+        }
+        let before = declared.features.len();
+        parse_permissions(&content, &relative, &mut declared.features);
+        if declared.features.len() > before {
+            declared.files.push(relative);
+        }
+    }
+
+    // android.hardware.*.xml copied from frameworks/native/data/etc
+    for entry in copied.entries.iter().filter(|entry| !entry.in_tree) {
+        let Some(name) = entry.destination.rsplit('/').next().and_then(|name| name.strip_suffix(".xml")) else {
+            continue;
+        };
+        let is_permission_file = entry.destination.contains("/permissions/");
+        if !is_permission_file || !(name.starts_with("android.hardware.") || name.starts_with("android.software.")) {
+            continue;
+        }
+        declared.features.push(Feature {
+            name: name.to_string(),
+            version: None,
+            unavailable: false,
+            from_file_name: true,
+            location: entry.location.clone(),
+        });
+    }
+
+    declared
+}
+
+// <permissions>
+//     <feature name="android.hardware.vulkan.level" version="1" />
+//     <unavailable-feature name="android.hardware.telephony" />
+// </permissions>
+fn parse_permissions(content: &str, relative: &str, features: &mut Vec<Feature>) {
+    let mut parser = EventReader::new(content.as_bytes());
+    let mut depth = 0;
+    let mut in_permissions = false;
+    loop {
+        match parser.next() {
+            Ok(XmlEvent::StartElement { name, attributes, .. }) => {
+                depth += 1;
+                if depth == 1 {
+                    in_permissions = name.local_name == "permissions" || name.local_name == "config";
+                    continue;
+                }
+                let unavailable = name.local_name == "unavailable-feature";
+                if depth != 2 || !in_permissions || (name.local_name != "feature" && !unavailable) {
+                    continue;
+                }
+                let attribute =
+                    |key: &str| attributes.iter().find(|a| a.name.local_name == key).map(|a| a.value.clone());
+                let Some(feature) = attribute("name") else {
+                    continue;
+                };
+                features.push(Feature {
+                    name: feature,
+                    version: attribute("version"),
+                    unavailable,
+                    from_file_name: false,
+                    location: relative.to_string(),
+                });
+            }
+            Ok(XmlEvent::EndElement { .. }) => depth -= 1,
+            Ok(XmlEvent::EndDocument) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+// --read-retries: set once before the scan and read by every parser, so the
+// count doesn't have to be threaded through each of them
+static READ_RETRIES: AtomicUsize = AtomicUsize::new(0);
+
+// Pause before the first retry; doubled on each further attempt
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+
+pub fn set_retries(retries: usize) {
+    READ_RETRIES.store(retries, Ordering::Relaxed);
+}
+
+// fs::read_to_string, retried for flaky (e.g. NFS/SMB) reads
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    retry(READ_RETRIES.load(Ordering::Relaxed), || fs::read_to_string(path))
+}
+
+// fs::read, retried for flaky reads
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    retry(READ_RETRIES.load(Ordering::Relaxed), || fs::read(path))
+}
+
+// File::open, retried for flaky reads
+pub fn open(path: &Path) -> io::Result<File> {
+    retry(READ_RETRIES.load(Ordering::Relaxed), || File::open(path))
+}
+
+// Runs `op` up to `retries` more times after a failure. A missing file or
+// non-UTF-8 content won't change on a second try, so those fail right away.
+pub fn retry<T>(retries: usize, mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut delay = RETRY_DELAY;
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Err(e) if attempt < retries && !matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::InvalidData) => {
+                thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A read that fails `failures` times before it succeeds, counting attempts
+    fn flaky(failures: usize, attempts: &mut usize) -> impl FnMut() -> io::Result<&'static str> + '_ {
+        move || {
+            *attempts += 1;
+            if *attempts <= failures { Err(io::Error::other("stale NFS handle")) } else { Ok("contents") }
+        }
+    }
+
+    #[test]
+    fn retries_until_the_read_succeeds() {
+        let mut attempts = 0;
+        assert_eq!(retry(2, flaky(2, &mut attempts)).unwrap(), "contents");
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn gives_up_after_the_last_retry() {
+        let mut attempts = 0;
+        assert!(retry(1, flaky(2, &mut attempts)).is_err());
+        assert_eq!(attempts, 2);
+
+        let mut attempts = 0;
+        assert!(retry(0, flaky(1, &mut attempts)).is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn missing_files_are_not_retried() {
+        let mut attempts = 0;
+        let result: io::Result<()> = retry(3, || {
+            attempts += 1;
+            Err(io::ErrorKind::NotFound.into())
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
@@ -0,0 +1,48 @@
+use std::path::{Path, PathBuf};
+use crate::walk::{self, WalkOptions};
+
+// One line of an Android fstab:
+// <src> <mount point> <type> <mount flags> <fs_mgr flags>
+#[derive(Debug)]
+pub struct FstabEntry {
+    pub mount_point: String,
+    pub fs_type: String,
+    // fileencryption=..., wait, slotselect, ...
+    pub fs_mgr_flags: Vec<String>,
+    // file:line
+    pub location: String,
+}
+
+// fstab.<hardware> files (fstab.qcom, fstab.default, ...) anywhere in the tree
+pub fn find_fstabs(tree_path: &Path, walk_options: &WalkOptions) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name == "fstab" || name.starts_with("fstab."), &mut files);
+    files.sort();
+    files
+}
+
+// `relative` is the file's path for locations. Lines with fewer than four
+// fields (the old recovery.fstab layout) are skipped.
+pub fn parse(content: &str, relative: &str) -> Vec<FstabEntry> {
+    let mut entries = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 {
+            continue;
+        }
+        let fs_mgr_flags = fields.get(4).map_or(Vec::new(), |field| {
+            field.split(',').filter(|flag| !flag.is_empty() && *flag != "defaults").map(str::to_string).collect()
+        });
+        entries.push(FstabEntry {
+            mount_point: fields[1].to_string(),
+            fs_type: fields[2].to_string(),
+            fs_mgr_flags,
+            location: format!("{}:{}", relative, index + 1),
+        });
+    }
+    entries
+}
@@ -0,0 +1,233 @@
+use std::path::Path;
+use crate::dts::DtsSources;
+use crate::fsread;
+use crate::makefile::{self, statements};
+use crate::walk::WalkOptions;
+
+// Compatible prefixes of GPU nodes: upstream "qcom,adreno-630.2", downstream
+// "qcom,kgsl-3d0", and "arm,mali-g72"/"arm,mali-bifrost"
+const GPU_COMPATIBLES: [&str; 3] = ["qcom,adreno", "qcom,kgsl-3d", "arm,mali"];
+
+// TARGET_BOARD_PLATFORM -> the GPU that SoC ships with
+const SOC_GPUS: [(&str, &str); 29] = [
+    ("msm8937", "Adreno 505"),
+    ("msm8953", "Adreno 506"),
+    ("msm8996", "Adreno 530"),
+    ("msm8998", "Adreno 540"),
+    ("sdm660", "Adreno 512"),
+    ("sdm710", "Adreno 616"),
+    ("sdm845", "Adreno 630"),
+    ("msmnile", "Adreno 640"),
+    ("sm8150", "Adreno 640"),
+    ("sm6150", "Adreno 612"),
+    ("trinket", "Adreno 610"),
+    ("bengal", "Adreno 610"),
+    ("atoll", "Adreno 618"),
+    ("lito", "Adreno 620"),
+    ("kona", "Adreno 650"),
+    ("sm8250", "Adreno 650"),
+    ("lahaina", "Adreno 660"),
+    ("sm8350", "Adreno 660"),
+    ("taro", "Adreno 730"),
+    ("kalama", "Adreno 740"),
+    ("mt6765", "PowerVR GE8320"),
+    ("mt6768", "Mali-G52 MC2"),
+    ("mt6785", "Mali-G76 MC4"),
+    ("mt6853", "Mali-G57 MC3"),
+    ("mt6893", "Mali-G77 MC9"),
+    ("exynos9810", "Mali-G72 MP18"),
+    ("exynos9820", "Mali-G76 MP12"),
+    ("exynos990", "Mali-G77 MP11"),
+    ("universal9810", "Mali-G72 MP18"),
+];
+
+// GPU node compatibles, TARGET_BOARD_PLATFORM(_GPU) and the graphics HAL and
+// driver packages, i.e. which GPU the tree is built for and what drives it
+#[derive(Debug, Default)]
+pub struct GpuInfo {
+    pub platform: Option<Var>,
+    // TARGET_BOARD_PLATFORM_GPU, e.g. qcom-adreno540 on older trees
+    pub platform_gpu: Option<Var>,
+    pub bindings: Vec<GpuBinding>,
+    // gralloc and graphics.allocator PRODUCT_PACKAGES entries
+    pub gralloc_packages: Vec<String>,
+    pub mapper_packages: Vec<String>,
+    // hwcomposer and graphics.composer
+    pub composer_packages: Vec<String>,
+    // EGL/GLES/Vulkan driver libraries (libEGL_adreno, vulkan.msmnile, ...)
+    pub driver_packages: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Var {
+    pub value: String,
+    pub location: String,
+}
+
+#[derive(Debug)]
+pub struct GpuBinding {
+    pub node: String,
+    pub compatible: String,
+    // "Adreno 640", from the compatible or qcom,chipid
+    pub model: Option<String>,
+    pub locations: Vec<String>,
+}
+
+impl GpuInfo {
+    pub fn is_empty(&self) -> bool {
+        self.platform.is_none()
+            && self.platform_gpu.is_none()
+            && self.bindings.is_empty()
+            && self.gralloc_packages.is_empty()
+            && self.mapper_packages.is_empty()
+            && self.composer_packages.is_empty()
+            && self.driver_packages.is_empty()
+    }
+
+    // The GPU TARGET_BOARD_PLATFORM's SoC ships with, from SOC_GPUS
+    pub fn expected_gpu(&self) -> Option<&'static str> {
+        let platform = self.platform.as_ref()?.value.to_lowercase();
+        SOC_GPUS.iter().find(|(soc, _)| *soc == platform).map(|(_, gpu)| *gpu)
+    }
+
+    // The GPU model and what it was read from: a DTS binding first, then
+    // TARGET_BOARD_PLATFORM_GPU, then the SoC table
+    pub fn model(&self) -> Option<(String, String)> {
+        if let Some(binding) = self.bindings.iter().find(|binding| binding.model.is_some()) {
+            return Some((binding.model.clone()?, format!("{} binding", binding.compatible)));
+        }
+        if let Some(var) = &self.platform_gpu
+            && let Some(model) = platform_gpu_model(&var.value)
+        {
+            return Some((model, format!("TARGET_BOARD_PLATFORM_GPU = {}", var.value)));
+        }
+        let platform = self.platform.as_ref()?;
+        Some((self.expected_gpu()?.to_string(), format!("expected for {}", platform.value)))
+    }
+
+    // (found in the DTS, expected for the platform) when the two disagree.
+    // The table's core counts ("MC4") aren't in the DTS, so only the model
+    // name is compared.
+    pub fn platform_mismatch(&self) -> Option<(&str, &'static str)> {
+        let expected = self.expected_gpu()?;
+        let found = self.bindings.iter().find_map(|binding| binding.model.as_deref())?;
+        let family = expected.split(" MC").next().and_then(|gpu| gpu.split(" MP").next()).unwrap_or(expected);
+        (!family.eq_ignore_ascii_case(found)).then_some((found, expected))
+    }
+}
+
+pub fn scan_gpu(
+    sources: &DtsSources,
+    device_mk: Option<&Path>,
+    board_config: Option<&Path>,
+    walk_options: &WalkOptions,
+) -> GpuInfo {
+    let mut gpu = GpuInfo::default();
+
+    for node in sources.nodes.values() {
+        let compatibles = node.strings("compatible");
+        let Some(compatible) = compatibles.iter().find(|c| GPU_COMPATIBLES.iter().any(|prefix| c.starts_with(prefix)))
+        else {
+            continue;
+        };
+        // The most specific compatible names the model; a kgsl node has it
+        // in qcom,chipid instead
+        let model = compatibles
+            .iter()
+            .find_map(|compatible| compatible_model(compatible))
+            .or_else(|| node.cells("qcom,chipid").first().and_then(|id| chipid_model(*id)));
+        gpu.bindings.push(GpuBinding {
+            node: node.path.clone(),
+            compatible: compatible.to_string(),
+            model,
+            locations: node.locations.clone(),
+        });
+    }
+
+    // Unreadable makefiles are already reported by the driver scan
+    for makefile in [board_config, device_mk].into_iter().flatten() {
+        if !walk_options.includes_file(makefile) {
+            continue;
+        }
+        let Ok(content) = fsread::read_to_string(makefile) else {
+            continue;
+        };
+        let file_name = makefile.file_name().unwrap_or_default().to_string_lossy().to_string();
+        for (line, statement) in statements(&content) {
+            let Some(makefile::Assignment { name, value, .. }) = makefile::parse_assignment(&statement) else {
+                continue;
+            };
+            let var = || Var {
+                value: value.trim().trim_matches('"').to_string(),
+                location: format!("{}:{}", file_name, line),
+            };
+            match name {
+                "TARGET_BOARD_PLATFORM" => gpu.platform = Some(var()),
+                "TARGET_BOARD_PLATFORM_GPU" => gpu.platform_gpu = Some(var()),
+                "PRODUCT_PACKAGES" => {
+                    for package in value.split_whitespace() {
+                        add_package(&mut gpu, package);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    gpu
+}
+
+fn add_package(gpu: &mut GpuInfo, package: &str) {
+    let lower = package.to_lowercase();
+    let list = if lower.contains("gralloc") || lower.contains("graphics.allocator") {
+        &mut gpu.gralloc_packages
+    } else if lower.contains("graphics.mapper") {
+        &mut gpu.mapper_packages
+    } else if lower.contains("hwcomposer") || lower.contains("graphics.composer") {
+        &mut gpu.composer_packages
+    } else if lower.starts_with("libegl")
+        || lower.starts_with("libgles")
+        || lower.starts_with("vulkan.")
+        || lower.contains("libvulkan")
+        || lower.contains("adreno")
+        || lower.contains("mali")
+    {
+        &mut gpu.driver_packages
+    } else {
+        return;
+    };
+    if !list.iter().any(|p| p == package) {
+        list.push(package.to_string());
+    }
+}
+
+// "qcom,adreno-630.2" -> Adreno 630, "arm,mali-g72" -> Mali-G72; family
+// compatibles (qcom,adreno, arm,mali-bifrost) name no model
+fn compatible_model(compatible: &str) -> Option<String> {
+    if let Some(rest) = compatible.strip_prefix("qcom,adreno-") {
+        let number = rest.split('.').next()?;
+        return number.chars().all(|c| c.is_ascii_digit()).then(|| format!("Adreno {}", number));
+    }
+    let rest = compatible.strip_prefix("arm,mali-")?;
+    let mut chars = rest.chars();
+    let series = chars.next()?.to_ascii_uppercase();
+    let number = chars.as_str();
+    let is_model = matches!(series, 'G' | 'T') && !number.is_empty() && number.chars().all(|c| c.is_ascii_digit());
+    is_model.then(|| format!("Mali-{}{}", series, number))
+}
+
+// Downstream kgsl's qcom,chipid is 0xCCMMmmPP (core, major, minor, patch):
+// 0x06040001 -> Adreno 640
+fn chipid_model(chipid: u64) -> Option<String> {
+    let core = (chipid >> 24) & 0xff;
+    let major = (chipid >> 16) & 0xff;
+    let minor = (chipid >> 8) & 0xff;
+    (core > 0 && major < 10 && minor < 10).then(|| format!("Adreno {}", core * 100 + major * 10 + minor))
+}
+
+// "qcom-adreno540" -> Adreno 540
+fn platform_gpu_model(value: &str) -> Option<String> {
+    let lower = value.to_lowercase();
+    let number = lower.split("adreno").nth(1)?.trim_start_matches(['-', '_']);
+    (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit())).then(|| format!("Adreno {}", number))
+}
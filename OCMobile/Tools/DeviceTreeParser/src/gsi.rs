@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use xml::reader::{EventReader, XmlEvent};
+use crate::boot::BootInfo;
+use crate::fsread;
+use crate::makefile::{self, statements};
+use crate::treble::TrebleInfo;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Makefile variables a GSI cares about that the Treble and Boot scans don't
+// already read
+const GSI_VARIABLES: [&str; 5] = [
+    "BOARD_BUILD_SYSTEM_ROOT_IMAGE",
+    "PRODUCT_USE_DYNAMIC_PARTITIONS",
+    "PRODUCT_RETROFIT_DYNAMIC_PARTITIONS",
+    "BOARD_SUPER_PARTITION_SIZE",
+    "PRODUCT_SHIPPING_API_LEVEL",
+];
+
+// What decides whether a generic system image can boot on top of this
+// vendor: the variables above and the device VINTF manifests
+#[derive(Debug, Default)]
+pub struct GsiInfo {
+    pub vars: BTreeMap<String, Var>,
+    pub manifests: Vec<Manifest>,
+}
+
+#[derive(Debug)]
+pub struct Var {
+    pub value: String,
+    pub location: String,
+}
+
+// A <manifest type="device"> VINTF manifest
+#[derive(Debug)]
+pub struct Manifest {
+    pub file: String,
+    // target-level, the Framework Compatibility Matrix version the vendor
+    // was built against
+    pub target_level: Option<String>,
+}
+
+// One line of the checklist; only the required checks decide the verdict
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    pub required: bool,
+    pub evidence: String,
+}
+
+impl GsiInfo {
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty() && self.manifests.is_empty()
+    }
+
+    pub fn files(&self) -> Vec<String> {
+        self.manifests.iter().map(|manifest| manifest.file.clone()).collect()
+    }
+
+    // The highest target-level among the device manifests, with its file
+    pub fn target_fcm(&self) -> Option<(&str, &str)> {
+        self.manifests
+            .iter()
+            .filter_map(|manifest| Some((manifest.target_level.as_deref()?, manifest.file.as_str())))
+            .max_by_key(|(level, _)| level.parse::<u32>().ok())
+    }
+
+    fn enabled(&self, name: &str) -> Option<&Var> {
+        self.vars.get(name).filter(|var| var.value == "true")
+    }
+}
+
+// The GSI checklist, pulled together from the Treble/VNDK, Boot and GSI
+// scans. Each check carries the flag or file that decided it.
+pub fn checklist(treble: &TrebleInfo, boot: &BootInfo, gsi: &GsiInfo) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let treble_evidence = ["PRODUCT_FULL_TREBLE_OVERRIDE", "PRODUCT_FULL_TREBLE", "PRODUCT_TREBLE_LINKER_NAMESPACES"]
+        .iter()
+        .find_map(|name| treble.flags.get(*name).map(|value| format!("{} = {}", name, value)))
+        .or_else(|| treble.vndk_version.as_ref().map(|version| format!("implied by BOARD_VNDK_VERSION = {}", version)))
+        .unwrap_or_else(|| "no PRODUCT_FULL_TREBLE or Treble flags".to_string());
+    checks.push(Check {
+        name: "Treble enabled",
+        passed: treble.is_compatible(),
+        required: true,
+        evidence: treble_evidence,
+    });
+
+    let vndk_evidence = match (&treble.vndk_version, treble.vndk_dirs.first()) {
+        (Some(version), _) => format!("BOARD_VNDK_VERSION = {}", version),
+        (None, Some(dir)) => format!("VNDK directory {}", dir),
+        (None, None) => "no BOARD_VNDK_VERSION or vndk directories".to_string(),
+    };
+    checks.push(Check {
+        name: "VNDK present",
+        passed: treble.vndk_version.is_some() || !treble.vndk_dirs.is_empty(),
+        required: true,
+        evidence: vndk_evidence,
+    });
+
+    // Dynamic partitions put system in super and boot it through first-stage
+    // init, which is system-as-root as far as a GSI is concerned
+    let dynamic = ["PRODUCT_USE_DYNAMIC_PARTITIONS", "PRODUCT_RETROFIT_DYNAMIC_PARTITIONS"]
+        .iter()
+        .find_map(|name| gsi.enabled(name).map(|var| format!("{} = true ({})", name, var.location)))
+        .or_else(|| {
+            let var = gsi.vars.get("BOARD_SUPER_PARTITION_SIZE")?;
+            Some(format!("BOARD_SUPER_PARTITION_SIZE = {} ({})", var.value, var.location))
+        });
+    let system_root = gsi.vars.get("BOARD_BUILD_SYSTEM_ROOT_IMAGE");
+    let (passed, evidence) = match (system_root, &dynamic) {
+        (Some(var), _) if var.value == "true" => {
+            (true, format!("BOARD_BUILD_SYSTEM_ROOT_IMAGE = true ({})", var.location))
+        }
+        (_, Some(dynamic)) => (true, format!("implied by {}", dynamic)),
+        (Some(var), None) => (false, format!("BOARD_BUILD_SYSTEM_ROOT_IMAGE = {} ({})", var.value, var.location)),
+        (None, None) => (false, "no BOARD_BUILD_SYSTEM_ROOT_IMAGE or dynamic partitions".to_string()),
+    };
+    checks.push(Check { name: "System-as-root", passed, required: true, evidence });
+
+    checks.push(Check {
+        name: "Dynamic partitions",
+        passed: dynamic.is_some(),
+        required: false,
+        evidence: dynamic.unwrap_or_else(|| "no PRODUCT_USE_DYNAMIC_PARTITIONS or super partition".to_string()),
+    });
+
+    let ab_evidence = if boot.ab_updater {
+        format!("AB_OTA_UPDATER = true, {} AB_OTA_PARTITIONS", boot.ab_partitions.len())
+    } else if !boot.ab_partitions.is_empty() {
+        format!("{} AB_OTA_PARTITIONS", boot.ab_partitions.len())
+    } else {
+        "no AB_OTA_UPDATER; needs an A-only GSI".to_string()
+    };
+    checks.push(Check {
+        name: "Seamless updates (A/B)",
+        passed: boot.is_ab(),
+        required: false,
+        evidence: ab_evidence,
+    });
+
+    let (passed, evidence) = match gsi.target_fcm() {
+        Some((level, file)) => (true, format!("target-level {} ({})", level, file)),
+        None if !gsi.manifests.is_empty() => (false, "device manifest has no target-level".to_string()),
+        None => (false, "no device VINTF manifest".to_string()),
+    };
+    checks.push(Check { name: "VINTF manifest", passed, required: false, evidence });
+
+    checks
+}
+
+// Whether every required check passed
+pub fn likely_to_boot(checks: &[Check]) -> bool {
+    checks.iter().filter(|check| check.required).all(|check| check.passed)
+}
+
+pub fn scan_gsi(
+    tree_path: &Path,
+    makefiles: &[&Path],
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> GsiInfo {
+    let mut gsi = GsiInfo::default();
+
+    // Unreadable makefiles are already reported by the driver scan
+    for makefile in makefiles {
+        if !walk_options.includes_file(makefile) {
+            continue;
+        }
+        let Ok(content) = fsread::read_to_string(makefile) else {
+            continue;
+        };
+        let file_name = makefile.file_name().unwrap_or_default().to_string_lossy().to_string();
+        for (line, statement) in statements(&content) {
+            let Some(makefile::Assignment { name, value, .. }) = makefile::parse_assignment(&statement) else {
+                continue;
+            };
+            if GSI_VARIABLES.contains(&name) {
+                gsi.vars.insert(
+                    name.to_string(),
+                    Var {
+                        value: value.trim().trim_matches('"').to_string(),
+                        location: format!("{}:{}", file_name, line),
+                    },
+                );
+            }
+        }
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    let is_manifest = |name: &str| name.ends_with(".xml") && name.contains("manifest");
+    walk::find_files(tree_path, walk_options, 0, &is_manifest, &mut files);
+    files.sort();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        match fsread::read_to_string(&file) {
+            Ok(content) => {
+                if let Some(manifest) = parse_manifest(&content, &relative) {
+                    gsi.manifests.push(manifest);
+                }
+            }
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+        }
+    }
+
+    gsi
+}
+
+// <manifest version="2.0" type="device" target-level="5">; framework
+// manifests and anything else are skipped
+fn parse_manifest(content: &str, relative: &str) -> Option<Manifest> {
+    let mut parser = EventReader::new(content.as_bytes());
+    loop {
+        match parser.next() {
+            Ok(XmlEvent::StartElement { name, attributes, .. }) => {
+                let attribute = |key: &str| attributes.iter().find(|a| a.name.local_name == key).map(|a| &a.value);
+                if name.local_name != "manifest" || attribute("type").is_none_or(|kind| kind != "device") {
+                    return None;
+                }
+                return Some(Manifest {
+                    file: relative.to_string(),
+                    target_level: attribute("target-level").map(|level| level.trim().to_string()),
+                });
+            }
+            Ok(XmlEvent::EndDocument) | Err(_) => return None,
+            Ok(_) => {}
+        }
+    }
+}
@@ -0,0 +1,171 @@
+// HAL packages from PRODUCT_PACKAGES, grouped so that the HIDL and AIDL
+// builds of one HAL (android.hardware.power@1.3-service.pixel,
+// android.hardware.power-service.pixel-libperfmgr) count once
+
+// Interfaces whose AIDL version was renamed or split, mapped to the family
+// they're reported under. Anything not listed is its own family.
+const FAMILY_RULES: [(&str, &str); 5] = [
+    ("keymaster", "security.keymint"),
+    ("audio.core", "audio"),
+    ("graphics.composer3", "graphics.composer"),
+    ("tetheroffload.config", "tetheroffload"),
+    ("tetheroffload.control", "tetheroffload"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Transport {
+    Hidl,
+    Aidl,
+    // Unversioned wrappers such as android.hardware.audio.service
+    Unknown,
+}
+
+#[derive(Debug)]
+pub struct Hal {
+    // Interface name below android.hardware., e.g. "power"
+    pub family: String,
+    pub packages: Vec<(Transport, String)>,
+}
+
+impl Transport {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transport::Hidl => "HIDL",
+            Transport::Aidl => "AIDL",
+            Transport::Unknown => "unknown",
+        }
+    }
+}
+
+impl Hal {
+    pub fn transports(&self) -> Vec<Transport> {
+        let mut transports: Vec<Transport> = self.packages.iter().map(|(transport, _)| *transport).collect();
+        transports.sort();
+        transports.dedup();
+        transports
+    }
+
+    // The package itself when there's only one, otherwise
+    // "android.hardware.power [HIDL+AIDL]: <packages>"
+    pub fn summary(&self) -> String {
+        if let [(_, package)] = self.packages.as_slice() {
+            return package.clone();
+        }
+        let transports: Vec<&str> = self.transports().iter().map(|t| t.as_str()).collect();
+        let packages: Vec<&str> = self.packages.iter().map(|(_, package)| package.as_str()).collect();
+        format!("android.hardware.{} [{}]: {}", self.family, transports.join("+"), packages.join(", "))
+    }
+}
+
+// Groups packages by family, keeping the order families first appear in
+pub fn normalize(packages: &[String]) -> Vec<Hal> {
+    let mut hals: Vec<Hal> = Vec::new();
+    for package in packages {
+        let Some((interface, transport)) = parse_package(package) else {
+            continue;
+        };
+        let family = family(interface);
+        let entry = (transport, package.clone());
+        match hals.iter_mut().find(|hal| hal.family == family) {
+            Some(hal) if hal.packages.contains(&entry) => {}
+            Some(hal) => hal.packages.push(entry),
+            None => hals.push(Hal { family: family.to_string(), packages: vec![entry] }),
+        }
+    }
+    hals
+}
+
+// android.hardware.<interface>@<version>...  -> HIDL
+// android.hardware.<interface>-service...    -> AIDL (also -V<n>-ndk libs)
+// android.hardware.<interface>.service/.impl -> transport unknown
+pub fn parse_package(package: &str) -> Option<(&str, Transport)> {
+    let name = package.strip_prefix("android.hardware.")?;
+
+    if let Some((interface, _)) = name.split_once('@') {
+        return Some((interface, Transport::Hidl));
+    }
+    if let Some((interface, suffix)) = name.split_once('-')
+        && (suffix.starts_with("service")
+            || suffix.starts_with("impl")
+            || suffix.strip_prefix('V').is_some_and(|v| v.starts_with(|c: char| c.is_ascii_digit())))
+    {
+        return Some((interface, Transport::Aidl));
+    }
+
+    let interface = name.strip_suffix(".service").or_else(|| name.strip_suffix(".impl")).unwrap_or(name);
+    Some((interface, Transport::Unknown))
+}
+
+fn family(interface: &str) -> &str {
+    FAMILY_RULES
+        .iter()
+        .find(|(name, _)| *name == interface)
+        .map_or(interface, |(_, family)| family)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn families(packages: &[&str]) -> Vec<(String, Vec<Transport>)> {
+        let packages: Vec<String> = packages.iter().map(|package| package.to_string()).collect();
+        normalize(&packages).into_iter().map(|hal| (hal.family.clone(), hal.transports())).collect()
+    }
+
+    #[test]
+    fn transports_come_from_the_package_name() {
+        assert_eq!(parse_package("android.hardware.power@1.3-service.pixel"), Some(("power", Transport::Hidl)));
+        assert_eq!(parse_package("android.hardware.power-service.pixel"), Some(("power", Transport::Aidl)));
+        assert_eq!(parse_package("android.hardware.power-V4-ndk"), Some(("power", Transport::Aidl)));
+        assert_eq!(parse_package("android.hardware.audio.service"), Some(("audio", Transport::Unknown)));
+        assert_eq!(parse_package("vendor.qti.hardware.perf@2.0"), None);
+    }
+
+    #[test]
+    fn hidl_and_aidl_builds_share_a_family() {
+        let hals = families(&[
+            "android.hardware.power@1.3-service.pixel",
+            "android.hardware.power-service.pixel-libperfmgr",
+            "android.hardware.light@2.0-service",
+        ]);
+        assert_eq!(
+            hals,
+            [
+                ("power".to_string(), vec![Transport::Hidl, Transport::Aidl]),
+                ("light".to_string(), vec![Transport::Hidl]),
+            ]
+        );
+    }
+
+    #[test]
+    fn renamed_interfaces_follow_the_family_rules() {
+        let hals = families(&[
+            "android.hardware.keymaster@4.1-service",
+            "android.hardware.security.keymint-service",
+            "android.hardware.audio.core-impl",
+            "android.hardware.audio.service",
+            "android.hardware.graphics.composer3-service",
+            "android.hardware.tetheroffload.config@1.0",
+            "android.hardware.tetheroffload.control@1.1",
+        ]);
+        let names: Vec<&str> = hals.iter().map(|(family, _)| family.as_str()).collect();
+        assert_eq!(names, ["security.keymint", "audio", "graphics.composer", "tetheroffload"]);
+    }
+
+    #[test]
+    fn a_lone_package_is_its_own_summary() {
+        let hals = normalize(&["android.hardware.light@2.0-service".to_string()]);
+        assert_eq!(hals[0].summary(), "android.hardware.light@2.0-service");
+
+        let hals = normalize(&[
+            "android.hardware.power@1.3-service.pixel".to_string(),
+            "android.hardware.power-service.pixel".to_string(),
+            "android.hardware.power-service.pixel".to_string(),
+        ]);
+        assert_eq!(
+            hals[0].summary(),
+            "android.hardware.power [HIDL+AIDL]: android.hardware.power@1.3-service.pixel, \
+             android.hardware.power-service.pixel"
+        );
+    }
+}
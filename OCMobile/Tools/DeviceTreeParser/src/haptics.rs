@@ -0,0 +1,142 @@
+use std::path::{Path, PathBuf};
+use crate::dts::{DtsNode, DtsSources};
+use crate::fsread;
+use crate::makefile::{split_assignment, statements};
+use crate::walk::{self, WalkOptions};
+
+// Compatible fragments of vibrator/haptics controllers: generic names, TI
+// DRV26xx, Awinic AW86xx, Cirrus CS40Lxx and the Qualcomm PMIC haptics blocks
+const HAPTIC_MARKERS: [&str; 6] = ["vibrator", "haptic", "drv26", "aw86", "cs40l", "sih68"];
+
+// Build files that mention vibrators without being config
+const SKIPPED_EXTENSIONS: [&str; 4] = [".mk", ".bp", ".dts", ".dtsi"];
+
+// Vibrator controllers from the DTS, the vibrator HAL packages from device.mk
+// and any haptics config shipped with the tree
+#[derive(Debug, Default)]
+pub struct Haptics {
+    pub controllers: Vec<HapticController>,
+    // Every vibrator/haptics PRODUCT_PACKAGES entry
+    pub hal_packages: Vec<String>,
+    pub config_files: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct HapticController {
+    pub node: String,
+    pub vendor: String,
+    pub compatible: Vec<String>,
+    // HAL packages that belong to this controller (see correlate)
+    pub hal_packages: Vec<String>,
+    pub locations: Vec<String>,
+}
+
+impl Haptics {
+    pub fn is_empty(&self) -> bool {
+        self.controllers.is_empty() && self.hal_packages.is_empty() && self.config_files.is_empty()
+    }
+}
+
+pub fn scan_haptics(
+    tree_path: &Path,
+    sources: &DtsSources,
+    device_mk: Option<&Path>,
+    walk_options: &WalkOptions,
+) -> Haptics {
+    let mut haptics = Haptics::default();
+
+    for node in sources.nodes.values() {
+        let compatible: Vec<String> = node.strings("compatible").into_iter().map(|s| s.to_string()).collect();
+        if !is_haptic(node, &compatible) {
+            continue;
+        }
+        haptics.controllers.push(HapticController {
+            node: node.path.clone(),
+            vendor: compatible
+                .iter()
+                .find_map(|c| c.split_once(',').map(|(vendor, _)| vendor.to_string()))
+                .unwrap_or_else(|| "unknown".to_string()),
+            compatible,
+            locations: node.locations.clone(),
+            ..Default::default()
+        });
+    }
+
+    // Unreadable device.mk is already reported by the driver scan
+    if let Some(device_mk) = device_mk
+        && walk_options.includes_file(device_mk)
+        && let Ok(content) = fsread::read_to_string(device_mk)
+    {
+        for (_, statement) in statements(&content) {
+            let Some(("PRODUCT_PACKAGES", value)) = split_assignment(&statement) else {
+                continue;
+            };
+            for package in value.split_whitespace() {
+                let lower = package.to_lowercase();
+                if (lower.contains("vibrator") || lower.contains("haptic"))
+                    && !haptics.hal_packages.iter().any(|p| p == package)
+                {
+                    haptics.hal_packages.push(package.to_string());
+                }
+            }
+        }
+    }
+
+    correlate(&mut haptics);
+    haptics.config_files = find_config_files(tree_path, walk_options);
+    haptics
+}
+
+fn is_haptic(node: &DtsNode, compatible: &[String]) -> bool {
+    // The PMIC blocks are "qcom,...-haptics"; a bare "vibrator" node name
+    // without a compatible is only a placeholder
+    !compatible.is_empty()
+        && (node.name().contains("vib") || node.name().contains("haptic")
+            || compatible.iter().any(|c| HAPTIC_MARKERS.iter().any(|marker| c.contains(marker))))
+}
+
+// A HAL package belongs to a controller when it names the controller's vendor
+// or part ("vendor.awinic.vibrator", "vibrator-service.cs40l25"). With a
+// single controller, a generic HAL (android.hardware.vibrator-service) can
+// only be driving that one.
+fn correlate(haptics: &mut Haptics) {
+    let single = haptics.controllers.len() == 1;
+    for controller in &mut haptics.controllers {
+        let parts: Vec<String> = controller
+            .compatible
+            .iter()
+            .filter_map(|c| c.split_once(',').map(|(_, part)| part))
+            .filter_map(|part| part.split(['-', '_']).next())
+            .map(|part| part.to_lowercase())
+            .collect();
+
+        for package in &haptics.hal_packages {
+            let lower = package.to_lowercase();
+            let named = lower.contains(&controller.vendor) || parts.iter().any(|part| lower.contains(part.as_str()));
+            if named || single {
+                controller.hal_packages.push(package.clone());
+            }
+        }
+    }
+}
+
+fn find_config_files(tree_path: &Path, walk_options: &WalkOptions) -> Vec<String> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| {
+            let lower = name.to_lowercase();
+            (lower.contains("haptic") || lower.contains("vibrator"))
+                && !SKIPPED_EXTENSIONS.iter().any(|ext| lower.ends_with(ext))
+        },
+        &mut files,
+    );
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|file| file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string())
+        .collect()
+}
@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
+use crate::fsread;
+use crate::HardwareReport;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Hex SHA-256 of a file, streamed so large firmware blobs aren't loaded whole
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = fsread::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Hashes every file the report refers to, keyed by path relative to the tree
+pub fn hash_report_files(
+    tree_path: &Path,
+    report: &HardwareReport,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> BTreeMap<String, String> {
+    let mut files: Vec<String> = Vec::new();
+
+    for (name, found) in &report.key_files {
+        if *found {
+            files.push(report.key_names_on_disk.get(name).unwrap_or(name).clone());
+        }
+    }
+    files.extend(report.dts_sources.files.iter().cloned());
+    files.extend(report.dtb_overlays.prebuilts.iter().cloned());
+    files.extend(report.dtb_overlays.config_files.iter().cloned());
+    for entry in &report.copied_files.entries {
+        if entry.in_tree && tree_path.join(&entry.source).is_file() {
+            files.push(entry.source.clone());
+        }
+    }
+    for touchscreen in &report.touchscreens {
+        files.extend(touchscreen.firmware_files.iter().cloned());
+    }
+    files.extend(report.linker_namespaces.files());
+    files.extend(report.gsi.files());
+    files.extend(report.encryption.fstab_files.iter().cloned());
+    for module in &report.apex.modules {
+        files.extend(module.files.iter().cloned());
+    }
+    files.extend(report.camera.config_files.iter().map(|config| config.file.clone()));
+    files.extend(report.camera.sensor_libs.iter().cloned());
+    files.extend(report.haptics.config_files.iter().cloned());
+    files.extend(report.biometrics.config_files.iter().cloned());
+    files.extend(report.drm.files());
+    files.extend(report.leds.rc_files());
+    files.extend(report.boot_sequence.rc_files.iter().cloned());
+    files.extend(report.declared_features.files.iter().cloned());
+    files.extend(report.wifi.configs.files.iter().map(|config| config.file.clone()));
+    files.extend(report.usb.files.iter().cloned());
+    files.extend(report.nfc.config_files.iter().map(|config| config.file.clone()));
+    files.extend(report.battery.profile_files.iter().map(|profile| profile.file.clone()));
+    files.extend(report.vendor_flags.files.iter().cloned());
+    files.extend(report.mixer_paths.files.iter().map(|mixer| mixer.file.clone()));
+    files.extend(report.perf_tuning.files.iter().map(|perf| perf.file.clone()));
+    files.extend(report.key_layouts.files.iter().map(|layout| layout.file.clone()));
+    files.extend(report.carrier_config.apn_files.iter().cloned());
+    files.extend(report.carrier_config.config_files.iter().cloned());
+    files.extend(report.carrier_config.carrier_list_blobs.iter().cloned());
+    files.extend(report.telephony.overlay_files.iter().cloned());
+    files.extend(report.module_consistency.prebuilt.iter().cloned());
+    files.extend(report.modem.firmware.iter().map(|firmware| firmware.file.clone()));
+    files.extend(report.kernel.prebuilt_image.iter().cloned());
+    files.extend(report.power.hint_files.iter().cloned());
+    files.extend(report.power.profile_files.iter().cloned());
+    files.extend(report.media_profiles.files.iter().cloned());
+    files.extend(report.cpu_topology.files.iter().cloned());
+    files.extend(report.filesystem_setup.rc_files.iter().cloned());
+
+    // Prebuilt modules are only recorded by name, so find their paths again
+    for dir in ["prebuilt", "proprietary", "vendor"] {
+        let mut modules: Vec<PathBuf> = Vec::new();
+        walk::find_files(&tree_path.join(dir), walk_options, 1, &|name| name.ends_with(".ko"), &mut modules);
+        for module in modules {
+            let relative = module.strip_prefix(tree_path).unwrap_or(&module);
+            files.push(relative.to_string_lossy().to_string());
+        }
+    }
+
+    let mut hashes = BTreeMap::new();
+    for file in files {
+        match sha256_file(&tree_path.join(&file)) {
+            Ok(hash) => {
+                hashes.insert(file, hash);
+            }
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, file, format!("could not hash: {}", e))),
+        }
+    }
+
+    hashes
+}
@@ -0,0 +1,538 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// mount/mkdir/symlink commands from init .rc scripts, i.e. the layout init
+// sets up at runtime on top of what fstab mounts
+#[derive(Debug, Default)]
+pub struct FilesystemSetup {
+    pub rc_files: Vec<String>,
+    pub mounts: Vec<Mount>,
+    pub directories: Vec<Directory>,
+    pub symlinks: Vec<Symlink>,
+    // ${ro.*} style properties referenced by these commands. They are left
+    // unexpanded since their values are only known on the device.
+    pub properties: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+pub struct Mount {
+    pub fs_type: String,
+    pub device: String,
+    pub target: String,
+    pub options: Vec<String>,
+    pub trigger: String,
+    pub location: String,
+}
+
+#[derive(Debug)]
+pub struct Directory {
+    pub path: String,
+    pub mode: Option<String>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub trigger: String,
+    pub location: String,
+}
+
+#[derive(Debug)]
+pub struct Symlink {
+    pub target: String,
+    pub link: String,
+    pub trigger: String,
+    pub location: String,
+}
+
+impl FilesystemSetup {
+    pub fn is_empty(&self) -> bool {
+        self.mounts.is_empty() && self.directories.is_empty() && self.symlinks.is_empty()
+    }
+
+    // Filesystem type -> number of mounts, e.g. tmpfs (3)
+    pub fn mount_types(&self) -> BTreeMap<&str, usize> {
+        let mut types = BTreeMap::new();
+        for mount in &self.mounts {
+            *types.entry(mount.fs_type.as_str()).or_insert(0) += 1;
+        }
+        types
+    }
+}
+
+pub fn scan_filesystem_setup(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> FilesystemSetup {
+    // ueventd*.rc use a different syntax (device node permissions)
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| name.ends_with(".rc") && !name.starts_with("ueventd"),
+        &mut files,
+    );
+    files.sort();
+
+    let mut setup = FilesystemSetup::default();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        match fsread::read_to_string(&file) {
+            Ok(content) => {
+                let before = setup.mounts.len() + setup.directories.len() + setup.symlinks.len();
+                parse_rc(&content, &relative, &mut setup);
+                if setup.mounts.len() + setup.directories.len() + setup.symlinks.len() > before {
+                    setup.rc_files.push(relative);
+                }
+            }
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+        }
+    }
+
+    setup
+}
+
+// (line, statement) pairs, with comments and blank lines dropped and
+// backslash continuations joined onto the line they start on
+fn statements(content: &str) -> Vec<(usize, String)> {
+    let mut statements = Vec::new();
+    let mut pending = String::new();
+    let mut start_line = 0;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if pending.is_empty() {
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            start_line = index + 1;
+        }
+
+        // Backslash continues a command on the next line
+        if let Some(continued) = trimmed.strip_suffix('\\') {
+            pending.push_str(continued);
+            pending.push(' ');
+            continue;
+        }
+        pending.push_str(trimmed);
+        statements.push((start_line, std::mem::take(&mut pending)));
+    }
+    statements
+}
+
+fn parse_rc(content: &str, file: &str, setup: &mut FilesystemSetup) {
+    // Commands only run inside "on <trigger>" sections; "service" sections
+    // hold options, not commands
+    let mut trigger: Option<String> = None;
+
+    for (line, statement) in statements(content) {
+        let tokens: Vec<&str> = statement.split_whitespace().collect();
+        let location = format!("{}:{}", file, line);
+        match tokens.first().copied() {
+            Some("on") => trigger = Some(tokens[1..].join(" ")),
+            Some("service") | Some("import") => trigger = None,
+            Some(command) => {
+                if let Some(trigger) = &trigger {
+                    parse_command(command, &tokens[1..], trigger, location, setup);
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+fn parse_command(command: &str, args: &[&str], trigger: &str, location: String, setup: &mut FilesystemSetup) {
+    let referenced = match command {
+        // mount <type> <device> <dir> [<flag>...] [<options>]
+        "mount" if args.len() >= 3 => {
+            setup.mounts.push(Mount {
+                fs_type: args[0].to_string(),
+                device: args[1].to_string(),
+                target: args[2].to_string(),
+                options: args[3..].iter().map(|s| s.to_string()).collect(),
+                trigger: trigger.to_string(),
+                location,
+            });
+            args
+        }
+        // mkdir <path> [<mode>] [<owner>] [<group>] [encryption=...]
+        "mkdir" if !args.is_empty() => {
+            let positional: Vec<&str> = args.iter().copied().filter(|a| !a.contains('=')).collect();
+            setup.directories.push(Directory {
+                path: args[0].to_string(),
+                mode: positional.get(1).map(|s| s.to_string()),
+                owner: positional.get(2).map(|s| s.to_string()),
+                group: positional.get(3).map(|s| s.to_string()),
+                trigger: trigger.to_string(),
+                location,
+            });
+            args
+        }
+        // symlink <target> <path>
+        "symlink" if args.len() >= 2 => {
+            setup.symlinks.push(Symlink {
+                target: args[0].to_string(),
+                link: args[1].to_string(),
+                trigger: trigger.to_string(),
+                location,
+            });
+            args
+        }
+        _ => return,
+    };
+
+    for arg in referenced {
+        collect_properties(arg, &mut setup.properties);
+    }
+}
+
+fn collect_properties(arg: &str, properties: &mut BTreeSet<String>) {
+    let mut rest = arg;
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        match after.find('}') {
+            Some(end) => {
+                // ${prop:-default} keeps only the property name
+                let name = after[..end].split(":-").next().unwrap_or("");
+                properties.insert(name.to_string());
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+}
+
+// Triggers in the order init reaches them during a normal boot; "on" sections
+// for anything else (property triggers, custom events) come after these
+const BOOT_STAGES: [&str; 12] = [
+    "early-init",
+    "init",
+    "late-init",
+    "early-fs",
+    "fs",
+    "post-fs",
+    "late-fs",
+    "post-fs-data",
+    "zygote-start",
+    "early-boot",
+    "boot",
+    "nonencrypted",
+];
+
+// Classes the platform's init.rc starts, and on which trigger. A device tree
+// rarely has class_start lines of its own, so without these most services
+// would look like nothing starts them.
+const PLATFORM_CLASS_STARTS: [(&str, &str); 5] = [
+    ("early_hal", "late-fs"),
+    ("core", "boot"),
+    ("hal", "boot"),
+    ("main", "nonencrypted"),
+    ("late_start", "nonencrypted"),
+];
+
+// Location given to the class starts above
+pub const PLATFORM_INIT_RC: &str = "platform init.rc";
+
+// Services and the triggers that start them: "start"/"restart"/"enable"
+// name one service, "class_start" every enabled service of a class. Every
+// .rc in the tree is read, so imports don't change what's in the map; they
+// are resolved to show how the scripts chain and to catch loops.
+#[derive(Debug, Default)]
+pub struct BootSequence {
+    pub rc_files: Vec<String>,
+    pub services: Vec<Service>,
+    // In boot order, one entry per trigger however many sections name it
+    pub triggers: Vec<Trigger>,
+    pub imports: Vec<Import>,
+}
+
+#[derive(Debug)]
+pub struct Service {
+    pub name: String,
+    // "default" when the service doesn't name a class
+    pub classes: Vec<String>,
+    // Skipped by class_start; only an explicit start (or ctl.start) runs it
+    pub disabled: bool,
+    pub oneshot: bool,
+    pub location: String,
+}
+
+#[derive(Debug)]
+pub struct Trigger {
+    pub name: String,
+    pub starts: Vec<Start>,
+}
+
+#[derive(Debug)]
+pub struct Start {
+    // start, restart, enable or class_start
+    pub command: String,
+    // Service name, or the class for class_start
+    pub target: String,
+    pub location: String,
+}
+
+#[derive(Debug)]
+pub struct Import {
+    // As written, e.g. /vendor/etc/init/hw/init.qcom.usb.rc
+    pub path: String,
+    // The .rc in the tree it was matched to by file name
+    pub resolved: Option<String>,
+    pub location: String,
+}
+
+impl BootSequence {
+    pub fn is_empty(&self) -> bool {
+        self.services.is_empty() && self.triggers.is_empty()
+    }
+
+    pub fn service(&self, name: &str) -> Option<&Service> {
+        self.services.iter().find(|service| service.name == name)
+    }
+
+    // Services a start brings up; a class_start skips disabled services
+    pub fn started_by<'a>(&'a self, start: &'a Start) -> Vec<&'a str> {
+        if start.command == "class_start" {
+            self.services
+                .iter()
+                .filter(|service| !service.disabled && service.classes.contains(&start.target))
+                .map(|service| service.name.as_str())
+                .collect()
+        } else {
+            vec![start.target.as_str()]
+        }
+    }
+
+    // Every service a trigger starts, in the order its sections do
+    pub fn services_on(&self, trigger: &str) -> Vec<&str> {
+        let mut services: Vec<&str> = Vec::new();
+        for trigger in self.triggers.iter().filter(|t| t.name == trigger) {
+            for start in &trigger.starts {
+                for service in self.started_by(start) {
+                    if !services.contains(&service) {
+                        services.push(service);
+                    }
+                }
+            }
+        }
+        services
+    }
+
+    // Services no trigger starts, neither by name nor through their class.
+    // Disabled ones may still be started with ctl.start by a HAL or app.
+    pub fn unstarted(&self) -> Vec<&Service> {
+        let started: BTreeSet<&str> = self
+            .triggers
+            .iter()
+            .flat_map(|trigger| &trigger.starts)
+            .flat_map(|start| self.started_by(start))
+            .collect();
+        self.services.iter().filter(|service| !started.contains(service.name.as_str())).collect()
+    }
+
+    // start/restart/enable targets with no service definition in the tree,
+    // usually platform services
+    pub fn undefined(&self) -> Vec<&Start> {
+        self.triggers
+            .iter()
+            .flat_map(|trigger| &trigger.starts)
+            .filter(|start| start.command != "class_start" && self.service(&start.target).is_none())
+            .collect()
+    }
+}
+
+pub fn scan_boot_sequence(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> BootSequence {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| name.ends_with(".rc") && !name.starts_with("ueventd"),
+        &mut files,
+    );
+    files.sort();
+
+    // Unreadable scripts are already reported by the filesystem setup scan
+    let mut boot = BootSequence::default();
+    let mut sections: Vec<Trigger> = Vec::new();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let Ok(content) = fsread::read_to_string(&file) else {
+            continue;
+        };
+        let before = boot.services.len() + sections.len() + boot.imports.len();
+        parse_boot_rc(&content, &relative, &mut boot, &mut sections);
+        if boot.services.len() + sections.len() + boot.imports.len() > before {
+            boot.rc_files.push(relative);
+        }
+    }
+
+    // Sections naming the same trigger run together, in file order
+    for section in sections.into_iter().filter(|section| !section.starts.is_empty()) {
+        match boot.triggers.iter_mut().find(|trigger| trigger.name == section.name) {
+            Some(trigger) => trigger.starts.extend(section.starts),
+            None => boot.triggers.push(section),
+        }
+    }
+    for (class, trigger) in PLATFORM_CLASS_STARTS {
+        let start = Start {
+            command: "class_start".to_string(),
+            target: class.to_string(),
+            location: PLATFORM_INIT_RC.to_string(),
+        };
+        if boot.started_by(&start).is_empty() {
+            continue;
+        }
+        match boot.triggers.iter_mut().find(|t| t.name == trigger) {
+            Some(existing) => existing.starts.push(start),
+            None => boot.triggers.push(Trigger { name: trigger.to_string(), starts: vec![start] }),
+        }
+    }
+    boot.triggers.sort_by_key(|trigger| stage(&trigger.name));
+
+    let rc_files = boot.rc_files.clone();
+    for import in &mut boot.imports {
+        import.resolved = resolve_import(&import.path, &rc_files);
+    }
+    warn_import_cycles(&boot.imports, warnings);
+
+    boot
+}
+
+fn parse_boot_rc(content: &str, file: &str, boot: &mut BootSequence, sections: &mut Vec<Trigger>) {
+    // Which section the following lines belong to: an "on" section's index
+    // in sections, or a service's in boot.services
+    enum Section {
+        On(usize),
+        Service(usize),
+        None,
+    }
+    let mut section = Section::None;
+
+    for (line, statement) in statements(content) {
+        let tokens: Vec<&str> = statement.split_whitespace().collect();
+        let Some(&keyword) = tokens.first() else {
+            continue;
+        };
+        let location = format!("{}:{}", file, line);
+        match (keyword, &section) {
+            ("on", _) => {
+                sections.push(Trigger { name: tokens[1..].join(" "), starts: Vec::new() });
+                section = Section::On(sections.len() - 1);
+            }
+            ("service", _) => {
+                let Some(name) = tokens.get(1) else {
+                    section = Section::None;
+                    continue;
+                };
+                boot.services.push(Service {
+                    name: name.to_string(),
+                    classes: vec!["default".to_string()],
+                    disabled: false,
+                    oneshot: false,
+                    location,
+                });
+                section = Section::Service(boot.services.len() - 1);
+            }
+            ("import", _) => {
+                if let Some(path) = tokens.get(1) {
+                    boot.imports.push(Import { path: path.to_string(), resolved: None, location });
+                }
+                section = Section::None;
+            }
+            ("start" | "restart" | "enable" | "class_start", Section::On(index)) => {
+                if let Some(target) = tokens.get(1) {
+                    sections[*index].starts.push(Start {
+                        command: keyword.to_string(),
+                        target: target.to_string(),
+                        location,
+                    });
+                }
+            }
+            ("class", Section::Service(index)) if tokens.len() > 1 => {
+                boot.services[*index].classes = tokens[1..].iter().map(|class| class.to_string()).collect();
+            }
+            ("disabled", Section::Service(index)) => boot.services[*index].disabled = true,
+            ("oneshot", Section::Service(index)) => boot.services[*index].oneshot = true,
+            _ => {}
+        }
+    }
+}
+
+// Boot stages first, in order; then other events; then property triggers.
+// "boot && property:..." sorts with boot.
+fn stage(trigger: &str) -> usize {
+    let events: Vec<&str> = trigger.split("&&").map(str::trim).filter(|part| !part.starts_with("property:")).collect();
+    match events.first() {
+        Some(event) => BOOT_STAGES.iter().position(|stage| stage == event).unwrap_or(BOOT_STAGES.len()),
+        None => BOOT_STAGES.len() + 1,
+    }
+}
+
+// An import is matched to a tree .rc by file name, preferring the one whose
+// path shares the most trailing directories with it. Directory imports and
+// paths with ${property} in the file name don't resolve.
+fn resolve_import(path: &str, rc_files: &[String]) -> Option<String> {
+    let name = path.rsplit('/').next()?;
+    if !name.ends_with(".rc") || name.contains("${") {
+        return None;
+    }
+    let wanted: Vec<&str> = path.rsplit('/').collect();
+    rc_files
+        .iter()
+        .filter(|file| file.rsplit('/').next() == Some(name))
+        .max_by_key(|file| {
+            let shared = file.rsplit('/').zip(&wanted).take_while(|(a, b)| a == *b).count();
+            // max_by_key keeps the last of equals, so earlier files win ties
+            (shared, std::cmp::Reverse(file.as_str()))
+        })
+        .cloned()
+}
+
+// One warning per import loop, at the import that closes it
+fn warn_import_cycles(imports: &[Import], warnings: &mut Vec<Warning>) {
+    let file_of = |import: &Import| import.location.rsplit_once(':').map_or("", |(file, _)| file).to_string();
+    let mut graph: BTreeMap<String, Vec<&Import>> = BTreeMap::new();
+    for import in imports.iter().filter(|import| import.resolved.is_some()) {
+        graph.entry(file_of(import)).or_default().push(import);
+    }
+
+    let mut reported: BTreeSet<Vec<String>> = BTreeSet::new();
+    let mut done: BTreeSet<String> = BTreeSet::new();
+    for start in graph.keys() {
+        let mut path = vec![start.clone()];
+        find_cycles(&graph, &mut path, &mut done, &mut reported, warnings);
+    }
+}
+
+fn find_cycles(
+    graph: &BTreeMap<String, Vec<&Import>>,
+    path: &mut Vec<String>,
+    done: &mut BTreeSet<String>,
+    reported: &mut BTreeSet<Vec<String>>,
+    warnings: &mut Vec<Warning>,
+) {
+    let Some(current) = path.last().cloned() else {
+        return;
+    };
+    if done.contains(&current) {
+        return;
+    }
+    for import in graph.get(&current).into_iter().flatten() {
+        let Some(target) = &import.resolved else {
+            continue;
+        };
+        if let Some(position) = path.iter().position(|file| file == target) {
+            let mut cycle: Vec<String> = path[position..].to_vec();
+            cycle.sort();
+            if reported.insert(cycle) {
+                let chain: Vec<&str> = path[position..].iter().chain([target]).map(String::as_str).collect();
+                let message = format!("import loop: {}", chain.join(" -> "));
+                warnings.push(Warning::new(WarningKind::CircularImport, import.location.clone(), message));
+            }
+            continue;
+        }
+        path.push(target.clone());
+        find_cycles(graph, path, done, reported, warnings);
+        path.pop();
+    }
+    done.insert(current);
+}
@@ -0,0 +1,130 @@
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use flate2::read::GzDecoder;
+use crate::fsread;
+use crate::makefile::{self, statements};
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Prebuilt kernel image names, most specific first
+const IMAGE_NAMES: [&str; 6] = ["Image.gz-dtb", "Image.gz", "Image-dtb", "Image", "zImage", "kernel"];
+
+// Decompressed kernels are tens of MB; the banner is always well inside this
+const MAX_IMAGE_BYTES: u64 = 128 * 1024 * 1024;
+
+#[derive(Debug, Default)]
+pub struct KernelInfo {
+    // TARGET_KERNEL_SOURCE, e.g. kernel/xiaomi/sm8150
+    pub source: Option<String>,
+    // TARGET_KERNEL_CONFIG (can list several fragments)
+    pub defconfigs: Vec<String>,
+    // Prebuilt image relative to the tree root
+    pub prebuilt_image: Option<String>,
+    // "Linux version ..." banner extracted from the prebuilt image
+    pub version: Option<String>,
+}
+
+pub fn scan_kernel(
+    tree_path: &Path,
+    board_config: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> KernelInfo {
+    let mut kernel = KernelInfo::default();
+    let mut prebuilt_var: Option<String> = None;
+
+    if let Some(board_config) = board_config
+        && walk_options.includes_file(board_config)
+        && let Ok(content) = fsread::read_to_string(board_config)
+    {
+        for (_, statement) in statements(&content) {
+            let Some(makefile::Assignment { name, value, .. }) = makefile::parse_assignment(&statement) else {
+                continue;
+            };
+            let value = Some(value.trim_matches('"').to_string()).filter(|value| !value.is_empty());
+
+            match name {
+                "TARGET_KERNEL_SOURCE" => kernel.source = value,
+                // Both "+=" and ":=" forms show up in the wild
+                "TARGET_KERNEL_CONFIG" => {
+                    if let Some(configs) = value {
+                        kernel.defconfigs.extend(configs.split_whitespace().map(|c| c.to_string()));
+                    }
+                }
+                "TARGET_PREBUILT_KERNEL" => prebuilt_var = value,
+                _ => {}
+            }
+        }
+    }
+
+    let image = match prebuilt_var
+        .as_deref()
+        .and_then(|var| resolve_tree_path(tree_path, var))
+        .filter(|image| walk_options.includes_file(image))
+    {
+        Some(image) => Some(image),
+        None => find_prebuilt_image(tree_path, walk_options),
+    };
+
+    if let Some(image) = image {
+        let relative = image.strip_prefix(tree_path).unwrap_or(&image).to_string_lossy().to_string();
+        match read_version_banner(&image) {
+            Ok(version) => kernel.version = version,
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, &relative, e.to_string())),
+        }
+        kernel.prebuilt_image = Some(relative);
+    }
+
+    kernel
+}
+
+// $(LOCAL_PATH)/prebuilt/Image.gz-dtb -> <tree>/prebuilt/Image.gz-dtb
+fn resolve_tree_path(tree_path: &Path, value: &str) -> Option<PathBuf> {
+    let relative = ["$(LOCAL_PATH)/", "$(DEVICE_PATH)/"]
+        .iter()
+        .find_map(|prefix| value.strip_prefix(prefix))?;
+    let path = tree_path.join(relative);
+    path.is_file().then_some(path)
+}
+
+fn find_prebuilt_image(tree_path: &Path, walk_options: &WalkOptions) -> Option<PathBuf> {
+    let mut found: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| IMAGE_NAMES.contains(&name), &mut found);
+
+    IMAGE_NAMES.iter().find_map(|name| {
+        let mut matches: Vec<&PathBuf> = found
+            .iter()
+            .filter(|path| path.file_name().is_some_and(|n| n == *name))
+            .collect();
+        matches.sort();
+        matches.first().map(|path| path.to_path_buf())
+    })
+}
+
+fn read_version_banner(image: &Path) -> io::Result<Option<String>> {
+    let mut magic = [0u8; 2];
+    let is_gzip = fsread::open(image)?.read_exact(&mut magic).is_ok() && magic == [0x1f, 0x8b];
+
+    let file = BufReader::new(fsread::open(image)?);
+    let mut bytes = Vec::new();
+    if is_gzip {
+        GzDecoder::new(file).take(MAX_IMAGE_BYTES).read_to_end(&mut bytes)?;
+    } else {
+        file.take(MAX_IMAGE_BYTES).read_to_end(&mut bytes)?;
+    }
+
+    Ok(find_banner(&bytes))
+}
+
+fn find_banner(bytes: &[u8]) -> Option<String> {
+    const BANNER: &[u8] = b"Linux version ";
+
+    let start = bytes.windows(BANNER.len()).position(|window| window == BANNER)?;
+    let banner = &bytes[start..];
+    let end = banner
+        .iter()
+        .position(|&b| b == 0 || b == b'\n')
+        .unwrap_or(banner.len().min(256));
+
+    Some(String::from_utf8_lossy(&banner[..end]).trim().to_string())
+}
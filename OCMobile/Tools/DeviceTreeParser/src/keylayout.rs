@@ -0,0 +1,126 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::symbols::Marker;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Input key layout (.kl) and key character map (.kcm) files, i.e. which
+// scan codes the physical buttons and keyboards produce
+#[derive(Debug, Default)]
+pub struct KeyLayouts {
+    pub files: Vec<KeyLayoutFile>,
+}
+
+#[derive(Debug)]
+pub struct KeyLayoutFile {
+    // Relative to the tree
+    pub file: String,
+    pub keys: Vec<KeyMapping>,
+}
+
+#[derive(Debug)]
+pub struct KeyMapping {
+    // "116", or "usage 0x0c0070" for HID usages; None in .kcm files, which
+    // are keyed by name
+    pub code: Option<String>,
+    pub name: String,
+    // Policy flags such as WAKE or VIRTUAL
+    pub flags: Vec<String>,
+}
+
+impl KeyLayouts {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn key_count(&self) -> usize {
+        self.files.iter().map(|file| file.keys.len()).sum()
+    }
+}
+
+impl KeyMapping {
+    // "116 → POWER (WAKE)"
+    pub fn summary(&self) -> String {
+        let mut summary = match &self.code {
+            Some(code) => format!("{} {} {}", code, Marker::Arrow, self.name),
+            None => self.name.clone(),
+        };
+        if !self.flags.is_empty() {
+            summary.push_str(&format!(" ({})", self.flags.join(", ")));
+        }
+        summary
+    }
+}
+
+pub fn scan_key_layouts(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> KeyLayouts {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| name.ends_with(".kl") || name.ends_with(".kcm"),
+        &mut files,
+    );
+    files.sort();
+
+    let mut layouts = KeyLayouts::default();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        match parse_key_file(&file) {
+            Ok(keys) => layouts.files.push(KeyLayoutFile { file: relative, keys }),
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+        }
+    }
+
+    layouts
+}
+
+fn parse_key_file(path: &Path) -> io::Result<Vec<KeyMapping>> {
+    let content = fsread::read_to_string(path)?;
+    let character_map = path.extension().is_some_and(|ext| ext == "kcm");
+
+    let mut keys = Vec::new();
+    for line in content.lines() {
+        // Comments run to the end of the line
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace();
+        if words.next() != Some("key") {
+            continue;
+        }
+
+        let key = if character_map { parse_kcm_key(words) } else { parse_kl_key(words) };
+        if let Some(key) = key {
+            keys.push(key);
+        }
+    }
+    Ok(keys)
+}
+
+// key 116 POWER WAKE
+// key usage 0x0c006F BRIGHTNESS_UP
+fn parse_kl_key<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<KeyMapping> {
+    let code = match words.next()? {
+        "usage" => format!("usage {}", words.next()?),
+        code => code.to_string(),
+    };
+    let name = words.next()?.to_string();
+    Some(KeyMapping {
+        code: Some(code),
+        name,
+        flags: words.map(|flag| flag.to_string()).collect(),
+    })
+}
+
+// key A {
+fn parse_kcm_key<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<KeyMapping> {
+    let name = words.next()?.trim_end_matches('{').to_string();
+    if name.is_empty() {
+        return None;
+    }
+    Some(KeyMapping {
+        code: None,
+        name,
+        flags: Vec::new(),
+    })
+}
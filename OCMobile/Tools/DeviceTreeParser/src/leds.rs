@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+use crate::dts::{DtsNode, DtsSources};
+use crate::fsread;
+use crate::makefile::{split_assignment, statements};
+use crate::walk::{self, WalkOptions};
+
+// Compatible fragments of backlight controllers: generic pwm/gpio
+// backlights, the Qualcomm WLED blocks and TI/Awinic/Kinetic/SGMicro
+// backlight ICs
+const BACKLIGHT_MARKERS: [&str; 6] = ["backlight", "wled", "lm36", "aw99", "ktd", "sgm37"];
+
+// sysfs directories the lights HAL and init scripts drive
+const SYSFS_DIRS: [&str; 2] = ["/sys/class/leds/", "/sys/class/backlight/"];
+
+// Notification/indicator LEDs and backlight controllers from the DTS, the
+// lights HAL packages from device.mk and the init.rc commands that set up
+// their sysfs nodes
+#[derive(Debug, Default)]
+pub struct LedInfo {
+    pub leds: Vec<Led>,
+    pub backlights: Vec<Backlight>,
+    // Every lights HAL PRODUCT_PACKAGES entry
+    pub hal_packages: Vec<String>,
+    pub sysfs_commands: Vec<SysfsCommand>,
+}
+
+#[derive(Debug)]
+pub struct Led {
+    pub node: String,
+    // The label property (the /sys/class/leds name), else the node name
+    pub label: String,
+    pub default_trigger: Option<String>,
+    // Compatible of the LED node, or of the controller it sits under
+    pub controller: Option<String>,
+    pub location: String,
+}
+
+#[derive(Debug)]
+pub struct Backlight {
+    pub node: String,
+    pub compatible: Vec<String>,
+    pub location: String,
+}
+
+// "write /sys/class/leds/red/brightness 0", "chown system system ...", ...
+#[derive(Debug)]
+pub struct SysfsCommand {
+    pub command: String,
+    pub path: String,
+    pub location: String,
+}
+
+impl LedInfo {
+    pub fn is_empty(&self) -> bool {
+        self.leds.is_empty()
+            && self.backlights.is_empty()
+            && self.hal_packages.is_empty()
+            && self.sysfs_commands.is_empty()
+    }
+
+    // LED labels init.rc or the HAL can reach under /sys/class/leds
+    pub fn sysfs_leds(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .sysfs_commands
+            .iter()
+            .filter_map(|command| command.path.strip_prefix("/sys/class/leds/"))
+            .filter_map(|rest| rest.split('/').next())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    // Init scripts touch LEDs the DTS doesn't define, i.e. the HAL will
+    // look for sysfs nodes that won't exist
+    pub fn undefined_sysfs_leds(&self) -> Vec<&str> {
+        if self.leds.is_empty() {
+            return Vec::new();
+        }
+        self.sysfs_leds().into_iter().filter(|name| !self.leds.iter().any(|led| led.label == *name)).collect()
+    }
+
+    pub fn rc_files(&self) -> Vec<String> {
+        let mut files: Vec<String> = self
+            .sysfs_commands
+            .iter()
+            .map(|command| command.location.rsplit_once(':').map_or(command.location.clone(), |(f, _)| f.to_string()))
+            .collect();
+        files.dedup();
+        files
+    }
+}
+
+pub fn scan_leds(
+    tree_path: &Path,
+    sources: &DtsSources,
+    device_mk: Option<&Path>,
+    walk_options: &WalkOptions,
+) -> LedInfo {
+    let mut leds = LedInfo::default();
+
+    for node in sources.nodes.values() {
+        let compatible: Vec<String> = node.strings("compatible").into_iter().map(|s| s.to_string()).collect();
+        let location = node.locations.first().cloned().unwrap_or_default();
+        if is_backlight(&compatible) {
+            leds.backlights.push(Backlight { node: node.path.clone(), compatible, location });
+            continue;
+        }
+        let parent = parent(sources, node);
+        if !is_led(node, parent) {
+            continue;
+        }
+        let controller = compatible
+            .first()
+            .cloned()
+            .or_else(|| parent.and_then(|parent| parent.string("compatible")).map(str::to_string));
+        leds.leds.push(Led {
+            node: node.path.clone(),
+            label: node.string("label").unwrap_or(node.name().split('@').next().unwrap_or_default()).to_string(),
+            default_trigger: node.string("linux,default-trigger").map(str::to_string),
+            controller,
+            location,
+        });
+    }
+
+    // Unreadable device.mk is already reported by the driver scan
+    if let Some(device_mk) = device_mk
+        && walk_options.includes_file(device_mk)
+        && let Ok(content) = fsread::read_to_string(device_mk)
+    {
+        for (_, statement) in statements(&content) {
+            let Some(("PRODUCT_PACKAGES", value)) = split_assignment(&statement) else {
+                continue;
+            };
+            for package in value.split_whitespace() {
+                let lower = package.to_lowercase();
+                if lower.contains("light")
+                    && !lower.contains("flashlight")
+                    && !leds.hal_packages.iter().any(|p| p == package)
+                {
+                    leds.hal_packages.push(package.to_string());
+                }
+            }
+        }
+    }
+
+    leds.sysfs_commands = find_sysfs_commands(tree_path, walk_options);
+    leds
+}
+
+fn parent<'a>(sources: &'a DtsSources, node: &DtsNode) -> Option<&'a DtsNode> {
+    let parent = match node.path.rsplit_once('/')? {
+        ("", _) => "/",
+        (parent, _) => parent,
+    };
+    sources.nodes.get(parent)
+}
+
+fn is_backlight(compatible: &[String]) -> bool {
+    compatible.iter().any(|c| BACKLIGHT_MARKERS.iter().any(|marker| c.contains(marker)))
+}
+
+// An LED class device: anything with a default trigger, or a child of an
+// LED controller (gpio-leds, pwm-leds, qcom,tri-led, ...), which lists one
+// child per LED
+fn is_led(node: &DtsNode, parent: Option<&DtsNode>) -> bool {
+    if node.properties.contains_key("linux,default-trigger") {
+        return true;
+    }
+    let Some(parent) = parent else {
+        return false;
+    };
+    let compatible: Vec<String> = parent.strings("compatible").into_iter().map(str::to_string).collect();
+    compatible.iter().any(|c| c.contains("led")) && !is_backlight(&compatible)
+}
+
+// Unreadable .rc files are already reported by the vendor flag scan
+fn find_sysfs_commands(tree_path: &Path, walk_options: &WalkOptions) -> Vec<SysfsCommand> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name.ends_with(".rc"), &mut files);
+    files.sort();
+
+    let mut commands = Vec::new();
+    for file in files {
+        let Ok(content) = fsread::read_to_string(&file) else {
+            continue;
+        };
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        for (index, line) in content.lines().enumerate() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let Some(&command) = tokens.first().filter(|command| !command.starts_with('#')) else {
+                continue;
+            };
+            let Some(path) = tokens.iter().find(|token| SYSFS_DIRS.iter().any(|dir| token.starts_with(dir))) else {
+                continue;
+            };
+            commands.push(SysfsCommand {
+                command: command.to_string(),
+                path: path.to_string(),
+                location: format!("{}:{}", relative, index + 1),
+            });
+        }
+    }
+    commands
+}
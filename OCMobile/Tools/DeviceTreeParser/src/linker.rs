@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Libraries exposed by public.libraries*.txt and the namespaces and links
+// configured by ld.config*.txt, which decide what a vendor or GSI process
+// may dlopen
+#[derive(Debug, Default)]
+pub struct LinkerNamespaces {
+    pub public_libraries: Vec<PublicLibraries>,
+    pub configs: Vec<LinkerConfig>,
+}
+
+#[derive(Debug)]
+pub struct PublicLibraries {
+    pub file: String,
+    // "libfoo.so", with a " 32"/" 64" bitness suffix where the file has one
+    pub libraries: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct LinkerConfig {
+    pub file: String,
+    // [system], [vendor], ... in file order
+    pub sections: Vec<LinkerSection>,
+}
+
+#[derive(Debug)]
+pub struct LinkerSection {
+    pub name: String,
+    // Executable directories mapped to it by dir.<section> lines
+    pub dirs: Vec<String>,
+    pub namespaces: Vec<Namespace>,
+}
+
+#[derive(Debug)]
+pub struct Namespace {
+    pub name: String,
+    pub isolated: bool,
+    pub visible: bool,
+    pub search_paths: Vec<String>,
+    pub permitted_paths: Vec<String>,
+    pub links: Vec<NamespaceLink>,
+}
+
+#[derive(Debug)]
+pub struct NamespaceLink {
+    pub target: String,
+    pub shared_libs: Vec<String>,
+    pub allow_all: bool,
+}
+
+impl LinkerNamespaces {
+    pub fn is_empty(&self) -> bool {
+        self.public_libraries.is_empty() && self.configs.is_empty()
+    }
+
+    pub fn files(&self) -> Vec<String> {
+        let public = self.public_libraries.iter().map(|list| list.file.clone());
+        public.chain(self.configs.iter().map(|config| config.file.clone())).collect()
+    }
+}
+
+impl LinkerSection {
+    fn new(name: &str) -> Self {
+        LinkerSection { name: name.to_string(), dirs: Vec::new(), namespaces: Vec::new() }
+    }
+
+    fn namespace_mut(&mut self, name: &str) -> &mut Namespace {
+        let index = match self.namespaces.iter().position(|namespace| namespace.name == name) {
+            Some(index) => index,
+            None => {
+                self.namespaces.push(Namespace {
+                    name: name.to_string(),
+                    isolated: false,
+                    visible: false,
+                    search_paths: Vec::new(),
+                    permitted_paths: Vec::new(),
+                    links: Vec::new(),
+                });
+                self.namespaces.len() - 1
+            }
+        };
+        &mut self.namespaces[index]
+    }
+}
+
+impl Namespace {
+    fn link_mut(&mut self, target: &str) -> &mut NamespaceLink {
+        let index = match self.links.iter().position(|link| link.target == target) {
+            Some(index) => index,
+            None => {
+                self.links.push(NamespaceLink { target: target.to_string(), shared_libs: Vec::new(), allow_all: false });
+                self.links.len() - 1
+            }
+        };
+        &mut self.links[index]
+    }
+}
+
+pub fn scan_linker_namespaces(
+    tree_path: &Path,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> LinkerNamespaces {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| {
+            name.ends_with(".txt") && (name.starts_with("public.libraries") || name.starts_with("ld.config"))
+        },
+        &mut files,
+    );
+    files.sort();
+
+    let mut linker = LinkerNamespaces::default();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let content = match fsread::read_to_string(&file) {
+            Ok(content) => content,
+            Err(e) => {
+                warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+                continue;
+            }
+        };
+        let name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+        if name.starts_with("public.libraries") {
+            linker.public_libraries.push(PublicLibraries { file: relative, libraries: public_libraries(&content) });
+        } else {
+            linker.configs.push(LinkerConfig { file: relative, sections: parse_ld_config(&content) });
+        }
+    }
+    linker
+}
+
+// One library per line, optionally followed by 32 or 64
+fn public_libraries(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+// The sectioned ld.config.txt format: dir.<section> = <path> lines map
+// executable directories to sections, then each [section] block configures
+// namespace.<name>.<property> keys. A file without any [section] (the
+// legacy VNDK-lite layout) gets a single unnamed one.
+fn parse_ld_config(content: &str) -> Vec<LinkerSection> {
+    let mut sections: Vec<LinkerSection> = Vec::new();
+    let mut dirs: Vec<(String, String)> = Vec::new();
+    let mut current: Option<usize> = None;
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            sections.push(LinkerSection::new(name.trim()));
+            current = Some(sections.len() - 1);
+            continue;
+        }
+
+        let (key, value, append) = match line.split_once("+=") {
+            Some((key, value)) => (key.trim(), value.trim(), true),
+            None => match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim(), false),
+                None => continue,
+            },
+        };
+
+        if current.is_none()
+            && let Some(section) = key.strip_prefix("dir.")
+        {
+            dirs.push((section.to_string(), value.to_string()));
+            continue;
+        }
+        let Some(rest) = key.strip_prefix("namespace.") else {
+            continue;
+        };
+        let index = match current {
+            Some(index) => index,
+            None => {
+                sections.push(LinkerSection::new(""));
+                current = Some(sections.len() - 1);
+                sections.len() - 1
+            }
+        };
+        let Some((namespace, property)) = rest.split_once('.') else {
+            continue;
+        };
+        let namespace = sections[index].namespace_mut(namespace);
+        apply_property(namespace, property, value, append);
+    }
+
+    for (section, dir) in dirs {
+        if let Some(section) = sections.iter_mut().find(|s| s.name == section) {
+            section.dirs.push(dir);
+        }
+    }
+    sections
+}
+
+fn apply_property(namespace: &mut Namespace, property: &str, value: &str, append: bool) {
+    let list = |value: &str| -> Vec<String> {
+        value.split(':').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+    };
+    match property {
+        "isolated" => namespace.isolated = value == "true",
+        "visible" => namespace.visible = value == "true",
+        "search.paths" => {
+            if !append {
+                namespace.search_paths.clear();
+            }
+            namespace.search_paths.extend(list(value));
+        }
+        "permitted.paths" => {
+            if !append {
+                namespace.permitted_paths.clear();
+            }
+            namespace.permitted_paths.extend(list(value));
+        }
+        "links" => {
+            for target in value.split(',').map(str::trim).filter(|target| !target.is_empty()) {
+                namespace.link_mut(target);
+            }
+        }
+        _ => {
+            // link.<target>.shared_libs / link.<target>.allow_all_shared_libs
+            let Some((target, what)) = property.strip_prefix("link.").and_then(|rest| rest.rsplit_once('.')) else {
+                return;
+            };
+            let link = namespace.link_mut(target);
+            match what {
+                "shared_libs" => {
+                    if !append {
+                        link.shared_libs.clear();
+                    }
+                    link.shared_libs.extend(list(value));
+                }
+                "allow_all_shared_libs" => link.allow_all = value == "true",
+                _ => {}
+            }
+        }
+    }
+}
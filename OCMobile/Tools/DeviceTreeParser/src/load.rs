@@ -0,0 +1,105 @@
+// --load: a HardwareReport read back from a --format json export, so a tree
+// that is no longer around can be re-rendered.
+//
+// Only the core of the report round-trips: tree path, device info, key
+// files/dirs, drivers, HALs, file hashes and warnings. Every other section
+// is derived from parsed sources (DTS nodes, makefiles, XML) that the JSON
+// only summarizes, so those come back empty and the text report says so.
+// An export would silently lose them, so --load refuses the --export-*
+// flags.
+
+use std::path::Path;
+use json::JsonValue;
+use crate::HardwareReport;
+use crate::error::AnalyzeError;
+use crate::fsread;
+use crate::hal;
+use crate::report_json::SCHEMA_VERSION;
+use crate::warning::{Warning, WarningKind};
+
+// The report, plus notes about anything in the file that couldn't be used
+pub fn load_report(path: &Path) -> Result<(HardwareReport, Vec<String>), AnalyzeError> {
+    let invalid = |message: String| AnalyzeError::InvalidReport { path: path.display().to_string(), message };
+    let content = fsread::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    let json = json::parse(&content).map_err(|e| invalid(format!("invalid JSON: {}", e)))?;
+    if !json.is_object() || !json["device_info"].is_object() || !json["drivers"].is_object() {
+        return Err(invalid("not a --format json report".to_string()));
+    }
+
+    let mut notes = Vec::new();
+    match json["schema_version"].as_u32() {
+        Some(SCHEMA_VERSION) => {}
+        Some(version) => notes.push(format!(
+            "report schema version {} differs from this tool's ({}); fields may be missing or misread",
+            version, SCHEMA_VERSION
+        )),
+        None => notes.push(format!(
+            "report has no schema_version (written before version {}); fields may be missing or misread",
+            SCHEMA_VERSION
+        )),
+    }
+
+    let mut report = HardwareReport {
+        tree_path: json["tree_path"].as_str().unwrap_or(".").to_string(),
+        structure_valid: json["structure_valid"].as_bool().unwrap_or(false),
+        device_info: strings(&json["device_info"]).into_iter().collect(),
+        key_files: bools(&json["key_files"]).into_iter().collect(),
+        key_dirs: bools(&json["key_dirs"]).into_iter().collect(),
+        key_names_on_disk: strings(&json["key_names_on_disk"]).into_iter().collect(),
+        drivers_scanned: json["drivers_scanned"].as_bool().unwrap_or(true),
+        max_depth: json["max_depth"].as_usize(),
+        max_entries: json["max_entries_per_category"].as_usize(),
+        file_hashes: strings(&json["file_hashes"]).into_iter().collect(),
+        loaded_from: Some(path.display().to_string()),
+        ..Default::default()
+    };
+
+    for (category, entry) in json["driver_categories"].entries() {
+        if entry["attempted"].as_bool() == Some(true) {
+            report.attempted_categories.insert(category.to_string());
+        }
+        if entry["truncated"].as_bool() == Some(true) {
+            report.truncated_categories.insert(category.to_string());
+        }
+    }
+    for (category, entries) in json["drivers"].entries() {
+        let entries: Vec<String> = entries.members().filter_map(|e| e.as_str()).map(str::to_string).collect();
+        report.drivers.insert(category.to_string(), entries);
+    }
+
+    // The HAL category holds one summary per family; the packages behind
+    // them are in "hals"
+    let packages: Vec<String> = json["hals"]
+        .members()
+        .flat_map(|hal| hal["packages"].members())
+        .filter_map(|package| package["name"].as_str())
+        .map(str::to_string)
+        .collect();
+    report.hals = hal::normalize(&packages);
+
+    for warning in json["warnings"].members() {
+        let name = warning["kind"].as_str().unwrap_or_default();
+        let Some(kind) = WarningKind::ALL.into_iter().find(|kind| kind.name() == name) else {
+            notes.push(format!("skipped a warning of unknown kind '{}'", name));
+            continue;
+        };
+        report.warnings.push(Warning::new(
+            kind,
+            warning["file"].as_str().unwrap_or_default(),
+            warning["message"].as_str().unwrap_or_default(),
+        ));
+    }
+
+    Ok((report, notes))
+}
+
+fn strings(object: &JsonValue) -> Vec<(String, String)> {
+    object
+        .entries()
+        .filter_map(|(key, value)| value.as_str().map(|value| (key.to_string(), value.to_string())))
+        .collect()
+}
+
+fn bools(object: &JsonValue) -> Vec<(String, bool)> {
+    object.entries().filter_map(|(key, value)| value.as_bool().map(|value| (key.to_string(), value))).collect()
+}
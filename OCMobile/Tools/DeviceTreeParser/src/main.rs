@@ -1,532 +1,6191 @@
-use std::env;
-use std::fs::{self, File, OpenOptions, Read, Write};
-use std::io::{BufReader, BufWriter, Write as IoWrite};
-use clap::Parser;
+mod apex;
+mod assert;
+mod battery;
+mod biometrics;
+mod boot;
+mod camera;
+mod carrier;
+mod category;
+mod copy_files;
+mod cpu_topology;
+mod drm;
+mod dts;
+mod encoding;
+mod encryption;
+mod error;
+mod dtbo;
+mod fdt;
+mod features;
+mod fsread;
+mod fstab;
+mod gpu;
+mod gsi;
+mod hal;
+mod haptics;
+mod hash;
+mod init_rc;
+mod kernel;
+mod keylayout;
+mod leds;
+mod linker;
+mod load;
+mod makefile;
+mod media;
+mod merge;
+mod mixer;
+mod modem;
+mod module_consistency;
+mod nfc;
+mod panel;
+mod partitions;
+mod perf;
+mod power;
+mod product;
+mod profile;
+mod query;
+mod regulator;
+mod remote;
+mod since;
+mod symbols;
+mod report_json;
+mod report_toml;
+mod sarif;
+#[cfg(test)]
+mod scratch;
+mod selftest;
+mod tee;
+mod telephony;
+mod template;
+mod touchscreen;
+mod treble;
+mod usb;
+mod vendor_flags;
+mod walk;
+mod warning;
+mod wifi_config;
+
+use std::fs;
+use std::io::{Read, Write};
+use clap::{Parser, ValueEnum};
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
+use apex::ApexInfo;
+use battery::BatteryInfo;
+use biometrics::Biometrics;
+use boot::BootInfo;
+use camera::CameraInfo;
+use carrier::CarrierConfig;
+use category::CategoryMap;
+use copy_files::CopiedFiles;
+use cpu_topology::CpuTopology;
+use drm::{DrmInfo, WidevineLevel};
+use dtbo::{DtbOverlays, DtboBuildMode};
+use encryption::EncryptionInfo;
+use dts::{DtsNode, DtsSources};
+use error::AnalyzeError;
+use features::DeclaredFeatures;
+use init_rc::{BootSequence, FilesystemSetup};
+use gpu::GpuInfo;
+use gsi::GsiInfo;
+use hal::Hal;
+use haptics::Haptics;
+use kernel::KernelInfo;
+use keylayout::KeyLayouts;
+use leds::LedInfo;
+use linker::LinkerNamespaces;
+use makefile::statements;
+use media::MediaProfiles;
+use merge::MergeInfo;
+use mixer::MixerPaths;
+use modem::ModemInfo;
+use module_consistency::ModuleConsistency;
+use nfc::NfcInfo;
+use panel::Panel;
+use partitions::PartitionSizes;
+use perf::PerfTuning;
+use power::PowerConfig;
+use profile::Profiler;
+use regulator::{PowerConsumer, Regulators, SupplyLink};
+use tee::TeeInfo;
+use telephony::TelephonyInfo;
+use touchscreen::Touchscreen;
+use treble::TrebleInfo;
+use usb::UsbInfo;
+use vendor_flags::VendorFlags;
+use symbols::Marker;
+use walk::{EntryKind, WalkOptions};
+use warning::{Warning, WarningKind};
+use wifi_config::WifiConfigs;
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    #[clap(short, long, value_parser)]
-    tree: String,
+    /// Device tree directory, or a single .dts/.dtsi file to inspect.
+    /// '-' reads a file listing from stdin instead: one path per line,
+    /// relative to --root (e.g. `git ls-files | DeviceTreeParser -t - --root .`)
+    #[clap(short, long, value_parser, required_unless_present_any = ["selftest", "git_url", "dtb", "load"])]
+    tree: Option<String>,
+
+    /// Decode a compiled device tree blob (.dtb/.dtbo) and show its bindings
+    /// and node tree, like a single .dts file
+    #[clap(long, value_parser, conflicts_with_all = ["tree", "git_url", "since", "merge"])]
+    dtb: Option<String>,
+
+    /// Analyze a shallow clone of this git repository instead of a local
+    /// tree (needs the git CLI)
+    #[clap(long, conflicts_with = "tree")]
+    git_url: Option<String>,
+
+    /// Branch or tag to clone with --git-url (default: the remote's default branch)
+    #[clap(long, requires = "git_url")]
+    git_ref: Option<String>,
+
+    /// Keep --git-url clones in this directory, keyed by URL and ref, and
+    /// reuse them on later runs instead of cloning into a temp dir
+    #[clap(long, requires = "git_url")]
+    git_cache: Option<String>,
+
+    /// Directory the stdin listing is relative to and files are read from
+    /// (only with --tree -)
+    #[clap(long, value_parser, default_value = ".")]
+    root: String,
+
+    /// Common/base tree the --tree inherits from. Both are analyzed and
+    /// merged into one report, the --tree's findings winning conflicts and
+    /// the base filling in the rest (rules in merge.rs)
+    #[clap(long, value_parser, conflicts_with_all = ["since", "hash"])]
+    merge: Option<String>,
+
+    /// Re-render a report saved with --format json instead of scanning a
+    /// tree. Device info, key files, drivers, HALs, file hashes and warnings
+    /// are restored; the other sections come back empty, so it can't be
+    /// combined with the --export-* flags
+    #[clap(
+        long,
+        value_parser,
+        conflicts_with_all = [
+            "tree", "git_url", "dtb", "merge", "since", "hash", "fail_if_missing",
+            "export_plist", "export_toml", "export_json_combined", "export_split",
+        ]
+    )]
+    load: Option<String>,
 
     #[clap(long, value_parser)]
     export_plist: Option<String>,
+
+    /// Write the report as TOML: the JSON document with objects as tables
+    /// and unset (null) fields left out
+    #[clap(long, value_parser)]
+    export_toml: Option<String>,
+
+    /// Write paths in exports relative to the tree root (the default)
+    #[clap(long, overrides_with = "absolute_paths")]
+    relative_paths: bool,
+
+    /// Write the tree's absolute location into exports instead of "."
+    #[clap(long, overrides_with = "relative_paths")]
+    absolute_paths: bool,
+
+    /// Write a JSON array with one {tree_path, device, vendor, report} entry
+    /// per analyzed tree
+    #[clap(long, value_parser)]
+    export_json_combined: Option<String>,
+
+    /// Write one <category>.json per driver category into this directory,
+    /// plus a summary.json mapping category names to file names
+    #[clap(long, value_parser)]
+    export_split: Option<String>,
+
+    /// Encoding of the export files: utf8, utf8-bom, or utf16le (with a BOM;
+    /// the plist declares UTF-16). TOML and JSON readers generally expect
+    /// plain UTF-8. Output on stdout is always UTF-8
+    #[clap(long, value_enum, default_value_t = OutputEncoding::Utf8)]
+    output_encoding: OutputEncoding,
+
+    /// Run the full analysis but only list what the exports would write
+    /// (destination, size, entry counts) instead of creating any files
+    #[clap(long)]
+    dry_run: bool,
+
+    /// Check a condition against the report, e.g. 'structure_valid == true'.
+    /// Repeatable; exits nonzero if any assertion fails.
+    #[clap(long = "assert", value_parser)]
+    assertions: Vec<String>,
+
+    /// Limit how deep the scanners descend (0 = top level only)
+    #[clap(long, value_parser)]
+    max_depth: Option<usize>,
+
+    /// Stop collecting a driver category after N entries and warn that it
+    /// was cut short (default: unlimited)
+    #[clap(long, value_parser, value_name = "N")]
+    max_entries_per_category: Option<usize>,
+
+    /// Only examine files directly in the tree directory, without descending
+    /// into subdirectories (same as --max-depth 0)
+    #[clap(long, conflicts_with = "max_depth")]
+    no_recursion: bool,
+
+    /// Follow symlinked files and directories while scanning
+    #[clap(long)]
+    follow_symlinks: bool,
+
+    /// Warn when a node's reg doesn't fit its parent's #address-cells/#size-cells
+    #[clap(long)]
+    validate_dts: bool,
+
+    /// Take vendor/device from the tree's path below this directory (the
+    /// device/ directory or the source root above it) instead of the last
+    /// two components of its absolute path. Trees outside it fall back to
+    /// the default
+    #[clap(long, value_parser)]
+    root_relative_to: Option<String>,
+
+    /// Compatible that identifies a board, so one DTS file should claim it
+    /// ('*' matches anything, e.g. 'xiaomi,*'). Repeatable; replaces the
+    /// default set: the root node's board compatibles and *-mtp, *-qrd,
+    /// *-cdp, *-idp, *-hdk, *-atp
+    #[clap(long = "board-compatible", value_parser)]
+    board_compatibles: Vec<String>,
+
+    /// Retry a failed file read up to N times (for flaky NFS/SMB mounts)
+    #[clap(long, value_parser, default_value_t = 0)]
+    read_retries: usize,
+
+    /// Print how long each analysis phase took, as a table on stderr
+    #[clap(long)]
+    profile: bool,
+
+    /// Exit with a nonzero status if this file (relative to the tree) is
+    /// missing. Repeatable; honors --ignore-case
+    #[clap(long, value_parser)]
+    fail_if_missing: Vec<String>,
+
+    /// Match key files and directories case-insensitively (e.g. boardconfig.mk)
+    #[clap(long)]
+    ignore_case: bool,
+
+    /// Record a SHA-256 for every file referenced by the report
+    #[clap(long)]
+    hash: bool,
+
+    /// Skip the driver and section scans and only report structure and
+    /// device info
+    #[clap(long)]
+    no_drivers: bool,
+
+    /// Only print errors and the final structure status
+    #[clap(short, long)]
+    quiet: bool,
+
+    /// Exit with a nonzero status if the analysis produced any warnings
+    #[clap(long)]
+    warnings_as_errors: bool,
+
+    /// Exit with a nonzero status if the analysis produced more than N
+    /// warnings. --warnings-as-errors is the same as 0 and wins over this
+    #[clap(long, value_parser, value_name = "N")]
+    fail_on_warning_count: Option<usize>,
+
+    /// Always exit 0 once the analysis has run, overriding the status set by
+    /// --assert, --fail-if-missing, --warnings-as-errors and
+    /// --fail-on-warning-count (failed checks are still printed). Unreadable
+    /// paths, git/stdin errors and failed --export-* writes still fail
+    #[clap(long)]
+    exit_zero: bool,
+
+    /// Output format for stdout
+    #[clap(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Show at most N entries per driver category (and per vendor in the
+    /// bindings list) in the text report (0 = all). Only the driver
+    /// categories are cut; the other sections always list everything
+    #[clap(long, value_parser, default_value_t = 0)]
+    top: usize,
+
+    /// Only print the deduped entry count of each driver category
+    #[clap(long)]
+    count_only: bool,
+
+    /// Shorter text report: one line per driver category, key files and
+    /// directories as found/missing counts and the other sections by name.
+    /// Lines are cut to the terminal width ($COLUMNS)
+    #[clap(long)]
+    compact: bool,
+
+    /// Run the scanners over built-in fixtures and check what they extract
+    #[clap(long, hide = true)]
+    selftest: bool,
+
+    /// Only list the key files and directories that are missing, plus any
+    /// missing --fail-if-missing files (skips the driver and section scans)
+    #[clap(long)]
+    only_missing: bool,
+
+    /// Only parse files changed since this git ref (git diff --name-only)
+    #[clap(long, value_parser)]
+    since: Option<String>,
+
+    /// Render the report through a handlebars template instead of the
+    /// built-in text layout (see templates/ for examples)
+    #[clap(long, value_parser)]
+    template: Option<PathBuf>,
+
+    /// Order of driver categories in the text report and exports
+    #[clap(long, value_enum, default_value_t = DriverSort::Name)]
+    sort: DriverSort,
+
+    /// Rename driver categories in the output, one "Category = New Name" per
+    /// line ('#' comments allowed). Categories given the same name are
+    /// merged, e.g. "GPU/Platform = Graphics" and "Camera Driver = Graphics"
+    #[clap(long, value_parser)]
+    category_map: Option<PathBuf>,
+
+    /// Only report driver categories found in this kind of source: dts,
+    /// board_config, device_mk or prebuilt. Repeatable; the other report
+    /// sections are not affected
+    #[clap(long, value_enum)]
+    filter_source: Vec<DriverSource>,
+
+    /// Status markers in the text report: unicode (✓ ✗ ⚠) or ascii
+    /// ([OK] [--] [!!]). Defaults to unicode, or ascii when the locale
+    /// isn't UTF-8
+    #[clap(long, value_enum)]
+    symbols: Option<SymbolSet>,
+
+    /// Marker colors on a terminal: default (green/red/yellow) or
+    /// colorblind (blue/vermillion/yellow). NO_COLOR turns colors off
+    #[clap(long, value_enum, default_value_t = ColorTheme::Default)]
+    color_theme: ColorTheme,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+    // One driver entry per line, after a leading metadata line
+    Ndjson,
+    // The --export-plist document, on stdout
+    Plist,
+    // Warnings and missing --fail-if-missing files as a SARIF 2.1.0 log
+    Sarif,
+    // The JSON report as TOML tables (report_toml.rs)
+    Toml,
+    // One flat line of headline numbers for dashboards
+    SummaryJson,
+}
+
+// --sort: the order driver categories are listed in. Entries within a
+// category stay sorted by name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum DriverSort {
+    // Alphabetical, so diffs between runs stay small
+    #[default]
+    Name,
+    // Grouped by where list_device_drivers finds them (BoardConfig.mk,
+    // device.mk, DTS, prebuilt modules)
+    Source,
+    // Grouped by the file they're read from, multi-file categories last
+    File,
+    // Most entries first
+    Count,
+}
+
+// --filter-source: where list_device_drivers finds a category, as
+// category::source names it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum DriverSource {
+    Dts,
+    #[value(name = "board_config")]
+    BoardConfig,
+    #[value(name = "device_mk")]
+    DeviceMk,
+    Prebuilt,
+}
+
+impl DriverSource {
+    fn id(&self) -> &'static str {
+        match self {
+            DriverSource::Dts => "dts",
+            DriverSource::BoardConfig => "board_config",
+            DriverSource::DeviceMk => "device_mk",
+            DriverSource::Prebuilt => "prebuilt",
+        }
+    }
+}
+
+// --output-encoding (see encoding.rs)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum OutputEncoding {
+    // No BOM
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16le,
+}
+
+// --symbols
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum SymbolSet {
+    Unicode,
+    Ascii,
+}
+
+// --color-theme
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+enum ColorTheme {
+    #[default]
+    Default,
+    Colorblind,
+}
+
+// Knobs that change what the analysis looks at
+#[derive(Debug, Default)]
+struct AnalysisOptions {
+    walk: WalkOptions,
+    scan_drivers: bool,
+    // Match KEY_FILES/KEY_DIRS case-insensitively (--ignore-case)
+    ignore_case: bool,
+    // Check reg against #address-cells/#size-cells (--validate-dts)
+    validate_dts: bool,
+    // --board-compatible patterns; empty means the defaults
+    board_compatibles: Vec<String>,
+    // --root-relative-to: where vendor/device path components start
+    device_root: Option<PathBuf>,
+    // --max-entries-per-category; None collects everything
+    max_entries: Option<usize>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 struct HardwareReport {
+    tree_path: String,
     device_info: HashMap<String, String>,
     key_files: HashMap<String, bool>,
     key_dirs: HashMap<String, bool>,
+    // Key file/dir name -> name actually found on disk, for entries only
+    // matched thanks to --ignore-case
+    key_names_on_disk: BTreeMap<String, String>,
+    // False with --no-drivers, so an empty map isn't mistaken for "none found"
+    drivers_scanned: bool,
+    // --max-depth/--no-recursion limit, so a shallow scan isn't read as the
+    // whole tree
+    max_depth: Option<usize>,
     drivers: HashMap<String, Vec<String>>,
+    // Categories the driver scan looked for, so one that's attempted but
+    // empty ("none found") can be told from one that was never looked at
+    // (e.g. no device.mk)
+    attempted_categories: BTreeSet<String>,
+    // Categories that hit --max-entries-per-category (the limit is kept in
+    // max_entries), so a capped list isn't read as the whole tree
+    truncated_categories: BTreeSet<String>,
+    max_entries: Option<usize>,
+    // --sort; set after the scan, like tree_path, and followed by every view
+    // that lists categories
+    driver_sort: DriverSort,
+    // --category-map, already applied to drivers/attempted_categories; kept
+    // to map the known category list and find each category's source
+    category_map: CategoryMap,
+    // The HAL category's packages grouped by family (see hal::normalize)
+    hals: Vec<Hal>,
+    dts_sources: DtsSources,
+    dtb_overlays: DtbOverlays,
+    panels: Vec<Panel>,
+    touchscreens: Vec<Touchscreen>,
+    camera: CameraInfo,
+    haptics: Haptics,
+    biometrics: Biometrics,
+    drm: DrmInfo,
+    tee: TeeInfo,
+    gpu: GpuInfo,
+    leds: LedInfo,
+    nfc: NfcInfo,
+    usb: UsbInfo,
+    regulators: Regulators,
+    power_tree: Vec<PowerConsumer>,
+    battery: BatteryInfo,
+    copied_files: CopiedFiles,
+    declared_features: DeclaredFeatures,
+    media_profiles: MediaProfiles,
+    mixer_paths: MixerPaths,
+    key_layouts: KeyLayouts,
+    carrier_config: CarrierConfig,
+    telephony: TelephonyInfo,
+    power: PowerConfig,
+    perf_tuning: PerfTuning,
+    cpu_topology: CpuTopology,
+    vendor_flags: VendorFlags,
+    filesystem_setup: FilesystemSetup,
+    boot_sequence: BootSequence,
+    kernel: KernelInfo,
+    module_consistency: ModuleConsistency,
+    modem: ModemInfo,
+    boot: BootInfo,
+    partition_sizes: PartitionSizes,
+    encryption: EncryptionInfo,
+    wifi: WifiInfo,
+    treble: TrebleInfo,
+    // System-as-root, dynamic partition and VINTF manifest inputs to the
+    // GSI checklist (see gsi::checklist)
+    gsi: GsiInfo,
+    apex: ApexInfo,
+    linker_namespaces: LinkerNamespaces,
+    // With --merge, what came from the base tree
+    merge: Option<MergeInfo>,
+    // With --load, the JSON report this was read from
+    loaded_from: Option<String>,
+    // Relative path -> SHA-256, only filled in with --hash
+    file_hashes: BTreeMap<String, String>,
+    warnings: Vec<Warning>,
     structure_valid: bool,
 }
 
-fn detect_android_device_tree_structure(tree_path: &str, export_plist: Option<String>) {
-    let path = Path::new(tree_path);
+#[derive(Debug, Default)]
+struct WifiInfo {
+    chipset: Option<String>,
+    supplicant_version: Option<String>,
+    hostapd_driver: Option<String>,
+    driver_module: Option<String>,
+    // WIFI_DRIVER_* variables (module path/name/args, firmware paths)
+    driver_vars: BTreeMap<String, String>,
+    // WCNSS_qcom_cfg.ini, hostapd.conf and supplicant overlays
+    configs: WifiConfigs,
+}
 
-    if !path.exists() {
-        eprintln!("Error: Path '{}' does not exist", tree_path);
-        return;
+impl WifiInfo {
+    fn is_empty(&self) -> bool {
+        self.fields().is_empty() && self.configs.is_empty()
     }
 
-    if !path.is_dir() {
-        eprintln!("Error: Path '{}' is not a directory", tree_path);
-        return;
+    // Labeled key/value pairs in display order
+    fn fields(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        let labeled = [
+            ("Chipset", &self.chipset),
+            ("Supplicant Version", &self.supplicant_version),
+            ("Hostapd Driver", &self.hostapd_driver),
+            ("Driver Module", &self.driver_module),
+        ];
+        for (label, value) in labeled {
+            if let Some(value) = value {
+                fields.push((label.to_string(), value.clone()));
+            }
+        }
+        for (name, value) in &self.driver_vars {
+            fields.push((name.clone(), value.clone()));
+        }
+        fields
     }
+}
 
-    println!("Analyzing Android device tree at: {}\n", tree_path);
+// Common Android device tree files and directories
+const KEY_FILES: [&str; 7] = [
+    "AndroidProducts.mk",
+    "BoardConfig.mk",
+    "device.mk",
+    "system.prop",
+    "vendorsetup.sh",
+    "extract-files.sh",
+    "setup-makefiles.sh",
+];
 
-    // Common Android device tree files and directories
-    let key_files = vec![
-        "AndroidProducts.mk",
-        "BoardConfig.mk",
-        "device.mk",
-        "system.prop",
-        "vendorsetup.sh",
-        "extract-files.sh",
-        "setup-makefiles.sh",
-    ];
+const KEY_DIRS: [&str; 7] = [
+    "overlay",
+    "proprietary",
+    "proprietary-files.txt",
+    "configs",
+    "rootdir",
+    "recovery",
+    "prebuilt",
+];
 
-    let key_dirs = vec![
-        "overlay",
-        "proprietary",
-        "proprietary-files.txt",
-        "configs",
-        "rootdir",
-        "recovery",
-        "prebuilt",
-    ];
+fn detect_android_device_tree_structure(
+    tree_path: &str,
+    options: &AnalysisOptions,
+    profiler: &mut Profiler,
+) -> Result<HardwareReport, AnalyzeError> {
+    let path = Path::new(tree_path);
+    let walk_options = &options.walk;
+
+    // A listing from stdin stands in for the directory itself
+    if walk_options.listing.is_none() {
+        if !path.exists() {
+            return Err(AnalyzeError::PathNotFound(tree_path.to_string()));
+        }
+
+        if !path.is_dir() {
+            return Err(AnalyzeError::NotADirectory(tree_path.to_string()));
+        }
+    }
 
     let mut found_files: HashMap<String, PathBuf> = HashMap::new();
-    let mut found_dirs: HashMap<String, PathBuf> = HashMap::new();
     let mut files_status: HashMap<String, bool> = HashMap::new();
     let mut dirs_status: HashMap<String, bool> = HashMap::new();
+    let mut key_names_on_disk: BTreeMap<String, String> = BTreeMap::new();
+
+    let matches = |key: &str, entry_name: &str| {
+        if options.ignore_case {
+            key.eq_ignore_ascii_case(entry_name)
+        } else {
+            key == entry_name
+        }
+    };
+
+    // Scan the tree directory
+    let started = Instant::now();
+    let entries: Vec<PathBuf> = match &walk_options.listing {
+        Some(listing) => listing.children(path).iter().map(|(entry_path, _)| entry_path.clone()).collect(),
+        None => fs::read_dir(path).map(|entries| entries.flatten().map(|entry| entry.path()).collect()).unwrap_or_default(),
+    };
+    for entry_path in entries {
+        let entry_name = entry_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let key = if walk_options.is_file(&entry_path) {
+            KEY_FILES.iter().find(|file| matches(file, &entry_name))
+        } else if walk_options.is_dir(&entry_path) {
+            KEY_DIRS.iter().find(|dir| matches(dir, &entry_name))
+        } else {
+            None
+        };
+        let Some(key) = key else {
+            continue;
+        };
+
+        // An exact match wins over a differently cased one
+        if *key != entry_name {
+            if files_status.contains_key(*key) || dirs_status.contains_key(*key) {
+                continue;
+            }
+            key_names_on_disk.insert(key.to_string(), entry_name);
+        } else {
+            key_names_on_disk.remove(*key);
+        }
+
+        if walk_options.is_file(&entry_path) {
+            found_files.insert(key.to_string(), entry_path);
+            files_status.insert(key.to_string(), true);
+        } else {
+            dirs_status.insert(key.to_string(), true);
+        }
+    }
+
+    // Mark missing files/dirs
+    for file in &KEY_FILES {
+        files_status.entry(file.to_string()).or_insert(false);
+    }
+    for dir in &KEY_DIRS {
+        dirs_status.entry(dir.to_string()).or_insert(false);
+    }
+    profiler.record("key-file scan", started);
+
+    let mut warnings = Vec::new();
+
+    // Detect device info from path or files
+    let device_info = extract_device_info(path, options.device_root.as_deref(), &found_files, &mut warnings)
+        .unwrap_or_default();
+
+    // Analyze structure validity
+    let has_makefile = found_files.contains_key("AndroidProducts.mk")
+        || found_files.contains_key("device.mk");
+    let has_board_config = found_files.contains_key("BoardConfig.mk");
+    let structure_valid = has_makefile && has_board_config;
+
+    if !has_makefile {
+        warnings.push(Warning::new(
+            WarningKind::MissingRequiredFile,
+            "AndroidProducts.mk",
+            "neither AndroidProducts.mk nor device.mk found",
+        ));
+    }
+    if !has_board_config {
+        warnings.push(Warning::new(WarningKind::MissingRequiredFile, "BoardConfig.mk", "BoardConfig.mk not found"));
+    }
+
+    // --no-drivers only wants the structure and device info, so neither the
+    // driver scan nor any of the section scanners below run
+    if !options.scan_drivers {
+        return Ok(HardwareReport {
+            tree_path: tree_path.to_string(),
+            device_info,
+            key_files: files_status,
+            key_dirs: dirs_status,
+            key_names_on_disk,
+            max_depth: walk_options.max_depth,
+            max_entries: options.max_entries,
+            warnings,
+            structure_valid,
+            ..Default::default()
+        });
+    }
+
+    // Parse device drivers
+    let mut attempted_categories = BTreeSet::new();
+    let (mut drivers, wifi, mut treble, truncated_categories) =
+        list_device_drivers(path, &found_files, options, &mut attempted_categories, &mut warnings, profiler);
+
+    // One HAL entry per family, however many HIDL/AIDL packages build it
+    let hals = match drivers.get_mut(category::HAL) {
+        Some(packages) => {
+            let hals = hal::normalize(packages);
+            *packages = hals.iter().map(Hal::summary).collect();
+            hals
+        }
+        None => Vec::new(),
+    };
+
+    // VNDK libraries/snapshots shipped with the tree
+    treble.vndk_dirs = profiler.time("VNDK directories", || treble::find_vndk_dirs(path, walk_options));
+
+    // .apex/.capex prebuilts, apex modules and the APEX packages
+    let apex = profiler.time("APEX", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        apex::scan_apex(path, device_mk, walk_options, &mut warnings)
+    });
+
+    // public.libraries*.txt exports and ld.config*.txt namespaces
+    let linker_namespaces = profiler.time("linker namespaces", || {
+        linker::scan_linker_namespaces(path, walk_options, &mut warnings)
+    });
+
+    // DTS sources and how they include each other
+    let dts_sources = profiler.time("DTS sources", || dts::collect_sources(path, walk_options, &mut warnings));
+    if options.validate_dts {
+        warnings.extend(profiler.time("DTS validation", || dts::validate_cells(&dts_sources)));
+    }
+    if let Some(bindings) = drivers.get(category::DEVICE_TREE_BINDINGS) {
+        warnings.extend(duplicate_board_compatibles(bindings, &dts_sources, &options.board_compatibles));
+    }
+
+    // Compiled .dtb/.dtbo files and the configs that assemble dtbo.img
+    let dtb_overlays = profiler.time("DTB overlays", || {
+        dtbo::scan_dtb_overlays(path, found_files.get("BoardConfig.mk").map(|p| p.as_path()), walk_options, &mut warnings)
+    });
+
+    // Display panels, merged across the .dtsi files that describe them
+    let panels = profiler.time("panels", || panel::find_panels(&dts_sources));
+
+    // Touch controllers and the firmware blobs that go with them
+    let touchscreens =
+        profiler.time("touchscreens", || touchscreen::find_touchscreens(path, &dts_sources, walk_options));
+
+    // Image sensors, camera configs/sensor modules and the camera packages
+    let camera = profiler.time("camera", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        camera::scan_camera(path, &dts_sources, device_mk, walk_options, &mut warnings)
+    });
+
+    // Vibrator controllers and the HAL packages that drive them
+    let haptics = profiler.time("haptics", || {
+        haptics::scan_haptics(path, &dts_sources, found_files.get("device.mk").map(|p| p.as_path()), walk_options)
+    });
+
+    // Fingerprint sensors, the biometrics HAL and fingerprint config
+    let biometrics = profiler.time("biometrics", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        biometrics::scan_biometrics(path, &dts_sources, device_mk, walk_options)
+    });
+
+    // Widevine: DRM HAL packages, BOARD_WIDEVINE_* flags and OEMCrypto blobs
+    let drm = profiler.time("DRM", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        let board_config = found_files.get("BoardConfig.mk").map(|p| p.as_path());
+        drm::scan_drm(path, device_mk, board_config, walk_options, &mut warnings)
+    });
+
+    // TEE type, keymaster/gatekeeper HALs and secure-world DTS bindings
+    let tee = profiler.time("TEE", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        let board_config = found_files.get("BoardConfig.mk").map(|p| p.as_path());
+        tee::scan_tee(&dts_sources, device_mk, board_config, walk_options)
+    });
+
+    // GPU DTS bindings, the platform's expected GPU and graphics HAL/driver packages
+    let gpu = profiler.time("GPU", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        let board_config = found_files.get("BoardConfig.mk").map(|p| p.as_path());
+        gpu::scan_gpu(&dts_sources, device_mk, board_config, walk_options)
+    });
+
+    // LED class devices, backlight controllers and the lights HAL
+    let leds = profiler.time("LEDs", || {
+        leds::scan_leds(path, &dts_sources, found_files.get("device.mk").map(|p| p.as_path()), walk_options)
+    });
+
+    // NFC controllers/secure elements, their packages and libnfc configs
+    let nfc = profiler.time("NFC", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        nfc::scan_nfc(path, &dts_sources, device_mk, walk_options, &mut warnings)
+    });
+
+    // USB gadget functions from init scripts, .prop files and BOARD_USB_*
+    let usb = profiler.time("USB", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        let board_config = found_files.get("BoardConfig.mk").map(|p| p.as_path());
+        usb::scan_usb(path, device_mk, board_config, walk_options)
+    });
+
+    // Fixed and PMIC regulators with their voltage constraints
+    let regulators = profiler.time("regulators", || regulator::find_regulators(&dts_sources));
+
+    // Device -> regulator -> parent regulator chains from *-supply properties
+    let power_tree = profiler.time("power tree", || regulator::build_power_tree(&dts_sources));
+
+    // Charger/fuel gauge bindings and battery profiles
+    let battery = profiler.time("battery", || battery::scan_battery(path, &dts_sources, walk_options));
+
+    // PRODUCT_COPY_FILES: which configs end up where on the device
+    let copied_files = profiler.time("PRODUCT_COPY_FILES", || {
+        copy_files::scan_copy_files(path, found_files.get("device.mk").map(|p| p.as_path()), &mut warnings)
+    });
+
+    // <feature> declarations from permission XML, in the tree or copied in
+    let declared_features = profiler.time("declared features", || {
+        features::scan_features(path, &copied_files, walk_options, &mut warnings)
+    });
+
+    // Camcorder profiles: recording qualities and codecs per camera
+    let media_profiles =
+        profiler.time("media profiles", || media::scan_media_profiles(path, walk_options, &mut warnings));
+
+    // Audio routes: <path> -> <ctl> mixer settings, one file per codec variant
+    let mixer_paths =
+        profiler.time("mixer paths", || mixer::scan_mixer_paths(path, walk_options, &mut warnings));
+
+    // Scan code -> key mappings from .kl/.kcm input configs
+    let key_layouts =
+        profiler.time("key layouts", || keylayout::scan_key_layouts(path, walk_options, &mut warnings));
+
+    // Modem/telephony config shipped as carrier XML
+    let carrier_config =
+        profiler.time("carrier config", || carrier::scan_carrier_config(path, walk_options, &mut warnings));
+
+    // SIM count, RIL class and network modes from overlay config.xml and props
+    let telephony = profiler.time("telephony", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        telephony::scan_telephony(path, device_mk, walk_options, &mut warnings)
+    });
+
+    // Power HAL hints and the framework power profile
+    let power = profiler.time("power config", || power::scan_power_config(path, walk_options, &mut warnings));
+
+    // Qualcomm perf HAL boost profiles and perf config store
+    let perf_tuning = profiler.time("perf tuning", || perf::scan_perf_tuning(path, walk_options, &mut warnings));
+
+    // Cpuset core masks and the task profiles that use them
+    let cpu_topology =
+        profiler.time("CPU topology", || cpu_topology::scan_cpu_topology(path, walk_options, &mut warnings));
+
+    // persist.vendor.*/ro.vendor.* toggles from PRODUCT_PROPERTY_OVERRIDES,
+    // prop files and init.rc
+    let vendor_flags = profiler.time("vendor flags", || {
+        let device_mk = found_files.get("device.mk").map(|p| p.as_path());
+        vendor_flags::scan_vendor_flags(path, device_mk, walk_options, &mut warnings)
+    });
+
+    // Kernel source/defconfig and the version of any prebuilt image
+    let kernel = profiler.time("kernel", || {
+        kernel::scan_kernel(path, found_files.get("BoardConfig.mk").map(|p| p.as_path()), walk_options, &mut warnings)
+    });
+
+    // Prebuilt .ko files vs. the modules BoardConfig.mk packages
+    let module_consistency = profiler.time("module consistency", || {
+        let board_config = found_files.get("BoardConfig.mk").map(|p| p.as_path());
+        module_consistency::scan_module_consistency(path, board_config, walk_options, &mut warnings)
+    });
+
+    // Modem firmware images, their baseband version and radio partition setup
+    let modem = profiler.time("modem", || {
+        modem::scan_modem(path, found_files.get("BoardConfig.mk").map(|p| p.as_path()), walk_options, &mut warnings)
+    });
+
+    // A/B slots and where recovery lives
+    let makefiles: Vec<&Path> =
+        ["BoardConfig.mk", "device.mk"].iter().filter_map(|name| found_files.get(*name)).map(|p| p.as_path()).collect();
+    let boot = profiler.time("boot", || boot::scan_boot(&makefiles, walk_options));
+
+    // System-as-root, dynamic partitions and the device manifest's FCM level
+    let gsi = profiler.time("GSI", || gsi::scan_gsi(path, &makefiles, walk_options, &mut warnings));
+
+    // Static partition sizes and filesystem types
+    let partition_sizes = profiler.time("partition sizes", || {
+        partitions::scan_partitions(found_files.get("BoardConfig.mk").map(|p| p.as_path()), walk_options)
+    });
+
+    // FBE/FDE and metadata encryption from the fstabs and BoardConfig.mk
+    let encryption = profiler.time("encryption", || {
+        let board_config = found_files.get("BoardConfig.mk").map(|p| p.as_path());
+        encryption::scan_encryption(path, board_config, walk_options, &mut warnings)
+    });
+
+    // Runtime mounts, directories and symlinks from init scripts
+    let filesystem_setup =
+        profiler.time("init scripts", || init_rc::scan_filesystem_setup(path, walk_options, &mut warnings));
+
+    // Which services each init trigger starts, and the imports between scripts
+    let boot_sequence =
+        profiler.time("boot sequence", || init_rc::scan_boot_sequence(path, walk_options, &mut warnings));
+
+    Ok(HardwareReport {
+        tree_path: tree_path.to_string(),
+        device_info,
+        key_files: files_status,
+        key_dirs: dirs_status,
+        key_names_on_disk,
+        drivers_scanned: true,
+        max_depth: walk_options.max_depth,
+        drivers,
+        attempted_categories,
+        truncated_categories,
+        max_entries: options.max_entries,
+        driver_sort: DriverSort::default(),
+        category_map: CategoryMap::default(),
+        hals,
+        dts_sources,
+        dtb_overlays,
+        panels,
+        touchscreens,
+        camera,
+        haptics,
+        biometrics,
+        drm,
+        tee,
+        gpu,
+        leds,
+        nfc,
+        usb,
+        regulators,
+        power_tree,
+        battery,
+        copied_files,
+        declared_features,
+        media_profiles,
+        mixer_paths,
+        key_layouts,
+        carrier_config,
+        telephony,
+        power,
+        perf_tuning,
+        cpu_topology,
+        vendor_flags,
+        filesystem_setup,
+        boot_sequence,
+        kernel,
+        module_consistency,
+        modem,
+        boot,
+        partition_sizes,
+        encryption,
+        wifi,
+        treble,
+        gsi,
+        apex,
+        linker_namespaces,
+        merge: None,
+        loaded_from: None,
+        file_hashes: BTreeMap::new(),
+        warnings,
+        structure_valid,
+    })
+}
+
+// " (found as boardconfig.mk)" for key files matched with --ignore-case
+fn found_as(report: &HardwareReport, name: &str) -> String {
+    match report.key_names_on_disk.get(name) {
+        Some(on_disk) => format!(" (found as {})", on_disk),
+        None => String::new(),
+    }
+}
+
+// "valid", "partial" (a makefile or BoardConfig.mk, but critical files
+// missing) or "invalid"
+fn structure_level(report: &HardwareReport) -> &'static str {
+    let found = |file: &str| report.key_files.get(file).copied().unwrap_or(false);
+    let has_makefile = found("AndroidProducts.mk") || found("device.mk");
+    let has_board_config = found("BoardConfig.mk");
+
+    if report.structure_valid {
+        "valid"
+    } else if has_makefile || has_board_config {
+        "partial"
+    } else {
+        "invalid"
+    }
+}
+
+fn structure_status(report: &HardwareReport) -> String {
+    match structure_level(report) {
+        "valid" => format!("Status: {} Valid Android device tree structure detected", Marker::Ok),
+        "partial" => format!("Status: {} Partial device tree structure (missing critical files)", Marker::Warn),
+        _ => format!("Status: {} Does not appear to be a valid Android device tree", Marker::Fail),
+    }
+}
+
+// --compact: the gist of display_report, a line per driver category
+fn display_compact(report: &HardwareReport, width: Option<usize>) {
+    println!("Analyzing Android device tree at: {}", report.tree_path);
+    if let Some(loaded_from) = &report.loaded_from {
+        println!("{} Loaded from {} (--load), most sections are empty", Marker::Warn, loaded_from);
+    }
+    if let Some(depth) = report.max_depth {
+        println!("{} Scan limited to depth {}, sections may be incomplete", Marker::Warn, depth);
+    }
+
+    let info = &report.device_info;
+    let values =
+        |keys: &[&str]| -> Vec<&str> { keys.iter().filter_map(|key| info.get(*key)).map(|v| v.as_str()).collect() };
+    let mut device = values(&["vendor", "device"]);
+    let product = values(&["product_name", "product_brand", "product_model"]);
+    if !device.is_empty() || !product.is_empty() {
+        let product = if product.is_empty() { String::new() } else { format!(" ({})", product.join(", ")) };
+        if device.is_empty() {
+            device.push("unknown");
+        }
+        println!("Device: {}{}", device.join("/"), product);
+    }
+
+    let key_paths = [("Files", &KEY_FILES[..], &report.key_files), ("Dirs", &KEY_DIRS[..], &report.key_dirs)];
+    for (label, names, found) in key_paths {
+        let missing: Vec<&str> =
+            names.iter().copied().filter(|name| !found.get(*name).copied().unwrap_or(false)).collect();
+        let label = format!("{}: {}/{} found", label, names.len() - missing.len(), names.len());
+        if missing.is_empty() {
+            println!("{}", label);
+        } else {
+            println!("{}", compact_line(&format!("{}, missing", label), &missing, width));
+        }
+    }
+    println!("{}", structure_status(report));
+
+    if !report.drivers_scanned {
+        println!("Drivers: skipped (--no-drivers)");
+    } else {
+        // "qcom,foo (in a.dts, b.dtsi)" -> "qcom,foo"; an empty WiFi list
+        // still has its BoardConfig settings
+        let wifi_name = report.category_map.unmerged(category::WIFI);
+        let entries = |category: &str| -> Vec<String> {
+            let mut entries: Vec<String> = report
+                .drivers
+                .get(category)
+                .into_iter()
+                .flatten()
+                .map(|entry| entry.split(" (in ").next().unwrap_or(entry).to_string())
+                .collect();
+            if entries.is_empty() && Some(category) == wifi_name {
+                let fields = report.wifi.fields().into_iter();
+                entries = fields.map(|(label, value)| format!("{}: {}", label, value)).collect();
+            }
+            entries.sort();
+            entries.dedup();
+            entries
+        };
+
+        let mut categories: Vec<&str> = report.drivers.keys().map(|c| c.as_str()).collect();
+        report_json::sort_categories(&mut categories, report);
+        for category in categories {
+            let entries = entries(category);
+            if !entries.is_empty() {
+                let truncated = if report.truncated_categories.contains(category) { ", truncated" } else { "" };
+                let label = format!("{} ({}{})", category, entries.len(), truncated);
+                println!("{}", compact_line(&label, &entries, width));
+            }
+        }
+        let empty: Vec<&str> = report
+            .attempted_categories
+            .iter()
+            .map(|category| category.as_str())
+            .filter(|category| entries(category).is_empty())
+            .collect();
+        if !empty.is_empty() {
+            println!("{}", compact_line("None found", &empty, width));
+        }
+        let not_scanned: Vec<&str> = report
+            .category_map
+            .known()
+            .into_iter()
+            .filter(|category| !report.attempted_categories.contains(*category))
+            .collect();
+        if !not_scanned.is_empty() {
+            println!("{}", compact_line("Not scanned", &not_scanned, width));
+        }
+    }
+
+    // The other sections by name; affected_categories lists the non-empty
+    // ones after the driver categories
+    let sections: Vec<String> = affected_categories(report)
+        .into_iter()
+        .filter(|section| !report.drivers.contains_key(section))
+        .collect();
+    println!("{}", compact_line(&format!("Sections ({})", sections.len()), &sections, width));
+}
+
+// "Label: a, b, c" cut to `width` columns, ending in an ellipsis if cut
+fn compact_line<T: std::fmt::Display>(label: &str, items: &[T], width: Option<usize>) -> String {
+    let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+    let line = format!("{}: {}", label, items.join(", "));
+    let ellipsis = Marker::Ellipsis.symbol();
+    match width {
+        Some(width) if line.chars().count() > width => {
+            let keep = width.saturating_sub(ellipsis.chars().count());
+            format!("{}{}", line.chars().take(keep).collect::<String>().trim_end(), ellipsis)
+        }
+        _ => line,
+    }
+}
+
+fn display_report(report: &HardwareReport, top: usize) {
+    println!("Analyzing Android device tree at: {}\n", report.tree_path);
+    if let Some(loaded_from) = &report.loaded_from {
+        println!(
+            "{} Loaded from {} (--load): only device info, key files, drivers, HALs and warnings are restored, \
+             so the other sections below are empty\n",
+            Marker::Warn,
+            loaded_from
+        );
+    }
+    match report.max_depth {
+        Some(0) => println!(
+            "{} Recursion disabled (--no-recursion): only files directly in the tree were scanned, \
+             so sections below may be incomplete\n",
+            Marker::Warn
+        ),
+        Some(depth) => println!(
+            "{} Scan limited to {} directory level(s) below the tree (--max-depth {}); \
+             sections below may be incomplete\n",
+            Marker::Warn,
+            depth,
+            depth
+        ),
+        None => {}
+    }
+
+    // Print results
+    println!("=== Device Tree Structure Detection ===\n");
+
+    if !report.device_info.is_empty() {
+        println!("Device Information:");
+        if let Some(vendor) = report.device_info.get("vendor") {
+            println!("  Vendor: {}", vendor);
+        }
+        if let Some(device) = report.device_info.get("device") {
+            println!("  Device: {}", device);
+        }
+        for (variable, key) in product::PRODUCT_VARIABLES {
+            if let Some(value) = report.device_info.get(key) {
+                let conditional = report.device_info.contains_key(&format!("{}_conditional", key));
+                let note = if conditional { " (set conditionally, first branch shown)" } else { "" };
+                println!("  {}: {}{}", variable, value, note);
+            }
+        }
+        if let Some(makefile) = report.device_info.get("product_makefile") {
+            println!("  Product Makefile: {}", makefile);
+        }
+        println!();
+    }
+
+    let files_found = report.key_files.values().filter(|found| **found).count();
+    println!("Key Files Found ({}/{}):", files_found, KEY_FILES.len());
+    for file in &KEY_FILES {
+        if report.key_files.get(*file).copied().unwrap_or(false) {
+            println!("  {} {}{}", Marker::Ok, file, found_as(report, file));
+        } else {
+            println!("  {} {} (missing)", Marker::Fail, file);
+        }
+    }
+
+    let dirs_found = report.key_dirs.values().filter(|found| **found).count();
+    println!("\nKey Directories Found ({}/{}):", dirs_found, KEY_DIRS.len());
+    for dir in &KEY_DIRS {
+        if report.key_dirs.get(*dir).copied().unwrap_or(false) {
+            println!("  {} {}{}", Marker::Ok, dir, found_as(report, dir));
+        } else {
+            println!("  {} {} (missing)", Marker::Fail, dir);
+        }
+    }
+
+    println!("\n=== Structure Analysis ===");
+    println!("{}", structure_status(report));
+
+    if let Some(merge) = &report.merge {
+        println!("\n=== Merged Base Tree ===");
+        display_merge(merge);
+    }
+
+    // None of the sections below were scanned
+    if !report.drivers_scanned {
+        println!("\n=== Device Drivers ===");
+        println!("Skipped (--no-drivers), along with the hardware sections");
+        return;
+    }
+
+    println!("\n=== Boot ===");
+    display_boot(&report.boot);
+
+    println!("\n=== Partition Sizes ===");
+    display_partition_sizes(&report.partition_sizes);
+
+    println!("\n=== Encryption ===");
+    display_encryption(&report.encryption);
+
+    println!("\n=== Kernel ===");
+    display_kernel(&report.kernel);
+
+    println!("\n=== Module Consistency ===");
+    display_module_consistency(&report.module_consistency);
+
+    println!("\n=== Modem/Baseband ===");
+    display_modem(&report.modem);
+
+    println!("\n=== Device Drivers ===");
+    if report.drivers.is_empty() {
+        println!("No device drivers found in the tree.");
+    } else {
+        // Categorize and display drivers
+        display_drivers_by_category(report, top);
+    }
+    display_unfilled_categories(report);
+
+    println!("\n=== Treble/VNDK ===");
+    display_treble(&report.treble);
+
+    println!("\n=== GSI Compatibility ===");
+    display_gsi(&report.treble, &report.boot, &report.gsi);
+
+    println!("\n=== APEX Modules ===");
+    display_apex(&report.apex);
+
+    println!("\n=== Linker Namespaces ===");
+    display_linker_namespaces(&report.linker_namespaces);
+
+    println!("\n=== Device Tree Sources ===");
+    display_dts_sources(&report.dts_sources);
+
+    println!("\n=== Aliases ===");
+    display_aliases(&report.dts_sources);
+
+    println!("\n=== Boot (chosen) ===");
+    display_chosen(&report.dts_sources);
+
+    println!("\n=== DTB Overlays ===");
+    display_dtb_overlays(&report.dtb_overlays);
+
+    println!("\n=== Display/Panel ===");
+    display_panels(&report.panels);
+
+    println!("\n=== Touchscreen ===");
+    display_touchscreens(&report.touchscreens);
+
+    println!("\n=== Camera ===");
+    display_camera(&report.camera);
+
+    println!("\n=== Haptics/Vibrator ===");
+    display_haptics(&report.haptics);
+
+    println!("\n=== Biometrics ===");
+    display_biometrics(&report.biometrics);
+
+    println!("\n=== DRM ===");
+    display_drm(&report.drm);
+
+    println!("\n=== TEE/Keymaster ===");
+    display_tee(&report.tee);
+
+    println!("\n=== Graphics/GPU ===");
+    display_gpu(&report.gpu);
+
+    println!("\n=== LEDs/Backlight ===");
+    display_leds(&report.leds);
+
+    println!("\n=== NFC/SE ===");
+    display_nfc(&report.nfc);
+
+    println!("\n=== USB ===");
+    display_usb(&report.usb);
+
+    println!("\n=== Regulators ===");
+    display_regulators(&report.regulators);
+
+    println!("\n=== Power Tree ===");
+    display_power_tree(&report.power_tree);
+
+    println!("\n=== Battery/Charger ===");
+    display_battery(&report.battery);
+
+    println!("\n=== Copied Files ===");
+    display_copied_files(&report.copied_files);
+
+    println!("\n=== Declared Features ===");
+    display_declared_features(&report.declared_features, &report.hals);
+
+    println!("\n=== Media Profiles ===");
+    display_media_profiles(&report.media_profiles);
+
+    println!("\n=== Audio Mixer Paths ===");
+    display_mixer_paths(&report.mixer_paths);
+
+    println!("\n=== Input Key Layouts ===");
+    display_key_layouts(&report.key_layouts);
+
+    println!("\n=== Carrier Config ===");
+    display_carrier_config(&report.carrier_config);
+
+    println!("\n=== Telephony ===");
+    display_telephony(&report.telephony);
+
+    println!("\n=== Power ===");
+    display_power_config(&report.power);
+
+    println!("\n=== Performance Tuning ===");
+    display_perf_tuning(&report.perf_tuning);
+
+    println!("\n=== CPU Topology ===");
+    display_cpu_topology(&report.cpu_topology);
+
+    println!("\n=== Vendor Feature Flags ===");
+    display_vendor_flags(&report.vendor_flags);
+
+    println!("\n=== Filesystem Setup ===");
+    display_filesystem_setup(&report.filesystem_setup);
+
+    println!("\n=== Boot Sequence ===");
+    display_boot_sequence(&report.boot_sequence);
+}
+
+// Single-file mode: bindings and node tree of one .dts/.dtsi and the files
+// it includes, without any of the Android tree checks
+fn inspect_dts_file(dts_path: &Path, quiet: bool, top: usize, validate_dts: bool) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let sources = dts::collect_file(dts_path, &mut warnings);
+    let title = format!("Analyzing device tree source: {}", dts_path.display());
+    inspect_sources(&title, &sources, warnings, quiet, top, validate_dts)
+}
+
+// --dtb: the same views over a decoded blob
+fn inspect_dtb_file(
+    dtb_path: &Path,
+    quiet: bool,
+    top: usize,
+    validate_dts: bool,
+) -> Result<Vec<Warning>, AnalyzeError> {
+    let path = dtb_path.display().to_string();
+    let data = fsread::read(dtb_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AnalyzeError::PathNotFound(path.clone()),
+        _ => AnalyzeError::InvalidDtb { path: path.clone(), message: e.to_string() },
+    })?;
+    let file = dtb_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| path.clone());
+    let sources =
+        fdt::decode(&data, &file).map_err(|message| AnalyzeError::InvalidDtb { path: path.clone(), message })?;
+    let title = format!("Analyzing device tree blob: {}", path);
+    Ok(inspect_sources(&title, &sources, Vec::new(), quiet, top, validate_dts))
+}
+
+fn inspect_sources(
+    title: &str,
+    sources: &DtsSources,
+    mut warnings: Vec<Warning>,
+    quiet: bool,
+    top: usize,
+    validate_dts: bool,
+) -> Vec<Warning> {
+    if validate_dts {
+        warnings.extend(dts::validate_cells(sources));
+    }
+
+    if quiet {
+        return warnings;
+    }
+
+    println!("{}\n", title);
+
+    println!("=== Device Tree Sources ===");
+    display_dts_sources(sources);
+
+    println!("\n=== Device Drivers ===");
+    let mut bindings = Vec::new();
+    for node in sources.nodes.values() {
+        let file = node
+            .property_locations
+            .get("compatible")
+            .and_then(|location| location.split(':').next())
+            .and_then(|file| Path::new(file).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for compatible in node.strings("compatible") {
+            add_binding(&mut bindings, compatible, &file, None);
+        }
+    }
+    if bindings.is_empty() {
+        println!("No compatible strings found.");
+    } else {
+        display_bindings_by_vendor(&bindings, top);
+    }
+
+    println!("\n=== Aliases ===");
+    display_aliases(sources);
+
+    println!("\n=== Boot (chosen) ===");
+    display_chosen(sources);
+
+    println!("\n=== Node Tree ===");
+    display_node_tree(sources);
+
+    println!("\n=== Warnings ===");
+    display_warnings(&warnings);
+
+    warnings
+}
+
+fn display_node_tree(sources: &DtsSources) {
+    if sources.nodes.is_empty() {
+        println!("No nodes found.");
+        return;
+    }
+
+    // Nodes are keyed by path, which doesn't sort parent-before-child
+    // ("/soc-foo" < "/soc/bar"), so build the hierarchy explicitly
+    let mut children: BTreeMap<&str, Vec<&DtsNode>> = BTreeMap::new();
+    let mut roots = Vec::new();
+    for node in sources.nodes.values() {
+        let parent = match node.path.rsplit_once('/') {
+            Some(("", _)) => "/",
+            Some((parent, _)) => parent,
+            None => "",
+        };
+        if node.path != "/" && sources.nodes.contains_key(parent) {
+            children.entry(parent).or_default().push(node);
+        } else {
+            // The root, or a &label reference that never resolved
+            roots.push(node);
+        }
+    }
+
+    for root in roots {
+        display_node(root, &children, 1);
+    }
+}
+
+fn display_node(node: &DtsNode, children: &BTreeMap<&str, Vec<&DtsNode>>, depth: usize) {
+    let labels: String = node.labels.iter().map(|label| format!("{}: ", label)).collect();
+    let compatible = node.strings("compatible");
+    let name = if node.path.starts_with('&') && !node.path.contains('/') { node.path.as_str() } else { node.name() };
+
+    if compatible.is_empty() {
+        println!("{}{}{}", "  ".repeat(depth), labels, name);
+    } else {
+        println!("{}{}{} [{}]", "  ".repeat(depth), labels, name, compatible.join(", "));
+    }
+
+    for child in children.get(node.path.as_str()).into_iter().flatten() {
+        display_node(child, children, depth + 1);
+    }
+}
+
+// Report sections that picked up anything, i.e. what the changed files
+// touched in --since mode
+fn affected_categories(report: &HardwareReport) -> Vec<String> {
+    let mut categories: Vec<String> = report.drivers.keys().cloned().collect();
+    categories.sort();
+
+    let sections = [
+        ("Boot", !report.boot.is_empty()),
+        ("Partition Sizes", !report.partition_sizes.is_empty()),
+        ("Encryption", !report.encryption.is_empty()),
+        ("Kernel", report.kernel.source.is_some() || !report.kernel.defconfigs.is_empty() || report.kernel.prebuilt_image.is_some()),
+        ("Module Consistency", !report.module_consistency.is_empty()),
+        ("Modem/Baseband", !report.modem.is_empty()),
+        ("Treble/VNDK", !report.treble.is_empty()),
+        ("GSI Compatibility", !report.treble.is_empty() || !report.boot.is_empty() || !report.gsi.is_empty()),
+        ("APEX Modules", !report.apex.is_empty()),
+        ("Linker Namespaces", !report.linker_namespaces.is_empty()),
+        ("Device Tree Sources", !report.dts_sources.files.is_empty()),
+        ("Aliases", !report.dts_sources.aliases().is_empty()),
+        ("Boot (chosen)", report.dts_sources.chosen().is_some()),
+        ("DTB Overlays", !report.dtb_overlays.is_empty()),
+        ("Display/Panel", !report.panels.is_empty()),
+        ("Touchscreen", !report.touchscreens.is_empty()),
+        ("Camera", !report.camera.is_empty()),
+        ("Haptics/Vibrator", !report.haptics.is_empty()),
+        ("Biometrics", !report.biometrics.is_empty()),
+        ("DRM", !report.drm.is_empty()),
+        ("TEE/Keymaster", !report.tee.is_empty()),
+        ("Graphics/GPU", !report.gpu.is_empty()),
+        ("LEDs/Backlight", !report.leds.is_empty()),
+        ("NFC/SE", !report.nfc.is_empty()),
+        ("USB", !report.usb.is_empty()),
+        ("Regulators", !report.regulators.is_empty()),
+        ("Power Tree", !report.power_tree.is_empty()),
+        ("Battery/Charger", !report.battery.is_empty()),
+        ("Copied Files", !report.copied_files.is_empty()),
+        ("Declared Features", !report.declared_features.is_empty()),
+        ("Media Profiles", !report.media_profiles.is_empty()),
+        ("Audio Mixer Paths", !report.mixer_paths.is_empty()),
+        ("Input Key Layouts", !report.key_layouts.is_empty()),
+        ("Carrier Config", !report.carrier_config.is_empty()),
+        ("Telephony", !report.telephony.is_empty()),
+        ("Power", !report.power.is_empty()),
+        ("Performance Tuning", !report.perf_tuning.is_empty()),
+        ("CPU Topology", !report.cpu_topology.is_empty()),
+        ("Vendor Feature Flags", !report.vendor_flags.is_empty()),
+        ("Filesystem Setup", !report.filesystem_setup.is_empty()),
+        ("Boot Sequence", !report.boot_sequence.is_empty()),
+    ];
+    for (section, affected) in sections {
+        if affected {
+            categories.push(section.to_string());
+        }
+    }
+
+    categories
+}
+
+// Key files/dirs that weren't found, in KEY_FILES/KEY_DIRS order, with any
+// missing --fail-if-missing files after the key files
+fn missing_key_entries<'a>(report: &HardwareReport, required: &'a [String]) -> (Vec<&'a str>, Vec<&'static str>) {
+    let mut files: Vec<&str> =
+        KEY_FILES.iter().copied().filter(|file| !report.key_files.get(*file).copied().unwrap_or(false)).collect();
+    for file in required {
+        if !files.contains(&file.as_str()) {
+            files.push(file);
+        }
+    }
+    let dirs = KEY_DIRS.iter().copied().filter(|dir| !report.key_dirs.get(*dir).copied().unwrap_or(false)).collect();
+    (files, dirs)
+}
+
+fn display_missing(files: &[&str], dirs: &[&str]) {
+    if files.is_empty() && dirs.is_empty() {
+        println!("{} No key files or directories missing", Marker::Ok);
+        return;
+    }
+    if !files.is_empty() {
+        println!("Missing Files ({}):", files.len());
+        for file in files {
+            println!("  {} {}", Marker::Fail, file);
+        }
+    }
+    if !dirs.is_empty() {
+        println!("Missing Directories ({}):", dirs.len());
+        for dir in dirs {
+            println!("  {} {}", Marker::Fail, dir);
+        }
+    }
+}
+
+fn display_driver_counts(counts: &[(&str, usize)]) {
+    println!("=== Driver Counts ===");
+    for (category, count) in counts {
+        println!("  {}: {}", category, count);
+    }
+    println!(
+        "\nTotal: {} entries in {} categories",
+        counts.iter().map(|(_, count)| count).sum::<usize>(),
+        counts.len()
+    );
+}
+
+fn display_warnings(warnings: &[Warning]) {
+    if warnings.is_empty() {
+        println!("No warnings.");
+        return;
+    }
+
+    for warning in warnings {
+        println!("  {} {}", Marker::Warn, warning);
+    }
+    println!("\nTotal warnings: {}", warnings.len());
+}
+
+fn list_device_drivers(
+    tree_path: &Path,
+    found_files: &HashMap<String, PathBuf>,
+    options: &AnalysisOptions,
+    attempted: &mut BTreeSet<String>,
+    warnings: &mut Vec<Warning>,
+    profiler: &mut Profiler,
+) -> (HashMap<String, Vec<String>>, WifiInfo, TrebleInfo, BTreeSet<String>) {
+    let walk_options = &options.walk;
+    let limit = options.max_entries;
+    let mut drivers = HashMap::new();
+    let mut wifi = WifiInfo::default();
+    let mut treble = TrebleInfo::default();
+    let mut truncated = BTreeSet::new();
+
+    // Scan for .dts and .dtsi files (Device Tree Source files); the binding
+    // list is capped as it's built, since one compatible can span many files
+    let capped =
+        profiler.time("DTS bindings", || scan_for_device_tree_sources(tree_path, &mut drivers, walk_options, 0, limit));
+    if capped {
+        truncated.insert(category::DEVICE_TREE_BINDINGS.to_string());
+    }
+    attempted.extend(category::DTS.iter().map(|c| c.to_string()));
+
+    // Parse BoardConfig.mk for kernel modules and drivers
+    if let Some(board_config_path) = found_files.get("BoardConfig.mk")
+        && walk_options.includes_file(board_config_path)
+    {
+        profiler.time("BoardConfig.mk", || {
+            parse_board_config(board_config_path, &mut drivers, &mut wifi, &mut treble, warnings)
+        });
+        attempted.extend(category::BOARD_CONFIG.iter().map(|c| c.to_string()));
+        cap_categories(&mut drivers, limit, &mut truncated);
+    }
+
+    // Parse device.mk for HAL and driver configurations
+    if let Some(device_mk_path) = found_files.get("device.mk")
+        && walk_options.includes_file(device_mk_path)
+    {
+        profiler.time("device.mk", || parse_device_mk(device_mk_path, &mut drivers, &mut treble, warnings));
+        attempted.extend(category::DEVICE_MK.iter().map(|c| c.to_string()));
+        cap_categories(&mut drivers, limit, &mut truncated);
+    }
+
+    // Look for prebuilt drivers in various locations
+    profiler.time("prebuilt modules", || scan_prebuilt_modules(tree_path, &mut drivers, walk_options));
+    attempted.extend(category::PREBUILT.iter().map(|c| c.to_string()));
+    cap_categories(&mut drivers, limit, &mut truncated);
+
+    // WiFi driver INI, hostapd and supplicant configs
+    wifi.configs = profiler.time("WiFi configs", || wifi_config::scan_wifi_configs(tree_path, walk_options, warnings));
+    if !wifi.configs.is_empty() {
+        drivers.entry(category::WIFI.to_string()).or_insert_with(Vec::new);
+        attempted.insert(category::WIFI.to_string());
+    }
+
+    for category in &truncated {
+        // DTS bindings and prebuilt modules come from all over the tree
+        let file = category::source(category).1.unwrap_or(".");
+        warnings.push(Warning::new(
+            WarningKind::TruncatedCategory,
+            file,
+            format!(
+                "'{}' stopped after {} entries (--max-entries-per-category); the rest were not collected",
+                category,
+                limit.unwrap_or_default()
+            ),
+        ));
+    }
+
+    (drivers, wifi, treble, truncated)
+}
+
+// --max-entries-per-category for the scans that fill several categories at
+// once: keep the first N distinct entries and note the categories cut short
+fn cap_categories(drivers: &mut HashMap<String, Vec<String>>, limit: Option<usize>, truncated: &mut BTreeSet<String>) {
+    let Some(limit) = limit else { return };
+    for (category, entries) in drivers.iter_mut() {
+        let mut seen = HashSet::new();
+        let before = entries.len();
+        entries.retain(|entry| seen.contains(entry.as_str()) || (seen.len() < limit && seen.insert(entry.clone())));
+        if entries.len() < before {
+            truncated.insert(category.clone());
+        }
+    }
+}
+
+// Returns whether any binding was left out because of the limit
+fn scan_for_device_tree_sources(
+    path: &Path,
+    drivers: &mut HashMap<String, Vec<String>>,
+    walk_options: &WalkOptions,
+    depth: usize,
+    limit: Option<usize>,
+) -> bool {
+    if !walk_options.within_depth(depth) {
+        return false;
+    }
+
+    let mut capped = false;
+    for (entry_path, kind) in walk::read_dir(path, walk_options) {
+        match kind {
+            EntryKind::File => {
+                let file_name = entry_path.file_name().unwrap().to_string_lossy();
+                if file_name.ends_with(".dts") || file_name.ends_with(".dtsi") {
+                    capped |= parse_dts_file(&entry_path, drivers, limit);
+                }
+            }
+            EntryKind::Dir => {
+                // Recursively scan subdirectories
+                capped |= scan_for_device_tree_sources(&entry_path, drivers, walk_options, depth + 1, limit);
+            }
+            EntryKind::Skip => {}
+        }
+    }
+    capped
+}
+
+fn parse_dts_file(dts_path: &Path, drivers: &mut HashMap<String, Vec<String>>, limit: Option<usize>) -> bool {
+    let mut capped = false;
+    if let Ok(content) = fsread::read_to_string(dts_path) {
+        let file_name = dts_path.file_name().unwrap().to_string_lossy().to_string();
+
+        // Look for compatible strings which indicate driver bindings
+        for line in content.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("compatible") {
+                // Extract compatible string: compatible = "vendor,device";
+                if let Some(compat_str) = extract_compatible_string(trimmed) {
+                    let bindings = drivers.entry(category::DEVICE_TREE_BINDINGS.to_string()).or_default();
+                    capped |= !add_binding(bindings, &compat_str, &file_name, limit);
+                }
+            }
+        }
+    }
+    capped
+}
+
+// A .dtsi included by several boards would otherwise be listed once per
+// file, so keep one "qcom,foo (in a.dts, b.dtsi)" entry per compatible.
+// Returns false if a new compatible was dropped because of the limit
+fn add_binding(bindings: &mut Vec<String>, compatible: &str, file_name: &str, limit: Option<usize>) -> bool {
+    let prefix = format!("{} (in ", compatible);
+    let full = limit.is_some_and(|limit| bindings.len() >= limit);
+
+    match bindings.iter_mut().find(|entry| entry.starts_with(&prefix)) {
+        Some(entry) => {
+            let mut files: Vec<&str> = entry[prefix.len()..entry.len() - 1].split(", ").collect();
+            if !files.contains(&file_name) {
+                files.push(file_name);
+                files.sort();
+                *entry = format!("{}{})", prefix, files.join(", "));
+            }
+        }
+        None if full => return false,
+        None => bindings.push(format!("{}{})", prefix, file_name)),
+    }
+    true
+}
+
+// Compatibles that usually name one board (Qualcomm reference designs)
+const BOARD_COMPATIBLE_PATTERNS: [&str; 6] = ["*-mtp", "*-qrd", "*-cdp", "*-idp", "*-hdk", "*-atp"];
+
+// Board-level compatibles claimed by more than one DTS file, which usually
+// means a board file was copied and its compatible never updated. Without
+// --board-compatible, the root node's compatibles but the last (the SoC,
+// which boards share) count as board-level too.
+fn duplicate_board_compatibles(bindings: &[String], sources: &DtsSources, patterns: &[String]) -> Vec<Warning> {
+    let mut patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+    if patterns.is_empty() {
+        patterns.extend(BOARD_COMPATIBLE_PATTERNS);
+        if let Some(root) = sources.nodes.get("/") {
+            let compatibles = root.strings("compatible");
+            patterns.extend(compatibles.iter().take(compatibles.len().saturating_sub(1)));
+        }
+    }
+
+    let mut warnings = Vec::new();
+    for binding in bindings {
+        // "qcom,sm8150-mtp (in a.dts, b.dts)", see add_binding
+        let Some((compatible, files)) = binding.strip_suffix(')').and_then(|b| b.split_once(" (in ")) else {
+            continue;
+        };
+        let files: Vec<&str> = files.split(", ").collect();
+        if files.len() < 2 || !patterns.iter().any(|pattern| glob_match(pattern, compatible)) {
+            continue;
+        }
+        // Bindings only keep file names; the DTS scan has the full paths
+        let files: Vec<&str> = files
+            .into_iter()
+            .map(|name| {
+                let path = sources.files.iter().find(|file| file.rsplit('/').next() == Some(name));
+                path.map_or(name, String::as_str)
+            })
+            .collect();
+        warnings.push(Warning::new(
+            WarningKind::DuplicateCompatible,
+            files[0],
+            format!("board compatible '{}' is claimed by {} files: {}", compatible, files.len(), files.join(", ")),
+        ));
+    }
+    warnings
+}
+
+// '*' matches any run of characters, everything else itself
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+fn extract_compatible_string(line: &str) -> Option<String> {
+    // Parse: compatible = "qcom,msm8996", "qcom,somename";
+    if let Some(start) = line.find('"')
+        && let Some(end) = line[start + 1..].find('"')
+    {
+        return Some(line[start + 1..start + 1 + end].to_string());
+    }
+    None
+}
+
+fn parse_board_config(
+    board_config_path: &Path,
+    drivers: &mut HashMap<String, Vec<String>>,
+    wifi: &mut WifiInfo,
+    treble: &mut TrebleInfo,
+    warnings: &mut Vec<Warning>,
+) {
+    let content = fsread::read_to_string(board_config_path);
+    if let Err(e) = &content {
+        warnings.push(Warning::new(WarningKind::UnreadableFile, "BoardConfig.mk", e.to_string()));
+    }
+    if let Ok(content) = content {
+        for (_, statement) in statements(&content) {
+            let Some(makefile::Assignment { name, value, .. }) = makefile::parse_assignment(&statement) else {
+                continue;
+            };
+
+            // Look for kernel module definitions
+            if name.contains("KERNEL_MODULES") {
+                let modules = value.split_whitespace().filter(|module| module.ends_with(".ko"));
+                let modules: Vec<String> = modules.map(|module| module.to_string()).collect();
+                if !modules.is_empty() {
+                    drivers.entry(category::KERNEL_MODULES.to_string()).or_default().extend(modules);
+                }
+            }
+
+            // Look for WiFi driver
+            if name.starts_with("BOARD_WLAN_DEVICE")
+                || name.starts_with("WPA_SUPPLICANT_VERSION")
+                || name.starts_with("BOARD_HOSTAPD_DRIVER")
+                || name.starts_with("WIFI_DRIVER_")
+            {
+                parse_wifi_variable(&statement, drivers, wifi);
+            }
+
+            // BOARD_VNDK_VERSION and Treble flags
+            treble.parse_variable(&statement);
+
+            // Look for Bluetooth
+            if (name.starts_with("BOARD_HAVE_BLUETOOTH") || name.starts_with("BOARD_BLUETOOTH_BDROID_BUILDCFG"))
+                && !value.is_empty()
+            {
+                drivers.entry(category::BLUETOOTH.to_string()).or_default().push(value.to_string());
+            }
+
+            // Look for GPU/Graphics
+            if name.starts_with("TARGET_BOARD_PLATFORM") && !value.is_empty() {
+                drivers.entry(category::GPU.to_string()).or_default().push(value.to_string());
+            }
+        }
+    }
+}
+
+fn parse_wifi_variable(line: &str, drivers: &mut HashMap<String, Vec<String>>, wifi: &mut WifiInfo) {
+    let (name, value) = match makefile::split_assignment(line) {
+        Some((name, value)) if !value.is_empty() => (name, value.trim_matches('"').to_string()),
+        _ => return,
+    };
+
+    // Keep the category even when only supplicant/hostapd settings exist
+    drivers.entry(category::WIFI.to_string()).or_default();
+
+    match name {
+        "BOARD_WLAN_DEVICE" => {
+            // The chipset is what identifies the driver
+            drivers.entry(category::WIFI.to_string()).or_default().push(value.clone());
+            wifi.chipset = Some(value);
+        }
+        "WPA_SUPPLICANT_VERSION" => wifi.supplicant_version = Some(value),
+        "BOARD_HOSTAPD_DRIVER" => wifi.hostapd_driver = Some(value),
+        _ => {
+            // WIFI_DRIVER_MODULE_PATH := /vendor/lib/modules/wlan.ko
+            // WIFI_DRIVER_MODULE_NAME := "wlan"
+            let module = match name {
+                "WIFI_DRIVER_MODULE_PATH" if value.ends_with(".ko") => {
+                    value.rsplit('/').next().map(|m| m.to_string())
+                }
+                "WIFI_DRIVER_MODULE_NAME" if wifi.driver_module.is_none() => {
+                    Some(format!("{}.ko", value.trim_end_matches(".ko")))
+                }
+                _ => None,
+            };
+            if let Some(module) = module {
+                drivers.entry(category::WIFI.to_string()).or_default().push(module.clone());
+                wifi.driver_module = Some(module);
+            }
+            wifi.driver_vars.insert(name.to_string(), value);
+        }
+    }
+}
+
+fn parse_device_mk(
+    device_mk_path: &Path,
+    drivers: &mut HashMap<String, Vec<String>>,
+    treble: &mut TrebleInfo,
+    warnings: &mut Vec<Warning>,
+) {
+    let content = fsread::read_to_string(device_mk_path);
+    if let Err(e) = &content {
+        warnings.push(Warning::new(WarningKind::UnreadableFile, "device.mk", e.to_string()));
+    }
+    if let Ok(content) = content {
+        for (_, statement) in statements(&content) {
+            let Some(makefile::Assignment { name, value, .. }) = makefile::parse_assignment(&statement) else {
+                continue;
+            };
+
+            // PRODUCT_FULL_TREBLE_OVERRIDE, PRODUCT_USE_VNDK etc.
+            treble.parse_variable(&statement);
+
+            // Variables like AUDIO_FEATURE_ENABLED_* and TARGET_USES_CAMERA_*
+            // are kept whole; package lists are split into packages
+            if !name.contains("PRODUCT_PACKAGES") {
+                if name.contains("AUDIO_") && !value.is_empty() {
+                    drivers.entry(category::AUDIO.to_string()).or_default().push(value.to_string());
+                }
+                if name.contains("CAMERA_") && !value.is_empty() {
+                    drivers.entry(category::CAMERA.to_string()).or_default().push(value.to_string());
+                }
+                continue;
+            }
+
+            for package in value.split_whitespace() {
+                // Look for HAL packages (Hardware Abstraction Layer)
+                if package.starts_with("android.hardware.") {
+                    drivers.entry(category::HAL.to_string()).or_default().push(package.to_string());
+                }
+                // Look for audio and camera HALs
+                if package.contains("audio.") {
+                    drivers.entry(category::AUDIO.to_string()).or_default().push(package.to_string());
+                }
+                if package.contains("camera.") {
+                    drivers.entry(category::CAMERA.to_string()).or_default().push(package.to_string());
+                }
+            }
+        }
+    }
+}
+
+fn scan_prebuilt_modules(tree_path: &Path, drivers: &mut HashMap<String, Vec<String>>, walk_options: &WalkOptions) {
+    let prebuilt_paths = vec![
+        tree_path.join("prebuilt"),
+        tree_path.join("proprietary"),
+        tree_path.join("vendor"),
+    ];
+
+    for prebuilt_path in prebuilt_paths {
+        if walk_options.is_dir(&prebuilt_path) {
+            // These live one level below the tree root
+            scan_for_ko_files(&prebuilt_path, drivers, walk_options, 1);
+        }
+    }
+}
+
+fn scan_for_ko_files(
+    path: &Path,
+    drivers: &mut HashMap<String, Vec<String>>,
+    walk_options: &WalkOptions,
+    depth: usize,
+) {
+    if !walk_options.within_depth(depth) {
+        return;
+    }
+
+    for (entry_path, kind) in walk::read_dir(path, walk_options) {
+        match kind {
+            EntryKind::File => {
+                let file_name = entry_path.file_name().unwrap().to_string_lossy();
+                if file_name.ends_with(".ko") {
+                    // .ko files are compiled kernel modules
+                    drivers.entry(category::PREBUILT_MODULES.to_string()).or_default().push(file_name.to_string());
+                }
+            }
+            EntryKind::Dir => scan_for_ko_files(&entry_path, drivers, walk_options, depth + 1),
+            EntryKind::Skip => {}
+        }
+    }
+}
+
+fn display_drivers_by_category(report: &HardwareReport, top: usize) {
+    let (drivers, wifi, map) = (&report.drivers, &report.wifi, &report.category_map);
+    let mut categories: Vec<&str> = drivers.keys().map(|c| c.as_str()).collect();
+    report_json::sort_categories(&mut categories, report);
+
+    // The WiFi and bindings layouts only fit a category that wasn't merged
+    // with another by --category-map
+    let (wifi_name, bindings_name) = (map.unmerged(category::WIFI), map.unmerged(category::DEVICE_TREE_BINDINGS));
+    for category in categories {
+        if Some(category) == wifi_name && !wifi.is_empty() {
+            println!("\n{}:", category);
+            for (label, value) in wifi.fields() {
+                println!("  {}: {}", label, value);
+            }
+            if !wifi.configs.is_empty() {
+                display_wifi_configs(&wifi.configs);
+            }
+            continue;
+        }
+
+        if Some(category) == bindings_name {
+            if let Some(bindings) = drivers.get(category) {
+                display_bindings_by_vendor(bindings, top);
+            }
+            continue;
+        }
+
+        if let Some(driver_list) = drivers.get(category) {
+            println!("\n{}:", category);
+            let mut unique_drivers: Vec<_> = driver_list.iter().collect();
+            unique_drivers.sort();
+            unique_drivers.dedup();
+
+            display_entries(&unique_drivers, top, "  ");
+        }
+    }
+
+    println!("\nTotal driver categories: {}", drivers.len());
+}
+
+fn display_wifi_configs(configs: &WifiConfigs) {
+    let bands = configs.bands();
+    if !bands.is_empty() {
+        println!("  Bands: {}", bands.join(", "));
+    }
+    let country_codes = configs.country_codes();
+    if !country_codes.is_empty() {
+        println!("  Country Code: {}", country_codes.join(", "));
+    }
+    let ap_standards = configs.ap_standards();
+    if !ap_standards.is_empty() {
+        println!("  AP Standards: {}", ap_standards.join(", "));
+    }
+    for (label, value, location) in configs.key_settings() {
+        println!("  {}: {} ({})", label, value, location);
+    }
+    println!("  Config Files:");
+    for file in &configs.files {
+        let kind = file.kind.as_str();
+        println!("    {} {} ({}, {} settings)", Marker::Bullet, file.file, kind, file.settings.len());
+    }
+}
+
+// Categories that were looked for but came up empty, then the ones whose
+// source file wasn't there to look at, and the ones cut short by
+// --max-entries-per-category
+fn display_unfilled_categories(report: &HardwareReport) {
+    // An empty "WiFi Driver" list still has its supplicant/hostapd settings shown
+    for category in &report.attempted_categories {
+        if !report.drivers.contains_key(category) {
+            println!("\n{}: none found", category);
+        }
+    }
+
+    let not_attempted: Vec<&str> = report
+        .category_map
+        .known()
+        .into_iter()
+        .filter(|category| !report.attempted_categories.contains(*category))
+        .collect();
+    if !not_attempted.is_empty() {
+        println!("\nNot scanned (source file missing): {}", not_attempted.join(", "));
+    }
+
+    if let Some(limit) = report.max_entries
+        && !report.truncated_categories.is_empty()
+    {
+        let truncated: Vec<&str> = report.truncated_categories.iter().map(|c| c.as_str()).collect();
+        let truncated = truncated.join(", ");
+        println!("\n{} Truncated at {} entries (--max-entries-per-category): {}", Marker::Warn, limit, truncated);
+    }
+}
+
+fn display_bindings_by_vendor(bindings: &[String], top: usize) {
+    // Group "qcom,msm8996 (in foo.dts)" entries by the part before the comma
+    let mut by_vendor: BTreeMap<&str, Vec<&String>> = BTreeMap::new();
+    for binding in bindings {
+        by_vendor.entry(binding_vendor(binding)).or_default().push(binding);
+    }
+
+    println!("\nDevice Tree Bindings:");
+    for (vendor, entries) in by_vendor.iter_mut() {
+        entries.sort();
+        entries.dedup();
+        println!("  {} ({}):", vendor, entries.len());
+        display_entries(entries, top, "    ");
+    }
+}
+
+// Bulleted entries, cut off after `top` (0 = all) with a "... and M more".
+// --top only applies to the driver categories and DTS bindings printed
+// through here.
+fn display_entries<T: std::fmt::Display>(entries: &[T], top: usize, indent: &str) {
+    let shown = if top == 0 { entries.len() } else { top.min(entries.len()) };
+    for entry in &entries[..shown] {
+        println!("{}{} {}", indent, Marker::Bullet, entry);
+    }
+    if shown < entries.len() {
+        println!("{}... and {} more", indent, entries.len() - shown);
+    }
+}
+
+fn binding_vendor(binding: &str) -> &str {
+    // Only look at the compatible string, not the "(file)" suffix
+    let compatible = binding.split(" (").next().unwrap_or(binding);
+    match compatible.split_once(',') {
+        Some((vendor, _)) if !vendor.is_empty() => vendor,
+        _ => "generic",
+    }
+}
+
+fn display_treble(treble: &TrebleInfo) {
+    if treble.is_empty() {
+        println!("No VNDK version, Treble flags or vndk directories found.");
+        return;
+    }
+
+    println!("VNDK Version: {}", treble.vndk_version.as_deref().unwrap_or("not set"));
+    println!("Treble Compatible: {}", if treble.is_compatible() { "yes" } else { "no" });
+
+    if !treble.flags.is_empty() {
+        println!("\nFlags:");
+        for (name, value) in &treble.flags {
+            println!("  {} {} = {}", Marker::Bullet, name, value);
+        }
+    }
+
+    if !treble.vndk_dirs.is_empty() {
+        println!("\nVNDK Directories:");
+        for dir in &treble.vndk_dirs {
+            println!("  {} {}", Marker::Bullet, dir);
+        }
+    }
+}
+
+fn display_gsi(treble: &TrebleInfo, boot: &BootInfo, gsi: &GsiInfo) {
+    if treble.is_empty() && boot.is_empty() && gsi.is_empty() {
+        println!("No Treble, A/B, system-as-root or VINTF manifest settings found.");
+        return;
+    }
+
+    let checks = gsi::checklist(treble, boot, gsi);
+    for check in &checks {
+        let marker = match (check.passed, check.required) {
+            (true, _) => Marker::Ok,
+            (false, true) => Marker::Fail,
+            (false, false) => Marker::Warn,
+        };
+        println!("{} {}: {}", marker, check.name, check.evidence);
+    }
+
+    println!("\nTarget FCM Version: {}", gsi.target_fcm().map_or("unknown", |(level, _)| level));
+    if let Some(var) = gsi.vars.get("PRODUCT_SHIPPING_API_LEVEL") {
+        println!("Shipping API Level: {} ({})", var.value, var.location);
+    }
+
+    let failed: Vec<&str> = checks.iter().filter(|check| check.required && !check.passed).map(|c| c.name).collect();
+    if failed.is_empty() {
+        println!("\nVerdict: {} a GSI is likely to boot", Marker::Ok);
+    } else {
+        println!("\nVerdict: {} a GSI is unlikely to boot (missing: {})", Marker::Fail, failed.join(", "));
+    }
+}
+
+fn display_apex(apex: &ApexInfo) {
+    if apex.is_empty() {
+        println!("No .apex/.capex prebuilts, apex modules or APEX packages found.");
+        return;
+    }
+
+    println!("Modules ({}, {} prebuilt):", apex.modules.len(), apex.prebuilt_count());
+    for module in &apex.modules {
+        let mut details = vec![if module.prebuilt { "prebuilt" } else { "built from source" }];
+        if module.compressed {
+            details.push("compressed");
+        }
+        if module.packaged {
+            details.push("in PRODUCT_PACKAGES");
+        }
+        println!("  {} {} ({})", Marker::Bullet, module.name, details.join(", "));
+        if !module.files.is_empty() {
+            println!("      Files: {}", module.files.join(", "));
+        }
+    }
+
+    for module in apex.unpackaged() {
+        println!("  {} {} is in the tree but not in PRODUCT_PACKAGES", Marker::Warn, module.name);
+    }
+}
+
+fn display_linker_namespaces(linker: &LinkerNamespaces) {
+    if linker.is_empty() {
+        println!("No public.libraries*.txt or ld.config*.txt files found.");
+        return;
+    }
+
+    if !linker.public_libraries.is_empty() {
+        println!("Public Libraries:");
+        for list in &linker.public_libraries {
+            println!("  {} {} ({})", Marker::Bullet, list.file, list.libraries.len());
+            for library in &list.libraries {
+                println!("      {}", library);
+            }
+        }
+    }
+
+    for config in &linker.configs {
+        println!("\n{}:", config.file);
+        for section in &config.sections {
+            let name = if section.name.is_empty() { "(no section)" } else { section.name.as_str() };
+            if section.dirs.is_empty() {
+                println!("  [{}]", name);
+            } else {
+                println!("  [{}] for {}", name, section.dirs.join(", "));
+            }
+            for namespace in &section.namespaces {
+                let mut flags = Vec::new();
+                if namespace.isolated {
+                    flags.push("isolated");
+                }
+                if namespace.visible {
+                    flags.push("visible");
+                }
+                if flags.is_empty() {
+                    println!("    {} {}", Marker::Bullet, namespace.name);
+                } else {
+                    println!("    {} {} ({})", Marker::Bullet, namespace.name, flags.join(", "));
+                }
+                if !namespace.search_paths.is_empty() {
+                    println!("        Search: {}", namespace.search_paths.join(":"));
+                }
+                if !namespace.permitted_paths.is_empty() {
+                    println!("        Permitted: {}", namespace.permitted_paths.join(":"));
+                }
+                for link in &namespace.links {
+                    let libs = if link.allow_all {
+                        "all libraries".to_string()
+                    } else if link.shared_libs.is_empty() {
+                        "no libraries".to_string()
+                    } else {
+                        link.shared_libs.join(", ")
+                    };
+                    println!("        {} {}: {}", Marker::Arrow, link.target, libs);
+                }
+            }
+        }
+    }
+}
+
+fn display_dts_sources(sources: &DtsSources) {
+    if sources.files.is_empty() {
+        println!("No .dts/.dtsi files found in the tree.");
+        return;
+    }
+
+    println!("Sources Found ({}):", sources.files.len());
+    for file in &sources.files {
+        println!("  {} {}", Marker::Bullet, file);
+    }
+
+    let chains = sources.include_chains();
+    if !chains.is_empty() {
+        println!("\nInclude Graph:");
+        for chain in chains {
+            println!("  {}", chain.join(&format!(" {} ", Marker::Arrow)));
+        }
+    }
+
+    let unused = sources.unused_includes();
+    if !unused.is_empty() {
+        println!("\nUnused Includes ({}):", unused.len());
+        for file in unused {
+            println!("  {} {} (never included)", Marker::Warn, file);
+        }
+    }
+}
+
+fn display_aliases(sources: &DtsSources) {
+    let aliases = sources.aliases();
+    if aliases.is_empty() {
+        println!("No /aliases node found.");
+        return;
+    }
+
+    for alias in aliases {
+        match alias.target {
+            Some(target) => println!("  {} = {} {} {}", alias.name, alias.value, Marker::Arrow, target),
+            None => println!("  {} {} = {} (target not in the sources)", Marker::Warn, alias.name, alias.value),
+        }
+    }
+}
+
+fn display_chosen(sources: &DtsSources) {
+    let Some(chosen) = sources.chosen() else {
+        println!("No /chosen node found.");
+        return;
+    };
+
+    // bootargs and stdout-path first, verbatim; the rest in name order
+    for property in ["bootargs", "stdout-path"] {
+        if let Some(value) = chosen.display_value(property) {
+            println!("{}: {}", property, value);
+        }
+    }
+    if let Some((target, options)) = sources.stdout_path() {
+        match target {
+            Some(target) => println!("  {} console: {}", Marker::Arrow, target),
+            None => println!("  {} console node not in the sources", Marker::Warn),
+        }
+        if let Some(options) = options {
+            println!("  {} options: {}", Marker::Arrow, options);
+        }
+    }
+
+    let others: Vec<&String> =
+        chosen.properties.keys().filter(|name| !matches!(name.as_str(), "bootargs" | "stdout-path")).collect();
+    if !others.is_empty() {
+        println!("\nOther Properties:");
+        for name in others {
+            match chosen.display_value(name) {
+                Some(value) if !value.is_empty() => println!("  {} = {}", name, value),
+                _ => println!("  {}", name),
+            }
+        }
+    }
+}
+
+fn display_dtb_overlays(overlays: &DtbOverlays) {
+    if overlays.is_empty() {
+        println!("No prebuilt .dtb/.dtbo files or dtbo configuration found in the tree.");
+        return;
+    }
+
+    match overlays.build_mode {
+        Some(DtboBuildMode::Source) => println!("dtbo.img: built from kernel source"),
+        Some(DtboBuildMode::Prebuilt) => println!("dtbo.img: prebuilt"),
+        None => println!("dtbo.img: not configured"),
+    }
+
+    if !overlays.board_vars.is_empty() {
+        println!("\nBoard Variables:");
+        for (name, value) in &overlays.board_vars {
+            println!("  {}: {}", name, value);
+        }
+    }
+
+    if !overlays.prebuilts.is_empty() {
+        println!("\nPrebuilt Blobs ({}):", overlays.prebuilts.len());
+        for prebuilt in &overlays.prebuilts {
+            println!("  {} {}", Marker::Bullet, prebuilt);
+        }
+    }
+
+    if !overlays.overlays.is_empty() {
+        println!("\nOverlays ({}):", overlays.config_files.join(", "));
+        for overlay in &overlays.overlays {
+            let mut ids = Vec::new();
+            if let Some(id) = &overlay.id {
+                ids.push(format!("id={}", id));
+            }
+            if let Some(rev) = &overlay.rev {
+                ids.push(format!("rev={}", rev));
+            }
+            if ids.is_empty() {
+                println!("  [{}] {}", overlay.index, overlay.file);
+            } else {
+                println!("  [{}] {} ({})", overlay.index, overlay.file, ids.join(", "));
+            }
+        }
+    }
+}
+
+fn display_panels(panels: &[Panel]) {
+    if panels.is_empty() {
+        println!("No display panels found in the DTS sources.");
+        return;
+    }
+
+    println!("Panels Found ({}):", panels.len());
+    for panel in panels {
+        println!("  {} {}", Marker::Bullet, panel.display_name());
+        println!("      Node: {}", panel.node);
+        if !panel.compatible.is_empty() {
+            println!("      Compatible: {}", panel.compatible.join(", "));
+        }
+        println!("      Resolution: {}", panel.resolution().unwrap_or_else(|| "unknown".to_string()));
+        if !panel.refresh_rates.is_empty() {
+            let rates: Vec<String> = panel.refresh_rates.iter().map(|r| r.to_string()).collect();
+            println!("      Refresh Rate: {} Hz", rates.join(", "));
+        }
+        println!("      Defined in: {}", panel.locations.join(", "));
+    }
+}
+
+fn display_camera(camera: &CameraInfo) {
+    if camera.is_empty() {
+        println!("No camera sensors, configs or sensor modules found.");
+        return;
+    }
+
+    if !camera.sensors.is_empty() {
+        println!("Sensors ({}):", camera.sensors.len());
+        for sensor in &camera.sensors {
+            println!("  {} {}", Marker::Bullet, sensor.model);
+            if !sensor.nodes.is_empty() {
+                println!("      DTS: {}", sensor.nodes.join(", "));
+            }
+            if !sensor.files.is_empty() {
+                println!("      Files: {}", sensor.files.join(", "));
+            }
+        }
+    }
+    if !camera.unnamed_nodes.is_empty() {
+        println!("\nSensor Nodes Without a Model ({}):", camera.unnamed_nodes.len());
+        for node in &camera.unnamed_nodes {
+            println!("  {} {}", Marker::Bullet, node);
+        }
+    }
+
+    if !camera.config_files.is_empty() {
+        println!("\nConfig Files:");
+        for config in &camera.config_files {
+            let mut details = Vec::new();
+            if !config.sensors.is_empty() {
+                details.push(format!("sensors {}", config.sensors.join(", ")));
+            }
+            if !config.settings.is_empty() {
+                details.push(format!("{} settings", config.settings.len()));
+            }
+            if details.is_empty() {
+                println!("  {} {}", Marker::Bullet, config.file);
+            } else {
+                println!("  {} {} ({})", Marker::Bullet, config.file, details.join("; "));
+            }
+        }
+    }
+    if !camera.sensor_libs.is_empty() {
+        println!("\nSensor Modules ({}):", camera.sensor_libs.len());
+        for lib in &camera.sensor_libs {
+            println!("  {} {}", Marker::Bullet, lib);
+        }
+    }
+    if !camera.hal_packages.is_empty() {
+        println!("\nHAL Packages: {}", camera.hal_packages.join(", "));
+    }
+
+    for sensor in camera.unsupported_sensors() {
+        println!(
+            "  {} {} is in the DTS but no sensor module or config in the tree names it",
+            Marker::Warn,
+            sensor.model
+        );
+    }
+    if (!camera.sensors.is_empty() || !camera.unnamed_nodes.is_empty()) && camera.hal_packages.is_empty() {
+        println!("  {} Sensors are described but device.mk builds no camera packages", Marker::Warn);
+    }
+}
+
+fn display_touchscreens(touchscreens: &[Touchscreen]) {
+    if touchscreens.is_empty() {
+        println!("No touchscreen controllers found in the DTS sources.");
+        return;
+    }
+
+    println!("Touchscreen Controllers ({}):", touchscreens.len());
+    for touchscreen in touchscreens {
+        println!("  {} {} ({})", Marker::Bullet, touchscreen.vendor, touchscreen.compatible.join(", "));
+        println!("      Node: {}", touchscreen.node);
+        if let Some(name) = &touchscreen.firmware_name {
+            println!("      Firmware Name: {}", name);
+        }
+        if touchscreen.firmware_files.is_empty() {
+            println!("      Firmware: none found");
+        } else {
+            println!("      Firmware: {}", touchscreen.firmware_files.join(", "));
+        }
+        println!("      Defined in: {}", touchscreen.locations.join(", "));
+    }
+}
+
+fn display_haptics(haptics: &Haptics) {
+    if haptics.is_empty() {
+        println!("No vibrator controllers, HAL packages or haptics config found.");
+        return;
+    }
+
+    if !haptics.controllers.is_empty() {
+        println!("Vibrator Controllers ({}):", haptics.controllers.len());
+        for controller in &haptics.controllers {
+            println!("  {} {} ({})", Marker::Bullet, controller.vendor, controller.compatible.join(", "));
+            println!("      Node: {}", controller.node);
+            if !controller.hal_packages.is_empty() {
+                println!("      HAL: {}", controller.hal_packages.join(", "));
+            }
+            println!("      Defined in: {}", controller.locations.join(", "));
+        }
+    }
+
+    if !haptics.hal_packages.is_empty() {
+        println!("\nHAL Packages:");
+        for package in &haptics.hal_packages {
+            println!("  {} {}", Marker::Bullet, package);
+        }
+    }
+
+    if !haptics.config_files.is_empty() {
+        println!("\nConfig Files:");
+        for file in &haptics.config_files {
+            println!("  {} {}", Marker::Bullet, file);
+        }
+    }
+}
+
+fn display_biometrics(biometrics: &Biometrics) {
+    if biometrics.is_empty() {
+        println!("No fingerprint sensors, biometrics HAL packages or fingerprint config found.");
+        return;
+    }
+
+    if !biometrics.sensors.is_empty() {
+        println!("Fingerprint Sensors ({}):", biometrics.sensors.len());
+        for sensor in &biometrics.sensors {
+            println!(
+                "  {} {} ({}, {})",
+                Marker::Bullet,
+                sensor.vendor,
+                sensor.kind.as_str(),
+                sensor.compatible.join(", ")
+            );
+            println!("      Node: {}", sensor.node);
+            if sensor.hal_packages.is_empty() {
+                println!("      HAL: {} no fingerprint HAL in PRODUCT_PACKAGES", Marker::Warn);
+            } else {
+                println!("      HAL: {}", sensor.hal_packages.join(", "));
+            }
+            println!("      Defined in: {}", sensor.locations.join(", "));
+        }
+    } else if !biometrics.fingerprint_hals().is_empty() {
+        println!("{} Fingerprint HAL is packaged but no sensor node was found in the DTS", Marker::Warn);
+    }
+
+    if !biometrics.hal_packages.is_empty() {
+        println!("\nHAL Packages:");
+        for package in &biometrics.hal_packages {
+            println!("  {} {}", Marker::Bullet, package);
+        }
+    }
+
+    if !biometrics.under_display_hints.is_empty() {
+        println!("\nUnder-Display Hints:");
+        for hint in &biometrics.under_display_hints {
+            println!("  {} {} ({})", Marker::Bullet, hint.text, hint.location);
+        }
+    }
+
+    if !biometrics.config_files.is_empty() {
+        println!("\nConfig Files:");
+        for file in &biometrics.config_files {
+            println!("  {} {}", Marker::Bullet, file);
+        }
+    }
+}
+
+fn display_drm(drm: &DrmInfo) {
+    if drm.is_empty() {
+        println!("No DRM HAL packages, BOARD_WIDEVINE_* flags or Widevine blobs found.");
+        return;
+    }
+
+    let level = drm.level();
+    let marker = match level {
+        WidevineLevel::Configured(_) => Marker::Ok,
+        _ => Marker::Warn,
+    };
+    println!("Widevine Level: {} {} ({})", marker, level.describe(), drm.level_reason());
+    if drm.widevine_hals().is_empty() {
+        println!("  {} No Widevine DRM HAL in PRODUCT_PACKAGES", Marker::Warn);
+    }
+
+    if !drm.hal_packages.is_empty() {
+        println!("\nDRM HAL Packages:");
+        for package in &drm.hal_packages {
+            println!("  {} {}", Marker::Bullet, package);
+        }
+    }
+
+    if !drm.board_flags.is_empty() {
+        println!("\nBoard Flags:");
+        for flag in &drm.board_flags {
+            println!("  {} = {} ({})", flag.name, flag.value, flag.location);
+        }
+    }
+
+    let blob_lists = [("OEMCrypto/Widevine Libraries", &drm.libraries), ("Widevine Trusted Apps", &drm.trusted_apps)];
+    for (title, blobs) in blob_lists {
+        if blobs.is_empty() {
+            continue;
+        }
+        println!("\n{}:", title);
+        for blob in blobs {
+            if blob.location == blob.value {
+                println!("  {} {}", Marker::Bullet, blob.value);
+            } else {
+                println!("  {} {} (listed in {})", Marker::Bullet, blob.value, blob.location);
+            }
+        }
+    }
+}
+
+fn display_gpu(gpu: &GpuInfo) {
+    if gpu.is_empty() {
+        println!("No GPU DTS bindings, TARGET_BOARD_PLATFORM or graphics packages found.");
+        return;
+    }
+
+    match gpu.model() {
+        Some((model, source)) => println!("GPU: {} {} ({})", Marker::Ok, model, source),
+        None => println!("GPU: {} model not identified", Marker::Warn),
+    }
+    if let Some(platform) = &gpu.platform {
+        let expected = match gpu.expected_gpu() {
+            Some(expected) => format!("ships with {}", expected),
+            None => "not in the SoC table".to_string(),
+        };
+        println!("Platform: {} ({}), {}", platform.value, platform.location, expected);
+    }
+    if let Some((found, expected)) = gpu.platform_mismatch() {
+        println!("  {} The DTS describes a {} GPU, but the platform ships with {}", Marker::Warn, found, expected);
+    }
+    if let Some(var) = &gpu.platform_gpu {
+        println!("TARGET_BOARD_PLATFORM_GPU: {} ({})", var.value, var.location);
+    }
+
+    if !gpu.bindings.is_empty() {
+        println!("\nDTS Bindings:");
+        for binding in &gpu.bindings {
+            let model = binding.model.as_deref().map(|model| format!(" ({})", model)).unwrap_or_default();
+            println!("  {} {} [{}]{}", Marker::Bullet, binding.node, binding.compatible, model);
+        }
+    }
+
+    let package_lists = [
+        ("Gralloc Packages", &gpu.gralloc_packages),
+        ("Mapper Packages", &gpu.mapper_packages),
+        ("Composer Packages", &gpu.composer_packages),
+        ("EGL/Vulkan Driver Libraries", &gpu.driver_packages),
+    ];
+    for (title, packages) in package_lists {
+        if !packages.is_empty() {
+            println!("\n{}:", title);
+            for package in packages {
+                println!("  {} {}", Marker::Bullet, package);
+            }
+        }
+    }
+}
+
+fn display_tee(tee: &TeeInfo) {
+    if tee.is_empty() {
+        println!("No TEE flags, keymaster/gatekeeper packages or secure-world DTS bindings found.");
+        return;
+    }
+
+    match (tee.tee_type(), tee.tee_source()) {
+        (Some(kind), Some(source)) => println!("TEE: {} {} ({})", Marker::Ok, kind.as_str(), source),
+        _ => println!("TEE: {} none detected", Marker::Warn),
+    }
+    let types = tee.tee_types();
+    if types.len() > 1 {
+        let names: Vec<&str> = types.iter().map(|kind| kind.as_str()).collect();
+        println!("  {} The tree points to more than one TEE: {}", Marker::Warn, names.join(", "));
+    }
+    match tee.keymaster_version() {
+        Some(version) => println!("Keymaster HAL: {}", version),
+        None => println!("Keymaster HAL: {} none in PRODUCT_PACKAGES", Marker::Warn),
+    }
+
+    let package_lists = [
+        ("Keymaster Packages", &tee.keymaster_packages),
+        ("Gatekeeper Packages", &tee.gatekeeper_packages),
+        ("TEE Services", &tee.tee_packages),
+    ];
+    for (title, packages) in package_lists {
+        if !packages.is_empty() {
+            println!("\n{}:", title);
+            for package in packages {
+                println!("  {} {}", Marker::Bullet, package);
+            }
+        }
+    }
+
+    if !tee.board_flags.is_empty() {
+        println!("\nBoard Flags:");
+        for flag in &tee.board_flags {
+            let note = if flag.conditional { ", conditional" } else { "" };
+            println!("  {} = {} ({}{})", flag.name, flag.value, flag.location, note);
+        }
+    }
+
+    if !tee.bindings.is_empty() {
+        println!("\nDTS Bindings:");
+        for binding in &tee.bindings {
+            println!("  {} {} [{}] ({})", Marker::Bullet, binding.node, binding.compatible, binding.tee.as_str());
+            if binding.keymaster_packages.is_empty() {
+                println!("    {} No {} keymaster HAL in PRODUCT_PACKAGES", Marker::Warn, binding.tee.as_str());
+            }
+            for package in &binding.keymaster_packages {
+                println!("    {} keymaster: {}", Marker::Arrow, package);
+            }
+        }
+    }
+}
+
+fn display_leds(leds: &LedInfo) {
+    if leds.is_empty() {
+        println!("No LEDs, backlight controllers or lights HAL found.");
+        return;
+    }
+
+    if !leds.leds.is_empty() {
+        println!("LEDs ({}):", leds.leds.len());
+        for led in &leds.leds {
+            match &led.default_trigger {
+                Some(trigger) => println!("  {} {} (trigger: {})", Marker::Bullet, led.label, trigger),
+                None => println!("  {} {}", Marker::Bullet, led.label),
+            }
+            println!("      Node: {}", led.node);
+            if let Some(controller) = &led.controller {
+                println!("      Controller: {}", controller);
+            }
+            println!("      Defined in: {}", led.location);
+        }
+    }
+
+    if !leds.backlights.is_empty() {
+        println!("\nBacklight Controllers ({}):", leds.backlights.len());
+        for backlight in &leds.backlights {
+            println!("  {} {} ({})", Marker::Bullet, backlight.node, backlight.compatible.join(", "));
+            println!("      Defined in: {}", backlight.location);
+        }
+    }
+
+    println!("\nLights HAL:");
+    if leds.hal_packages.is_empty() {
+        // Without a lights HAL nothing drives the notification LED or
+        // the backlight from the framework
+        let marker = if leds.leds.is_empty() && leds.backlights.is_empty() { Marker::Fail } else { Marker::Warn };
+        println!("  {} No lights HAL package in PRODUCT_PACKAGES", marker);
+    }
+    for package in &leds.hal_packages {
+        println!("  {} {}", Marker::Ok, package);
+    }
+
+    if !leds.sysfs_commands.is_empty() {
+        println!("\nInit sysfs Setup:");
+        for command in &leds.sysfs_commands {
+            println!("  {} {} {} ({})", Marker::Bullet, command.command, command.path, command.location);
+        }
+        for name in leds.undefined_sysfs_leds() {
+            println!("  {} /sys/class/leds/{} has no matching LED in the DTS", Marker::Warn, name);
+        }
+    }
+}
+
+fn display_nfc(nfc: &NfcInfo) {
+    if nfc.is_empty() {
+        println!("No NFC controllers, secure elements, packages or libnfc config found.");
+        return;
+    }
+
+    if !nfc.devices.is_empty() {
+        println!("Devices ({}):", nfc.devices.len());
+        for device in &nfc.devices {
+            let kind = match device.kind {
+                nfc::NfcKind::Controller => "NFC",
+                nfc::NfcKind::SecureElement => "Secure element",
+            };
+            println!("  {} {}: {} ({})", Marker::Bullet, kind, device.vendor, device.compatible.join(", "));
+            println!("      Node: {}", device.node);
+            if let Some(config) = &device.vendor_config {
+                println!("      Vendor config: {}", config);
+            }
+            println!("      Defined in: {}", device.locations.join(", "));
+        }
+    }
+
+    if !nfc.hal_packages.is_empty() {
+        println!("\nPackages:");
+        for package in &nfc.hal_packages {
+            println!("  {} {}", Marker::Bullet, package);
+        }
+    }
+
+    if !nfc.config_files.is_empty() {
+        println!("\nConfig Files:");
+        for config in &nfc.config_files {
+            println!("  {} {}", Marker::Bullet, config.file);
+            for (key, value) in &config.settings {
+                println!("      {} = {}", key, value);
+            }
+        }
+    }
+
+    if nfc.devices.iter().any(|device| device.kind == nfc::NfcKind::Controller) && !nfc.has_nci_config() {
+        println!("\n  {} NFC controller found but no libnfc-nci.conf in the tree", Marker::Warn);
+    }
+}
+
+fn display_usb(usb: &UsbInfo) {
+    if usb.is_empty() {
+        println!("No sys.usb.config settings, USB compositions or BOARD_USB_* flags found.");
+        return;
+    }
+
+    match usb.default_setting() {
+        Some(setting) => println!("Default Config: {} ({}, {})", setting.value, setting.property, setting.location),
+        None => println!("Default Config: unknown (no persist.sys.usb.config or boot-time setprop)"),
+    }
+    match usb.adb_by_default() {
+        Some(true) => println!("ADB by Default: {} yes", Marker::Ok),
+        Some(false) => println!("ADB by Default: no"),
+        None => println!("ADB by Default: unknown"),
+    }
+    if let Some(secure) = &usb.adb_secure {
+        println!("ro.adb.secure: {}", secure);
+    }
+    let functions = usb.functions();
+    if !functions.is_empty() {
+        println!("Functions: {}", functions.join(", "));
+    }
+
+    if !usb.settings.is_empty() {
+        println!("\nConfig Settings ({}):", usb.settings.len());
+        for setting in &usb.settings {
+            let trigger = setting.trigger.as_deref().map(|t| format!(" on {}", t)).unwrap_or_default();
+            println!("  {} {}={}{} ({})", Marker::Bullet, setting.property, setting.value, trigger, setting.location);
+        }
+    }
+
+    if !usb.compositions.is_empty() {
+        println!("\nCompositions ({}):", usb.compositions.len());
+        for composition in &usb.compositions {
+            println!("  {} {}", Marker::Bullet, composition);
+        }
+    }
+
+    if !usb.board_flags.is_empty() {
+        println!("\nBoardConfig Flags:");
+        for flag in &usb.board_flags {
+            println!("  {} {} = {} ({})", Marker::Bullet, flag.name, flag.value, flag.location);
+        }
+    }
+}
+
+fn display_regulators(regulators: &Regulators) {
+    if regulators.is_empty() {
+        println!("No regulators or power domains found in the DTS sources.");
+        return;
+    }
+
+    if !regulators.regulators.is_empty() {
+        println!("Regulators ({}):", regulators.regulators.len());
+        for regulator in &regulators.regulators {
+            let always_on = if regulator.always_on { " (always-on)" } else { "" };
+            println!("  {} {}{}", Marker::Bullet, regulator.display_name(), always_on);
+            println!("      Node: {}", regulator.node);
+            if regulator.fixed {
+                println!("      Type: fixed");
+            } else {
+                println!("      Type: PMIC ({})", regulator.provider.as_deref().unwrap_or("unknown"));
+            }
+            if let Some(range) = regulator.voltage_range() {
+                println!("      Voltage: {}", range);
+            }
+            println!("      Defined in: {}", regulator.locations.join(", "));
+        }
+    }
+
+    if !regulators.power_domains.is_empty() {
+        println!("\nPower Domain Providers ({}):", regulators.power_domains.len());
+        for domain in &regulators.power_domains {
+            if domain.compatible.is_empty() {
+                println!("  {} {}", Marker::Bullet, domain.node);
+            } else {
+                println!("  {} {} ({})", Marker::Bullet, domain.node, domain.compatible.join(", "));
+            }
+        }
+    }
+
+    println!(
+        "\nTotal: {} regulators ({} fixed, {} PMIC, {} always-on), {} power domain providers",
+        regulators.regulators.len(),
+        regulators.fixed_count(),
+        regulators.regulators.len() - regulators.fixed_count(),
+        regulators.always_on_count(),
+        regulators.power_domains.len()
+    );
+}
+
+fn display_power_tree(consumers: &[PowerConsumer]) {
+    if consumers.is_empty() {
+        println!("No *-supply references found in the DTS sources.");
+        return;
+    }
+
+    for consumer in consumers {
+        println!("{}:", consumer.node);
+        display_supply_links(&consumer.supplies, 1);
+    }
+}
+
+fn display_supply_links(links: &[SupplyLink], depth: usize) {
+    for link in links {
+        let cycle = if link.cycle { " (cycle)" } else { "" };
+        let indent = "  ".repeat(depth);
+        println!("{}{} {} {} {}{}", indent, Marker::Bullet, link.property, Marker::Arrow, link.describe(), cycle);
+        display_supply_links(&link.parents, depth + 1);
+    }
+}
+
+fn display_battery(battery: &BatteryInfo) {
+    if battery.is_empty() {
+        println!("No charger, fuel gauge or battery profile found.");
+        return;
+    }
+
+    if !battery.controllers.is_empty() {
+        println!("Charger/Fuel Gauge Controllers ({}):", battery.controllers.len());
+        for controller in &battery.controllers {
+            let kind = match controller.kind {
+                battery::SupplyKind::Charger => "Charger",
+                battery::SupplyKind::FuelGauge => "Fuel gauge",
+            };
+            match controller.description {
+                Some(description) => {
+                    println!("  {} {}: {} - {}", Marker::Bullet, kind, controller.binding, description)
+                }
+                None => println!("  {} {}: {}", Marker::Bullet, kind, controller.binding),
+            }
+            println!("      Node: {}", controller.node);
+            println!("      Defined in: {}", controller.locations.join(", "));
+        }
+    }
+
+    if !battery.profiles.is_empty() {
+        println!("\nBattery Profiles ({}):", battery.profiles.len());
+        for profile in &battery.profiles {
+            let mut details = Vec::new();
+            if let Some(capacity) = profile.capacity_mah {
+                details.push(format!("{} mAh", capacity));
+            }
+            if let Some(chemistry) = &profile.chemistry {
+                details.push(chemistry.clone());
+            }
+            if let Some(max) = profile.max_microvolt {
+                details.push(format!("max {}V", max as f64 / 1_000_000.0));
+            }
+            let name = profile.battery_type.as_deref().unwrap_or(&profile.node);
+            if details.is_empty() {
+                println!("  {} {}", Marker::Bullet, name);
+            } else {
+                println!("  {} {} ({})", Marker::Bullet, name, details.join(", "));
+            }
+            println!("      Defined in: {}", profile.locations.join(", "));
+        }
+    }
+
+    if !battery.profile_files.is_empty() {
+        println!("\nBattery Data Files:");
+        for file in &battery.profile_files {
+            match file.capacity_mah {
+                Some(capacity) => println!("  {} {} ({} mAh)", Marker::Bullet, file.file, capacity),
+                None => println!("  {} {}", Marker::Bullet, file.file),
+            }
+        }
+    }
+}
+
+fn display_copied_files(copied: &CopiedFiles) {
+    if copied.is_empty() {
+        println!("No PRODUCT_COPY_FILES entries found in device.mk.");
+        return;
+    }
+
+    for (partition, entries) in copied.by_partition() {
+        println!("{} ({}):", partition, entries.len());
+        for entry in entries {
+            println!("  {} {} {} {}", Marker::Bullet, entry.source, Marker::Arrow, entry.destination);
+        }
+        println!();
+    }
+
+    let external = copied.entries.iter().filter(|entry| !entry.in_tree).count();
+    println!(
+        "Total: {} copied files ({} from the tree, {} from elsewhere in the source)",
+        copied.entries.len(),
+        copied.entries.len() - external,
+        external
+    );
+}
+
+fn display_declared_features(declared: &DeclaredFeatures, hals: &[Hal]) {
+    if declared.is_empty() {
+        println!("No <feature> declarations found in permission XML or PRODUCT_COPY_FILES.");
+        return;
+    }
+
+    let available = declared.available();
+    println!("Features ({}):", available.len());
+    for feature in &available {
+        let version = feature.version.as_ref().map_or(String::new(), |version| format!(" (version {})", version));
+        let source = if feature.from_file_name { ", copied from the platform" } else { "" };
+        println!("  {} {}{} [{}{}]", Marker::Bullet, feature.name, version, feature.location, source);
+    }
+
+    let unavailable: Vec<&str> = declared
+        .features
+        .iter()
+        .filter(|feature| feature.unavailable)
+        .map(|feature| feature.name.as_str())
+        .collect();
+    if !unavailable.is_empty() {
+        println!("\nUnavailable Features ({}):", unavailable.len());
+        for name in unavailable {
+            println!("  {} {}", Marker::Fail, name);
+        }
+    }
+
+    let missing = declared.missing_hals(hals);
+    if !missing.is_empty() {
+        println!("\nFeatures Without a HAL Package:");
+        for (feature, family) in missing {
+            let hal = format!("android.hardware.{}", family);
+            println!("  {} {} {} {} (may ship as a prebuilt)", Marker::Warn, feature, Marker::Arrow, hal);
+        }
+    }
+}
+
+fn display_media_profiles(media: &MediaProfiles) {
+    if media.is_empty() {
+        println!("No camcorder profiles found in media_profiles*.xml.");
+        return;
+    }
+
+    println!("Profile Files:");
+    for file in &media.files {
+        println!("  {} {}", Marker::Bullet, file);
+    }
+
+    // Grouped by camera, in file order within each camera
+    let mut by_camera: BTreeMap<&str, Vec<&media::CamcorderProfile>> = BTreeMap::new();
+    for profile in &media.profiles {
+        by_camera.entry(profile.camera_id.as_deref().unwrap_or("default")).or_default().push(profile);
+    }
+
+    for (camera, profiles) in by_camera {
+        println!("\nCamera {} ({} profiles):", camera, profiles.len());
+        for profile in profiles {
+            let mut parts = Vec::new();
+            if let Some(video) = &profile.video {
+                parts.push(video.summary());
+            }
+            if let Some(audio) = &profile.audio {
+                parts.push(audio.summary());
+            }
+            if let Some(format) = &profile.file_format {
+                parts.push(format.clone());
+            }
+            println!("  {} {}: {}", Marker::Bullet, profile.quality, parts.join(", "));
+        }
+    }
+}
+
+fn display_mixer_paths(mixer: &MixerPaths) {
+    if mixer.is_empty() {
+        println!("No mixer_paths*.xml found in the tree.");
+        return;
+    }
+
+    println!("Mixer Paths: {} in {} file(s)", mixer.path_count(), mixer.files.len());
+    for file in &mixer.files {
+        println!(
+            "\n{} ({}, {} paths, {} default controls):",
+            file.file,
+            file.variant_name(),
+            file.paths.len(),
+            file.defaults.len()
+        );
+        for path in file.key_paths() {
+            let mut details = vec![format!("{} controls", path.controls.len())];
+            if !path.includes.is_empty() {
+                details.push(format!("includes {}", path.includes.join(", ")));
+            }
+            println!("  {} {} ({})", Marker::Bullet, path.name, details.join("; "));
+            for control in &path.controls {
+                println!("      {} = {}", control.name, control.value);
+            }
+        }
+        for (path, include) in file.unresolved_includes() {
+            println!("  {} {} includes undefined path {}", Marker::Warn, path, include);
+        }
+    }
+}
+
+fn display_key_layouts(layouts: &KeyLayouts) {
+    if layouts.is_empty() {
+        println!("No .kl/.kcm key layout files found in the tree.");
+        return;
+    }
+
+    for layout in &layouts.files {
+        println!("{} ({} keys):", layout.file, layout.keys.len());
+        for key in &layout.keys {
+            println!("  {} {}", Marker::Bullet, key.summary());
+        }
+        println!();
+    }
+
+    println!("Total: {} keys in {} files", layouts.key_count(), layouts.files.len());
+}
+
+fn display_carrier_config(config: &CarrierConfig) {
+    if config.is_empty() {
+        println!("No carrier/APN config files found in the tree.");
+        return;
+    }
+
+    if !config.apn_files.is_empty() {
+        println!("APN Files:");
+        for file in &config.apn_files {
+            println!("  {} {}", Marker::Bullet, file);
+        }
+        println!("\nAPNs ({}):", config.apns.len() + config.apns_omitted);
+        for apn in &config.apns {
+            println!("  {} {}", Marker::Bullet, apn);
+        }
+        if config.apns_omitted > 0 {
+            println!("  +{} more", config.apns_omitted);
+        }
+    }
+
+    if !config.config_files.is_empty() {
+        println!("\nCarrier Config Files ({}):", config.config_files.len());
+        for file in &config.config_files {
+            println!("  {} {}", Marker::Bullet, file);
+        }
+        if !config.carrier_ids.is_empty() {
+            println!("\nCarrier IDs ({}):", config.carrier_ids.len() + config.carrier_ids_omitted);
+            for id in &config.carrier_ids {
+                println!("  {} {}", Marker::Bullet, id);
+            }
+            if config.carrier_ids_omitted > 0 {
+                println!("  +{} more", config.carrier_ids_omitted);
+            }
+        }
+    }
+
+    if !config.carrier_list_blobs.is_empty() {
+        println!("\nCarrier List Blobs:");
+        for blob in &config.carrier_list_blobs {
+            println!("  {} {}", Marker::Bullet, blob);
+        }
+    }
+}
+
+fn display_telephony(telephony: &TelephonyInfo) {
+    if telephony.is_empty() {
+        println!("No telephony overlay or radio properties found; framework defaults apply.");
+    }
+
+    match telephony.sim_count() {
+        Some((count, source)) => println!("  SIM slots: {} (from {})", count, source),
+        None => println!("  SIM slots: unknown (framework default: 1)"),
+    }
+    match telephony.ril_class() {
+        Some(class) => println!("  RIL class: {}", class),
+        None => println!("  RIL class: default (RIL)"),
+    }
+    let modes = telephony.default_network_modes();
+    if modes.is_empty() {
+        println!("  Default network mode: unknown (framework default)");
+    } else {
+        println!("  Default network mode: {}", modes.join(" | "));
+    }
+    let families = telephony.radio_families();
+    if !families.is_empty() {
+        println!("  Radio: {}", families.join("/"));
+    }
+    if telephony.is_empty() {
+        return;
+    }
+
+    if !telephony.properties.is_empty() {
+        println!("\nProperties:");
+        for (name, property) in &telephony.properties {
+            println!("  {} {} = {} ({})", Marker::Bullet, name, property.value, property.location);
+        }
+    }
+    if !telephony.resources.is_empty() {
+        println!("\nOverlay Resources ({}):", telephony.overlay_files.join(", "));
+        for (name, resource) in &telephony.resources {
+            println!("  {} {} = {} ({})", Marker::Bullet, name, resource.value, resource.location);
+        }
+    }
+}
+
+fn display_power_config(power: &PowerConfig) {
+    if power.is_empty() {
+        println!("No powerhint.json or power_profile.xml found in the tree.");
+        return;
+    }
+
+    if !power.hint_files.is_empty() {
+        println!("Power Hint Files:");
+        for file in &power.hint_files {
+            println!("  {} {}", Marker::Bullet, file);
+        }
+        println!("\nPower Hint Nodes ({}):", power.hint_nodes.len());
+        for node in &power.hint_nodes {
+            println!("  {} {}", Marker::Bullet, node);
+        }
+        println!("\nPower Hints ({}):", power.hints.len());
+        for hint in &power.hints {
+            println!("  {} {}", Marker::Bullet, hint);
+        }
+    }
+
+    if !power.profile_files.is_empty() {
+        println!("\nPower Profile ({}):", power.profile_files.join(", "));
+        for (component, value) in &power.profile {
+            println!("  {}: {}", component, value);
+        }
+    }
+
+    println!(
+        "\nTotal: {} hint nodes, {} power hints, {} power profile values",
+        power.hint_nodes.len(),
+        power.hints.len(),
+        power.profile.len()
+    );
+}
+
+fn display_perf_tuning(perf: &PerfTuning) {
+    if perf.is_empty() {
+        println!("No perfboostsconfig.xml, powerhint.xml or perfconfigstore.xml found in the tree.");
+        return;
+    }
+
+    println!("Config Files:");
+    for file in &perf.files {
+        println!("  {} {} ({} boosts, {} properties)", Marker::Bullet, file.file, file.boosts, file.properties);
+    }
+
+    let targets = perf.targets();
+    if !targets.is_empty() {
+        println!("\nTargets: {}", targets.join(", "));
+    }
+
+    let named = perf.named();
+    if !named.is_empty() {
+        println!("\nNamed Boosts ({}):", named.len());
+        for boost in named {
+            let mut details = vec![format!("{} perf locks", boost.resources.len())];
+            if let Some(timeout) = &boost.timeout {
+                details.push(format!("timeout {}", timeout));
+            }
+            if let Some(target) = &boost.target {
+                details.push(target.clone());
+            }
+            if let Some(resolution) = &boost.resolution {
+                details.push(resolution.clone());
+            }
+            let marker = if boost.enabled { Marker::Bullet } else { Marker::Warn };
+            let disabled = if boost.enabled { "" } else { ", disabled" };
+            println!("  {} {}: {}{}", marker, boost.label(), details.join(", "), disabled);
+        }
+    }
+
+    let opcodes = perf.opcodes();
+    if !opcodes.is_empty() {
+        println!("\nPerf Lock Opcodes ({}): {}", opcodes.len(), opcodes.join(", "));
+    }
+
+    if !perf.properties.is_empty() {
+        println!("\nPerf Config Properties ({}):", perf.properties.len());
+        for property in &perf.properties {
+            match &property.target {
+                Some(target) => println!("  {} = {} [{}]", property.name, property.value, target),
+                None => println!("  {} = {}", property.name, property.value),
+            }
+        }
+    }
+
+    println!(
+        "\nTotal: {} boosts ({} enabled, {} named), {} perf config properties",
+        perf.boosts.len(),
+        perf.enabled_count(),
+        perf.named().len(),
+        perf.properties.len()
+    );
+}
+
+fn display_merge(merge: &MergeInfo) {
+    println!("Base: {}", merge.base_path);
+    if merge.inherited_sections.is_empty() && merge.inherited_entries.is_empty() && merge.inherited_values.is_empty() {
+        println!("Nothing inherited; the device tree overrides or repeats everything in the base.");
+        return;
+    }
+
+    if !merge.inherited_sections.is_empty() {
+        println!("\nSections From the Base ({}):", merge.inherited_sections.len());
+        for section in &merge.inherited_sections {
+            println!("  {} {}", Marker::Bullet, section);
+        }
+    }
+    if !merge.inherited_entries.is_empty() {
+        println!("\nDriver Entries From the Base ({}):", merge.inherited_entry_count());
+        for (category, entries) in &merge.inherited_entries {
+            println!("  {}:", category);
+            for entry in entries {
+                println!("    {} {}", Marker::Bullet, entry);
+            }
+        }
+    }
+    if !merge.inherited_values.is_empty() {
+        println!("\nValues From the Base ({}):", merge.inherited_values.len());
+        for value in &merge.inherited_values {
+            println!("  {} {}", Marker::Bullet, value);
+        }
+    }
+}
+
+fn display_boot(boot: &BootInfo) {
+    if boot.is_empty() {
+        println!("No A/B or recovery settings found in BoardConfig.mk/device.mk (assuming A-only).");
+        return;
+    }
+
+    println!("Partition Scheme: {}", boot.scheme());
+    println!("A/B OTA Updater: {}", boot.ab_updater);
+    println!("Recovery: {}", boot.recovery.map_or("unknown", |r| r.name()));
+
+    if !boot.ab_partitions.is_empty() {
+        println!("\nSlotted Partitions ({}):", boot.ab_partitions.len());
+        for partition in &boot.ab_partitions {
+            println!("  {} {}_a / {}_b", Marker::Bullet, partition, partition);
+        }
+    }
+}
+
+fn display_partition_sizes(sizes: &PartitionSizes) {
+    if sizes.is_empty() {
+        println!("No BOARD_*_PARTITION_SIZE or BOARD_FLASH_BLOCK_SIZE in BoardConfig.mk.");
+        return;
+    }
+
+    if let Some(block_size) = &sizes.flash_block_size {
+        println!("Flash Block Size: {}", block_size);
+    }
+
+    if !sizes.partitions.is_empty() {
+        println!("\nPartitions ({}):", sizes.partitions.len());
+    }
+    for partition in &sizes.partitions {
+        let mut details = Vec::new();
+        match (&partition.size, partition.bytes) {
+            (Some(_), Some(bytes)) => details.push(format!("{} bytes ({})", bytes, partitions::human_size(bytes))),
+            (Some(size), None) => details.push(size.clone()),
+            (None, _) => details.push("no size".to_string()),
+        }
+        if let Some(fs_type) = &partition.fs_type {
+            details.push(fs_type.clone());
+        }
+        println!("  {} {}: {} ({})", Marker::Bullet, partition.name, details.join(", "), partition.location);
+        if let Some(reason) = partition.unresolved() {
+            println!("      {} size unresolved: {}", Marker::Warn, reason);
+        }
+    }
+}
+
+fn display_encryption(encryption: &EncryptionInfo) {
+    if encryption.is_empty() {
+        println!("No fstab /data or /metadata entries or encryption settings found.");
+        return;
+    }
+
+    match encryption.mode() {
+        Some(mode) => println!("Mode: {} ({})", mode.name(), mode.description()),
+        None => println!("Mode: unknown (no fstab mounts /data)"),
+    }
+    for data in &encryption.data {
+        println!("\n/data ({}, {}):", data.location, data.fs_type);
+        if let (Some(contents), Some(filenames)) = (&data.contents, &data.filenames) {
+            println!("  Contents: {}", contents);
+            println!("  Filenames: {}", filenames);
+            println!("  Policy: {}", data.policy.as_deref().unwrap_or("not set (v1 before Android 11)"));
+        }
+        if !data.options.is_empty() {
+            println!("  Options: {}", data.options.join(", "));
+        }
+        if let Some(cipher) = &data.metadata_cipher {
+            let cipher = if cipher.is_empty() { "default" } else { cipher.as_str() };
+            match &data.key_directory {
+                Some(dir) => println!("  Metadata Encryption: {} (keys in {})", cipher, dir),
+                None => println!("  Metadata Encryption: {}", cipher),
+            }
+        }
+        if !data.flags.is_empty() {
+            println!("  Flags: {}", data.flags.join(","));
+        }
+    }
+
+    if !encryption.metadata_mounts.is_empty() {
+        println!("\nMetadata Partition: {}", encryption.metadata_mounts.join(", "));
+    }
+    if !encryption.vars.is_empty() {
+        println!("\nBoardConfig.mk:");
+        for (name, value) in &encryption.vars {
+            println!("  {} {} = {}", Marker::Bullet, name, value);
+        }
+    }
+
+    if encryption.modes_differ() {
+        println!("  {} The fstabs disagree on how /data is encrypted", Marker::Warn);
+    }
+    let metadata_cipher = encryption.data.iter().any(|data| data.metadata_cipher.is_some());
+    if encryption.metadata_mounts.is_empty() && (metadata_cipher || encryption.uses_metadata_partition()) {
+        println!(
+            "  {} Metadata encryption or BOARD_USES_METADATA_PARTITION is set but no fstab mounts /metadata",
+            Marker::Warn
+        );
+    }
+}
+
+fn display_module_consistency(modules: &ModuleConsistency) {
+    if modules.is_empty() {
+        println!("No prebuilt kernel modules or BOARD_*KERNEL_MODULES found.");
+        return;
+    }
+    if modules.declared.is_empty() && modules.wildcard_dirs.is_empty() {
+        println!("{} prebuilt module(s); none packaged from the tree, nothing to check.", modules.prebuilt.len());
+        return;
+    }
+
+    println!("Prebuilt modules: {}", modules.prebuilt.len());
+    println!("Declared modules: {}", modules.declared.len());
+    for dir in &modules.wildcard_dirs {
+        println!("  Wildcard: {}/*.ko", dir);
+    }
+    if modules.is_consistent() {
+        println!("  {} Every prebuilt module is declared and every declared module exists", Marker::Ok);
+        return;
+    }
+
+    if !modules.unreferenced.is_empty() {
+        println!("\nUnreferenced Modules ({}):", modules.unreferenced.len());
+        for file in &modules.unreferenced {
+            println!("  {} {}", Marker::Warn, file);
+        }
+    }
+    if !modules.missing.is_empty() {
+        println!("\nMissing Modules ({}):", modules.missing.len());
+        for module in &modules.missing {
+            println!("  {} {} ({}, {})", Marker::Fail, module.name, module.variable, module.location);
+        }
+    }
+}
+
+fn display_kernel(kernel: &KernelInfo) {
+    let unknown = || "unknown".to_string();
+    let defconfig = if kernel.defconfigs.is_empty() { unknown() } else { kernel.defconfigs.join(", ") };
+
+    println!("  Source: {}", kernel.source.clone().unwrap_or_else(unknown));
+    println!("  Defconfig: {}", defconfig);
+    println!("  Prebuilt Image: {}", kernel.prebuilt_image.clone().unwrap_or_else(|| "none".to_string()));
+    println!("  Version: {}", kernel.version.clone().unwrap_or_else(unknown));
+}
+
+fn display_modem(modem: &ModemInfo) {
+    if modem.is_empty() {
+        println!("No modem firmware or radio configuration found.");
+        return;
+    }
+
+    if modem.no_radio_image() {
+        println!("  Radio image: not built (TARGET_NO_RADIOIMAGE)");
+    }
+    for (name, value) in &modem.vars {
+        println!("  {}: {}", name, value);
+    }
+    if !modem.required_baseband.is_empty() {
+        println!("  Required baseband: {}", modem.required_baseband.join(" | "));
+    }
+
+    if !modem.firmware.is_empty() {
+        println!("\nFirmware ({}):", modem.firmware.len());
+        for firmware in &modem.firmware {
+            match &firmware.version {
+                Some(version) => println!("  {} {}: {}", Marker::Bullet, firmware.file, version),
+                None => println!("  {} {}: present, version unknown", Marker::Bullet, firmware.file),
+            }
+        }
+    }
+}
+
+fn display_cpu_topology(topology: &CpuTopology) {
+    if topology.is_empty() {
+        println!("No cpuset writes or task profiles found.");
+        return;
+    }
+
+    if !topology.groups.is_empty() {
+        println!("Cpuset Groups ({}):", topology.groups.len());
+        for (name, group) in &topology.groups {
+            let name = if name.is_empty() { "(root)" } else { name.as_str() };
+            println!("  {} {}: cpus {}", Marker::Bullet, name, group.cpus.as_deref().unwrap_or("not set"));
+            if !group.profiles.is_empty() {
+                println!("      Profiles: {}", group.profiles.join(", "));
+            }
+            if !group.locations.is_empty() {
+                println!("      Written in: {}", group.locations.join(", "));
+            }
+        }
+    }
+
+    let clusters = topology.clusters();
+    if !clusters.is_empty() {
+        println!("\nCore Groups (cores sharing the same cpusets):");
+        for (cores, groups) in clusters {
+            let range = match (cores.first(), cores.last()) {
+                (Some(first), Some(last)) if first != last => format!("cpu{}-{}", first, last),
+                (Some(first), _) => format!("cpu{}", first),
+                _ => continue,
+            };
+            println!("  {} {}: {}", Marker::Bullet, range, groups.join(", "));
+        }
+    }
+
+    let unjoined = topology.profiles.iter().filter(|profile| profile.cpuset.is_none()).count();
+    if !topology.profiles.is_empty() {
+        println!(
+            "\nTask Profiles: {} ({} join a cpuset)",
+            topology.profiles.len(),
+            topology.profiles.len() - unjoined
+        );
+    }
+}
+
+fn display_vendor_flags(vendor_flags: &VendorFlags) {
+    if vendor_flags.is_empty() {
+        println!("No persist.vendor.* or ro.vendor.* properties found.");
+        return;
+    }
+
+    for (group, flags) in vendor_flags.groups() {
+        println!("{}.* ({}):", group, flags.len());
+        for (name, flag) in flags {
+            println!("  {} {} = {} ({}, {})", Marker::Bullet, name, flag.value, flag.location, flag.source.label());
+            if !flag.overridden.is_empty() {
+                println!("      Overrides: {}", flag.overridden.join(", "));
+            }
+        }
+    }
+    println!("\nSet in: {}", vendor_flags.files.join(", "));
+}
+
+fn display_filesystem_setup(setup: &FilesystemSetup) {
+    if setup.is_empty() {
+        println!("No mount/mkdir/symlink commands found in init scripts.");
+        return;
+    }
+
+    println!("Init Scripts ({}):", setup.rc_files.len());
+    for file in &setup.rc_files {
+        println!("  {} {}", Marker::Bullet, file);
+    }
+
+    if !setup.mounts.is_empty() {
+        let types: Vec<String> = setup
+            .mount_types()
+            .iter()
+            .map(|(fs_type, count)| format!("{} ({})", fs_type, count))
+            .collect();
+        println!("\nMounts ({}): {}", setup.mounts.len(), types.join(", "));
+        for mount in &setup.mounts {
+            let options = if mount.options.is_empty() { String::new() } else { format!(" [{}]", mount.options.join(" ")) };
+            println!(
+                "  {} {} {} {} {}{} (on {})",
+                Marker::Bullet,
+                mount.fs_type,
+                mount.device,
+                Marker::Arrow,
+                mount.target,
+                options,
+                mount.trigger
+            );
+        }
+    }
+
+    if !setup.directories.is_empty() {
+        println!("\nDirectories ({}):", setup.directories.len());
+        for dir in &setup.directories {
+            let owner: Vec<&str> = [&dir.mode, &dir.owner, &dir.group].iter().filter_map(|v| v.as_deref()).collect();
+            if owner.is_empty() {
+                println!("  {} {}", Marker::Bullet, dir.path);
+            } else {
+                println!("  {} {} ({})", Marker::Bullet, dir.path, owner.join(" "));
+            }
+        }
+    }
+
+    if !setup.symlinks.is_empty() {
+        println!("\nSymlinks ({}):", setup.symlinks.len());
+        for symlink in &setup.symlinks {
+            println!("  {} {} {} {}", Marker::Bullet, symlink.link, Marker::Arrow, symlink.target);
+        }
+    }
+
+    if !setup.properties.is_empty() {
+        println!("\nProperty Placeholders (not expanded):");
+        for property in &setup.properties {
+            println!("  {} ${{{}}}", Marker::Warn, property);
+        }
+    }
+}
+
+fn display_boot_sequence(boot: &BootSequence) {
+    if boot.is_empty() {
+        println!("No services or start/class_start commands found in init scripts.");
+        return;
+    }
+
+    println!("Init Scripts ({}):", boot.rc_files.len());
+    for file in &boot.rc_files {
+        println!("  {} {}", Marker::Bullet, file);
+    }
+
+    if !boot.services.is_empty() {
+        println!("\nServices ({}):", boot.services.len());
+        for service in &boot.services {
+            let mut flags = vec![format!("class {}", service.classes.join(" "))];
+            flags.extend(service.disabled.then(|| "disabled".to_string()));
+            flags.extend(service.oneshot.then(|| "oneshot".to_string()));
+            println!("  {} {} ({}) [{}]", Marker::Bullet, service.name, flags.join(", "), service.location);
+        }
+    }
+
+    if !boot.triggers.is_empty() {
+        println!("\nTriggers (in boot order):");
+        for trigger in &boot.triggers {
+            println!("  on {}", trigger.name);
+            for start in &trigger.starts {
+                let services = boot.started_by(start);
+                if start.command == "class_start" {
+                    let services =
+                        if services.is_empty() { "no enabled services".to_string() } else { services.join(", ") };
+                    println!("    {} class_start {}: {} ({})", Marker::Arrow, start.target, services, start.location);
+                } else {
+                    println!("    {} {} {} ({})", Marker::Arrow, start.command, start.target, start.location);
+                }
+            }
+        }
+    }
+
+    let unstarted = boot.unstarted();
+    if !unstarted.is_empty() {
+        println!("\nNot Started by Any Trigger ({}):", unstarted.len());
+        for service in unstarted {
+            let why = if service.disabled {
+                "disabled, so only ctl.start can run it".to_string()
+            } else {
+                format!("nothing starts class {}", service.classes.join("/"))
+            };
+            println!("  {} {} ({})", Marker::Warn, service.name, why);
+        }
+    }
+
+    let undefined = boot.undefined();
+    if !undefined.is_empty() {
+        println!("\nStarted but Not Defined in the Tree (platform services?):");
+        for start in undefined {
+            println!("  {} {} {} ({})", Marker::Bullet, start.command, start.target, start.location);
+        }
+    }
+
+    if !boot.imports.is_empty() {
+        println!("\nImports ({}):", boot.imports.len());
+        for import in &boot.imports {
+            match &import.resolved {
+                Some(file) => println!("  {} {} {} {}", Marker::Bullet, import.path, Marker::Arrow, file),
+                None => println!("  {} {} (not in the tree)", Marker::Warn, import.path),
+            }
+        }
+    }
+}
+
+fn export_to_plist(report: &HardwareReport, plist_path: &str, encoding: OutputEncoding) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    write_plist(report, &mut buffer, encoding)?;
+    encoding::write(Path::new(plist_path), &String::from_utf8_lossy(&buffer), encoding)
+}
+
+fn export_to_toml(report: &HardwareReport, toml_path: &str, encoding: OutputEncoding) -> std::io::Result<()> {
+    let mut buffer = Vec::new();
+    report_toml::write_toml(report, &mut buffer)?;
+    encoding::write(Path::new(toml_path), &String::from_utf8_lossy(&buffer), encoding)
+}
+
+// --dry-run: every requested export rendered in memory, as one line per
+// file it would write
+fn export_previews(args: &Args, report: &HardwareReport) -> Vec<String> {
+    let mut previews = Vec::new();
+    let size = |text: &str| encoding::encode(text, args.output_encoding).len();
+
+    if let Some(plist_path) = &args.export_plist {
+        let mut buffer = Vec::new();
+        match write_plist(report, &mut buffer, args.output_encoding) {
+            Ok(()) => {
+                let plist = String::from_utf8_lossy(&buffer);
+                let sections = plist.lines().filter(|line| line.starts_with("\t<key>")).count();
+                previews.push(export_preview(Path::new(plist_path), size(&plist), &format!("{} sections", sections)));
+            }
+            Err(e) => previews.push(format!("  {} {}: {}", Marker::Fail.symbol(), plist_path, e)),
+        }
+    }
+
+    if let Some(toml_path) = &args.export_toml {
+        let mut buffer = Vec::new();
+        match report_toml::write_toml(report, &mut buffer) {
+            Ok(()) => {
+                let toml = String::from_utf8_lossy(&buffer);
+                let tables = toml.lines().filter(|line| line.starts_with('[')).count();
+                previews.push(export_preview(Path::new(toml_path), size(&toml), &format!("{} tables", tables)));
+            }
+            Err(e) => previews.push(format!("  {} {}: {}", Marker::Fail.symbol(), toml_path, e)),
+        }
+    }
+
+    if let Some(json_path) = &args.export_json_combined {
+        let combined = report_json::combined_to_json(&[report]).pretty(2);
+        previews.push(export_preview(Path::new(json_path), size(&combined), "1 tree"));
+    }
+
+    if let Some(split_dir) = &args.export_split {
+        for file in report_json::split_files(report) {
+            let path = Path::new(split_dir).join(&file.name);
+            let unit = if file.name == "summary.json" { "categories" } else { "entries" };
+            let entries = format!("{} {}", file.entries, unit);
+            previews.push(export_preview(&path, size(&file.contents), &entries));
+        }
+    }
+
+    if previews.is_empty() {
+        let exports = "--export-plist, --export-toml, --export-json-combined, --export-split";
+        previews.push(format!("No exports requested ({}).", exports));
+    } else {
+        previews.insert(0, "Nothing written; the exports would create:".to_string());
+    }
+    previews
+}
+
+fn export_preview(path: &Path, size: usize, contents: &str) -> String {
+    let overwrite = if path.exists() { ", overwrites an existing file" } else { "" };
+    format!("  {} {} ({} bytes, {}{})", Marker::Arrow.symbol(), path.display(), size, contents, overwrite)
+}
+
+// The document is written as UTF-8 either way; `encoding` is what it will be
+// converted to, for the XML declaration
+fn write_plist(report: &HardwareReport, file: &mut impl Write, encoding: OutputEncoding) -> std::io::Result<()> {
+    // Write plist header
+    writeln!(file, "<?xml version=\"1.0\" encoding=\"{}\"?>", encoding::xml_name(encoding))?;
+    writeln!(file, "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">")?;
+    writeln!(file, "<plist version=\"1.0\">")?;
+    writeln!(file, "<dict>")?;
+
+    // Device Information
+    writeln!(file, "\t<key>DeviceInformation</key>")?;
+    writeln!(file, "\t<dict>")?;
+    let mut info: Vec<_> = report.device_info.iter().collect();
+    info.sort_by_key(|(k, _)| *k);
+    for (key, value) in info {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(key))?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(value))?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Structure Validity
+    writeln!(file, "\t<key>StructureValid</key>")?;
+    writeln!(file, "\t<{} />", if report.structure_valid { "true" } else { "false" })?;
+
+    // Recursion (false with --no-recursion)
+    writeln!(file, "\t<key>Recursive</key>")?;
+    writeln!(file, "\t<{} />", if report.max_depth != Some(0) { "true" } else { "false" })?;
+
+    // Key Files
+    writeln!(file, "\t<key>KeyFiles</key>")?;
+    writeln!(file, "\t<dict>")?;
+    let mut files: Vec<_> = report.key_files.iter().collect();
+    files.sort_by_key(|(k, _)| *k);
+    for (file_name, found) in files {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(file_name))?;
+        writeln!(file, "\t\t<{} />", if *found { "true" } else { "false" })?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Key Directories
+    writeln!(file, "\t<key>KeyDirectories</key>")?;
+    writeln!(file, "\t<dict>")?;
+    let mut dirs: Vec<_> = report.key_dirs.iter().collect();
+    dirs.sort_by_key(|(k, _)| *k);
+    for (dir_name, found) in dirs {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(dir_name))?;
+        writeln!(file, "\t\t<{} />", if *found { "true" } else { "false" })?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    if !report.key_names_on_disk.is_empty() {
+        writeln!(file, "\t<key>KeyNamesOnDisk</key>")?;
+        writeln!(file, "\t<dict>")?;
+        for (name, on_disk) in &report.key_names_on_disk {
+            writeln!(file, "\t\t<key>{}</key>", escape_xml(name))?;
+            writeln!(file, "\t\t<string>{}</string>", escape_xml(on_disk))?;
+        }
+        writeln!(file, "\t</dict>")?;
+    }
+
+    // Device Drivers
+    writeln!(file, "\t<key>DeviceDrivers</key>")?;
+    writeln!(file, "\t<dict>")?;
+    // Attempted categories that came up empty get an empty array
+    let mut categories: Vec<&str> =
+        report.drivers.keys().chain(&report.attempted_categories).map(|c| c.as_str()).collect();
+    categories.sort();
+    categories.dedup();
+    report_json::sort_categories(&mut categories, report);
+    for category in categories {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(category))?;
+        writeln!(file, "\t\t<array>")?;
+        let mut unique_drivers: Vec<_> = report.drivers.get(category).into_iter().flatten().collect();
+        unique_drivers.sort();
+        unique_drivers.dedup();
+        for driver in unique_drivers {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(driver))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Which driver categories the scan looked for at all
+    writeln!(file, "\t<key>AttemptedDriverCategories</key>")?;
+    writeln!(file, "\t<array>")?;
+    for category in &report.attempted_categories {
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(category))?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // HALs grouped by family
+    writeln!(file, "\t<key>HALs</key>")?;
+    writeln!(file, "\t<array>")?;
+    for hal in &report.hals {
+        writeln!(file, "\t\t<dict>")?;
+        writeln!(file, "\t\t\t<key>Family</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&hal.family))?;
+        writeln!(file, "\t\t\t<key>Transports</key>")?;
+        writeln!(file, "\t\t\t<array>")?;
+        for transport in hal.transports() {
+            writeln!(file, "\t\t\t\t<string>{}</string>", transport.as_str())?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+        writeln!(file, "\t\t\t<key>Packages</key>")?;
+        writeln!(file, "\t\t\t<array>")?;
+        for (_, package) in &hal.packages {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(package))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // Device Tree Sources
+    writeln!(file, "\t<key>DeviceTreeSources</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>Files</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for source in &report.dts_sources.files {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(source))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>IncludeGraph</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for (source, includes) in &report.dts_sources.includes {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(source))?;
+        writeln!(file, "\t\t\t<array>")?;
+        for include in includes {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(include))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t\t<key>UnusedIncludes</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for source in report.dts_sources.unused_includes() {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(source))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Aliases: name -> resolved node path, else the value as written
+    writeln!(file, "\t<key>Aliases</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for alias in report.dts_sources.aliases() {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(alias.name))?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(alias.target.unwrap_or(alias.value)))?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Boot (chosen)
+    writeln!(file, "\t<key>Chosen</key>")?;
+    writeln!(file, "\t<dict>")?;
+    if let Some(chosen) = report.dts_sources.chosen() {
+        for name in chosen.properties.keys() {
+            writeln!(file, "\t\t<key>{}</key>", escape_xml(name))?;
+            writeln!(file, "\t\t<string>{}</string>", escape_xml(chosen.display_value(name).unwrap_or_default()))?;
+        }
+    }
+    if let Some((Some(target), _)) = report.dts_sources.stdout_path() {
+        writeln!(file, "\t\t<key>ConsoleNode</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(target))?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Boot
+    let boot = &report.boot;
+    writeln!(file, "\t<key>Boot</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>PartitionScheme</key>")?;
+    writeln!(file, "\t\t<string>{}</string>", boot.scheme())?;
+    writeln!(file, "\t\t<key>ABOTAUpdater</key>")?;
+    writeln!(file, "\t\t<{} />", boot.ab_updater)?;
+    if let Some(recovery) = boot.recovery {
+        writeln!(file, "\t\t<key>Recovery</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", recovery.name())?;
+    }
+    writeln!(file, "\t\t<key>SlottedPartitions</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for partition in &boot.ab_partitions {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(partition))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Partition Sizes
+    writeln!(file, "\t<key>PartitionSizes</key>")?;
+    writeln!(file, "\t<dict>")?;
+    if let Some(block_size) = &report.partition_sizes.flash_block_size {
+        writeln!(file, "\t\t<key>FlashBlockSize</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(block_size))?;
+    }
+    writeln!(file, "\t\t<key>Partitions</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for partition in &report.partition_sizes.partitions {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&partition.name))?;
+        writeln!(file, "\t\t\t<dict>")?;
+        if let Some(size) = &partition.size {
+            writeln!(file, "\t\t\t\t<key>Size</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(size))?;
+        }
+        if let Some(bytes) = partition.bytes {
+            writeln!(file, "\t\t\t\t<key>Bytes</key>")?;
+            writeln!(file, "\t\t\t\t<integer>{}</integer>", bytes)?;
+        }
+        if let Some(fs_type) = &partition.fs_type {
+            writeln!(file, "\t\t\t\t<key>FileSystemType</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(fs_type))?;
+        }
+        writeln!(file, "\t\t\t\t<key>Location</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&partition.location))?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Encryption
+    let encryption = &report.encryption;
+    writeln!(file, "\t<key>Encryption</key>")?;
+    writeln!(file, "\t<dict>")?;
+    if let Some(mode) = encryption.mode() {
+        writeln!(file, "\t\t<key>Mode</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", mode.name())?;
+    }
+    writeln!(file, "\t\t<key>Data</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for data in &encryption.data {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Location</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&data.location))?;
+        writeln!(file, "\t\t\t\t<key>Mode</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", data.mode.name())?;
+        for (key, value) in [
+            ("Contents", &data.contents),
+            ("Filenames", &data.filenames),
+            ("Policy", &data.policy),
+            ("MetadataEncryption", &data.metadata_cipher),
+            ("KeyDirectory", &data.key_directory),
+        ] {
+            if let Some(value) = value {
+                writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+                writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+            }
+        }
+        writeln!(file, "\t\t\t\t<key>Options</key>")?;
+        writeln!(file, "\t\t\t\t<array>")?;
+        for option in &data.options {
+            writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(option))?;
+        }
+        writeln!(file, "\t\t\t\t</array>")?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>MetadataMounts</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for location in &encryption.metadata_mounts {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(location))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Variables</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for (name, value) in &encryption.vars {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(name))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(value))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Kernel
+    let kernel = &report.kernel;
+    writeln!(file, "\t<key>Kernel</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for (key, value) in [
+        ("Source", &kernel.source),
+        ("PrebuiltImage", &kernel.prebuilt_image),
+        ("Version", &kernel.version),
+    ] {
+        if let Some(value) = value {
+            writeln!(file, "\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t<string>{}</string>", escape_xml(value))?;
+        }
+    }
+    writeln!(file, "\t\t<key>Defconfigs</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for defconfig in &kernel.defconfigs {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(defconfig))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Module Consistency
+    let modules = &report.module_consistency;
+    let declared: Vec<String> = modules.declared.iter().map(|module| module.name.clone()).collect();
+    let missing: Vec<String> = modules.missing.iter().map(|module| module.name.clone()).collect();
+    writeln!(file, "\t<key>ModuleConsistency</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for (key, list) in [
+        ("Declared", &declared),
+        ("WildcardDirs", &modules.wildcard_dirs),
+        ("Prebuilt", &modules.prebuilt),
+        ("Unreferenced", &modules.unreferenced),
+        ("Missing", &missing),
+    ] {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for entry in list {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(entry))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Modem/Baseband
+    writeln!(file, "\t<key>Modem</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>Firmware</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for firmware in &report.modem.firmware {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>File</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&firmware.file))?;
+        if let Some(version) = &firmware.version {
+            writeln!(file, "\t\t\t\t<key>Version</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(version))?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>RequiredBaseband</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for version in &report.modem.required_baseband {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(version))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Variables</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for (name, value) in &report.modem.vars {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(name))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(value))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // DTB Overlays
+    let overlays = &report.dtb_overlays;
+    writeln!(file, "\t<key>DtbOverlays</key>")?;
+    writeln!(file, "\t<dict>")?;
+    if let Some(mode) = overlays.build_mode {
+        writeln!(file, "\t\t<key>BuildMode</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", mode.name())?;
+    }
+    writeln!(file, "\t\t<key>BoardVariables</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for (name, value) in &overlays.board_vars {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(name))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(value))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t\t<key>Prebuilts</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for prebuilt in &overlays.prebuilts {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(prebuilt))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Overlays</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for overlay in &overlays.overlays {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>File</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&overlay.file))?;
+        writeln!(file, "\t\t\t\t<key>Index</key>")?;
+        writeln!(file, "\t\t\t\t<integer>{}</integer>", overlay.index)?;
+        for (key, value) in [("Id", &overlay.id), ("Rev", &overlay.rev)] {
+            if let Some(value) = value {
+                writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+                writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+            }
+        }
+        writeln!(file, "\t\t\t\t<key>DefinedIn</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&overlay.location))?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Display Panels
+    writeln!(file, "\t<key>DisplayPanels</key>")?;
+    writeln!(file, "\t<array>")?;
+    for panel in &report.panels {
+        writeln!(file, "\t\t<dict>")?;
+        writeln!(file, "\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&panel.node))?;
+        if let Some(name) = &panel.name {
+            writeln!(file, "\t\t\t<key>Name</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(name))?;
+        }
+        writeln!(file, "\t\t\t<key>Compatible</key>")?;
+        writeln!(file, "\t\t\t<array>")?;
+        for compatible in &panel.compatible {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(compatible))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+        if let Some(width) = panel.width {
+            writeln!(file, "\t\t\t<key>Width</key>")?;
+            writeln!(file, "\t\t\t<integer>{}</integer>", width)?;
+        }
+        if let Some(height) = panel.height {
+            writeln!(file, "\t\t\t<key>Height</key>")?;
+            writeln!(file, "\t\t\t<integer>{}</integer>", height)?;
+        }
+        writeln!(file, "\t\t\t<key>RefreshRates</key>")?;
+        writeln!(file, "\t\t\t<array>")?;
+        for rate in &panel.refresh_rates {
+            writeln!(file, "\t\t\t\t<integer>{}</integer>", rate)?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+        writeln!(file, "\t\t\t<key>DefinedIn</key>")?;
+        writeln!(file, "\t\t\t<array>")?;
+        for location in &panel.locations {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(location))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // Touchscreen
+    writeln!(file, "\t<key>Touchscreens</key>")?;
+    writeln!(file, "\t<array>")?;
+    for touchscreen in &report.touchscreens {
+        writeln!(file, "\t\t<dict>")?;
+        writeln!(file, "\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&touchscreen.node))?;
+        writeln!(file, "\t\t\t<key>Vendor</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&touchscreen.vendor))?;
+        writeln!(file, "\t\t\t<key>Compatible</key>")?;
+        writeln!(file, "\t\t\t<array>")?;
+        for compatible in &touchscreen.compatible {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(compatible))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+        if let Some(name) = &touchscreen.firmware_name {
+            writeln!(file, "\t\t\t<key>FirmwareName</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(name))?;
+        }
+        writeln!(file, "\t\t\t<key>FirmwareFiles</key>")?;
+        writeln!(file, "\t\t\t<array>")?;
+        for firmware in &touchscreen.firmware_files {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(firmware))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // Camera
+    writeln!(file, "\t<key>Camera</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>Sensors</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for sensor in &report.camera.sensors {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Model</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&sensor.model))?;
+        for (key, values) in [("Nodes", &sensor.nodes), ("Files", &sensor.files)] {
+            writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t\t\t<array>")?;
+            for value in values {
+                writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(value))?;
+            }
+            writeln!(file, "\t\t\t\t</array>")?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    let config_files: Vec<String> = report.camera.config_files.iter().map(|config| config.file.clone()).collect();
+    for (key, values) in [
+        ("UnnamedNodes", &report.camera.unnamed_nodes),
+        ("ConfigFiles", &config_files),
+        ("SensorModules", &report.camera.sensor_libs),
+        ("HALPackages", &report.camera.hal_packages),
+    ] {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for value in values {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(value))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Haptics/Vibrator
+    writeln!(file, "\t<key>Haptics</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>Controllers</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for controller in &report.haptics.controllers {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&controller.node))?;
+        writeln!(file, "\t\t\t\t<key>Vendor</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&controller.vendor))?;
+        for (key, list) in [("Compatible", &controller.compatible), ("HALPackages", &controller.hal_packages)] {
+            writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t\t\t<array>")?;
+            for value in list {
+                writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(value))?;
+            }
+            writeln!(file, "\t\t\t\t</array>")?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    for (key, list) in [("HALPackages", &report.haptics.hal_packages), ("ConfigFiles", &report.haptics.config_files)] {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for value in list {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(value))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Biometrics
+    writeln!(file, "\t<key>Biometrics</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>Sensors</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for sensor in &report.biometrics.sensors {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&sensor.node))?;
+        writeln!(file, "\t\t\t\t<key>Vendor</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&sensor.vendor))?;
+        writeln!(file, "\t\t\t\t<key>Kind</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", sensor.kind.as_str())?;
+        for (key, list) in [("Compatible", &sensor.compatible), ("HALPackages", &sensor.hal_packages)] {
+            writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t\t\t<array>")?;
+            for value in list {
+                writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(value))?;
+            }
+            writeln!(file, "\t\t\t\t</array>")?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    for (key, list) in
+        [("HALPackages", &report.biometrics.hal_packages), ("ConfigFiles", &report.biometrics.config_files)]
+    {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for value in list {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(value))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // DRM
+    let drm = &report.drm;
+    writeln!(file, "\t<key>DRM</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>WidevineLevel</key>")?;
+    writeln!(file, "\t\t<string>{}</string>", escape_xml(&drm.level().describe()))?;
+    writeln!(file, "\t\t<key>HALPackages</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for package in &drm.hal_packages {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(package))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>BoardFlags</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for flag in &drm.board_flags {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&flag.name))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&flag.value))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    for (key, blobs) in [("Libraries", &drm.libraries), ("TrustedApps", &drm.trusted_apps)] {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for blob in blobs {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&blob.value))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // TEE/Keymaster
+    let tee = &report.tee;
+    writeln!(file, "\t<key>TEE</key>")?;
+    writeln!(file, "\t<dict>")?;
+    if let Some(kind) = tee.tee_type() {
+        writeln!(file, "\t\t<key>Type</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", kind.as_str())?;
+    }
+    if let Some(version) = tee.keymaster_version() {
+        writeln!(file, "\t\t<key>KeymasterVersion</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(&version))?;
+    }
+    let package_lists = [
+        ("KeymasterPackages", &tee.keymaster_packages),
+        ("GatekeeperPackages", &tee.gatekeeper_packages),
+        ("TEEServices", &tee.tee_packages),
+    ];
+    for (key, packages) in package_lists {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for package in packages {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(package))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t\t<key>BoardFlags</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for flag in &tee.board_flags {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&flag.name))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&flag.value))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t\t<key>Bindings</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for binding in &tee.bindings {
+        writeln!(file, "\t\t\t<dict>")?;
+        for (key, value) in [("Node", &binding.node), ("Compatible", &binding.compatible)] {
+            writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+        }
+        writeln!(file, "\t\t\t\t<key>TEE</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", binding.tee.as_str())?;
+        writeln!(file, "\t\t\t\t<key>KeymasterPackages</key>")?;
+        writeln!(file, "\t\t\t\t<array>")?;
+        for package in &binding.keymaster_packages {
+            writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(package))?;
+        }
+        writeln!(file, "\t\t\t\t</array>")?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Graphics/GPU
+    let gpu = &report.gpu;
+    writeln!(file, "\t<key>GPU</key>")?;
+    writeln!(file, "\t<dict>")?;
+    if let Some((model, _)) = gpu.model() {
+        writeln!(file, "\t\t<key>Model</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(&model))?;
+    }
+    if let Some(platform) = &gpu.platform {
+        writeln!(file, "\t\t<key>Platform</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(&platform.value))?;
+    }
+    if let Some(expected) = gpu.expected_gpu() {
+        writeln!(file, "\t\t<key>ExpectedGPU</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", expected)?;
+    }
+    let package_lists = [
+        ("GrallocPackages", &gpu.gralloc_packages),
+        ("MapperPackages", &gpu.mapper_packages),
+        ("ComposerPackages", &gpu.composer_packages),
+        ("DriverLibraries", &gpu.driver_packages),
+    ];
+    for (key, packages) in package_lists {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for package in packages {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(package))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t\t<key>Bindings</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for binding in &gpu.bindings {
+        writeln!(file, "\t\t\t<dict>")?;
+        for (key, value) in [("Node", &binding.node), ("Compatible", &binding.compatible)] {
+            writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+        }
+        if let Some(model) = &binding.model {
+            writeln!(file, "\t\t\t\t<key>Model</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(model))?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // LEDs/Backlight
+    writeln!(file, "\t<key>LEDs</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>LEDs</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for led in &report.leds.leds {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&led.node))?;
+        writeln!(file, "\t\t\t\t<key>Label</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&led.label))?;
+        if let Some(trigger) = &led.default_trigger {
+            writeln!(file, "\t\t\t\t<key>DefaultTrigger</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(trigger))?;
+        }
+        if let Some(controller) = &led.controller {
+            writeln!(file, "\t\t\t\t<key>Controller</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(controller))?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Backlights</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for backlight in &report.leds.backlights {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&backlight.node))?;
+        writeln!(file, "\t\t\t\t<key>Compatible</key>")?;
+        writeln!(file, "\t\t\t\t<array>")?;
+        for compatible in &backlight.compatible {
+            writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(compatible))?;
+        }
+        writeln!(file, "\t\t\t\t</array>")?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>HALPackages</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for package in &report.leds.hal_packages {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(package))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>SysfsPaths</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for command in &report.leds.sysfs_commands {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&command.path))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // NFC/SE
+    writeln!(file, "\t<key>NFC</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>Devices</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for device in &report.nfc.devices {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&device.node))?;
+        writeln!(file, "\t\t\t\t<key>Kind</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", device.kind.as_str())?;
+        writeln!(file, "\t\t\t\t<key>Vendor</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&device.vendor))?;
+        writeln!(file, "\t\t\t\t<key>Compatible</key>")?;
+        writeln!(file, "\t\t\t\t<array>")?;
+        for compatible in &device.compatible {
+            writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(compatible))?;
+        }
+        writeln!(file, "\t\t\t\t</array>")?;
+        if let Some(config) = &device.vendor_config {
+            writeln!(file, "\t\t\t\t<key>VendorConfig</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(config))?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Packages</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for package in &report.nfc.hal_packages {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(package))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>ConfigFiles</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for config in &report.nfc.config_files {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&config.file))?;
+        writeln!(file, "\t\t\t<dict>")?;
+        for (key, value) in &config.settings {
+            writeln!(file, "\t\t\t\t<key>{}</key>", escape_xml(key))?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // USB
+    let usb = &report.usb;
+    writeln!(file, "\t<key>USB</key>")?;
+    writeln!(file, "\t<dict>")?;
+    if let Some(setting) = usb.default_setting() {
+        writeln!(file, "\t\t<key>DefaultConfig</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(&setting.value))?;
+    }
+    if let Some(adb) = usb.adb_by_default() {
+        writeln!(file, "\t\t<key>ADBByDefault</key>")?;
+        writeln!(file, "\t\t<{} />", adb)?;
+    }
+    if let Some(secure) = &usb.adb_secure {
+        writeln!(file, "\t\t<key>ADBSecure</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(secure))?;
+    }
+    writeln!(file, "\t\t<key>Functions</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for function in usb.functions() {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(function))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Compositions</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for composition in &usb.compositions {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(composition))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>BoardFlags</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for flag in &usb.board_flags {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&flag.name))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&flag.value))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Regulators
+    writeln!(file, "\t<key>Regulators</key>")?;
+    writeln!(file, "\t<array>")?;
+    for regulator in &report.regulators.regulators {
+        writeln!(file, "\t\t<dict>")?;
+        writeln!(file, "\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&regulator.node))?;
+        if let Some(name) = &regulator.name {
+            writeln!(file, "\t\t\t<key>Name</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(name))?;
+        }
+        writeln!(file, "\t\t\t<key>Fixed</key>")?;
+        writeln!(file, "\t\t\t<{} />", regulator.fixed)?;
+        if let Some(provider) = &regulator.provider {
+            writeln!(file, "\t\t\t<key>Provider</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(provider))?;
+        }
+        if let Some(min) = regulator.min_microvolt {
+            writeln!(file, "\t\t\t<key>MinMicrovolt</key>")?;
+            writeln!(file, "\t\t\t<integer>{}</integer>", min)?;
+        }
+        if let Some(max) = regulator.max_microvolt {
+            writeln!(file, "\t\t\t<key>MaxMicrovolt</key>")?;
+            writeln!(file, "\t\t\t<integer>{}</integer>", max)?;
+        }
+        writeln!(file, "\t\t\t<key>AlwaysOn</key>")?;
+        writeln!(file, "\t\t\t<{} />", regulator.always_on)?;
+        writeln!(file, "\t\t\t<key>DefinedIn</key>")?;
+        writeln!(file, "\t\t\t<array>")?;
+        for location in &regulator.locations {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(location))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</array>")?;
+    writeln!(file, "\t<key>PowerDomains</key>")?;
+    writeln!(file, "\t<array>")?;
+    for domain in &report.regulators.power_domains {
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(&domain.node))?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // Power Tree
+    writeln!(file, "\t<key>PowerTree</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for consumer in &report.power_tree {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(&consumer.node))?;
+        write_supply_links(file, &consumer.supplies, 2)?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Battery/Charger
+    writeln!(file, "\t<key>Battery</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>Controllers</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for controller in &report.battery.controllers {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&controller.node))?;
+        writeln!(file, "\t\t\t\t<key>Kind</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", controller.kind.as_str())?;
+        writeln!(file, "\t\t\t\t<key>Binding</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&controller.binding))?;
+        if let Some(description) = controller.description {
+            writeln!(file, "\t\t\t\t<key>Description</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(description))?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Profiles</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for profile in &report.battery.profiles {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Node</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&profile.node))?;
+        for (key, value) in [("BatteryType", &profile.battery_type), ("Chemistry", &profile.chemistry)] {
+            if let Some(value) = value {
+                writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+                writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+            }
+        }
+        for (key, value) in [("CapacityMah", profile.capacity_mah), ("MaxMicrovolt", profile.max_microvolt)] {
+            if let Some(value) = value {
+                writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+                writeln!(file, "\t\t\t\t<integer>{}</integer>", value)?;
+            }
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>ProfileFiles</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for profile_file in &report.battery.profile_files {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>File</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&profile_file.file))?;
+        if let Some(capacity) = profile_file.capacity_mah {
+            writeln!(file, "\t\t\t\t<key>CapacityMah</key>")?;
+            writeln!(file, "\t\t\t\t<integer>{}</integer>", capacity)?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Merged Base Tree, only with --merge
+    if let Some(merge) = &report.merge {
+        writeln!(file, "\t<key>Merge</key>")?;
+        writeln!(file, "\t<dict>")?;
+        writeln!(file, "\t\t<key>BasePath</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(&merge.base_path))?;
+        let lists = [("InheritedSections", &merge.inherited_sections), ("InheritedValues", &merge.inherited_values)];
+        for (key, values) in lists {
+            writeln!(file, "\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t<array>")?;
+            for value in values {
+                writeln!(file, "\t\t\t<string>{}</string>", escape_xml(value))?;
+            }
+            writeln!(file, "\t\t</array>")?;
+        }
+        writeln!(file, "\t\t<key>InheritedEntries</key>")?;
+        writeln!(file, "\t\t<dict>")?;
+        for (category, entries) in &merge.inherited_entries {
+            writeln!(file, "\t\t\t<key>{}</key>", escape_xml(category))?;
+            writeln!(file, "\t\t\t<array>")?;
+            for entry in entries {
+                writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(entry))?;
+            }
+            writeln!(file, "\t\t\t</array>")?;
+        }
+        writeln!(file, "\t\t</dict>")?;
+        writeln!(file, "\t</dict>")?;
+    }
+
+    // Warnings
+    writeln!(file, "\t<key>Warnings</key>")?;
+    writeln!(file, "\t<array>")?;
+    for warning in &report.warnings {
+        writeln!(file, "\t\t<dict>")?;
+        writeln!(file, "\t\t\t<key>Kind</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", warning.kind.name())?;
+        writeln!(file, "\t\t\t<key>File</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&warning.file))?;
+        writeln!(file, "\t\t\t<key>Message</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&warning.message))?;
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // WiFi
+    writeln!(file, "\t<key>WiFi</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for (label, value) in report.wifi.fields() {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(&label))?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(&value))?;
+    }
+    writeln!(file, "\t\t<key>ConfigFiles</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for config in &report.wifi.configs.files {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>File</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&config.file))?;
+        writeln!(file, "\t\t\t\t<key>Kind</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", config.kind.as_str())?;
+        writeln!(file, "\t\t\t\t<key>Settings</key>")?;
+        writeln!(file, "\t\t\t\t<dict>")?;
+        for setting in &config.settings {
+            writeln!(file, "\t\t\t\t\t<key>{}</key>", escape_xml(&setting.key))?;
+            writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(&setting.value))?;
+        }
+        writeln!(file, "\t\t\t\t</dict>")?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // File Hashes (only with --hash)
+    if !report.file_hashes.is_empty() {
+        writeln!(file, "\t<key>FileHashes</key>")?;
+        writeln!(file, "\t<dict>")?;
+        for (path, sha256) in &report.file_hashes {
+            writeln!(file, "\t\t<key>{}</key>", escape_xml(path))?;
+            writeln!(file, "\t\t<dict>")?;
+            writeln!(file, "\t\t\t<key>sha256</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", sha256)?;
+            writeln!(file, "\t\t</dict>")?;
+        }
+        writeln!(file, "\t</dict>")?;
+    }
+
+    // Copied Files
+    writeln!(file, "\t<key>CopiedFiles</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for (partition, entries) in report.copied_files.by_partition() {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(partition))?;
+        writeln!(file, "\t\t<array>")?;
+        for entry in entries {
+            writeln!(file, "\t\t\t<dict>")?;
+            writeln!(file, "\t\t\t\t<key>Source</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&entry.source))?;
+            writeln!(file, "\t\t\t\t<key>Destination</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&entry.destination))?;
+            writeln!(file, "\t\t\t\t<key>InTree</key>")?;
+            writeln!(file, "\t\t\t\t<{} />", entry.in_tree)?;
+            writeln!(file, "\t\t\t</dict>")?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Declared Features
+    writeln!(file, "\t<key>DeclaredFeatures</key>")?;
+    writeln!(file, "\t<array>")?;
+    for feature in &report.declared_features.features {
+        writeln!(file, "\t\t<dict>")?;
+        writeln!(file, "\t\t\t<key>Name</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&feature.name))?;
+        if let Some(version) = &feature.version {
+            writeln!(file, "\t\t\t<key>Version</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(version))?;
+        }
+        writeln!(file, "\t\t\t<key>Unavailable</key>")?;
+        writeln!(file, "\t\t\t<{} />", feature.unavailable)?;
+        writeln!(file, "\t\t\t<key>FromFileName</key>")?;
+        writeln!(file, "\t\t\t<{} />", feature.from_file_name)?;
+        writeln!(file, "\t\t\t<key>Location</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&feature.location))?;
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // Media Profiles
+    writeln!(file, "\t<key>MediaProfiles</key>")?;
+    writeln!(file, "\t<array>")?;
+    for profile in &report.media_profiles.profiles {
+        writeln!(file, "\t\t<dict>")?;
+        if let Some(camera_id) = &profile.camera_id {
+            writeln!(file, "\t\t\t<key>CameraId</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(camera_id))?;
+        }
+        writeln!(file, "\t\t\t<key>Quality</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&profile.quality))?;
+        if let Some(format) = &profile.file_format {
+            writeln!(file, "\t\t\t<key>FileFormat</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(format))?;
+        }
+        if let Some(video) = &profile.video {
+            writeln!(file, "\t\t\t<key>VideoCodec</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&video.codec))?;
+            for (key, value) in [("Width", video.width), ("Height", video.height), ("FrameRate", video.frame_rate)] {
+                if let Some(value) = value {
+                    writeln!(file, "\t\t\t<key>{}</key>", key)?;
+                    writeln!(file, "\t\t\t<integer>{}</integer>", value)?;
+                }
+            }
+            if let Some(bit_rate) = video.bit_rate {
+                writeln!(file, "\t\t\t<key>VideoBitRate</key>")?;
+                writeln!(file, "\t\t\t<integer>{}</integer>", bit_rate)?;
+            }
+        }
+        if let Some(audio) = &profile.audio {
+            writeln!(file, "\t\t\t<key>AudioCodec</key>")?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&audio.codec))?;
+            for (key, value) in [("SampleRate", audio.sample_rate), ("Channels", audio.channels)] {
+                if let Some(value) = value {
+                    writeln!(file, "\t\t\t<key>{}</key>", key)?;
+                    writeln!(file, "\t\t\t<integer>{}</integer>", value)?;
+                }
+            }
+            if let Some(bit_rate) = audio.bit_rate {
+                writeln!(file, "\t\t\t<key>AudioBitRate</key>")?;
+                writeln!(file, "\t\t\t<integer>{}</integer>", bit_rate)?;
+            }
+        }
+        writeln!(file, "\t\t\t<key>DefinedIn</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&profile.file))?;
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // Audio Mixer Paths
+    writeln!(file, "\t<key>MixerPaths</key>")?;
+    writeln!(file, "\t<array>")?;
+    for mixer_file in &report.mixer_paths.files {
+        writeln!(file, "\t\t<dict>")?;
+        writeln!(file, "\t\t\t<key>File</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&mixer_file.file))?;
+        writeln!(file, "\t\t\t<key>Variant</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(mixer_file.variant_name()))?;
+        writeln!(file, "\t\t\t<key>DefaultControls</key>")?;
+        writeln!(file, "\t\t\t<integer>{}</integer>", mixer_file.defaults.len())?;
+        writeln!(file, "\t\t\t<key>Paths</key>")?;
+        writeln!(file, "\t\t\t<dict>")?;
+        for path in &mixer_file.paths {
+            writeln!(file, "\t\t\t\t<key>{}</key>", escape_xml(&path.name))?;
+            writeln!(file, "\t\t\t\t<dict>")?;
+            writeln!(file, "\t\t\t\t\t<key>Controls</key>")?;
+            writeln!(file, "\t\t\t\t\t<integer>{}</integer>", path.controls.len())?;
+            writeln!(file, "\t\t\t\t\t<key>Includes</key>")?;
+            writeln!(file, "\t\t\t\t\t<array>")?;
+            for include in &path.includes {
+                writeln!(file, "\t\t\t\t\t\t<string>{}</string>", escape_xml(include))?;
+            }
+            writeln!(file, "\t\t\t\t\t</array>")?;
+            writeln!(file, "\t\t\t\t</dict>")?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // Input Key Layouts
+    writeln!(file, "\t<key>KeyLayouts</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for layout in &report.key_layouts.files {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(&layout.file))?;
+        writeln!(file, "\t\t<array>")?;
+        for key in &layout.keys {
+            writeln!(file, "\t\t\t<dict>")?;
+            if let Some(code) = &key.code {
+                writeln!(file, "\t\t\t\t<key>Code</key>")?;
+                writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(code))?;
+            }
+            writeln!(file, "\t\t\t\t<key>Name</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&key.name))?;
+            if !key.flags.is_empty() {
+                writeln!(file, "\t\t\t\t<key>Flags</key>")?;
+                writeln!(file, "\t\t\t\t<array>")?;
+                for flag in &key.flags {
+                    writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(flag))?;
+                }
+                writeln!(file, "\t\t\t\t</array>")?;
+            }
+            writeln!(file, "\t\t\t</dict>")?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Treble/VNDK
+    let treble = &report.treble;
+    writeln!(file, "\t<key>Treble</key>")?;
+    writeln!(file, "\t<dict>")?;
+    if let Some(version) = &treble.vndk_version {
+        writeln!(file, "\t\t<key>VNDKVersion</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(version))?;
+    }
+    writeln!(file, "\t\t<key>Compatible</key>")?;
+    writeln!(file, "\t\t<{} />", treble.is_compatible())?;
+    writeln!(file, "\t\t<key>Flags</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for (name, value) in &treble.flags {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(name))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(value))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t\t<key>VNDKDirectories</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for dir in &treble.vndk_dirs {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(dir))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // GSI Compatibility
+    let checks = gsi::checklist(&report.treble, &report.boot, &report.gsi);
+    writeln!(file, "\t<key>GSICompatibility</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>LikelyToBoot</key>")?;
+    writeln!(file, "\t\t<{} />", gsi::likely_to_boot(&checks))?;
+    if let Some((level, _)) = report.gsi.target_fcm() {
+        writeln!(file, "\t\t<key>TargetFCMVersion</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(level))?;
+    }
+    writeln!(file, "\t\t<key>Checks</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for check in &checks {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Name</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(check.name))?;
+        writeln!(file, "\t\t\t\t<key>Passed</key>")?;
+        writeln!(file, "\t\t\t\t<{} />", check.passed)?;
+        writeln!(file, "\t\t\t\t<key>Required</key>")?;
+        writeln!(file, "\t\t\t\t<{} />", check.required)?;
+        writeln!(file, "\t\t\t\t<key>Evidence</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&check.evidence))?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // APEX Modules
+    writeln!(file, "\t<key>APEXModules</key>")?;
+    writeln!(file, "\t<array>")?;
+    for module in &report.apex.modules {
+        writeln!(file, "\t\t<dict>")?;
+        writeln!(file, "\t\t\t<key>Name</key>")?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&module.name))?;
+        let flags = [("Prebuilt", module.prebuilt), ("Compressed", module.compressed), ("Packaged", module.packaged)];
+        for (key, value) in flags {
+            writeln!(file, "\t\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t\t<{} />", value)?;
+        }
+        writeln!(file, "\t\t\t<key>Files</key>")?;
+        writeln!(file, "\t\t\t<array>")?;
+        for value in &module.files {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</array>")?;
+
+    // Linker Namespaces
+    let linker = &report.linker_namespaces;
+    writeln!(file, "\t<key>LinkerNamespaces</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>PublicLibraries</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for list in &linker.public_libraries {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&list.file))?;
+        writeln!(file, "\t\t\t<array>")?;
+        for library in &list.libraries {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(library))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t\t<key>Configs</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for config in &linker.configs {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&config.file))?;
+        writeln!(file, "\t\t\t<dict>")?;
+        for section in &config.sections {
+            writeln!(file, "\t\t\t\t<key>{}</key>", escape_xml(&section.name))?;
+            writeln!(file, "\t\t\t\t<dict>")?;
+            for namespace in &section.namespaces {
+                // Namespace -> linked namespaces and the libraries they share
+                writeln!(file, "\t\t\t\t\t<key>{}</key>", escape_xml(&namespace.name))?;
+                writeln!(file, "\t\t\t\t\t<dict>")?;
+                for link in &namespace.links {
+                    writeln!(file, "\t\t\t\t\t\t<key>{}</key>", escape_xml(&link.target))?;
+                    writeln!(file, "\t\t\t\t\t\t<array>")?;
+                    for library in &link.shared_libs {
+                        writeln!(file, "\t\t\t\t\t\t\t<string>{}</string>", escape_xml(library))?;
+                    }
+                    writeln!(file, "\t\t\t\t\t\t</array>")?;
+                }
+                writeln!(file, "\t\t\t\t\t</dict>")?;
+            }
+            writeln!(file, "\t\t\t\t</dict>")?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Carrier Config
+    let carrier = &report.carrier_config;
+    writeln!(file, "\t<key>CarrierConfig</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for (key, list) in [
+        ("APNFiles", &carrier.apn_files),
+        ("APNs", &carrier.apns),
+        ("CarrierConfigFiles", &carrier.config_files),
+        ("CarrierIDs", &carrier.carrier_ids),
+        ("CarrierListBlobs", &carrier.carrier_list_blobs),
+    ] {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for entry in list {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(entry))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t\t<key>APNsOmitted</key>")?;
+    writeln!(file, "\t\t<integer>{}</integer>", carrier.apns_omitted)?;
+    writeln!(file, "\t\t<key>CarrierIDsOmitted</key>")?;
+    writeln!(file, "\t\t<integer>{}</integer>", carrier.carrier_ids_omitted)?;
+    writeln!(file, "\t</dict>")?;
+
+    // Telephony
+    let telephony = &report.telephony;
+    writeln!(file, "\t<key>Telephony</key>")?;
+    writeln!(file, "\t<dict>")?;
+    if let Some((count, source)) = telephony.sim_count() {
+        writeln!(file, "\t\t<key>SIMSlots</key>")?;
+        writeln!(file, "\t\t<integer>{}</integer>", count)?;
+        writeln!(file, "\t\t<key>SIMSlotsSource</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(source))?;
+    }
+    if let Some(class) = telephony.ril_class() {
+        writeln!(file, "\t\t<key>RILClass</key>")?;
+        writeln!(file, "\t\t<string>{}</string>", escape_xml(class))?;
+    }
+    let modes = telephony.default_network_modes();
+    let families: Vec<String> = telephony.radio_families().iter().map(|family| family.to_string()).collect();
+    let lists = [
+        ("DefaultNetworkModes", &modes),
+        ("RadioFamilies", &families),
+        ("OverlayFiles", &telephony.overlay_files),
+    ];
+    for (key, list) in lists {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for entry in list {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(entry))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    for (key, values) in [("Properties", &telephony.properties), ("Resources", &telephony.resources)] {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<dict>")?;
+        for (name, value) in values {
+            writeln!(file, "\t\t\t<key>{}</key>", escape_xml(name))?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&value.value))?;
+        }
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Power
+    let power = &report.power;
+    writeln!(file, "\t<key>Power</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for (key, list) in [
+        ("PowerHintFiles", &power.hint_files),
+        ("PowerHintNodes", &power.hint_nodes),
+        ("PowerHints", &power.hints),
+        ("PowerProfileFiles", &power.profile_files),
+    ] {
+        writeln!(file, "\t\t<key>{}</key>", key)?;
+        writeln!(file, "\t\t<array>")?;
+        for entry in list {
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(entry))?;
+        }
+        writeln!(file, "\t\t</array>")?;
+    }
+    writeln!(file, "\t\t<key>PowerProfile</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for (component, value) in &power.profile {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(component))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(value))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Performance Tuning
+    let perf = &report.perf_tuning;
+    writeln!(file, "\t<key>PerformanceTuning</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>ConfigFiles</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for perf_file in &perf.files {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&perf_file.file))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Boosts</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for boost in &perf.boosts {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Id</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&boost.id))?;
+        for (key, value) in [
+            ("Name", &boost.name),
+            ("Type", &boost.kind),
+            ("Timeout", &boost.timeout),
+            ("Target", &boost.target),
+            ("Resolution", &boost.resolution),
+        ] {
+            if let Some(value) = value {
+                writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+                writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+            }
+        }
+        writeln!(file, "\t\t\t\t<key>Enabled</key>")?;
+        writeln!(file, "\t\t\t\t<{}/>", boost.enabled)?;
+        writeln!(file, "\t\t\t\t<key>PerfLocks</key>")?;
+        writeln!(file, "\t\t\t\t<integer>{}</integer>", boost.resources.len())?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Properties</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for property in &perf.properties {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&property.name))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&property.value))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // CPU Topology
+    writeln!(file, "\t<key>CpuTopology</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>CpusetGroups</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for (name, group) in &report.cpu_topology.groups {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(name))?;
+        writeln!(file, "\t\t\t<dict>")?;
+        if let Some(cpus) = &group.cpus {
+            writeln!(file, "\t\t\t\t<key>Cpus</key>")?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(cpus))?;
+        }
+        writeln!(file, "\t\t\t\t<key>Cores</key>")?;
+        writeln!(file, "\t\t\t\t<array>")?;
+        for core in &group.cores {
+            writeln!(file, "\t\t\t\t\t<integer>{}</integer>", core)?;
+        }
+        writeln!(file, "\t\t\t\t</array>")?;
+        writeln!(file, "\t\t\t\t<key>Profiles</key>")?;
+        writeln!(file, "\t\t\t\t<array>")?;
+        for profile in &group.profiles {
+            writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(profile))?;
+        }
+        writeln!(file, "\t\t\t\t</array>")?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t\t<key>TaskProfiles</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for profile in &report.cpu_topology.profiles {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&profile.name))?;
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(profile.cpuset.as_deref().unwrap_or("")))?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Vendor Feature Flags, grouped as in the text report
+    writeln!(file, "\t<key>VendorFeatureFlags</key>")?;
+    writeln!(file, "\t<dict>")?;
+    for (group, flags) in report.vendor_flags.groups() {
+        writeln!(file, "\t\t<key>{}</key>", escape_xml(&group))?;
+        writeln!(file, "\t\t<dict>")?;
+        for (name, flag) in flags {
+            writeln!(file, "\t\t\t<key>{}</key>", escape_xml(name))?;
+            writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&flag.value))?;
+        }
+        writeln!(file, "\t\t</dict>")?;
+    }
+    writeln!(file, "\t</dict>")?;
+
+    // Filesystem Setup
+    let setup = &report.filesystem_setup;
+    writeln!(file, "\t<key>FilesystemSetup</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>InitScripts</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for rc_file in &setup.rc_files {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(rc_file))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Mounts</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for mount in &setup.mounts {
+        writeln!(file, "\t\t\t<dict>")?;
+        for (key, value) in [
+            ("Type", &mount.fs_type),
+            ("Device", &mount.device),
+            ("Target", &mount.target),
+            ("Trigger", &mount.trigger),
+            ("Location", &mount.location),
+        ] {
+            writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+        }
+        writeln!(file, "\t\t\t\t<key>Options</key>")?;
+        writeln!(file, "\t\t\t\t<array>")?;
+        for option in &mount.options {
+            writeln!(file, "\t\t\t\t\t<string>{}</string>", escape_xml(option))?;
+        }
+        writeln!(file, "\t\t\t\t</array>")?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Directories</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for dir in &setup.directories {
+        writeln!(file, "\t\t\t<dict>")?;
+        writeln!(file, "\t\t\t\t<key>Path</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&dir.path))?;
+        for (key, value) in [("Mode", &dir.mode), ("Owner", &dir.owner), ("Group", &dir.group)] {
+            if let Some(value) = value {
+                writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+                writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+            }
+        }
+        writeln!(file, "\t\t\t\t<key>Location</key>")?;
+        writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(&dir.location))?;
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>Symlinks</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for symlink in &setup.symlinks {
+        writeln!(file, "\t\t\t<dict>")?;
+        for (key, value) in [("Link", &symlink.link), ("Target", &symlink.target), ("Location", &symlink.location)] {
+            writeln!(file, "\t\t\t\t<key>{}</key>", key)?;
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(value))?;
+        }
+        writeln!(file, "\t\t\t</dict>")?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t\t<key>PropertyPlaceholders</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for property in &setup.properties {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(property))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Boot Sequence: trigger -> services it starts
+    let boot = &report.boot_sequence;
+    writeln!(file, "\t<key>BootSequence</key>")?;
+    writeln!(file, "\t<dict>")?;
+    writeln!(file, "\t\t<key>Triggers</key>")?;
+    writeln!(file, "\t\t<dict>")?;
+    for trigger in &boot.triggers {
+        writeln!(file, "\t\t\t<key>{}</key>", escape_xml(&trigger.name))?;
+        writeln!(file, "\t\t\t<array>")?;
+        for service in boot.services_on(&trigger.name) {
+            writeln!(file, "\t\t\t\t<string>{}</string>", escape_xml(service))?;
+        }
+        writeln!(file, "\t\t\t</array>")?;
+    }
+    writeln!(file, "\t\t</dict>")?;
+    writeln!(file, "\t\t<key>NotStarted</key>")?;
+    writeln!(file, "\t\t<array>")?;
+    for service in boot.unstarted() {
+        writeln!(file, "\t\t\t<string>{}</string>", escape_xml(&service.name))?;
+    }
+    writeln!(file, "\t\t</array>")?;
+    writeln!(file, "\t</dict>")?;
+
+    // Close plist
+    writeln!(file, "</dict>")?;
+    writeln!(file, "</plist>")?;
+
+    Ok(())
+}
+
+// Supplies as nested <array>s of <dict>s, one level per hop up the chain
+fn write_supply_links(file: &mut impl Write, links: &[SupplyLink], depth: usize) -> std::io::Result<()> {
+    let indent = "\t".repeat(depth);
+    writeln!(file, "{}<array>", indent)?;
+    for link in links {
+        writeln!(file, "{}\t<dict>", indent)?;
+        writeln!(file, "{}\t\t<key>Property</key>", indent)?;
+        writeln!(file, "{}\t\t<string>{}</string>", indent, escape_xml(&link.property))?;
+        writeln!(file, "{}\t\t<key>Reference</key>", indent)?;
+        writeln!(file, "{}\t\t<string>{}</string>", indent, escape_xml(&link.reference))?;
+        if let Some(target) = &link.target {
+            writeln!(file, "{}\t\t<key>Target</key>", indent)?;
+            writeln!(file, "{}\t\t<string>{}</string>", indent, escape_xml(target))?;
+        }
+        if let Some(name) = &link.name {
+            writeln!(file, "{}\t\t<key>Name</key>", indent)?;
+            writeln!(file, "{}\t\t<string>{}</string>", indent, escape_xml(name))?;
+        }
+        if link.cycle {
+            writeln!(file, "{}\t\t<key>Cycle</key>", indent)?;
+            writeln!(file, "{}\t\t<true />", indent)?;
+        }
+        if !link.parents.is_empty() {
+            writeln!(file, "{}\t\t<key>Supplies</key>", indent)?;
+            write_supply_links(file, &link.parents, depth + 2)?;
+        }
+        writeln!(file, "{}\t</dict>", indent)?;
+    }
+    writeln!(file, "{}</array>", indent)
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn extract_device_info(
+    path: &Path,
+    device_root: Option<&Path>,
+    found_files: &HashMap<String, PathBuf>,
+    warnings: &mut Vec<Warning>,
+) -> Option<HashMap<String, String>> {
+    let mut info = HashMap::new();
+
+    // Try to extract from path (common format: vendor/manufacturer/device);
+    // resolve it first so "-t ." or "-t raphael" still has components to split
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    match device_root.and_then(|root| path_below_device_root(&path, root)) {
+        Some(parts) => {
+            if let Some(vendor) = parts.first() {
+                info.insert("vendor".to_string(), vendor.clone());
+            }
+            if let Some(device) = parts.get(1) {
+                info.insert("device".to_string(), device.clone());
+            }
+        }
+        None => {
+            let path_str = path.to_string_lossy();
+            let parts: Vec<&str> = path_str.split('/').collect();
+
+            if parts.len() >= 2 {
+                info.insert("vendor".to_string(), parts[parts.len() - 2].to_string());
+                info.insert("device".to_string(), parts[parts.len() - 1].to_string());
+            }
+        }
+    }
+
+    // PRODUCT_NAME/DEVICE/BRAND/MODEL/MANUFACTURER from the product
+    // makefile AndroidProducts.mk lists and the makefiles it inherits
+    let identity = product::product_identity(
+        &path,
+        found_files.get("AndroidProducts.mk").map(|p| p.as_path()),
+        found_files.get("device.mk").map(|p| p.as_path()),
+        warnings,
+    );
+    for (key, value) in identity.values {
+        info.insert(key.to_string(), value);
+    }
+    for key in identity.conditional {
+        info.insert(format!("{}_conditional", key), "true".to_string());
+    }
+    if let Some(makefile) = identity.makefile {
+        info.insert("product_makefile".to_string(), makefile);
+    }
+
+    if info.is_empty() {
+        None
+    } else {
+        Some(info)
+    }
+}
+
+// --root-relative-to: the tree's path components below the root, with a
+// leading "device" dropped so the source root works as well as device/
+// itself: <root>/device/xiaomi/raphael -> [xiaomi, raphael]. None when the
+// tree isn't below the root.
+fn path_below_device_root(path: &Path, root: &Path) -> Option<Vec<String>> {
+    let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let relative = path.strip_prefix(&root).ok()?;
+    let mut parts: Vec<String> = relative.iter().map(|part| part.to_string_lossy().to_string()).collect();
+    if parts.first().is_some_and(|first| first == "device") {
+        parts.remove(0);
+    }
+    if parts.is_empty() { None } else { Some(parts) }
+}
 
-    // Scan the tree directory
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let entry_path = entry.path();
-            let entry_name = entry.file_name().to_string_lossy().to_string();
+fn check_assertions(report: &HardwareReport, assertions: &[assert::Assertion], quiet: bool) -> bool {
+    if !quiet {
+        println!("\n=== Assertions ===");
+    }
 
-            if entry_path.is_file() && key_files.contains(&entry_name.as_str()) {
-                found_files.insert(entry_name.clone(), entry_path);
-                files_status.insert(entry_name, true);
-            } else if entry_path.is_dir() && key_dirs.contains(&entry_name.as_str()) {
-                found_dirs.insert(entry_name.clone(), entry_path);
-                dirs_status.insert(entry_name, true);
+    let mut all_passed = true;
+    for assertion in assertions {
+        match assertion.evaluate(report) {
+            Ok(()) => {
+                if !quiet {
+                    println!("  {} {}", Marker::Ok, assertion.source);
+                }
+            }
+            Err(reason) => {
+                // Failures are errors, so they still show up with --quiet
+                if quiet {
+                    eprintln!("Assertion failed: {} ({})", assertion.source, reason);
+                } else {
+                    println!("  {} {} ({})", Marker::Fail, assertion.source, reason);
+                }
+                all_passed = false;
             }
         }
     }
 
-    // Mark missing files/dirs
-    for file in &key_files {
-        files_status.entry(file.to_string()).or_insert(false);
-    }
-    for dir in &key_dirs {
-        dirs_status.entry(dir.to_string()).or_insert(false);
+    all_passed
+}
+
+// Prints the error the way --format asks for and exits
+fn fail(error: AnalyzeError, format: OutputFormat) -> ! {
+    print_error(&error, format);
+    remote::remove_scratch();
+    std::process::exit(error.exit_code());
+}
+
+// The most warnings a run may produce before it fails, if anything limits it
+fn warning_threshold(args: &Args) -> Option<usize> {
+    if args.warnings_as_errors { Some(0) } else { args.fail_on_warning_count }
+}
+
+// The failure for a warning count over the threshold
+fn warning_check(threshold: Option<usize>, warnings_as_errors: bool, count: usize) -> Option<AnalyzeError> {
+    let threshold = threshold?;
+    if count <= threshold {
+        None
+    } else if warnings_as_errors {
+        Some(AnalyzeError::WarningsAsErrors(count))
+    } else {
+        Some(AnalyzeError::TooManyWarnings { count, threshold })
     }
+}
 
-    // Detect device info from path or files
-    let device_info = extract_device_info(path, &found_files).unwrap_or_else(HashMap::new);
+// "3 of 5 allowed" for the warning summary
+fn warning_budget(count: usize, threshold: usize) -> String {
+    let marker = if count > threshold { Marker::Fail } else { Marker::Ok };
+    format!("{} {} of {} allowed", marker, count, threshold)
+}
 
-    // Print results
-    println!("=== Device Tree Structure Detection ===\n");
+// Like fail, but with --exit-zero a failed check is only printed
+fn fail_check(error: AnalyzeError, format: OutputFormat, exit_zero: bool) {
+    if exit_zero && error.is_check_failure() {
+        print_error(&error, format);
+    } else {
+        fail(error, format);
+    }
+}
 
-    if !device_info.is_empty() {
-        println!("Device Information:");
-        if let Some(vendor) = device_info.get("vendor") {
-            println!("  Vendor: {}", vendor);
-        }
-        if let Some(device) = device_info.get("device") {
-            println!("  Device: {}", device);
+fn print_error(error: &AnalyzeError, format: OutputFormat) {
+    match format {
+        OutputFormat::Text | OutputFormat::Plist | OutputFormat::Toml => eprintln!("Error: {}", error),
+        OutputFormat::Json | OutputFormat::Ndjson | OutputFormat::Sarif | OutputFormat::SummaryJson => {
+            eprintln!("{}", error.to_json().dump())
         }
-        println!();
     }
+}
 
-    println!("Key Files Found ({}/{}):", found_files.len(), key_files.len());
-    for file in &key_files {
-        if found_files.contains_key(*file) {
-            println!("  ✓ {}", file);
-        } else {
-            println!("  ✗ {} (missing)", file);
+fn main() {
+    let args = Args::parse();
+    fsread::set_retries(args.read_retries);
+    symbols::init(args.symbols, args.color_theme);
+
+    if args.selftest {
+        let failed = selftest::run();
+        if failed > 0 {
+            fail(AnalyzeError::SelftestFailed(failed), args.format);
         }
+        return;
     }
 
-    println!("\nKey Directories Found ({}/{}):", found_dirs.len(), key_dirs.len());
-    for dir in &key_dirs {
-        if found_dirs.contains_key(*dir) {
-            println!("  ✓ {}", dir);
-        } else {
-            println!("  ✗ {} (missing)", dir);
+    // Parse assertions up front so a typo fails before the scan
+    let mut assertions = Vec::new();
+    for expr in &args.assertions {
+        match assert::parse(expr) {
+            Ok(assertion) => assertions.push(assertion),
+            Err(e) => fail(
+                AnalyzeError::InvalidAssertion {
+                    expr: expr.clone(),
+                    message: e,
+                },
+                args.format,
+            ),
         }
     }
 
-    // Analyze structure validity
-    println!("\n=== Structure Analysis ===");
-    let has_makefile = found_files.contains_key("AndroidProducts.mk")
-        || found_files.contains_key("device.mk");
-    let has_board_config = found_files.contains_key("BoardConfig.mk");
-    let structure_valid = has_makefile && has_board_config;
+    // Likewise the category map
+    let category_map = match &args.category_map {
+        Some(path) => match CategoryMap::load(path) {
+            Ok(map) => map,
+            Err(message) => fail(
+                AnalyzeError::InvalidCategoryMap {
+                    path: path.display().to_string(),
+                    message,
+                },
+                args.format,
+            ),
+        },
+        None => CategoryMap::default(),
+    };
 
-    if structure_valid {
-        println!("Status: ✓ Valid Android device tree structure detected");
-    } else if has_makefile || has_board_config {
-        println!("Status: ⚠ Partial device tree structure (missing critical files)");
+    // --git-url stands in for --tree; clap only lets --tree be left out with
+    // it or --selftest
+    let tree_arg = match &args.git_url {
+        Some(url) => match remote::clone(url, args.git_ref.as_deref(), args.git_cache.as_deref().map(Path::new)) {
+            Ok(checkout) => checkout.to_string_lossy().to_string(),
+            Err(e) => fail(e, args.format),
+        },
+        None => args.tree.clone().unwrap_or_default(),
+    };
+
+    // --tree - enumerates the tree from stdin; files are still read from --root
+    let (tree, listing) = if tree_arg == "-" {
+        let mut text = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut text) {
+            fail(AnalyzeError::Stdin(e.to_string()), args.format);
+        }
+        let listing = walk::Listing::parse(Path::new(&args.root), &text);
+        (args.root.clone(), Some(Arc::new(listing)))
     } else {
-        println!("Status: ✗ Does not appear to be a valid Android device tree");
-    }
+        (tree_arg, None)
+    };
 
-    // Parse and list device drivers
-    println!("\n=== Device Drivers ===");
-    let drivers = list_device_drivers(path);
+    // Resolve --since before scanning so a bad ref or a non-git tree fails fast
+    let changed_files = match &args.since {
+        Some(git_ref) => match since::changed_files(Path::new(&tree), git_ref) {
+            Ok(files) => Some(files),
+            Err(e) => fail(e, args.format),
+        },
+        None => None,
+    };
 
-    // Export to plist if requested
-    if let Some(plist_path) = export_plist {
-        let report = HardwareReport {
-            device_info,
-            key_files: files_status,
-            key_dirs: dirs_status,
-            drivers,
-            structure_valid,
-        };
+    let options = AnalysisOptions {
+        walk: WalkOptions {
+            max_depth: if args.no_recursion { Some(0) } else { args.max_depth },
+            follow_symlinks: args.follow_symlinks,
+            only_files: changed_files.as_ref().map(|files| {
+                Arc::new(files.iter().map(|file| Path::new(&tree).join(file)).collect::<BTreeSet<_>>())
+            }),
+            listing,
+        },
+        scan_drivers: !args.no_drivers && !args.only_missing,
+        ignore_case: args.ignore_case,
+        validate_dts: args.validate_dts,
+        board_compatibles: args.board_compatibles.clone(),
+        device_root: args.root_relative_to.as_ref().map(PathBuf::from),
+        max_entries: args.max_entries_per_category,
+    };
 
-        match export_to_plist(&report, &plist_path) {
-            Ok(_) => println!("\n✓ Hardware report exported to: {}", plist_path),
-            Err(e) => eprintln!("\n✗ Failed to export plist: {}", e),
+    let tree_path = Path::new(&tree);
+    let single_file = if let Some(dtb) = &args.dtb {
+        match inspect_dtb_file(Path::new(dtb), args.quiet, args.top, args.validate_dts) {
+            Ok(warnings) => Some(warnings),
+            Err(e) => fail(e, args.format),
+        }
+    } else if options.walk.listing.is_none()
+        && tree_path.is_file()
+        && matches!(tree_path.extension().and_then(|e| e.to_str()), Some("dts" | "dtsi"))
+    {
+        Some(inspect_dts_file(tree_path, args.quiet, args.top, args.validate_dts))
+    } else {
+        None
+    };
+    if let Some(warnings) = single_file {
+        if args.quiet && warning_threshold(&args).is_some() {
+            for warning in &warnings {
+                eprintln!("Warning: {}", warning);
+            }
         }
+        if let Some(error) = warning_check(warning_threshold(&args), args.warnings_as_errors, warnings.len()) {
+            fail_check(error, args.format, args.exit_zero);
+        }
+        return;
     }
-}
-
-fn list_device_drivers(tree_path: &Path) -> HashMap<String, Vec<String>> {
-    let mut drivers = HashMap::new();
 
-    // Scan for .dts and .dtsi files (Device Tree Source files)
-    scan_for_device_tree_sources(tree_path, &mut drivers);
+    let mut profiler = Profiler::default();
+    let mut report = match &args.load {
+        Some(load_path) => match load::load_report(Path::new(load_path)) {
+            Ok((report, notes)) => {
+                for note in notes {
+                    eprintln!("{} {}: {}", Marker::Warn.symbol(), load_path, note);
+                }
+                report
+            }
+            Err(e) => fail(e, args.format),
+        },
+        None => match detect_android_device_tree_structure(&tree, &options, &mut profiler) {
+            Ok(report) => report,
+            Err(e) => fail(e, args.format),
+        },
+    };
+    report.driver_sort = args.sort;
 
-    // Parse BoardConfig.mk for kernel modules and drivers
-    let board_config_path = tree_path.join("BoardConfig.mk");
-    if board_config_path.exists() {
-        parse_board_config(&board_config_path, &mut drivers);
+    // The base tree is always scanned from disk, even with --tree -
+    let base_walk = WalkOptions { listing: None, ..options.walk.clone() };
+    if let Some(base_tree) = &args.merge {
+        let base_options = AnalysisOptions { walk: base_walk.clone(), ..options };
+        let base = match detect_android_device_tree_structure(base_tree, &base_options, &mut profiler) {
+            Ok(base) => base,
+            Err(e) => fail(e, args.format),
+        };
+        report = merge::merge(report, base);
     }
 
-    // Parse device.mk for HAL and driver configurations
-    let device_mk_path = tree_path.join("device.mk");
-    if device_mk_path.exists() {
-        parse_device_mk(&device_mk_path, &mut drivers);
-    }
+    // Renamed once the drivers are final, so --merge and the HAL grouping
+    // still see the built-in names
+    report.category_map = category_map;
+    report.category_map.filter_sources(args.filter_source.iter().map(DriverSource::id).collect());
+    report.category_map.apply(&mut report.drivers, &mut report.attempted_categories, &mut report.truncated_categories);
 
-    // Look for prebuilt drivers in various locations
-    scan_prebuilt_modules(tree_path, &mut drivers);
+    // --fail-if-missing files, checked once the key-file scan has run; with
+    // --merge either tree may provide them
+    let missing_files: Vec<String> = args
+        .fail_if_missing
+        .iter()
+        .filter(|file| walk::resolve_file(Path::new(&tree), file, &options.walk, args.ignore_case).is_none())
+        .filter(|file| {
+            args.merge.as_ref().is_none_or(|base| {
+                walk::resolve_file(Path::new(base), file, &base_walk, args.ignore_case).is_none()
+            })
+        })
+        .cloned()
+        .collect();
 
-    if drivers.is_empty() {
-        println!("No device drivers found in the tree.");
-    } else {
-        // Categorize and display drivers
-        display_drivers_by_category(&drivers);
-    }
+    // The full text report; --quiet, --count-only, --template and JSON
+    // output each keep stdout to themselves
+    let verbose = args.format == OutputFormat::Text
+        && !args.quiet
+        && !args.count_only
+        && !args.only_missing
+        && args.template.is_none();
 
-    drivers
-}
+    if verbose && args.compact {
+        display_compact(&report, symbols::terminal_width());
 
-fn scan_for_device_tree_sources(path: &Path, drivers: &mut HashMap<String, Vec<String>>) {
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let entry_path = entry.path();
+        if let (Some(git_ref), Some(files)) = (&args.since, &changed_files) {
+            let affected = affected_categories(&report);
+            let label = format!("Changed since {} ({} files), affected ({})", git_ref, files.len(), affected.len());
+            println!("{}", compact_line(&label, &affected, symbols::terminal_width()));
+        }
+    } else if verbose {
+        display_report(&report, args.top);
 
-            if entry_path.is_file() {
-                let file_name = entry_path.file_name().unwrap().to_string_lossy();
-                if file_name.ends_with(".dts") || file_name.ends_with(".dtsi") {
-                    parse_dts_file(&entry_path, drivers);
-                }
-            } else if entry_path.is_dir() {
-                // Recursively scan subdirectories
-                scan_for_device_tree_sources(&entry_path, drivers);
+        if let (Some(git_ref), Some(files)) = (&args.since, &changed_files) {
+            println!("\n=== Changed Since {} ===", git_ref);
+            println!("Changed Files ({}):", files.len());
+            for file in files {
+                println!("  {} {}", Marker::Bullet, file);
+            }
+            let affected = affected_categories(&report);
+            println!("\nAffected Categories ({}):", affected.len());
+            for category in affected {
+                println!("  {} {}", Marker::Bullet, category);
             }
         }
     }
-}
-
-fn parse_dts_file(dts_path: &Path, drivers: &mut HashMap<String, Vec<String>>) {
-    if let Ok(content) = fs::read_to_string(dts_path) {
-        let file_name = dts_path.file_name().unwrap().to_string_lossy().to_string();
 
-        // Look for compatible strings which indicate driver bindings
-        for line in content.lines() {
-            let trimmed = line.trim();
-            if trimmed.starts_with("compatible") {
-                // Extract compatible string: compatible = "vendor,device";
-                if let Some(compat_str) = extract_compatible_string(trimmed) {
-                    drivers.entry("Device Tree Bindings".to_string())
-                        .or_insert_with(Vec::new)
-                        .push(format!("{} ({})", compat_str, file_name));
-                }
+    if args.hash {
+        let mut hash_warnings = Vec::new();
+        report.file_hashes = profiler.time("hashing", || {
+            hash::hash_report_files(Path::new(&tree), &report, &options.walk, &mut hash_warnings)
+        });
+        report.warnings.extend(hash_warnings);
+        if verbose && args.compact {
+            println!("File hashes: {}", report.file_hashes.len());
+        } else if verbose {
+            println!("\n=== File Hashes ===");
+            for (file, sha256) in &report.file_hashes {
+                println!("  {}  {}", sha256, file);
             }
         }
     }
-}
 
-fn extract_compatible_string(line: &str) -> Option<String> {
-    // Parse: compatible = "qcom,msm8996", "qcom,somename";
-    if let Some(start) = line.find('"') {
-        if let Some(end) = line[start + 1..].find('"') {
-            return Some(line[start + 1..start + 1 + end].to_string());
+    let threshold = warning_threshold(&args);
+    if verbose && args.compact {
+        match threshold {
+            Some(threshold) => println!("Warnings: {}", warning_budget(report.warnings.len(), threshold)),
+            None => println!("Warnings: {}", report.warnings.len()),
+        }
+    } else if verbose {
+        println!("\n=== Warnings ===");
+        display_warnings(&report.warnings);
+        if let Some(threshold) = threshold {
+            println!("Warning budget: {}", warning_budget(report.warnings.len(), threshold));
+        }
+    } else if threshold.is_some() {
+        for warning in &report.warnings {
+            eprintln!("Warning: {}", warning);
         }
     }
-    None
-}
-
-fn parse_board_config(board_config_path: &Path, drivers: &mut HashMap<String, Vec<String>>) {
-    if let Ok(content) = fs::read_to_string(board_config_path) {
-        for line in content.lines() {
-            let trimmed = line.trim();
 
-            // Look for kernel module definitions
-            if trimmed.contains("BOARD_VENDOR_KERNEL_MODULES") ||
-               trimmed.contains("KERNEL_MODULES") {
-                if let Some(modules) = extract_kernel_modules(trimmed) {
-                    for module in modules {
-                        drivers.entry("Kernel Modules".to_string())
-                            .or_insert_with(Vec::new)
-                            .push(module);
-                    }
-                }
-            }
+    // Every other path in the report is already relative to the tree, so
+    // only the root itself differs between machines. Device info was taken
+    // from the real path during the scan.
+    // (the two flags override each other, so at most one is set). A loaded
+    // report keeps the path it was saved with.
+    if report.loaded_from.is_none() {
+        report.tree_path = if args.absolute_paths && !args.relative_paths {
+            fs::canonicalize(&tree).map_or_else(|_| tree.clone(), |path| path.display().to_string())
+        } else {
+            ".".to_string()
+        };
+    }
 
-            // Look for WiFi driver
-            if trimmed.starts_with("BOARD_WLAN_DEVICE") || trimmed.starts_with("WPA_SUPPLICANT_VERSION") {
-                if let Some(value) = extract_makefile_value(trimmed) {
-                    drivers.entry("WiFi Driver".to_string())
-                        .or_insert_with(Vec::new)
-                        .push(value);
-                }
-            }
+    if args.dry_run {
+        let previews = profiler.time("dry run", || export_previews(&args, &report));
+        // The text report owns stdout; otherwise keep it parseable
+        if verbose {
+            println!("\n=== Dry Run ===");
+            previews.iter().for_each(|line| println!("{}", line));
+        } else {
+            previews.iter().for_each(|line| eprintln!("{}", line));
+        }
+    }
 
-            // Look for Bluetooth
-            if trimmed.starts_with("BOARD_HAVE_BLUETOOTH") || trimmed.starts_with("BOARD_BLUETOOTH_BDROID_BUILDCFG") {
-                if let Some(value) = extract_makefile_value(trimmed) {
-                    drivers.entry("Bluetooth Driver".to_string())
-                        .or_insert_with(Vec::new)
-                        .push(value);
-                }
-            }
+    // A failed export doesn't stop the others, but fails the run
+    let mut export_errors = Vec::new();
 
-            // Look for GPU/Graphics
-            if trimmed.starts_with("TARGET_BOARD_PLATFORM") {
-                if let Some(value) = extract_makefile_value(trimmed) {
-                    drivers.entry("GPU/Platform".to_string())
-                        .or_insert_with(Vec::new)
-                        .push(value);
+    // Export to plist if requested
+    if let Some(plist_path) = &args.export_plist
+        && !args.dry_run
+    {
+        match profiler.time("plist export", || export_to_plist(&report, plist_path, args.output_encoding)) {
+            Ok(_) => {
+                if verbose {
+                    println!("\n{} Hardware report exported to: {}", Marker::Ok, plist_path);
                 }
             }
+            Err(e) => export_errors.push(AnalyzeError::Export {
+                what: "plist",
+                path: plist_path.clone(),
+                message: e.to_string(),
+            }),
         }
     }
-}
 
-fn parse_device_mk(device_mk_path: &Path, drivers: &mut HashMap<String, Vec<String>>) {
-    if let Ok(content) = fs::read_to_string(device_mk_path) {
-        for line in content.lines() {
-            let trimmed = line.trim();
-
-            // Look for HAL packages (Hardware Abstraction Layer)
-            if trimmed.contains("PRODUCT_PACKAGES") {
-                if trimmed.contains("android.hardware.") {
-                    if let Some(hal) = extract_hal_name(trimmed) {
-                        drivers.entry("HAL (Hardware Abstraction Layer)".to_string())
-                            .or_insert_with(Vec::new)
-                            .push(hal);
-                    }
+    if let Some(toml_path) = &args.export_toml
+        && !args.dry_run
+    {
+        match profiler.time("TOML export", || export_to_toml(&report, toml_path, args.output_encoding)) {
+            Ok(_) => {
+                if verbose {
+                    println!("\n{} Hardware report exported to: {}", Marker::Ok, toml_path);
                 }
             }
+            Err(e) => export_errors.push(AnalyzeError::Export {
+                what: "TOML",
+                path: toml_path.clone(),
+                message: e.to_string(),
+            }),
+        }
+    }
 
-            // Look for audio HAL
-            if trimmed.contains("audio.") || trimmed.contains("AUDIO_") {
-                if let Some(value) = extract_makefile_value(trimmed) {
-                    drivers.entry("Audio Driver".to_string())
-                        .or_insert_with(Vec::new)
-                        .push(value);
+    if let Some(json_path) = &args.export_json_combined
+        && !args.dry_run
+    {
+        let combined = profiler.time("combined JSON export", || report_json::combined_to_json(&[&report]).pretty(2));
+        match encoding::write(Path::new(json_path), &combined, args.output_encoding) {
+            Ok(_) => {
+                if verbose {
+                    println!("\n{} Combined JSON exported to: {}", Marker::Ok, json_path);
                 }
             }
+            Err(e) => export_errors.push(AnalyzeError::Export {
+                what: "combined JSON",
+                path: json_path.clone(),
+                message: e.to_string(),
+            }),
+        }
+    }
 
-            // Look for camera HAL
-            if trimmed.contains("camera.") || trimmed.contains("CAMERA_") {
-                if let Some(value) = extract_makefile_value(trimmed) {
-                    drivers.entry("Camera Driver".to_string())
-                        .or_insert_with(Vec::new)
-                        .push(value);
+    if let Some(split_dir) = &args.export_split
+        && !args.dry_run
+    {
+        let written = profiler.time("split export", || {
+            report_json::write_split(&report, Path::new(split_dir), args.output_encoding)
+        });
+        match written {
+            Ok(count) => {
+                if verbose {
+                    println!("\n{} {} category files and summary.json exported to: {}", Marker::Ok, count, split_dir);
                 }
             }
+            Err(e) => export_errors.push(AnalyzeError::Export {
+                what: "split files",
+                path: split_dir.clone(),
+                message: e.to_string(),
+            }),
         }
     }
-}
 
-fn scan_prebuilt_modules(tree_path: &Path, drivers: &mut HashMap<String, Vec<String>>) {
-    let prebuilt_paths = vec![
-        tree_path.join("prebuilt"),
-        tree_path.join("proprietary"),
-        tree_path.join("vendor"),
-    ];
+    let assertions_passed = assertions.is_empty() || check_assertions(&report, &assertions, !verbose);
 
-    for prebuilt_path in prebuilt_paths {
-        if prebuilt_path.exists() {
-            scan_for_ko_files(&prebuilt_path, drivers);
+    if let Some(template_path) = &args.template {
+        match profiler.time("template", || template::render(template_path, &report)) {
+            Ok(rendered) => print!("{}", rendered),
+            Err(e) => fail(AnalyzeError::Template(e), args.format),
         }
     }
-}
 
-fn scan_for_ko_files(path: &Path, drivers: &mut HashMap<String, Vec<String>>) {
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries.flatten() {
-            let entry_path = entry.path();
-
-            if entry_path.is_file() {
-                let file_name = entry_path.file_name().unwrap().to_string_lossy();
-                if file_name.ends_with(".ko") {
-                    // .ko files are compiled kernel modules
-                    drivers.entry("Prebuilt Kernel Modules".to_string())
-                        .or_insert_with(Vec::new)
-                        .push(file_name.to_string());
-                }
-            } else if entry_path.is_dir() {
-                scan_for_ko_files(&entry_path, drivers);
+    match args.format {
+        OutputFormat::Text if args.template.is_some() => {}
+        OutputFormat::Text if args.quiet => println!("{}", structure_status(&report)),
+        OutputFormat::Text if args.only_missing => {
+            let (files, dirs) = missing_key_entries(&report, &missing_files);
+            display_missing(&files, &dirs);
+        }
+        OutputFormat::Json | OutputFormat::Ndjson if args.only_missing => {
+            let (files, dirs) = missing_key_entries(&report, &missing_files);
+            let json = report_json::missing_to_json(&files, &dirs);
+            match args.format {
+                OutputFormat::Ndjson => println!("{}", json.dump()),
+                _ => println!("{}", json.pretty(2)),
+            }
+        }
+        OutputFormat::Text if args.count_only => display_driver_counts(&report_json::driver_counts(&report)),
+        OutputFormat::Text => {}
+        OutputFormat::Json if args.count_only => {
+            let counts = report_json::driver_counts(&report);
+            println!("{}", report_json::counts_to_json(&counts).pretty(2));
+        }
+        OutputFormat::Json => {
+            let stdout = std::io::stdout();
+            let written = profiler.time("JSON export", || report_json::write_json(&report, &mut stdout.lock()));
+            if let Err(e) = written
+                && e.kind() != std::io::ErrorKind::BrokenPipe
+            {
+                eprintln!("\n{} Failed to write JSON: {}", Marker::Fail.symbol(), e);
+            }
+        }
+        // --count-only and --only-missing have no plist form; the full
+        // report is written either way
+        OutputFormat::Plist => {
+            let stdout = std::io::stdout();
+            let written =
+                profiler.time("plist export", || write_plist(&report, &mut stdout.lock(), OutputEncoding::Utf8));
+            if let Err(e) = written
+                && e.kind() != std::io::ErrorKind::BrokenPipe
+            {
+                eprintln!("\n{} Failed to write plist: {}", Marker::Fail.symbol(), e);
+            }
+        }
+        // Like plist, always the full report
+        OutputFormat::Toml => {
+            let stdout = std::io::stdout();
+            let written = profiler.time("TOML export", || report_toml::write_toml(&report, &mut stdout.lock()));
+            if let Err(e) = written
+                && e.kind() != std::io::ErrorKind::BrokenPipe
+            {
+                eprintln!("\n{} Failed to write TOML: {}", Marker::Fail.symbol(), e);
+            }
+        }
+        // Only warnings go into SARIF, so --count-only and --only-missing
+        // don't change it
+        OutputFormat::Sarif => println!("{}", sarif::report_to_sarif(&report, &missing_files).pretty(2)),
+        // Always the one summary line, whatever else was asked for
+        OutputFormat::SummaryJson => println!("{}", report_json::summary_to_json(&report).dump()),
+        OutputFormat::Ndjson => {
+            let stdout = std::io::stdout();
+            let written = if args.count_only {
+                report_json::write_counts_ndjson(&report_json::driver_counts(&report), &mut stdout.lock())
+            } else {
+                report_json::write_ndjson(&report, &mut stdout.lock())
+            };
+            // A reader like `head` closing the pipe early is not an error
+            if let Err(e) = written
+                && e.kind() != std::io::ErrorKind::BrokenPipe
+            {
+                eprintln!("\n{} Failed to write NDJSON: {}", Marker::Fail.symbol(), e);
             }
         }
     }
-}
 
-fn extract_kernel_modules(line: &str) -> Option<Vec<String>> {
-    // Extract module names from lines like: BOARD_VENDOR_KERNEL_MODULES := module1.ko module2.ko
-    if let Some(pos) = line.find(":=") {
-        let modules_str = line[pos + 2..].trim();
-        let modules: Vec<String> = modules_str
-            .split_whitespace()
-            .filter(|s| !s.is_empty() && s.ends_with(".ko"))
-            .map(|s| s.to_string())
-            .collect();
-        if !modules.is_empty() {
-            return Some(modules);
-        }
+    if args.profile {
+        profiler.print();
     }
-    None
-}
 
-fn extract_makefile_value(line: &str) -> Option<String> {
-    // Extract value from VAR := value or VAR = value
-    if let Some(pos) = line.find(":=").or_else(|| line.find('=')) {
-        let value = line[pos + if line.chars().nth(pos + 1) == Some('=') { 2 } else { 1 }..]
-            .trim()
-            .to_string();
-        if !value.is_empty() {
-            return Some(value);
-        }
+    // Done with the tree; a temporary --git-url clone can go
+    remote::remove_scratch();
+
+    // Not a check failure, so --exit-zero doesn't cover it
+    if let Some(error) = export_errors.pop() {
+        export_errors.iter().for_each(|earlier| print_error(earlier, args.format));
+        fail(error, args.format);
     }
-    None
-}
 
-fn extract_hal_name(line: &str) -> Option<String> {
-    // Extract HAL names like android.hardware.audio@2.0-impl
-    if let Some(start) = line.find("android.hardware.") {
-        let substring = &line[start..];
-        if let Some(end) = substring.find(char::is_whitespace).or_else(|| Some(substring.len())) {
-            return Some(substring[..end].trim().to_string());
-        }
+    if !assertions_passed {
+        fail_check(AnalyzeError::AssertionsFailed, args.format, args.exit_zero);
+    }
+
+    if !missing_files.is_empty() {
+        fail_check(AnalyzeError::MissingFiles(missing_files), args.format, args.exit_zero);
+    }
+
+    if let Some(error) = warning_check(threshold, args.warnings_as_errors, report.warnings.len()) {
+        fail_check(error, args.format, args.exit_zero);
     }
-    None
 }
 
-fn display_drivers_by_category(drivers: &HashMap<String, Vec<String>>) {
-    let mut categories: Vec<_> = drivers.keys().collect();
-    categories.sort();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
 
-    for category in categories {
-        if let Some(driver_list) = drivers.get(category) {
-            println!("\n{}:", category);
-            let mut unique_drivers: Vec<_> = driver_list.iter().collect();
-            unique_drivers.sort();
-            unique_drivers.dedup();
+    // --only-missing (like --no-drivers) only needs the key-file scan
+    #[test]
+    fn only_missing_skips_the_scanners() {
+        let tree = Scratch::new(
+            "only-missing",
+            &[
+                ("BoardConfig.mk", "TARGET_KERNEL_SOURCE := kernel/xiaomi/sm8150\n"),
+                ("device.mk", "PRODUCT_PACKAGES += android.hardware.light@2.0-service\n"),
+                ("arch/arm64/boot/dts/board.dts", "/ { model = \"board\"; };\n"),
+            ],
+        );
+        let report = scan(tree.path(), &AnalysisOptions { scan_drivers: false, ..Default::default() });
 
-            for driver in unique_drivers {
-                println!("  • {}", driver);
-            }
-        }
+        let (files, dirs) = missing_key_entries(&report, &[]);
+        assert!(!files.contains(&"BoardConfig.mk") && !files.contains(&"device.mk"));
+        assert!(files.contains(&"system.prop"));
+        assert_eq!(dirs.len(), KEY_DIRS.len());
+        assert!(!report.drivers_scanned);
+        assert!(report.drivers.is_empty());
+        assert!(report.kernel.source.is_none());
+        assert!(report.dts_sources.files.is_empty());
     }
 
-    println!("\nTotal driver categories: {}", drivers.len());
-}
+    fn scan(tree: &Path, options: &AnalysisOptions) -> HardwareReport {
+        detect_android_device_tree_structure(&tree.to_string_lossy(), options, &mut Profiler::default()).unwrap()
+    }
 
-fn export_to_plist(report: &HardwareReport, plist_path: &str) -> std::io::Result<()> {
-    let mut file = File::create(plist_path)?;
+    #[test]
+    fn ignore_case_finds_a_lowercase_board_config() {
+        let tree = Scratch::new("ignore-case", &[("boardconfig.mk", ""), ("device.mk", ""), ("Overlay/.keep", "")]);
 
-    // Write plist header
-    writeln!(file, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
-    writeln!(file, "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">")?;
-    writeln!(file, "<plist version=\"1.0\">")?;
-    writeln!(file, "<dict>")?;
+        let report = scan(tree.path(), &AnalysisOptions::default());
+        assert_eq!(report.key_files.get("BoardConfig.mk"), Some(&false));
+        assert!(!report.structure_valid);
 
-    // Device Information
-    writeln!(file, "\t<key>DeviceInformation</key>")?;
-    writeln!(file, "\t<dict>")?;
-    for (key, value) in &report.device_info {
-        writeln!(file, "\t\t<key>{}</key>", escape_xml(key))?;
-        writeln!(file, "\t\t<string>{}</string>", escape_xml(value))?;
+        let report = scan(tree.path(), &AnalysisOptions { ignore_case: true, ..Default::default() });
+        assert_eq!(report.key_files.get("BoardConfig.mk"), Some(&true));
+        assert_eq!(report.key_names_on_disk.get("BoardConfig.mk").map(String::as_str), Some("boardconfig.mk"));
+        assert_eq!(report.key_dirs.get("overlay"), Some(&true));
+        assert!(report.structure_valid);
+        // device.mk matched exactly, so there's nothing to note
+        assert!(!report.key_names_on_disk.contains_key("device.mk"));
     }
-    writeln!(file, "\t</dict>")?;
 
-    // Structure Validity
-    writeln!(file, "\t<key>StructureValid</key>")?;
-    writeln!(file, "\t<{} />", if report.structure_valid { "true" } else { "false" })?;
+    const WIDGET: [(&str, &str); 2] = [
+        (
+            "device/acme/widget/device.mk",
+            "PRODUCT_NAME := lineage_widget\n\
+             PRODUCT_DEVICE := widget\n\
+             PRODUCT_BRAND := Acme\n\
+             PRODUCT_MODEL := Widget Pro\n\
+             PRODUCT_MANUFACTURER := Acme\n\
+             PRODUCT_PACKAGES += android.hardware.light@2.0-service android.hardware.vibrator-service\n",
+        ),
+        (
+            "device/acme/widget/BoardConfig.mk",
+            "TARGET_BOARD_PLATFORM := msmnile\nBOARD_HAVE_BLUETOOTH := true\nBOARD_WLAN_DEVICE := qcwcn\n",
+        ),
+    ];
 
-    // Key Files
-    writeln!(file, "\t<key>KeyFiles</key>")?;
-    writeln!(file, "\t<dict>")?;
-    let mut files: Vec<_> = report.key_files.iter().collect();
-    files.sort_by_key(|(k, _)| *k);
-    for (file_name, found) in files {
-        writeln!(file, "\t\t<key>{}</key>", escape_xml(file_name))?;
-        writeln!(file, "\t\t<{} />", if *found { "true" } else { "false" })?;
+    // device_info and drivers are HashMaps, so two scans iterate them in
+    // different orders; the plist mustn't
+    #[test]
+    fn plist_is_identical_across_runs() {
+        let tree = Scratch::new("plist-stable", &WIDGET);
+        let device = tree.path().join("device/acme/widget");
+        let options = AnalysisOptions { scan_drivers: true, ..Default::default() };
+        let plists: Vec<Vec<u8>> = (0..2)
+            .map(|_| {
+                let mut plist = Vec::new();
+                write_plist(&scan(&device, &options), &mut plist, OutputEncoding::Utf8).unwrap();
+                plist
+            })
+            .collect();
+        assert_eq!(plists[0], plists[1]);
+
+        let plist = String::from_utf8(plists[0].clone()).unwrap();
+        let keys = ["<key>device</key>", "<key>product_brand</key>", "<key>product_model</key>", "<key>vendor</key>"];
+        let offsets: Vec<usize> = keys.iter().map(|key| plist.find(key).unwrap()).collect();
+        assert!(offsets.is_sorted());
     }
-    writeln!(file, "\t</dict>")?;
 
-    // Key Directories
-    writeln!(file, "\t<key>KeyDirectories</key>")?;
-    writeln!(file, "\t<dict>")?;
-    let mut dirs: Vec<_> = report.key_dirs.iter().collect();
-    dirs.sort_by_key(|(k, _)| *k);
-    for (dir_name, found) in dirs {
-        writeln!(file, "\t\t<key>{}</key>", escape_xml(dir_name))?;
-        writeln!(file, "\t\t<{} />", if *found { "true" } else { "false" })?;
+    // Exports are made portable by writing "." as tree_path; every other
+    // path must already be relative to the tree
+    #[test]
+    fn exported_paths_have_no_tree_prefix() {
+        let mut files = WIDGET.to_vec();
+        files.extend([
+            ("device/acme/widget/arch/arm64/boot/dts/widget.dts", "/ { model = \"widget\"; };\n"),
+            ("device/acme/widget/rootdir/etc/init.widget.rc", "on boot\n    mkdir /data/vendor/widget 0770\n"),
+            ("device/acme/widget/configs/media_profiles_V1_0.xml", "<MediaSettings/>\n"),
+        ]);
+        let tree = Scratch::new("relative-paths", &files);
+        let device = tree.path().join("device/acme/widget");
+        let mut report = scan(&device, &AnalysisOptions { scan_drivers: true, ..Default::default() });
+        report.tree_path = ".".to_string();
+
+        assert_eq!(report.dts_sources.files, ["arch/arm64/boot/dts/widget.dts"]);
+        assert_eq!(report.filesystem_setup.rc_files, ["rootdir/etc/init.widget.rc"]);
+        assert_eq!(report.media_profiles.files, ["configs/media_profiles_V1_0.xml"]);
+        let json = report_json::report_to_json(&report).dump();
+        assert!(!json.contains(&*tree.path().to_string_lossy()), "{}", json);
     }
-    writeln!(file, "\t</dict>")?;
 
-    // Device Drivers
-    writeln!(file, "\t<key>DeviceDrivers</key>")?;
-    writeln!(file, "\t<dict>")?;
-    let mut categories: Vec<_> = report.drivers.keys().collect();
-    categories.sort();
-    for category in categories {
-        if let Some(driver_list) = report.drivers.get(category) {
-            writeln!(file, "\t\t<key>{}</key>", escape_xml(category))?;
-            writeln!(file, "\t\t<array>")?;
-            let mut unique_drivers: Vec<_> = driver_list.iter().collect();
-            unique_drivers.sort();
-            unique_drivers.dedup();
-            for driver in unique_drivers {
-                writeln!(file, "\t\t\t<string>{}</string>", escape_xml(driver))?;
+    // --format plist writes to any Write, not just the --export-plist file
+    #[test]
+    fn plist_writes_into_a_buffer() {
+        let tree = Scratch::new("plist-buffer", &WIDGET);
+        let report = scan(&tree.path().join("device/acme/widget"), &AnalysisOptions::default());
+        let mut plist: Vec<u8> = Vec::new();
+        write_plist(&report, &mut plist, OutputEncoding::Utf8).unwrap();
+
+        let mut elements = Vec::new();
+        for event in xml::reader::EventReader::new(plist.as_slice()) {
+            if let xml::reader::XmlEvent::StartElement { name, .. } = event.unwrap() {
+                elements.push(name.local_name);
             }
-            writeln!(file, "\t\t</array>")?;
         }
-    }
-    writeln!(file, "\t</dict>")?;
-
-    // Close plist
-    writeln!(file, "</dict>")?;
-    writeln!(file, "</plist>")?;
+        assert_eq!(elements[..2], ["plist", "dict"]);
 
-    Ok(())
-}
+        // The same bytes the file export writes
+        let file = tree.path().join("report.plist");
+        export_to_plist(&report, &file.to_string_lossy(), OutputEncoding::Utf8).unwrap();
+        assert_eq!(fs::read(file).unwrap(), plist);
+    }
 
-fn escape_xml(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&apos;")
-}
+    // A "\\" continuation block contributes every value, not only the one on
+    // the assignment's own line
+    #[test]
+    fn continuation_lines_reach_the_driver_categories() {
+        let tree = Scratch::new(
+            "continuations",
+            &[
+                (
+                    "BoardConfig.mk",
+                    "BOARD_VENDOR_KERNEL_MODULES := \\\n    wlan.ko \\\n\ttouch.ko\n\
+                     TARGET_KERNEL_CONFIG := \\\n    vendor/widget_defconfig \\\n    vendor/debug.config\n",
+                ),
+                (
+                    "device.mk",
+                    "PRODUCT_PACKAGES += \\\n    audio.primary.msmnile \\\n    camera.device@3.2-impl\n",
+                ),
+            ],
+        );
+        let report = scan(tree.path(), &AnalysisOptions { scan_drivers: true, ..Default::default() });
 
-fn extract_device_info(path: &Path, found_files: &HashMap<String, PathBuf>) -> Option<HashMap<String, String>> {
-    let mut info = HashMap::new();
+        assert_eq!(report.drivers[category::KERNEL_MODULES], ["wlan.ko", "touch.ko"]);
+        assert_eq!(report.kernel.defconfigs, ["vendor/widget_defconfig", "vendor/debug.config"]);
+        assert_eq!(report.drivers[category::AUDIO], ["audio.primary.msmnile"]);
+        assert_eq!(report.drivers[category::CAMERA], ["camera.device@3.2-impl"]);
+    }
 
-    // Try to extract from path (common format: vendor/manufacturer/device)
-    let path_str = path.to_string_lossy();
-    let parts: Vec<&str> = path_str.split('/').collect();
+    // HAL normalization sees every package of a multi-line PRODUCT_PACKAGES
+    // block, grouped into families
+    #[test]
+    fn multi_line_product_packages_are_normalized() {
+        let tree = Scratch::new(
+            "hal-block",
+            &[(
+                "device.mk",
+                "PRODUCT_PACKAGES += \\\n\
+                     android.hardware.audio@6.0-impl \\\n\
+                     android.hardware.audio.effect@6.0-impl \\\n\
+                     android.hardware.camera.provider@2.4-impl \\\n\
+                     android.hardware.camera.provider@2.4-service_64 \\\n\
+                     android.hardware.light@2.0-impl\n",
+            )],
+        );
+        let report = scan(tree.path(), &AnalysisOptions { scan_drivers: true, ..Default::default() });
 
-    if parts.len() >= 2 {
-        info.insert("vendor".to_string(), parts[parts.len() - 2].to_string());
-        info.insert("device".to_string(), parts[parts.len() - 1].to_string());
+        let families: Vec<(&str, usize)> =
+            report.hals.iter().map(|hal| (hal.family.as_str(), hal.packages.len())).collect();
+        assert_eq!(families, [("audio", 1), ("audio.effect", 1), ("camera.provider", 2), ("light", 1)]);
+        assert!(report.hals.iter().all(|hal| hal.transports() == [hal::Transport::Hidl]));
+        assert_eq!(report.drivers[category::HAL].len(), 4);
     }
 
-    // Try to parse from AndroidProducts.mk or device.mk
-    if let Some(android_products) = found_files.get("AndroidProducts.mk") {
-        if let Ok(content) = fs::read_to_string(android_products) {
-            for line in content.lines() {
-                if line.contains("PRODUCT_NAME") {
-                    info.insert("product_name".to_string(),
-                        line.split('=').nth(1).unwrap_or("").trim().to_string());
-                }
-            }
-        }
-    }
+    // "-t ." names the working directory, whose last two components are
+    // vendor/device; --root-relative-to resolves relative roots the same
+    // way. Tests run from the crate directory, so no chdir is needed.
+    #[test]
+    fn dot_as_the_tree_uses_the_working_directory() {
+        let cwd = std::env::current_dir().unwrap().canonicalize().unwrap();
+        let components: Vec<String> = cwd.iter().map(|part| part.to_string_lossy().to_string()).collect();
+        let [.., grandparent, parent, name] = components.as_slice() else {
+            panic!("{} is too shallow", cwd.display());
+        };
 
-    if info.is_empty() {
-        None
-    } else {
-        Some(info)
-    }
-}
+        let info = extract_device_info(Path::new("."), None, &HashMap::new(), &mut Vec::new()).unwrap();
+        assert_eq!(info.get("vendor"), Some(parent));
+        assert_eq!(info.get("device"), Some(name));
 
-fn main() {
-    let args = Args::parse();
-    detect_android_device_tree_structure(&args.tree, args.export_plist);
+        let root = Path::new("../../..");
+        let info = extract_device_info(Path::new("."), Some(root), &HashMap::new(), &mut Vec::new()).unwrap();
+        assert_eq!(info.get("vendor"), Some(grandparent));
+        assert_eq!(info.get("device"), Some(parent));
+    }
 }
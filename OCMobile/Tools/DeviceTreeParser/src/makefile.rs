@@ -0,0 +1,173 @@
+// Makefile statement and assignment parsing shared by the BoardConfig.mk and
+// device.mk scans
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AssignOp {
+    // =
+    Recursive,
+    // := and ::=
+    Simple,
+    // +=
+    Append,
+    // ?=
+    Conditional,
+}
+
+#[derive(Debug)]
+pub struct Assignment<'a> {
+    pub name: &'a str,
+    pub op: AssignOp,
+    pub value: &'a str,
+}
+
+// Logical lines with their starting line number, "\" continuations joined
+// and comments dropped
+pub fn statements(content: &str) -> Vec<(usize, String)> {
+    let mut statements = Vec::new();
+    let mut pending = String::new();
+    let mut start_line = 0;
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        if pending.is_empty() {
+            start_line = index + 1;
+        }
+        match line.trim_end().strip_suffix('\\') {
+            Some(continued) => {
+                pending.push_str(continued);
+                pending.push(' ');
+            }
+            None => {
+                pending.push_str(line);
+                let statement = std::mem::take(&mut pending);
+                if !statement.trim().is_empty() {
+                    statements.push((start_line, statement.trim().to_string()));
+                }
+            }
+        }
+    }
+
+    statements
+}
+
+// "NAME OP VALUE" with any mix of spaces and tabs (or none) around the
+// operator, optionally behind export/override. Rules, conditionals and
+// $(call ...) lines aren't assignments and give None.
+pub fn parse_assignment(statement: &str) -> Option<Assignment<'_>> {
+    let mut rest = statement.trim_start();
+    for keyword in ["export", "override"] {
+        if let Some(after) = rest.strip_prefix(keyword)
+            && after.starts_with([' ', '\t'])
+        {
+            rest = after.trim_start();
+        }
+    }
+
+    let name_end = rest.find(|c: char| c.is_whitespace() || matches!(c, ':' | '+' | '?' | '='))?;
+    let name = &rest[..name_end];
+    if name.is_empty() {
+        return None;
+    }
+
+    let after = rest[name_end..].trim_start();
+    let (op, value) = if let Some(value) = after.strip_prefix("::=") {
+        (AssignOp::Simple, value)
+    } else if let Some(value) = after.strip_prefix(":=") {
+        (AssignOp::Simple, value)
+    } else if let Some(value) = after.strip_prefix("+=") {
+        (AssignOp::Append, value)
+    } else if let Some(value) = after.strip_prefix("?=") {
+        (AssignOp::Conditional, value)
+    } else if let Some(value) = after.strip_prefix('=') {
+        (AssignOp::Recursive, value)
+    } else {
+        return None;
+    };
+
+    Some(Assignment { name, op, value: value.trim() })
+}
+
+// "VAR += value" -> (VAR, value), for when the operator doesn't matter
+pub fn split_assignment(statement: &str) -> Option<(&str, &str)> {
+    parse_assignment(statement).map(|assignment| (assignment.name, assignment.value))
+}
+
+// Substitutes the $(VAR) and ${VAR} references `lookup` knows; anything
+// else (unknown variables, $(call ...), a lone $) is left as written
+pub fn expand(value: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let close = match after.chars().next() {
+            Some('(') => ')',
+            Some('{') => '}',
+            _ => {
+                expanded.push('$');
+                rest = after;
+                continue;
+            }
+        };
+        let Some(end) = after.find(close) else {
+            expanded.push_str(&rest[start..]);
+            return expanded;
+        };
+
+        match lookup(&after[1..end]) {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &after[end + 1..];
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(statement: &str) -> Option<(&str, AssignOp, &str)> {
+        parse_assignment(statement).map(|assignment| (assignment.name, assignment.op, assignment.value))
+    }
+
+    #[test]
+    fn tabs_and_extra_spaces_around_the_operator() {
+        let expected = Some(("BOARD_WLAN_DEVICE", AssignOp::Simple, "qcwcn"));
+        assert_eq!(parsed("BOARD_WLAN_DEVICE := qcwcn"), expected);
+        assert_eq!(parsed("BOARD_WLAN_DEVICE\t:=\tqcwcn"), expected);
+        assert_eq!(parsed("  BOARD_WLAN_DEVICE   \t :=    qcwcn \t"), expected);
+        assert_eq!(parsed("BOARD_WLAN_DEVICE:=qcwcn"), expected);
+        assert_eq!(parsed("export\tBOARD_WLAN_DEVICE  :=  qcwcn"), expected);
+    }
+
+    #[test]
+    fn every_operator() {
+        assert_eq!(parsed("A = 1"), Some(("A", AssignOp::Recursive, "1")));
+        assert_eq!(parsed("A ::= 1"), Some(("A", AssignOp::Simple, "1")));
+        assert_eq!(parsed("A\t+=\t1"), Some(("A", AssignOp::Append, "1")));
+        assert_eq!(parsed("A ?= 1"), Some(("A", AssignOp::Conditional, "1")));
+        assert_eq!(parsed("override A := "), Some(("A", AssignOp::Simple, "")));
+    }
+
+    #[test]
+    fn non_assignments() {
+        assert_eq!(parsed("$(call inherit-product, vendor/acme/widget.mk)"), None);
+        assert_eq!(parsed("ifeq ($(TARGET_DEVICE),widget)"), None);
+        assert_eq!(parsed("include $(CLEAR_VARS)"), None);
+    }
+
+    #[test]
+    fn statements_join_continuations_and_drop_comments() {
+        let content = "# header\nPRODUCT_PACKAGES += \\\n\tfoo \\\n    bar # trailing\n\nA := 1\n";
+        let statements = statements(content);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0].0, 2);
+        let packages = parsed(&statements[0].1).map(|(_, _, value)| value.split_whitespace().collect::<Vec<_>>());
+        assert_eq!(packages, Some(vec!["foo", "bar"]));
+        assert_eq!(statements[1], (6, "A := 1".to_string()));
+    }
+}
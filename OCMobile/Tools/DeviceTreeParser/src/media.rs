@@ -0,0 +1,169 @@
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use xml::attribute::OwnedAttribute;
+use xml::reader::{EventReader, XmlEvent};
+use crate::fsread;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Camcorder profiles from media_profiles*.xml, i.e. what each camera can
+// record and with which codecs
+#[derive(Debug, Default)]
+pub struct MediaProfiles {
+    pub files: Vec<String>,
+    pub profiles: Vec<CamcorderProfile>,
+}
+
+#[derive(Debug, Default)]
+pub struct CamcorderProfile {
+    // <CamcorderProfiles cameraId="N">; absent in old single-camera files
+    pub camera_id: Option<String>,
+    pub quality: String,
+    pub file_format: Option<String>,
+    pub video: Option<VideoEncoding>,
+    pub audio: Option<AudioEncoding>,
+    pub file: String,
+}
+
+#[derive(Debug, Default)]
+pub struct VideoEncoding {
+    pub codec: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<u32>,
+    pub bit_rate: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct AudioEncoding {
+    pub codec: String,
+    pub bit_rate: Option<u64>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+}
+
+impl MediaProfiles {
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+}
+
+impl VideoEncoding {
+    // "h264 1920x1080@30fps"
+    pub fn summary(&self) -> String {
+        let mut summary = self.codec.clone();
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            summary.push_str(&format!(" {}x{}", width, height));
+        }
+        if let Some(frame_rate) = self.frame_rate {
+            summary.push_str(&format!("@{}fps", frame_rate));
+        }
+        summary
+    }
+}
+
+impl AudioEncoding {
+    // "aac 48000Hz 2ch"
+    pub fn summary(&self) -> String {
+        let mut summary = self.codec.clone();
+        if let Some(sample_rate) = self.sample_rate {
+            summary.push_str(&format!(" {}Hz", sample_rate));
+        }
+        if let Some(channels) = self.channels {
+            summary.push_str(&format!(" {}ch", channels));
+        }
+        summary
+    }
+}
+
+pub fn scan_media_profiles(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> MediaProfiles {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| name.starts_with("media_profiles") && name.ends_with(".xml"),
+        &mut files,
+    );
+    files.sort();
+
+    let mut media = MediaProfiles::default();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        match parse_media_profiles(&file, &relative, &mut media.profiles) {
+            Ok(()) => media.files.push(relative),
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+        }
+    }
+
+    media
+}
+
+fn parse_media_profiles(path: &Path, relative: &str, profiles: &mut Vec<CamcorderProfile>) -> io::Result<()> {
+    // <CamcorderProfiles cameraId="0">
+    //   <EncoderProfile quality="1080p" fileFormat="mp4" duration="30">
+    //     <Video codec="h264" bitRate="17000000" width="1920" height="1080" frameRate="30" />
+    //     <Audio codec="aac" bitRate="96000" sampleRate="48000" channels="1" />
+    //   </EncoderProfile>
+    // </CamcorderProfiles>
+    let file = fsread::open(path)?;
+
+    let mut camera_id: Option<String> = None;
+    let mut current: Option<CamcorderProfile> = None;
+
+    for event in EventReader::new(BufReader::new(file)) {
+        match event {
+            Ok(XmlEvent::StartElement { name, attributes, .. }) => match name.local_name.as_str() {
+                "CamcorderProfiles" => camera_id = attribute(&attributes, "cameraId").map(|s| s.to_string()),
+                "EncoderProfile" => {
+                    current = Some(CamcorderProfile {
+                        camera_id: camera_id.clone(),
+                        quality: attribute(&attributes, "quality").unwrap_or("unknown").to_string(),
+                        file_format: attribute(&attributes, "fileFormat").map(|s| s.to_string()),
+                        file: relative.to_string(),
+                        ..Default::default()
+                    })
+                }
+                "Video" => {
+                    if let Some(profile) = current.as_mut() {
+                        profile.video = Some(VideoEncoding {
+                            codec: attribute(&attributes, "codec").unwrap_or("unknown").to_string(),
+                            width: number(&attributes, "width"),
+                            height: number(&attributes, "height"),
+                            frame_rate: number(&attributes, "frameRate"),
+                            bit_rate: number(&attributes, "bitRate"),
+                        });
+                    }
+                }
+                "Audio" => {
+                    if let Some(profile) = current.as_mut() {
+                        profile.audio = Some(AudioEncoding {
+                            codec: attribute(&attributes, "codec").unwrap_or("unknown").to_string(),
+                            bit_rate: number(&attributes, "bitRate"),
+                            sample_rate: number(&attributes, "sampleRate"),
+                            channels: number(&attributes, "channels"),
+                        });
+                    }
+                }
+                _ => {}
+            },
+            Ok(XmlEvent::EndElement { name }) => match name.local_name.as_str() {
+                "EncoderProfile" => profiles.extend(current.take()),
+                "CamcorderProfiles" => camera_id = None,
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    Ok(())
+}
+
+fn attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attributes.iter().find(|a| a.name.local_name == name).map(|a| a.value.as_str())
+}
+
+fn number<T: std::str::FromStr>(attributes: &[OwnedAttribute], name: &str) -> Option<T> {
+    attribute(attributes, name)?.trim().parse().ok()
+}
@@ -0,0 +1,697 @@
+// --merge: folds the report of a common/base tree into the --tree report,
+// giving the effective view of a device tree that inherits from the base.
+// The device (overlay) tree wins every conflict:
+//
+// - Key files/dirs count as present if either tree has them, and the
+//   structure check is redone on the result.
+// - Device info keys, Wi-Fi fields and vendor flags take the overlay's
+//   value; the base only fills in what the overlay lacks. A base vendor
+//   flag the overlay sets again is listed among that flag's overrides.
+// - Driver categories are the union of both trees' entries, except the
+//   ones holding single BoardConfig values (Wi-Fi chipset, Bluetooth,
+//   platform), where the overlay's value replaces the base's. HAL families
+//   the overlay has replace the base's packages for that family.
+// - Every other section is merged field by field (see Merge): single
+//   values and map keys the overlay lacks come from the base, package lists
+//   are unioned, and other lists are the overlay's unless it has none, as
+//   their entries (DTS nodes, panels, sensors, ...) reference each other.
+// - Warnings from both trees are kept, base file paths prefixed with the
+//   base tree.
+//
+// What came from the base is recorded in MergeInfo for provenance.
+
+use std::collections::{btree_map, hash_map, BTreeMap, HashMap};
+use std::path::Path;
+use crate::apex::ApexInfo;
+use crate::battery::BatteryInfo;
+use crate::biometrics::Biometrics;
+use crate::boot::BootInfo;
+use crate::camera::CameraInfo;
+use crate::carrier::CarrierConfig;
+use crate::copy_files::CopiedFiles;
+use crate::cpu_topology::CpuTopology;
+use crate::drm::DrmInfo;
+use crate::dtbo::DtbOverlays;
+use crate::dts::DtsSources;
+use crate::encryption::EncryptionInfo;
+use crate::features::DeclaredFeatures;
+use crate::gpu::GpuInfo;
+use crate::gsi::GsiInfo;
+use crate::hal::Hal;
+use crate::haptics::Haptics;
+use crate::init_rc::{BootSequence, FilesystemSetup};
+use crate::kernel::KernelInfo;
+use crate::keylayout::KeyLayouts;
+use crate::leds::LedInfo;
+use crate::linker::LinkerNamespaces;
+use crate::media::MediaProfiles;
+use crate::mixer::MixerPaths;
+use crate::modem::ModemInfo;
+use crate::module_consistency::ModuleConsistency;
+use crate::nfc::NfcInfo;
+use crate::partitions::PartitionSizes;
+use crate::perf::PerfTuning;
+use crate::power::PowerConfig;
+use crate::regulator::Regulators;
+use crate::tee::TeeInfo;
+use crate::telephony::TelephonyInfo;
+use crate::treble::TrebleInfo;
+use crate::usb::UsbInfo;
+use crate::vendor_flags::VendorFlag;
+use crate::warning::{Warning, WarningKind};
+use crate::HardwareReport;
+use crate::category;
+
+#[derive(Debug, Default)]
+pub struct MergeInfo {
+    pub base_path: String,
+    // Sections the overlay had nothing for, taken from the base
+    pub inherited_sections: Vec<String>,
+    // Driver category -> entries only the base has
+    pub inherited_entries: BTreeMap<String, Vec<String>>,
+    // "device_info.codename", "wifi.chipset", "vendor_flags.<prop>", ...
+    pub inherited_values: Vec<String>,
+}
+
+impl MergeInfo {
+    pub fn inherited_entry_count(&self) -> usize {
+        self.inherited_entries.values().map(Vec::len).sum()
+    }
+}
+
+pub fn merge(mut overlay: HardwareReport, base: HardwareReport) -> HardwareReport {
+    let mut info = MergeInfo { base_path: base.tree_path.clone(), ..Default::default() };
+
+    for (name, found) in base.key_files {
+        *overlay.key_files.entry(name).or_insert(false) |= found;
+    }
+    for (name, found) in base.key_dirs {
+        *overlay.key_dirs.entry(name).or_insert(false) |= found;
+    }
+    for (name, on_disk) in base.key_names_on_disk {
+        overlay.key_names_on_disk.entry(name).or_insert(on_disk);
+    }
+
+    for (key, value) in base.device_info {
+        if let hash_map::Entry::Vacant(entry) = overlay.device_info.entry(key) {
+            info.inherited_values.push(format!("device_info.{}", entry.key()));
+            entry.insert(value);
+        }
+    }
+
+    merge_drivers(&mut overlay, base.drivers, base.hals, &mut info);
+    overlay.drivers_scanned |= base.drivers_scanned;
+    overlay.attempted_categories.extend(base.attempted_categories);
+    overlay.truncated_categories.extend(base.truncated_categories);
+    overlay.max_entries = overlay.max_entries.or(base.max_entries);
+
+    let wifi = &mut overlay.wifi;
+    for (field, value, base_value) in [
+        ("chipset", &mut wifi.chipset, base.wifi.chipset),
+        ("supplicant_version", &mut wifi.supplicant_version, base.wifi.supplicant_version),
+        ("hostapd_driver", &mut wifi.hostapd_driver, base.wifi.hostapd_driver),
+        ("driver_module", &mut wifi.driver_module, base.wifi.driver_module),
+    ] {
+        if value.is_none() && base_value.is_some() {
+            info.inherited_values.push(format!("wifi.{}", field));
+            *value = base_value;
+        }
+    }
+    for (name, value) in base.wifi.driver_vars {
+        if let btree_map::Entry::Vacant(entry) = wifi.driver_vars.entry(name) {
+            info.inherited_values.push(format!("wifi.{}", entry.key()));
+            entry.insert(value);
+        }
+    }
+    if wifi.configs.is_empty() && !base.wifi.configs.is_empty() {
+        info.inherited_values.push("wifi.config_files".to_string());
+        let base_path = Path::new(&info.base_path);
+        for mut config in base.wifi.configs.files {
+            config.file = base_path.join(&config.file).to_string_lossy().to_string();
+            wifi.configs.files.push(config);
+        }
+    }
+
+    let base_path = Path::new(&info.base_path).to_path_buf();
+    for (name, flag) in base.vendor_flags.flags {
+        let location = base_path.join(&flag.location).to_string_lossy().to_string();
+        match overlay.vendor_flags.flags.get_mut(&name) {
+            Some(existing) => existing.overridden.insert(0, format!("{} ({})", flag.value, location)),
+            None => {
+                info.inherited_values.push(format!("vendor_flags.{}", name));
+                overlay.vendor_flags.flags.insert(name, VendorFlag { location, ..flag });
+            }
+        }
+    }
+    for file in base.vendor_flags.files {
+        overlay.vendor_flags.files.push(base_path.join(file).to_string_lossy().to_string());
+    }
+
+    section(&mut info, "Boot", "boot", &mut overlay.boot, base.boot, |s| s.is_empty());
+    let partition_sizes = &mut overlay.partition_sizes;
+    section(&mut info, "Partition Sizes", "partition_sizes", partition_sizes, base.partition_sizes, |s| s.is_empty());
+    section(&mut info, "Encryption", "encryption", &mut overlay.encryption, base.encryption, |s| s.is_empty());
+    section(&mut info, "Kernel", "kernel", &mut overlay.kernel, base.kernel, |s| {
+        s.source.is_none() && s.defconfigs.is_empty() && s.prebuilt_image.is_none()
+    });
+    let modules = &mut overlay.module_consistency;
+    section(&mut info, "Module Consistency", "module_consistency", modules, base.module_consistency, |s| s.is_empty());
+    section(&mut info, "Modem/Baseband", "modem", &mut overlay.modem, base.modem, |s| s.is_empty());
+    section(&mut info, "Treble/VNDK", "treble", &mut overlay.treble, base.treble, |s| s.is_empty());
+    section(&mut info, "GSI Compatibility", "gsi", &mut overlay.gsi, base.gsi, |s| s.is_empty());
+    section(&mut info, "APEX Modules", "apex", &mut overlay.apex, base.apex, |s| s.is_empty());
+    let namespaces = &mut overlay.linker_namespaces;
+    section(&mut info, "Linker Namespaces", "linker_namespaces", namespaces, base.linker_namespaces, |s| s.is_empty());
+    let dts = &mut overlay.dts_sources;
+    section(&mut info, "Device Tree Sources", "dts_sources", dts, base.dts_sources, |s| s.files.is_empty());
+    section(&mut info, "DTB Overlays", "dtb_overlays", &mut overlay.dtb_overlays, base.dtb_overlays, |s| s.is_empty());
+    section(&mut info, "Display/Panel", "panels", &mut overlay.panels, base.panels, |s| s.is_empty());
+    section(&mut info, "Touchscreen", "touchscreens", &mut overlay.touchscreens, base.touchscreens, |s| s.is_empty());
+    section(&mut info, "Camera", "camera", &mut overlay.camera, base.camera, |s| s.is_empty());
+    section(&mut info, "Haptics/Vibrator", "haptics", &mut overlay.haptics, base.haptics, |s| s.is_empty());
+    section(&mut info, "Biometrics", "biometrics", &mut overlay.biometrics, base.biometrics, |s| s.is_empty());
+    section(&mut info, "DRM", "drm", &mut overlay.drm, base.drm, |s| s.is_empty());
+    section(&mut info, "TEE/Keymaster", "tee", &mut overlay.tee, base.tee, |s| s.is_empty());
+    section(&mut info, "Graphics/GPU", "gpu", &mut overlay.gpu, base.gpu, |s| s.is_empty());
+    section(&mut info, "LEDs/Backlight", "leds", &mut overlay.leds, base.leds, |s| s.is_empty());
+    section(&mut info, "NFC/SE", "nfc", &mut overlay.nfc, base.nfc, |s| s.is_empty());
+    section(&mut info, "USB", "usb", &mut overlay.usb, base.usb, |s| s.is_empty());
+    section(&mut info, "Regulators", "regulators", &mut overlay.regulators, base.regulators, |s| s.is_empty());
+    section(&mut info, "Power Tree", "power_tree", &mut overlay.power_tree, base.power_tree, |s| s.is_empty());
+    section(&mut info, "Battery/Charger", "battery", &mut overlay.battery, base.battery, |s| s.is_empty());
+    let copied = &mut overlay.copied_files;
+    section(&mut info, "Copied Files", "copied_files", copied, base.copied_files, |s| s.is_empty());
+    let features = &mut overlay.declared_features;
+    section(&mut info, "Declared Features", "declared_features", features, base.declared_features, |s| s.is_empty());
+    let profiles = &mut overlay.media_profiles;
+    section(&mut info, "Media Profiles", "media_profiles", profiles, base.media_profiles, |s| s.is_empty());
+    let mixer = &mut overlay.mixer_paths;
+    section(&mut info, "Audio Mixer Paths", "mixer_paths", mixer, base.mixer_paths, |s| s.is_empty());
+    let layouts = &mut overlay.key_layouts;
+    section(&mut info, "Input Key Layouts", "key_layouts", layouts, base.key_layouts, |s| s.is_empty());
+    let carrier = &mut overlay.carrier_config;
+    section(&mut info, "Carrier Config", "carrier_config", carrier, base.carrier_config, |s| s.is_empty());
+    section(&mut info, "Telephony", "telephony", &mut overlay.telephony, base.telephony, |s| s.is_empty());
+    section(&mut info, "Power", "power", &mut overlay.power, base.power, |s| s.is_empty());
+    let perf = &mut overlay.perf_tuning;
+    section(&mut info, "Performance Tuning", "perf_tuning", perf, base.perf_tuning, |s| s.is_empty());
+    let topology = &mut overlay.cpu_topology;
+    section(&mut info, "CPU Topology", "cpu_topology", topology, base.cpu_topology, |s| s.is_empty());
+    let filesystem = &mut overlay.filesystem_setup;
+    section(&mut info, "Filesystem Setup", "filesystem_setup", filesystem, base.filesystem_setup, |s| s.is_empty());
+    let sequence = &mut overlay.boot_sequence;
+    section(&mut info, "Boot Sequence", "boot_sequence", sequence, base.boot_sequence, |s| s.is_empty());
+    for (file, hash) in base.file_hashes {
+        overlay.file_hashes.entry(base_path.join(file).to_string_lossy().to_string()).or_insert(hash);
+    }
+
+    // Required files one tree lacks may come from the other, so the
+    // structure check is redone on the merged key files
+    let mut warnings: Vec<Warning> = overlay.warnings.drain(..).collect();
+    for mut warning in base.warnings {
+        warning.file = base_path.join(&warning.file).to_string_lossy().to_string();
+        warnings.push(warning);
+    }
+    warnings.retain(|warning| warning.kind != WarningKind::MissingRequiredFile);
+    let found = |name: &str| overlay.key_files.get(name).copied().unwrap_or(false);
+    let has_makefile = found("AndroidProducts.mk") || found("device.mk");
+    let has_board_config = found("BoardConfig.mk");
+    if !has_makefile {
+        warnings.push(Warning::new(
+            WarningKind::MissingRequiredFile,
+            "AndroidProducts.mk",
+            "neither AndroidProducts.mk nor device.mk found in either tree",
+        ));
+    }
+    if !has_board_config {
+        warnings.push(Warning::new(
+            WarningKind::MissingRequiredFile,
+            "BoardConfig.mk",
+            "BoardConfig.mk not found in either tree",
+        ));
+    }
+    overlay.structure_valid = has_makefile && has_board_config;
+    overlay.warnings = warnings;
+
+    overlay.merge = Some(info);
+    overlay
+}
+
+fn merge_drivers(
+    overlay: &mut HardwareReport,
+    base_drivers: HashMap<String, Vec<String>>,
+    base_hals: Vec<Hal>,
+    info: &mut MergeInfo,
+) {
+    // HAL entries are per-family summaries, so families are merged rather
+    // than the summary strings
+    let mut inherited_hals = Vec::new();
+    for base_hal in base_hals {
+        if !overlay.hals.iter().any(|hal| hal.family == base_hal.family) {
+            inherited_hals.push(base_hal.summary());
+            overlay.hals.push(base_hal);
+        }
+    }
+    if !inherited_hals.is_empty() {
+        overlay.drivers.insert(category::HAL.to_string(), overlay.hals.iter().map(Hal::summary).collect());
+        info.inherited_entries.insert(category::HAL.to_string(), inherited_hals);
+    }
+
+    for (category, entries) in base_drivers {
+        if category == category::HAL {
+            continue;
+        }
+        let existing = overlay.drivers.entry(category.clone()).or_default();
+        let mut inherited = Vec::new();
+        for entry in entries {
+            // A BoardConfig value the overlay assigns again is replaced,
+            // not added to
+            let variable = scalar_variable(&category, &entry);
+            let overridden = variable.is_some() && existing.iter().any(|e| scalar_variable(&category, e) == variable);
+            if !overridden && !existing.contains(&entry) {
+                existing.push(entry.clone());
+                inherited.push(entry);
+            }
+        }
+        if !inherited.is_empty() {
+            inherited.sort();
+            inherited.dedup();
+            info.inherited_entries.insert(category, inherited);
+        }
+    }
+}
+
+
+// The BoardConfig variable a driver entry holds, for the categories that
+// list single values rather than collecting them (see parse_board_config).
+// Entries don't record their variable, so it's told from the value's shape.
+fn scalar_variable(category: &str, entry: &str) -> Option<&'static str> {
+    match category {
+        category::WIFI if entry.ends_with(".ko") => Some("WIFI_DRIVER_MODULE_PATH"),
+        category::WIFI => Some("BOARD_WLAN_DEVICE"),
+        category::BLUETOOTH if entry == "true" || entry == "false" => Some("BOARD_HAVE_BLUETOOTH"),
+        category::BLUETOOTH => Some("BOARD_BLUETOOTH_BDROID_BUILDCFG"),
+        category::GPU if ["adreno", "mali", "powervr"].iter().any(|gpu| entry.to_lowercase().contains(gpu)) => {
+            Some("TARGET_BOARD_PLATFORM_GPU")
+        }
+        category::GPU => Some("TARGET_BOARD_PLATFORM"),
+        _ => None,
+    }
+}
+
+// Merges one section: the base's whole when the overlay found nothing,
+// otherwise field by field
+fn section<T: Merge>(
+    info: &mut MergeInfo,
+    name: &str,
+    key: &str,
+    overlay: &mut T,
+    base: T,
+    is_empty: impl Fn(&T) -> bool,
+) {
+    if is_empty(&base) {
+        return;
+    }
+    if is_empty(overlay) {
+        *overlay = base;
+        info.inherited_sections.push(name.to_string());
+        return;
+    }
+    let mut fields = Fields::default();
+    overlay.merge(base, &mut fields);
+    info.inherited_values.extend(fields.inherited.into_iter().map(|field| format!("{}.{}", key, field)));
+}
+
+// A section that can take the fields it lacks from the base tree's
+trait Merge {
+    fn merge(&mut self, base: Self, fields: &mut Fields);
+}
+
+// The fields of one section the base filled in, e.g. "source" or
+// "vars.BOARD_KERNEL_CMDLINE"
+#[derive(Default)]
+struct Fields {
+    inherited: Vec<String>,
+}
+
+impl Fields {
+    // The overlay's value if it set one, otherwise the base's
+    fn value<T>(&mut self, name: &str, overlay: &mut Option<T>, base: Option<T>) {
+        if overlay.is_none() && base.is_some() {
+            *overlay = base;
+            self.inherited.push(name.to_string());
+        }
+    }
+
+    // The overlay's list if it has one, otherwise the base's; entries from
+    // the two trees aren't mixed
+    fn list<T>(&mut self, name: &str, overlay: &mut Vec<T>, base: Vec<T>) {
+        if overlay.is_empty() && !base.is_empty() {
+            *overlay = base;
+            self.inherited.push(name.to_string());
+        }
+    }
+
+    // PRODUCT_PACKAGES from both trees end up in the build, so package
+    // lists are unioned
+    fn packages(&mut self, name: &str, overlay: &mut Vec<String>, base: Vec<String>) {
+        let before = overlay.len();
+        for package in base {
+            if !overlay.contains(&package) {
+                overlay.push(package);
+            }
+        }
+        if overlay.len() > before {
+            self.inherited.push(name.to_string());
+        }
+    }
+
+    // Keys the overlay lacks come from the base
+    fn map<V>(&mut self, name: &str, overlay: &mut BTreeMap<String, V>, base: BTreeMap<String, V>) {
+        for (key, value) in base {
+            if let btree_map::Entry::Vacant(entry) = overlay.entry(key) {
+                self.inherited.push(format!("{}.{}", name, entry.key()));
+                entry.insert(value);
+            }
+        }
+    }
+
+    // A list of named entries (partitions, flags, ...), merged like a map
+    fn keyed<T>(&mut self, name: &str, overlay: &mut Vec<T>, base: Vec<T>, key: impl Fn(&T) -> &str) {
+        for entry in base {
+            if !overlay.iter().any(|existing| key(existing) == key(&entry)) {
+                self.inherited.push(format!("{}.{}", name, key(&entry)));
+                overlay.push(entry);
+            }
+        }
+    }
+}
+
+// Panels, touchscreens and power consumers reference DTS nodes and each
+// other, so a list section is the overlay's whole
+impl<T> Merge for Vec<T> {
+    fn merge(&mut self, _base: Self, _fields: &mut Fields) {}
+}
+
+impl Merge for BootInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("ab_partitions", &mut self.ab_partitions, base.ab_partitions);
+        if !self.ab_updater && base.ab_updater {
+            self.ab_updater = true;
+            fields.inherited.push("ab_updater".to_string());
+        }
+        fields.value("recovery", &mut self.recovery, base.recovery);
+        fields.map("vars", &mut self.vars, base.vars);
+    }
+}
+
+impl Merge for PartitionSizes {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.keyed("partitions", &mut self.partitions, base.partitions, |partition| &partition.name);
+        fields.value("flash_block_size", &mut self.flash_block_size, base.flash_block_size);
+    }
+}
+
+impl Merge for EncryptionInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("fstab_files", &mut self.fstab_files, base.fstab_files);
+        fields.list("data", &mut self.data, base.data);
+        fields.list("metadata_mounts", &mut self.metadata_mounts, base.metadata_mounts);
+        fields.map("vars", &mut self.vars, base.vars);
+    }
+}
+
+impl Merge for KernelInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.value("source", &mut self.source, base.source);
+        fields.list("defconfigs", &mut self.defconfigs, base.defconfigs);
+        fields.value("prebuilt_image", &mut self.prebuilt_image, base.prebuilt_image);
+        fields.value("version", &mut self.version, base.version);
+    }
+}
+
+// A cross-check of one tree's declarations against that tree's .ko files,
+// so its fields only make sense together; the overlay's wins
+impl Merge for ModuleConsistency {
+    fn merge(&mut self, _base: Self, _fields: &mut Fields) {}
+}
+
+impl Merge for ModemInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("firmware", &mut self.firmware, base.firmware);
+        fields.map("vars", &mut self.vars, base.vars);
+        fields.list("required_baseband", &mut self.required_baseband, base.required_baseband);
+    }
+}
+
+impl Merge for TrebleInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.value("vndk_version", &mut self.vndk_version, base.vndk_version);
+        fields.map("flags", &mut self.flags, base.flags);
+        fields.list("vndk_dirs", &mut self.vndk_dirs, base.vndk_dirs);
+    }
+}
+
+impl Merge for GsiInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.map("vars", &mut self.vars, base.vars);
+        fields.list("manifests", &mut self.manifests, base.manifests);
+    }
+}
+
+impl Merge for ApexInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("modules", &mut self.modules, base.modules);
+    }
+}
+
+impl Merge for LinkerNamespaces {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("public_libraries", &mut self.public_libraries, base.public_libraries);
+        fields.list("configs", &mut self.configs, base.configs);
+    }
+}
+
+// Nodes carry file:line locations into the overlay's files and are keyed
+// by path, which both trees may use, so the sources aren't mixed
+impl Merge for DtsSources {
+    fn merge(&mut self, _base: Self, _fields: &mut Fields) {}
+}
+
+impl Merge for DtbOverlays {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("prebuilts", &mut self.prebuilts, base.prebuilts);
+        fields.list("config_files", &mut self.config_files, base.config_files);
+        fields.list("overlays", &mut self.overlays, base.overlays);
+        fields.map("board_vars", &mut self.board_vars, base.board_vars);
+        fields.value("build_mode", &mut self.build_mode, base.build_mode);
+    }
+}
+
+impl Merge for CameraInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("sensors", &mut self.sensors, base.sensors);
+        fields.list("unnamed_nodes", &mut self.unnamed_nodes, base.unnamed_nodes);
+        fields.packages("hal_packages", &mut self.hal_packages, base.hal_packages);
+        fields.list("config_files", &mut self.config_files, base.config_files);
+        fields.list("sensor_modules", &mut self.sensor_libs, base.sensor_libs);
+    }
+}
+
+impl Merge for Haptics {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("controllers", &mut self.controllers, base.controllers);
+        fields.packages("hal_packages", &mut self.hal_packages, base.hal_packages);
+        fields.list("config_files", &mut self.config_files, base.config_files);
+    }
+}
+
+impl Merge for Biometrics {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("sensors", &mut self.sensors, base.sensors);
+        fields.packages("hal_packages", &mut self.hal_packages, base.hal_packages);
+        fields.list("config_files", &mut self.config_files, base.config_files);
+        fields.list("under_display_hints", &mut self.under_display_hints, base.under_display_hints);
+    }
+}
+
+impl Merge for DrmInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.packages("hal_packages", &mut self.hal_packages, base.hal_packages);
+        fields.keyed("board_flags", &mut self.board_flags, base.board_flags, |flag| &flag.name);
+        fields.list("libraries", &mut self.libraries, base.libraries);
+        fields.list("trusted_apps", &mut self.trusted_apps, base.trusted_apps);
+    }
+}
+
+impl Merge for TeeInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.keyed("board_flags", &mut self.board_flags, base.board_flags, |flag| &flag.name);
+        fields.packages("keymaster_packages", &mut self.keymaster_packages, base.keymaster_packages);
+        fields.packages("gatekeeper_packages", &mut self.gatekeeper_packages, base.gatekeeper_packages);
+        fields.packages("tee_packages", &mut self.tee_packages, base.tee_packages);
+        fields.list("bindings", &mut self.bindings, base.bindings);
+    }
+}
+
+impl Merge for GpuInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.value("platform", &mut self.platform, base.platform);
+        fields.value("platform_gpu", &mut self.platform_gpu, base.platform_gpu);
+        fields.list("bindings", &mut self.bindings, base.bindings);
+        fields.packages("gralloc_packages", &mut self.gralloc_packages, base.gralloc_packages);
+        fields.packages("mapper_packages", &mut self.mapper_packages, base.mapper_packages);
+        fields.packages("composer_packages", &mut self.composer_packages, base.composer_packages);
+        fields.packages("driver_packages", &mut self.driver_packages, base.driver_packages);
+    }
+}
+
+impl Merge for LedInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("leds", &mut self.leds, base.leds);
+        fields.list("backlights", &mut self.backlights, base.backlights);
+        fields.packages("hal_packages", &mut self.hal_packages, base.hal_packages);
+        fields.list("sysfs_commands", &mut self.sysfs_commands, base.sysfs_commands);
+    }
+}
+
+impl Merge for NfcInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("devices", &mut self.devices, base.devices);
+        fields.packages("hal_packages", &mut self.hal_packages, base.hal_packages);
+        fields.list("config_files", &mut self.config_files, base.config_files);
+    }
+}
+
+impl Merge for UsbInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("settings", &mut self.settings, base.settings);
+        fields.list("compositions", &mut self.compositions, base.compositions);
+        fields.keyed("board_flags", &mut self.board_flags, base.board_flags, |flag| &flag.name);
+        fields.value("adb_secure", &mut self.adb_secure, base.adb_secure);
+        fields.list("files", &mut self.files, base.files);
+    }
+}
+
+impl Merge for Regulators {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("regulators", &mut self.regulators, base.regulators);
+        fields.list("power_domains", &mut self.power_domains, base.power_domains);
+    }
+}
+
+impl Merge for BatteryInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("controllers", &mut self.controllers, base.controllers);
+        fields.list("profiles", &mut self.profiles, base.profiles);
+        fields.list("profile_files", &mut self.profile_files, base.profile_files);
+    }
+}
+
+// Both trees' PRODUCT_COPY_FILES are installed; the overlay's wins a
+// destination both copy to
+impl Merge for CopiedFiles {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.keyed("entries", &mut self.entries, base.entries, |entry| &entry.destination);
+    }
+}
+
+impl Merge for DeclaredFeatures {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.keyed("features", &mut self.features, base.features, |feature| &feature.name);
+        fields.list("files", &mut self.files, base.files);
+    }
+}
+
+impl Merge for MediaProfiles {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("files", &mut self.files, base.files);
+        fields.list("profiles", &mut self.profiles, base.profiles);
+    }
+}
+
+impl Merge for MixerPaths {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("files", &mut self.files, base.files);
+    }
+}
+
+impl Merge for KeyLayouts {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("files", &mut self.files, base.files);
+    }
+}
+
+impl Merge for CarrierConfig {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("apn_files", &mut self.apn_files, base.apn_files);
+        // The omitted counts go with the list they were cut from
+        if self.apns.is_empty() && !base.apns.is_empty() {
+            self.apns_omitted = base.apns_omitted;
+        }
+        fields.list("apns", &mut self.apns, base.apns);
+        fields.list("config_files", &mut self.config_files, base.config_files);
+        if self.carrier_ids.is_empty() && !base.carrier_ids.is_empty() {
+            self.carrier_ids_omitted = base.carrier_ids_omitted;
+        }
+        fields.list("carrier_ids", &mut self.carrier_ids, base.carrier_ids);
+        fields.list("carrier_list_blobs", &mut self.carrier_list_blobs, base.carrier_list_blobs);
+    }
+}
+
+impl Merge for TelephonyInfo {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("overlay_files", &mut self.overlay_files, base.overlay_files);
+        fields.map("resources", &mut self.resources, base.resources);
+        fields.map("properties", &mut self.properties, base.properties);
+    }
+}
+
+impl Merge for PowerConfig {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("hint_files", &mut self.hint_files, base.hint_files);
+        fields.list("hint_nodes", &mut self.hint_nodes, base.hint_nodes);
+        fields.list("hints", &mut self.hints, base.hints);
+        fields.list("profile_files", &mut self.profile_files, base.profile_files);
+        fields.map("profile", &mut self.profile, base.profile);
+    }
+}
+
+impl Merge for PerfTuning {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("files", &mut self.files, base.files);
+        fields.list("boosts", &mut self.boosts, base.boosts);
+        fields.list("properties", &mut self.properties, base.properties);
+    }
+}
+
+impl Merge for CpuTopology {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.map("groups", &mut self.groups, base.groups);
+        fields.list("profiles", &mut self.profiles, base.profiles);
+        fields.list("files", &mut self.files, base.files);
+    }
+}
+
+impl Merge for FilesystemSetup {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("rc_files", &mut self.rc_files, base.rc_files);
+        fields.list("mounts", &mut self.mounts, base.mounts);
+        fields.list("directories", &mut self.directories, base.directories);
+        fields.list("symlinks", &mut self.symlinks, base.symlinks);
+        let before = self.properties.len();
+        self.properties.extend(base.properties);
+        if self.properties.len() > before {
+            fields.inherited.push("properties".to_string());
+        }
+    }
+}
+
+impl Merge for BootSequence {
+    fn merge(&mut self, base: Self, fields: &mut Fields) {
+        fields.list("rc_files", &mut self.rc_files, base.rc_files);
+        fields.list("services", &mut self.services, base.services);
+        fields.list("triggers", &mut self.triggers, base.triggers);
+        fields.list("imports", &mut self.imports, base.imports);
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use xml::attribute::OwnedAttribute;
+use xml::reader::{EventReader, XmlEvent};
+use crate::fsread;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Path names worth listing on their own: the outputs and inputs that break
+// first on a port
+const KEY_PATH_MARKERS: [&str; 5] = ["speaker", "headphone", "headset", "handset", "earpiece"];
+
+// Use-case -> ALSA mixer control routes from mixer_paths*.xml. Targets with
+// several codecs (or codec revisions) ship one file per variant, e.g.
+// mixer_paths_tavil.xml and mixer_paths_wcd9385.xml, and the audio HAL
+// picks one at runtime.
+#[derive(Debug, Default)]
+pub struct MixerPaths {
+    pub files: Vec<MixerFile>,
+}
+
+#[derive(Debug)]
+pub struct MixerFile {
+    pub file: String,
+    // File name suffix after mixer_paths_, e.g. "tavil"; None for mixer_paths.xml
+    pub variant: Option<String>,
+    // Top-level <ctl>s: the state every control is reset to
+    pub defaults: Vec<MixerControl>,
+    pub paths: Vec<MixerPath>,
+}
+
+#[derive(Debug)]
+pub struct MixerPath {
+    pub name: String,
+    pub controls: Vec<MixerControl>,
+    // Nested <path name=.../> references to other paths
+    pub includes: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct MixerControl {
+    pub name: String,
+    // value="...", with id="N" controls written as "N: value"
+    pub value: String,
+}
+
+impl MixerPaths {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn path_count(&self) -> usize {
+        self.files.iter().map(|file| file.paths.len()).sum()
+    }
+}
+
+impl MixerFile {
+    pub fn variant_name(&self) -> &str {
+        self.variant.as_deref().unwrap_or("default")
+    }
+
+    // Speaker/headphone/... paths, in file order
+    pub fn key_paths(&self) -> Vec<&MixerPath> {
+        self.paths
+            .iter()
+            .filter(|path| {
+                let name = path.name.to_lowercase();
+                KEY_PATH_MARKERS.iter().any(|marker| name.contains(marker))
+            })
+            .collect()
+    }
+
+    // (path, included name) for includes no path in this file defines; the
+    // HAL silently skips them, so the route is missing its controls
+    pub fn unresolved_includes(&self) -> Vec<(&str, &str)> {
+        let mut unresolved = Vec::new();
+        for path in &self.paths {
+            for include in &path.includes {
+                if !self.paths.iter().any(|other| other.name == *include) {
+                    unresolved.push((path.name.as_str(), include.as_str()));
+                }
+            }
+        }
+        unresolved
+    }
+}
+
+pub fn scan_mixer_paths(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> MixerPaths {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| name.starts_with("mixer_paths") && name.ends_with(".xml"),
+        &mut files,
+    );
+    files.sort();
+
+    let mut mixer = MixerPaths::default();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let variant = file
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("mixer_paths_"))
+            .and_then(|name| name.strip_suffix(".xml"))
+            .map(str::to_string);
+        match parse_mixer_paths(&file) {
+            Ok((defaults, paths)) => mixer.files.push(MixerFile { file: relative, variant, defaults, paths }),
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+        }
+    }
+
+    mixer
+}
+
+fn parse_mixer_paths(path: &Path) -> io::Result<(Vec<MixerControl>, Vec<MixerPath>)> {
+    // <mixer>
+    //   <ctl name="RX INT7_1 MIX1 INP0" value="ZERO" />
+    //   <path name="speaker">
+    //     <ctl name="SpkrLeft COMP Switch" value="1" />
+    //     <path name="speaker-protected" />
+    //   </path>
+    // </mixer>
+    let file = fsread::open(path)?;
+
+    let mut defaults = Vec::new();
+    let mut paths: Vec<MixerPath> = Vec::new();
+    // Paths being read, innermost last; a nested <path> without children
+    // is an include of its parent
+    let mut open: Vec<MixerPath> = Vec::new();
+
+    for event in EventReader::new(BufReader::new(file)) {
+        match event {
+            Ok(XmlEvent::StartElement { name, attributes, .. }) => match name.local_name.as_str() {
+                "path" => open.push(MixerPath {
+                    name: attribute(&attributes, "name").unwrap_or_default().to_string(),
+                    controls: Vec::new(),
+                    includes: Vec::new(),
+                }),
+                "ctl" => {
+                    let Some(control) = control(&attributes) else {
+                        continue;
+                    };
+                    match open.last_mut() {
+                        Some(path) => path.controls.push(control),
+                        None => defaults.push(control),
+                    }
+                }
+                _ => {}
+            },
+            Ok(XmlEvent::EndElement { name }) if name.local_name == "path" => {
+                let Some(path) = open.pop() else {
+                    continue;
+                };
+                match open.last_mut() {
+                    Some(parent) if path.controls.is_empty() && path.includes.is_empty() => {
+                        parent.includes.push(path.name)
+                    }
+                    _ => paths.push(path),
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    Ok((defaults, paths))
+}
+
+fn control(attributes: &[OwnedAttribute]) -> Option<MixerControl> {
+    let name = attribute(attributes, "name")?.to_string();
+    let value = attribute(attributes, "value").unwrap_or_default();
+    let value = match attribute(attributes, "id") {
+        Some(id) => format!("{}: {}", id, value),
+        None => value.to_string(),
+    };
+    Some(MixerControl { name, value })
+}
+
+fn attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attributes.iter().find(|a| a.name.local_name == name).map(|a| a.value.as_str())
+}
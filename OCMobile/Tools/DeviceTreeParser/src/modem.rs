@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::makefile::{split_assignment, statements};
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Whole-image modem firmware names (Qualcomm NON-HLOS, Pixel radio images)
+const IMAGE_NAMES: [&str; 4] = ["NON-HLOS.bin", "modem.img", "modem.bin", "radio.img"];
+
+// Version strings embedded in modem images: the Qualcomm image info block,
+// a bare Qualcomm MPSS build ID and MediaTek MOLY builds
+const VERSION_MARKERS: [&str; 3] = ["QC_IMAGE_VERSION_STRING=", "MPSS.", "MOLY."];
+
+// Modem images are often hundreds of MB; read them in chunks, and stop
+// looking after this much
+const CHUNK_BYTES: usize = 1024 * 1024;
+const MAX_SCAN_BYTES: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Default)]
+pub struct ModemInfo {
+    pub firmware: Vec<ModemFirmware>,
+    // TARGET_NO_RADIOIMAGE, ADD_RADIO_FILES, BOARD_*MODEM*/*RADIO* sizes, ...
+    pub vars: BTreeMap<String, String>,
+    // "require version-baseband=..." from board-info.txt
+    pub required_baseband: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ModemFirmware {
+    pub file: String,
+    // None when no version string could be found in the image
+    pub version: Option<String>,
+}
+
+impl ModemInfo {
+    pub fn is_empty(&self) -> bool {
+        self.firmware.is_empty() && self.vars.is_empty() && self.required_baseband.is_empty()
+    }
+
+    // TARGET_NO_RADIOIMAGE := true, i.e. the build doesn't flash a modem
+    pub fn no_radio_image(&self) -> bool {
+        self.vars.get("TARGET_NO_RADIOIMAGE").is_some_and(|value| value == "true")
+    }
+}
+
+pub fn scan_modem(
+    tree_path: &Path,
+    board_config: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> ModemInfo {
+    let mut modem = ModemInfo::default();
+
+    // Unreadable BoardConfig.mk is already reported by the driver scan
+    if let Some(board_config) = board_config
+        && walk_options.includes_file(board_config)
+        && let Ok(content) = fsread::read_to_string(board_config)
+    {
+        for (_, statement) in statements(&content) {
+            if let Some((name, value)) = split_assignment(&statement)
+                && is_modem_variable(name)
+            {
+                modem.vars.insert(name.to_string(), value.trim_matches('"').to_string());
+            }
+        }
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| {
+            IMAGE_NAMES.contains(&name)
+                || (name.starts_with("radio-") && name.ends_with(".img"))
+                || name.ends_with(".mbn")
+                || name == "board-info.txt"
+        },
+        &mut files,
+    );
+    files.sort();
+
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+
+        if name == "board-info.txt" {
+            match fsread::read_to_string(&file) {
+                Ok(content) => modem.required_baseband.extend(
+                    content
+                        .lines()
+                        .filter_map(|line| line.trim().strip_prefix("require version-baseband="))
+                        .flat_map(|versions| versions.split('|'))
+                        .map(|version| version.trim().to_string()),
+                ),
+                Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+            }
+            continue;
+        }
+
+        // Other .mbn blobs are TrustZone, DSP and GPU firmware
+        if name.ends_with(".mbn") && !is_modem_mbn(&relative) {
+            continue;
+        }
+
+        match read_version(&file) {
+            Ok(version) => modem.firmware.push(ModemFirmware { file: relative, version }),
+            Err(e) => {
+                warnings.push(Warning::new(WarningKind::UnreadableFile, &relative, e.to_string()));
+                modem.firmware.push(ModemFirmware { file: relative, version: None });
+            }
+        }
+    }
+
+    modem
+}
+
+fn is_modem_variable(name: &str) -> bool {
+    matches!(name, "TARGET_NO_RADIOIMAGE" | "ADD_RADIO_FILES" | "TARGET_BOARD_INFO_FILE")
+        || (name.starts_with("BOARD_") && (name.contains("MODEM") || name.contains("RADIO")))
+}
+
+// radio/*.mbn, or modem.mbn/mba.mbn/qdsp6m.mbn anywhere
+fn is_modem_mbn(relative: &str) -> bool {
+    let lower = relative.to_lowercase();
+    let name = lower.rsplit('/').next().unwrap_or(&lower);
+    lower.split('/').any(|component| component == "radio")
+        || ["modem", "mba", "qdsp6m"].iter().any(|prefix| name.starts_with(prefix))
+}
+
+// Scans the image in chunks, carrying the tail of each chunk over so a
+// string split across two reads is still found
+fn read_version(path: &Path) -> io::Result<Option<String>> {
+    let mut file = fsread::open(path)?.take(MAX_SCAN_BYTES);
+    let mut window: Vec<u8> = Vec::new();
+    let mut chunk = vec![0u8; CHUNK_BYTES];
+
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            return Ok(find_version(&window));
+        }
+        window.extend_from_slice(&chunk[..read]);
+        if let Some(version) = find_version(&window) {
+            return Ok(Some(version));
+        }
+        let keep = window.len().min(256);
+        window.drain(..window.len() - keep);
+    }
+}
+
+fn find_version(bytes: &[u8]) -> Option<String> {
+    VERSION_MARKERS.iter().find_map(|marker| {
+        let start = bytes.windows(marker.len()).position(|window| window == marker.as_bytes())?;
+        // The image info block's value follows the "=", the bare IDs include
+        // the marker itself
+        let value_start = if marker.ends_with('=') { start + marker.len() } else { start };
+        let value = &bytes[value_start..];
+        let end = value.iter().position(|b| !b.is_ascii_graphic())?;
+        (end > 0 && end <= 128).then(|| String::from_utf8_lossy(&value[..end]).to_string())
+    })
+}
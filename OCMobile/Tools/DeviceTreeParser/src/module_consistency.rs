@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::makefile::{parse_assignment, statements, AssignOp};
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Prebuilt .ko files in the tree cross-checked against the modules
+// BoardConfig.mk packages (BOARD_VENDOR_KERNEL_MODULES,
+// BOARD_VENDOR_RAMDISK_KERNEL_MODULES, ...). Modules are matched by file
+// name, as declarations usually point into $(KERNEL_MODULES_OUT) or another
+// tree rather than at the checked-in copy.
+#[derive(Debug, Default)]
+pub struct ModuleConsistency {
+    pub declared: Vec<DeclaredModule>,
+    // Directories packaged whole through $(wildcard dir/*.ko)
+    pub wildcard_dirs: Vec<String>,
+    // .ko files in the tree, relative to the tree root
+    pub prebuilt: Vec<String>,
+    // Prebuilt files no declaration or wildcard packages
+    pub unreferenced: Vec<String>,
+    // Declarations with no prebuilt file of that name
+    pub missing: Vec<DeclaredModule>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeclaredModule {
+    // File name, e.g. "wlan.ko"
+    pub name: String,
+    pub variable: String,
+    pub location: String,
+}
+
+impl ModuleConsistency {
+    pub fn is_empty(&self) -> bool {
+        self.declared.is_empty() && self.wildcard_dirs.is_empty() && self.prebuilt.is_empty()
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.unreferenced.is_empty() && self.missing.is_empty()
+    }
+}
+
+pub fn scan_module_consistency(
+    tree_path: &Path,
+    board_config: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> ModuleConsistency {
+    let mut modules = ModuleConsistency::default();
+
+    // Unreadable BoardConfig.mk is already reported by the driver scan
+    if let Some(board_config) = board_config
+        && walk_options.includes_file(board_config)
+        && let Ok(content) = fsread::read_to_string(board_config)
+    {
+        let file_name = board_config.file_name().map_or("BoardConfig.mk".into(), |n| n.to_string_lossy());
+        // Variable -> its modules and wildcard directories
+        let mut variables: BTreeMap<String, (Vec<DeclaredModule>, Vec<String>)> = BTreeMap::new();
+        for (line, statement) in statements(&content) {
+            let Some(assignment) = parse_assignment(&statement) else {
+                continue;
+            };
+            if !assignment.name.starts_with("BOARD_") || !assignment.name.ends_with("KERNEL_MODULES") {
+                continue;
+            }
+            // := and = replace what the variable held so far, ?= only
+            // applies while it's unset
+            let declared = variables.entry(assignment.name.to_string()).or_default();
+            match assignment.op {
+                AssignOp::Append => {}
+                AssignOp::Simple | AssignOp::Recursive => *declared = (Vec::new(), Vec::new()),
+                AssignOp::Conditional if !declared.0.is_empty() || !declared.1.is_empty() => continue,
+                AssignOp::Conditional => {}
+            }
+            let (rest, wildcards) = take_wildcards(assignment.value);
+            declared.1.extend(wildcards);
+            for token in rest.split_whitespace().filter(|token| token.ends_with(".ko")) {
+                declared.0.push(DeclaredModule {
+                    name: token.rsplit('/').next().unwrap_or(token).to_string(),
+                    variable: assignment.name.to_string(),
+                    location: format!("{}:{}", file_name, line),
+                });
+            }
+        }
+        for (mut declared, wildcard_dirs) in variables.into_values() {
+            modules.declared.append(&mut declared);
+            modules.wildcard_dirs.extend(wildcard_dirs);
+        }
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name.ends_with(".ko"), &mut files);
+    files.sort();
+    modules.prebuilt =
+        files.iter().map(|file| file.strip_prefix(tree_path).unwrap_or(file).to_string_lossy().to_string()).collect();
+
+    // Nothing is packaged from the tree at all (e.g. modules built from
+    // kernel source), so there's nothing to cross-check
+    if modules.declared.is_empty() && modules.wildcard_dirs.is_empty() {
+        return modules;
+    }
+
+    for file in &modules.prebuilt {
+        let (dir, name) = file.rsplit_once('/').unwrap_or(("", file));
+        let declared = modules.declared.iter().any(|module| module.name == name);
+        let wildcard = modules.wildcard_dirs.iter().any(|wildcard| {
+            dir == wildcard || dir.ends_with(&format!("/{}", wildcard))
+        });
+        if !declared && !wildcard {
+            warnings.push(Warning::new(
+                WarningKind::UnreferencedModule,
+                file,
+                "prebuilt module is not listed in any BOARD_*KERNEL_MODULES variable",
+            ));
+            modules.unreferenced.push(file.clone());
+        }
+    }
+
+    for module in &modules.declared {
+        let on_disk = modules.prebuilt.iter().any(|file| file.rsplit('/').next() == Some(module.name.as_str()));
+        if !on_disk && !modules.missing.iter().any(|missing| missing.name == module.name) {
+            warnings.push(Warning::new(
+                WarningKind::MissingModule,
+                &module.location,
+                format!("{} lists '{}', which is not in the tree", module.variable, module.name),
+            ));
+            modules.missing.push(module.clone());
+        }
+    }
+
+    modules
+}
+
+// Splits "$(wildcard $(LOCAL_PATH)/modules/*.ko) foo.ko" into the rest
+// ("foo.ko") and the tree-relative directories of *.ko patterns
+// (["modules"]). A leading $(VAR)/ is dropped, as it's usually the tree.
+fn take_wildcards(value: &str) -> (String, Vec<String>) {
+    let mut rest = String::new();
+    let mut dirs = Vec::new();
+    let mut remaining = value;
+
+    while let Some(start) = remaining.find("$(wildcard ") {
+        rest.push_str(&remaining[..start]);
+        let inner = &remaining[start + "$(wildcard ".len()..];
+        // Patterns may contain $(VAR)s, so find the matching parenthesis
+        let mut depth = 1;
+        let end = inner
+            .char_indices()
+            .find(|(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })
+            .map_or(inner.len(), |(index, _)| index);
+
+        for pattern in inner[..end].split_whitespace() {
+            let Some(dir) = pattern.strip_suffix("/*.ko") else {
+                continue;
+            };
+            let dir = match dir.strip_prefix("$(").and_then(|d| d.split_once(')')) {
+                Some((_, path)) => path.trim_start_matches('/'),
+                None => dir,
+            };
+            dirs.push(dir.to_string());
+        }
+        remaining = inner.get(end + 1..).unwrap_or("");
+    }
+    rest.push_str(remaining);
+
+    (rest, dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    fn scan(name: &str, files: &[(&str, &str)]) -> (ModuleConsistency, Vec<Warning>) {
+        let tree = Scratch::new(name, files);
+        let board_config = tree.path().join("BoardConfig.mk");
+        let mut warnings = Vec::new();
+        let modules = scan_module_consistency(tree.path(), Some(&board_config), &WalkOptions::default(), &mut warnings);
+        (modules, warnings)
+    }
+
+    #[test]
+    fn dead_and_missing_modules_are_flagged() {
+        let (modules, warnings) = scan(
+            "modules",
+            &[
+                (
+                    "BoardConfig.mk",
+                    "BOARD_VENDOR_KERNEL_MODULES := \\\n\
+                         $(KERNEL_MODULES_OUT)/wlan.ko \\\n\
+                         $(KERNEL_MODULES_OUT)/touch.ko\n\
+                     BOARD_VENDOR_RAMDISK_KERNEL_MODULES += $(LOCAL_PATH)/prebuilt/modules/panel.ko\n",
+                ),
+                ("prebuilt/modules/wlan.ko", ""),
+                ("prebuilt/modules/panel.ko", ""),
+                ("prebuilt/modules/old_camera.ko", ""),
+            ],
+        );
+
+        let declared: Vec<&str> = modules.declared.iter().map(|module| module.name.as_str()).collect();
+        assert_eq!(declared, ["wlan.ko", "touch.ko", "panel.ko"]);
+        assert_eq!(modules.unreferenced, ["prebuilt/modules/old_camera.ko"]);
+        let missing: Vec<(&str, &str)> =
+            modules.missing.iter().map(|module| (module.name.as_str(), module.location.as_str())).collect();
+        assert_eq!(missing, [("touch.ko", "BoardConfig.mk:1")]);
+        assert!(!modules.is_consistent());
+
+        let kinds: Vec<WarningKind> = warnings.iter().map(|warning| warning.kind).collect();
+        assert_eq!(kinds, [WarningKind::UnreferencedModule, WarningKind::MissingModule]);
+    }
+
+    #[test]
+    fn wildcards_and_reassignments() {
+        // The wildcard packages the whole directory, and := drops the
+        // earlier declaration of gone.ko
+        let (modules, warnings) = scan(
+            "modules-wildcard",
+            &[
+                (
+                    "BoardConfig.mk",
+                    "BOARD_VENDOR_KERNEL_MODULES := gone.ko\n\
+                     BOARD_VENDOR_KERNEL_MODULES := $(wildcard $(LOCAL_PATH)/modules/*.ko)\n\
+                     BOARD_VENDOR_KERNEL_MODULES ?= ignored.ko\n",
+                ),
+                ("modules/wlan.ko", ""),
+                ("modules/touch.ko", ""),
+            ],
+        );
+        assert_eq!(modules.wildcard_dirs, ["modules"]);
+        assert!(modules.declared.is_empty());
+        assert!(modules.is_consistent(), "{:?}", modules);
+        assert!(warnings.is_empty());
+
+        // Nothing packaged from the tree: no cross-check at all
+        let (modules, warnings) = scan("modules-none", &[("BoardConfig.mk", ""), ("modules/wlan.ko", "")]);
+        assert_eq!(modules.prebuilt, ["modules/wlan.ko"]);
+        assert!(modules.unreferenced.is_empty() && warnings.is_empty());
+    }
+}
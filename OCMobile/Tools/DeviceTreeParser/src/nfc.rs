@@ -0,0 +1,196 @@
+use std::path::{Path, PathBuf};
+use crate::dts::{DtsNode, DtsSources};
+use crate::fsread;
+use crate::makefile::{split_assignment, statements};
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Compatible fragments of NFC controllers: NXP PN5xx/SN1xx, ST ST21/ST54,
+// the Qualcomm NQ wrapper around NXP parts and Samsung SEC/S3FWRN
+const NFC_MARKERS: [&str; 8] = ["nfc", "pn5", "pn544", "sn1", "st21", "nq-nci", "s3fwrn", "sec-nfc"];
+
+// Embedded secure elements hang off the same vendors' SPI buses; matched
+// against whole words of the compatible ("nxp,sn1xx-ese", "st,st54j_se")
+const SE_MARKERS: [&str; 4] = ["ese", "se", "p61", "p73"];
+
+// libnfc-nci.conf settings worth showing: logging, the HAL to load and where
+// card emulation traffic gets routed
+const KEY_SETTINGS: [&str; 8] = [
+    "NFC_DEBUG_ENABLED",
+    "NCI_HAL_MODULE",
+    "NFA_STORAGE",
+    "DEFAULT_ROUTE",
+    "DEFAULT_ISODEP_ROUTE",
+    "DEFAULT_OFFHOST_ROUTE",
+    "OFFHOST_ROUTE_ESE",
+    "OFFHOST_ROUTE_UICC",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NfcKind {
+    Controller,
+    SecureElement,
+}
+
+// NFC controllers and secure elements from the DTS, the NFC/SE packages from
+// device.mk and the libnfc-*.conf/libese-*.conf files shipped with the tree
+#[derive(Debug, Default)]
+pub struct NfcInfo {
+    pub devices: Vec<NfcDevice>,
+    pub hal_packages: Vec<String>,
+    pub config_files: Vec<NfcConfig>,
+}
+
+#[derive(Debug)]
+pub struct NfcDevice {
+    pub node: String,
+    pub kind: NfcKind,
+    pub vendor: String,
+    pub compatible: Vec<String>,
+    // libnfc-<vendor>.conf, when the tree ships one
+    pub vendor_config: Option<String>,
+    pub locations: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct NfcConfig {
+    pub file: String,
+    // KEY_SETTINGS present in the file, in file order
+    pub settings: Vec<(String, String)>,
+}
+
+impl NfcKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NfcKind::Controller => "nfc",
+            NfcKind::SecureElement => "secure_element",
+        }
+    }
+}
+
+impl NfcInfo {
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty() && self.hal_packages.is_empty() && self.config_files.is_empty()
+    }
+
+    // The stack reads libnfc-nci.conf from /vendor/etc or /system/etc;
+    // without it NFC fails to start even when the controller probes
+    pub fn has_nci_config(&self) -> bool {
+        self.config_files.iter().any(|config| config.file.ends_with("libnfc-nci.conf"))
+    }
+}
+
+pub fn scan_nfc(
+    tree_path: &Path,
+    sources: &DtsSources,
+    device_mk: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> NfcInfo {
+    let mut nfc = NfcInfo::default();
+
+    for node in sources.nodes.values() {
+        let compatible: Vec<String> = node.strings("compatible").into_iter().map(|s| s.to_string()).collect();
+        let Some(kind) = kind(node, &compatible) else {
+            continue;
+        };
+        nfc.devices.push(NfcDevice {
+            node: node.path.clone(),
+            kind,
+            vendor: compatible
+                .iter()
+                .find_map(|c| c.split_once(',').map(|(vendor, _)| vendor.to_string()))
+                .unwrap_or_else(|| "unknown".to_string()),
+            compatible,
+            vendor_config: None,
+            locations: node.locations.clone(),
+        });
+    }
+
+    // Unreadable device.mk is already reported by the driver scan
+    if let Some(device_mk) = device_mk
+        && walk_options.includes_file(device_mk)
+        && let Ok(content) = fsread::read_to_string(device_mk)
+    {
+        for (_, statement) in statements(&content) {
+            let Some(("PRODUCT_PACKAGES", value)) = split_assignment(&statement) else {
+                continue;
+            };
+            for package in value.split_whitespace() {
+                let lower = package.to_lowercase();
+                if (lower.contains("nfc") || lower.contains("secure_element") || lower == "secureelement")
+                    && !nfc.hal_packages.iter().any(|p| p == package)
+                {
+                    nfc.hal_packages.push(package.to_string());
+                }
+            }
+        }
+    }
+
+    for file in find_config_files(tree_path, walk_options) {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        match fsread::read_to_string(&file) {
+            Ok(content) => nfc.config_files.push(NfcConfig { file: relative, settings: parse_settings(&content) }),
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+        }
+    }
+
+    // libnfc-nxp.conf, libnfc-st.conf, ...: the vendor half of the NCI config
+    for device in &mut nfc.devices {
+        let name = format!("libnfc-{}.conf", device.vendor);
+        device.vendor_config =
+            nfc.config_files.iter().map(|config| &config.file).find(|file| file.ends_with(&name)).cloned();
+    }
+
+    nfc
+}
+
+fn kind(node: &DtsNode, compatible: &[String]) -> Option<NfcKind> {
+    if compatible.is_empty() {
+        return None;
+    }
+    let parts: Vec<&str> =
+        compatible.iter().map(|c| c.split_once(',').map_or(c.as_str(), |(_, part)| part)).collect();
+
+    // SE markers first: "nxp,sn1xx-ese" is the eSE half of an NFC part
+    if parts.iter().flat_map(|part| part.split(['-', '_'])).any(|word| SE_MARKERS.contains(&word)) {
+        Some(NfcKind::SecureElement)
+    } else if node.name().contains("nfc") || parts.iter().any(|part| NFC_MARKERS.iter().any(|m| part.contains(m))) {
+        Some(NfcKind::Controller)
+    } else {
+        None
+    }
+}
+
+fn find_config_files(tree_path: &Path, walk_options: &WalkOptions) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| (name.starts_with("libnfc") || name.starts_with("libese")) && name.ends_with(".conf"),
+        &mut files,
+    );
+    files.sort();
+    files
+}
+
+// KEY=value lines; values in {...} blocks (routing tables, RF settings) may
+// span lines and are skipped. Later assignments win, as in the NCI loader.
+fn parse_settings(content: &str) -> Vec<(String, String)> {
+    let mut settings: Vec<(String, String)> = Vec::new();
+    for line in content.lines().map(str::trim).filter(|line| !line.starts_with('#')) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim().trim_matches('"'));
+        if !KEY_SETTINGS.contains(&key) || value.starts_with('{') {
+            continue;
+        }
+        match settings.iter_mut().find(|(existing, _)| existing == key) {
+            Some(setting) => setting.1 = value.to_string(),
+            None => settings.push((key.to_string(), value.to_string())),
+        }
+    }
+    settings
+}
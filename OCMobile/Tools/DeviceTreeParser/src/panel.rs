@@ -0,0 +1,108 @@
+use crate::dts::{DtsNode, DtsSources};
+
+// Qualcomm MDSS panels keep their timings in child nodes
+// (qcom,mdss-dsi-display-timings/timing@N); generic panels use
+// display-timings with hactive/vactive
+const WIDTH_PROPERTIES: [&str; 2] = ["qcom,mdss-dsi-panel-width", "hactive"];
+const HEIGHT_PROPERTIES: [&str; 2] = ["qcom,mdss-dsi-panel-height", "vactive"];
+const REFRESH_PROPERTIES: [&str; 3] = [
+    "qcom,mdss-dsi-panel-framerate",
+    "qcom,dsi-supported-dfps-list",
+    "qcom,mdss-dsi-panel-refresh-rate",
+];
+
+#[derive(Debug, Default)]
+pub struct Panel {
+    pub node: String,
+    pub name: Option<String>,
+    pub compatible: Vec<String>,
+    pub width: Option<u64>,
+    pub height: Option<u64>,
+    pub refresh_rates: Vec<u64>,
+    // "file:line" of every block describing the panel, plus timings
+    // from files that don't otherwise touch it
+    pub locations: Vec<String>,
+}
+
+impl Panel {
+    pub fn resolution(&self) -> Option<String> {
+        match (self.width, self.height) {
+            (Some(width), Some(height)) => Some(format!("{}x{}", width, height)),
+            (Some(width), None) => Some(format!("{}x?", width)),
+            (None, Some(height)) => Some(format!("?x{}", height)),
+            (None, None) => None,
+        }
+    }
+
+    // Panel name, falling back to the first compatible and then the node name
+    pub fn display_name(&self) -> &str {
+        self.name
+            .as_deref()
+            .or(self.compatible.first().map(|c| c.as_str()))
+            .unwrap_or_else(|| self.node.rsplit('/').next().unwrap_or(&self.node))
+    }
+}
+
+pub fn find_panels(sources: &DtsSources) -> Vec<Panel> {
+    let mut panels: Vec<Panel> = Vec::new();
+
+    for node in sources.nodes.values() {
+        // Timing subnodes belong to the panel already collected
+        if panels.iter().any(|p| node.path.starts_with(&format!("{}/", p.node))) {
+            continue;
+        }
+        if !is_panel(sources, node) {
+            continue;
+        }
+
+        let mut panel = Panel {
+            node: node.path.clone(),
+            name: node.string("qcom,mdss-dsi-panel-name").map(|s| s.to_string()),
+            compatible: node.strings("compatible").into_iter().map(|s| s.to_string()).collect(),
+            locations: node.locations.clone(),
+            ..Default::default()
+        };
+
+        for part in std::iter::once(node).chain(sources.descendants(node)) {
+            if panel.width.is_none() {
+                panel.width = first_cell(part, &WIDTH_PROPERTIES);
+            }
+            if panel.height.is_none() {
+                panel.height = first_cell(part, &HEIGHT_PROPERTIES);
+            }
+            for property in REFRESH_PROPERTIES {
+                panel.refresh_rates.extend(part.cells(property));
+            }
+            // Timings overridden from another file show up as well
+            for location in &part.locations {
+                let file = location.rsplit_once(':').map_or(location.as_str(), |(file, _)| file);
+                if !panel.locations.iter().any(|l| l.starts_with(&format!("{}:", file))) {
+                    panel.locations.push(location.clone());
+                }
+            }
+        }
+
+        panel.refresh_rates.sort();
+        panel.refresh_rates.dedup();
+        panels.push(panel);
+    }
+
+    panels
+}
+
+fn is_panel(sources: &DtsSources, node: &DtsNode) -> bool {
+    if node.properties.contains_key("qcom,mdss-dsi-panel-name") || node.is_compatible_with("panel") {
+        return true;
+    }
+
+    // DSI controllers are "dsi"-compatible too; only count nodes that
+    // actually describe a mode
+    node.is_compatible_with("dsi")
+        && std::iter::once(node)
+            .chain(sources.descendants(node))
+            .any(|part| first_cell(part, &WIDTH_PROPERTIES).is_some() || first_cell(part, &REFRESH_PROPERTIES).is_some())
+}
+
+fn first_cell(node: &DtsNode, properties: &[&str]) -> Option<u64> {
+    properties.iter().find_map(|property| node.cells(property).first().copied())
+}
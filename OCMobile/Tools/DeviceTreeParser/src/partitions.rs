@@ -0,0 +1,126 @@
+use std::path::Path;
+use crate::fsread;
+use crate::makefile::{self, statements};
+use crate::walk::WalkOptions;
+
+// Static partition layout from BoardConfig.mk: BOARD_<NAME>_PARTITION_SIZE
+// and BOARD_<NAME>_FILE_SYSTEM_TYPE, what non-dynamic devices build their
+// images against
+#[derive(Debug, Default)]
+pub struct PartitionSizes {
+    // In the order each partition first appears
+    pub partitions: Vec<Partition>,
+    // BOARD_FLASH_BLOCK_SIZE, as assigned
+    pub flash_block_size: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Partition {
+    // "system", "vendor_boot", "dtbo", ...
+    pub name: String,
+    // The size as assigned, None when only a filesystem type is set
+    pub size: Option<String>,
+    // The size in bytes, when it's a plain decimal or 0x number
+    pub bytes: Option<u64>,
+    pub fs_type: Option<String>,
+    // file:line of the size, or of the filesystem type without one
+    pub location: String,
+}
+
+impl PartitionSizes {
+    pub fn is_empty(&self) -> bool {
+        self.partitions.is_empty() && self.flash_block_size.is_none()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Partition> {
+        self.partitions.iter().find(|partition| partition.name == name)
+    }
+}
+
+impl Partition {
+    // Why the size has no byte count, if it has a size at all
+    pub fn unresolved(&self) -> Option<&'static str> {
+        let size = self.size.as_deref()?;
+        if self.bytes.is_some() {
+            None
+        } else if size.contains("$(") {
+            Some("make expression, not evaluated")
+        } else {
+            Some("not a number")
+        }
+    }
+}
+
+pub fn scan_partitions(board_config: Option<&Path>, walk_options: &WalkOptions) -> PartitionSizes {
+    let mut sizes = PartitionSizes::default();
+
+    // Unreadable BoardConfig.mk is already reported by the driver scan
+    let Some(board_config) = board_config.filter(|path| walk_options.includes_file(path)) else {
+        return sizes;
+    };
+    let Ok(content) = fsread::read_to_string(board_config) else {
+        return sizes;
+    };
+    let file_name = board_config.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+    for (line, statement) in statements(&content) {
+        let Some(makefile::Assignment { name, value, .. }) = makefile::parse_assignment(&statement) else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"');
+        let location = format!("{}:{}", file_name, line);
+
+        if name == "BOARD_FLASH_BLOCK_SIZE" {
+            sizes.flash_block_size = Some(value.to_string());
+        } else if let Some(partition) = partition_name(name, "_PARTITION_SIZE") {
+            let partition = entry(&mut sizes, partition, &location);
+            partition.size = Some(value.to_string());
+            partition.bytes = parse_bytes(value);
+            partition.location = location;
+        } else if let Some(partition) = partition_name(name, "_FILE_SYSTEM_TYPE") {
+            entry(&mut sizes, partition, &location).fs_type = Some(value.to_string());
+        }
+    }
+
+    sizes
+}
+
+// BOARD_SYSTEMIMAGE_PARTITION_SIZE -> system, BOARD_DTBOIMG_PARTITION_SIZE
+// -> dtbo, BOARD_VENDOR_BOOTIMAGE_PARTITION_SIZE -> vendor_boot
+fn partition_name(variable: &str, suffix: &str) -> Option<String> {
+    let name = variable.strip_prefix("BOARD_")?.strip_suffix(suffix)?;
+    let name = name.strip_suffix("IMAGE").or_else(|| name.strip_suffix("IMG")).unwrap_or(name);
+    (!name.is_empty()).then(|| name.to_lowercase())
+}
+
+fn entry<'a>(sizes: &'a mut PartitionSizes, name: String, location: &str) -> &'a mut Partition {
+    let index = match sizes.partitions.iter().position(|partition| partition.name == name) {
+        Some(index) => index,
+        None => {
+            sizes.partitions.push(Partition {
+                name,
+                size: None,
+                bytes: None,
+                fs_type: None,
+                location: location.to_string(),
+            });
+            sizes.partitions.len() - 1
+        }
+    };
+    &mut sizes.partitions[index]
+}
+
+fn parse_bytes(value: &str) -> Option<u64> {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+// 3221225472 -> "3.00 GiB"; below 1 GiB in MiB
+pub fn human_size(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    const GIB: f64 = MIB * 1024.0;
+    let bytes = bytes as f64;
+    if bytes >= GIB { format!("{:.2} GiB", bytes / GIB) } else { format!("{:.2} MiB", bytes / MIB) }
+}
@@ -0,0 +1,219 @@
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use xml::attribute::OwnedAttribute;
+use xml::reader::{EventReader, XmlEvent};
+use crate::fsread;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Qualcomm perf HAL configs: boost profiles (perfboostsconfig.xml), power
+// HAL perf locks (powerhint.xml) and the perf config store
+const PERF_FILES: [&str; 3] = ["perfboostsconfig.xml", "powerhint.xml", "perfconfigstore.xml"];
+
+// VENDOR_HINT_* IDs from the perf HAL's hint list, the boosts a build is
+// usually checked for
+const HINT_NAMES: [(u32, &str); 12] = [
+    (0x1080, "VENDOR_HINT_SCROLL_BOOST"),
+    (0x1081, "VENDOR_HINT_FIRST_LAUNCH_BOOST"),
+    (0x1082, "VENDOR_HINT_SUBSEQ_LAUNCH_BOOST"),
+    (0x1083, "VENDOR_HINT_ANIM_BOOST"),
+    (0x1084, "VENDOR_HINT_ACTIVITY_BOOST"),
+    (0x1085, "VENDOR_HINT_TOUCH_BOOST"),
+    (0x1086, "VENDOR_HINT_MTP_BOOST"),
+    (0x1087, "VENDOR_HINT_DRAG_BOOST"),
+    (0x1088, "VENDOR_HINT_PACKAGE_INSTALL_BOOST"),
+    (0x1089, "VENDOR_HINT_ROTATION_LATENCY_BOOST"),
+    (0x1090, "VENDOR_HINT_ROTATION_ANIM_BOOST"),
+    (0x1091, "VENDOR_HINT_PERFORMANCE_MODE"),
+];
+
+// Boost profiles and perf properties from the perf HAL's XML configs.
+// Vendors nest these differently, so any element with a Resources attribute
+// is a boost and any with Name and Value is a property, wherever it sits.
+#[derive(Debug, Default)]
+pub struct PerfTuning {
+    pub files: Vec<PerfFile>,
+    pub boosts: Vec<Boost>,
+    pub properties: Vec<PerfProperty>,
+}
+
+#[derive(Debug)]
+pub struct PerfFile {
+    pub file: String,
+    pub boosts: usize,
+    pub properties: usize,
+}
+
+#[derive(Debug)]
+pub struct Boost {
+    // Hint ID as written, e.g. "0x00001081"
+    pub id: String,
+    // The Name attribute, else the VENDOR_HINT_* name for a known ID
+    pub name: Option<String>,
+    // Sub-type of the hint (launch boost type 1/2, ...)
+    pub kind: Option<String>,
+    pub enabled: bool,
+    pub timeout: Option<String>,
+    // SoC the boost applies to ("msmnile"); None for every target
+    pub target: Option<String>,
+    pub resolution: Option<String>,
+    // (opcode, value) perf lock pairs, as written
+    pub resources: Vec<(String, String)>,
+    pub file: String,
+}
+
+#[derive(Debug)]
+pub struct PerfProperty {
+    pub name: String,
+    pub value: String,
+    pub target: Option<String>,
+    pub file: String,
+}
+
+impl PerfTuning {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn enabled_count(&self) -> usize {
+        self.boosts.iter().filter(|boost| boost.enabled).count()
+    }
+
+    pub fn named(&self) -> Vec<&Boost> {
+        self.boosts.iter().filter(|boost| boost.name.is_some()).collect()
+    }
+
+    // Targets any boost or property is limited to, sorted
+    pub fn targets(&self) -> Vec<&str> {
+        let boosts = self.boosts.iter().filter_map(|boost| boost.target.as_deref());
+        let properties = self.properties.iter().filter_map(|property| property.target.as_deref());
+        let mut targets: Vec<&str> = boosts
+            .chain(properties)
+            .flat_map(|target| target.split(','))
+            .map(str::trim)
+            .filter(|target| !target.is_empty())
+            .collect();
+        targets.sort();
+        targets.dedup();
+        targets
+    }
+
+    // Every perf lock opcode the boosts use, lowercased and sorted
+    pub fn opcodes(&self) -> Vec<String> {
+        let mut opcodes: Vec<String> = self
+            .boosts
+            .iter()
+            .flat_map(|boost| &boost.resources)
+            .map(|(opcode, _)| opcode.to_lowercase())
+            .collect();
+        opcodes.sort();
+        opcodes.dedup();
+        opcodes
+    }
+}
+
+impl Boost {
+    // "VENDOR_HINT_SCROLL_BOOST (0x00001080)", or the ID alone
+    pub fn label(&self) -> String {
+        let label = match &self.name {
+            Some(name) => format!("{} ({})", name, self.id),
+            None => self.id.clone(),
+        };
+        match &self.kind {
+            Some(kind) if !kind.is_empty() => format!("{} type {}", label, kind),
+            _ => label,
+        }
+    }
+}
+
+pub fn scan_perf_tuning(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> PerfTuning {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| PERF_FILES.contains(&name), &mut files);
+    files.sort();
+
+    let mut perf = PerfTuning::default();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let (boosts, properties) = (perf.boosts.len(), perf.properties.len());
+        if let Err(e) = parse_perf_xml(&file, &relative, &mut perf) {
+            warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+            continue;
+        }
+        perf.files.push(PerfFile {
+            file: relative,
+            boosts: perf.boosts.len() - boosts,
+            properties: perf.properties.len() - properties,
+        });
+    }
+
+    perf
+}
+
+fn parse_perf_xml(path: &Path, relative: &str, perf: &mut PerfTuning) -> io::Result<()> {
+    // <BoostConfigs><PerfBoost>
+    //   <Config Id="0x00001081" Type="1" Enable="true" Timeout="0" Target="msmnile"
+    //       Resources="0x40C00000, 0x1, 0x40804000, 0xFFF" />
+    // </PerfBoost></BoostConfigs>
+    // <PerfConfigsStore><PerfConfigs>
+    //   <Prop Name="ro.vendor.perf.ss" Value="true" />
+    // </PerfConfigs></PerfConfigsStore>
+    let file = fsread::open(path)?;
+
+    for event in EventReader::new(BufReader::new(file)) {
+        match event {
+            Ok(XmlEvent::StartElement { attributes, .. }) => {
+                let target = attribute(&attributes, "target").map(str::to_string);
+                if let Some(resources) = attribute(&attributes, "resources") {
+                    let id = attribute(&attributes, "id").unwrap_or("?").to_string();
+                    let name = attribute(&attributes, "name").map(str::to_string).or_else(|| hint_name(&id));
+                    perf.boosts.push(Boost {
+                        name,
+                        kind: attribute(&attributes, "type").map(str::to_string),
+                        // Enable is left out as often as it's "true"
+                        enabled: attribute(&attributes, "enable").is_none_or(|enable| enable != "false"),
+                        timeout: attribute(&attributes, "timeout").map(str::to_string),
+                        target,
+                        resolution: attribute(&attributes, "resolution").map(str::to_string),
+                        resources: resource_pairs(resources),
+                        file: relative.to_string(),
+                        id,
+                    });
+                } else if let Some(name) = attribute(&attributes, "name")
+                    && let Some(value) = attribute(&attributes, "value")
+                {
+                    perf.properties.push(PerfProperty {
+                        name: name.to_string(),
+                        value: value.to_string(),
+                        target,
+                        file: relative.to_string(),
+                    });
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    Ok(())
+}
+
+// "0x40C00000, 0x1, 0x40804000, 0xFFF" -> [(0x40C00000, 0x1), ...]; a
+// trailing opcode without a value is kept with an empty one
+fn resource_pairs(resources: &str) -> Vec<(String, String)> {
+    let values: Vec<&str> =
+        resources.split(|c: char| c == ',' || c.is_whitespace()).filter(|value| !value.is_empty()).collect();
+    values
+        .chunks(2)
+        .map(|pair| (pair[0].to_string(), pair.get(1).map_or(String::new(), |value| value.to_string())))
+        .collect()
+}
+
+fn hint_name(id: &str) -> Option<String> {
+    let id = u32::from_str_radix(id.trim_start_matches("0x").trim_start_matches("0X"), 16).ok()?;
+    HINT_NAMES.iter().find(|(hint, _)| *hint == id).map(|(_, name)| name.to_string())
+}
+
+// Attribute names vary in case between vendors (Id/ID/id)
+fn attribute<'a>(attributes: &'a [OwnedAttribute], name: &str) -> Option<&'a str> {
+    attributes.iter().find(|a| a.name.local_name.eq_ignore_ascii_case(name)).map(|a| a.value.as_str())
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use xml::reader::{EventReader, XmlEvent};
+use crate::fsread;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+#[derive(Debug, Default)]
+pub struct PowerConfig {
+    pub hint_files: Vec<String>,
+    // Sysfs nodes the power HAL controls (powerhint.json "Nodes")
+    pub hint_nodes: Vec<String>,
+    // Power hints/states with actions defined (powerhint.json "Actions")
+    pub hints: Vec<String>,
+    pub profile_files: Vec<String>,
+    // power_profile.xml component -> value(s), e.g. screen.on -> 100
+    pub profile: BTreeMap<String, String>,
+}
+
+impl PowerConfig {
+    pub fn is_empty(&self) -> bool {
+        self.hint_files.is_empty() && self.profile_files.is_empty()
+    }
+}
+
+pub fn scan_power_config(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> PowerConfig {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| (name.starts_with("powerhint") && name.ends_with(".json")) || name == "power_profile.xml",
+        &mut files,
+    );
+    files.sort();
+
+    let mut config = PowerConfig::default();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+
+        let parsed = if relative.ends_with(".json") {
+            config.hint_files.push(relative.clone());
+            parse_powerhint_json(&file, &mut config)
+        } else {
+            config.profile_files.push(relative.clone());
+            parse_power_profile_xml(&file, &mut config.profile).map_err(|e| e.to_string())
+        };
+
+        if let Err(e) = parsed {
+            warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e));
+        }
+    }
+
+    config.hint_nodes.sort();
+    config.hint_nodes.dedup();
+    config.hints.sort();
+    config.hints.dedup();
+
+    config
+}
+
+fn parse_powerhint_json(path: &Path, config: &mut PowerConfig) -> Result<(), String> {
+    // {"Nodes": [{"Name": "CPULittleClusterMaxFreq", ...}],
+    //  "Actions": [{"PowerHint": "SUSTAINED_PERFORMANCE", "Node": ..., ...}]}
+    // Anything else (vendor extensions) is ignored.
+    let content = fsread::read_to_string(path).map_err(|e| e.to_string())?;
+    let parsed = json::parse(&content).map_err(|e| format!("invalid JSON: {}", e))?;
+
+    for node in parsed["Nodes"].members() {
+        if let Some(name) = node["Name"].as_str() {
+            config.hint_nodes.push(name.to_string());
+        }
+    }
+
+    for action in parsed["Actions"].members() {
+        if let Some(hint) = action["PowerHint"].as_str() {
+            config.hints.push(hint.to_string());
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_power_profile_xml(path: &Path, profile: &mut BTreeMap<String, String>) -> io::Result<()> {
+    // <item name="screen.on">100</item>
+    // <array name="cpu.core_speeds.cluster0"><value>300000</value>...</array>
+    let file = fsread::open(path)?;
+
+    let mut current_item: Option<String> = None;
+    let mut current_array: Option<(String, Vec<String>)> = None;
+    let mut text = String::new();
+
+    for event in EventReader::new(BufReader::new(file)) {
+        match event {
+            Ok(XmlEvent::StartElement { name, attributes, .. }) => {
+                text.clear();
+                let name_attr = attributes.iter().find(|a| a.name.local_name == "name").map(|a| a.value.clone());
+                match name.local_name.as_str() {
+                    "item" => current_item = name_attr,
+                    "array" => current_array = name_attr.map(|n| (n, Vec::new())),
+                    _ => {}
+                }
+            }
+            Ok(XmlEvent::Characters(chars)) => text.push_str(&chars),
+            Ok(XmlEvent::EndElement { name }) => match name.local_name.as_str() {
+                "item" => {
+                    if let Some(item) = current_item.take() {
+                        profile.insert(item, text.trim().to_string());
+                    }
+                }
+                "value" => {
+                    if let Some((_, values)) = current_array.as_mut() {
+                        values.push(text.trim().to_string());
+                    }
+                }
+                "array" => {
+                    if let Some((array, values)) = current_array.take() {
+                        profile.insert(array, values.join(", "));
+                    }
+                }
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    Ok(())
+}
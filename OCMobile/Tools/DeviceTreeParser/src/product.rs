@@ -0,0 +1,273 @@
+// Product identity: the PRODUCT_NAME/DEVICE/BRAND/MODEL/MANUFACTURER a lunch
+// target builds with. They're set in the product makefiles
+// AndroidProducts.mk lists, or in makefiles those inherit, so every
+// inherit-product/include that resolves into the tree is followed.
+//
+// Only the tree is read. Inherited makefiles from the platform or a vendor
+// config (e.g. $(SRC_TARGET_DIR)/product/full_base.mk) are skipped; their
+// defaults are rarely what a device sets anyway.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::makefile::{self, AssignOp, statements};
+use crate::warning::{Warning, WarningKind};
+
+// Variable -> device_info key
+pub const PRODUCT_VARIABLES: [(&str, &str); 5] = [
+    ("PRODUCT_NAME", "product_name"),
+    ("PRODUCT_DEVICE", "product_device"),
+    ("PRODUCT_BRAND", "product_brand"),
+    ("PRODUCT_MODEL", "product_model"),
+    ("PRODUCT_MANUFACTURER", "product_manufacturer"),
+];
+
+// $(VAR)s that name the directory of the makefile being read
+const DIR_VARIABLES: [&str; 3] = ["LOCAL_PATH", "LOCAL_DIR", "DEVICE_PATH"];
+
+// Inherited makefiles nested deeper than this are skipped, so an include
+// loop can't run away
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+#[derive(Debug, Default)]
+pub struct ProductIdentity {
+    // device_info key -> value
+    pub values: Vec<(&'static str, String)>,
+    // Keys only set inside ifeq/ifdef blocks, which aren't evaluated; the
+    // first such value is used
+    pub conditional: Vec<&'static str>,
+    // The product makefile the values were read from, relative to the tree
+    pub makefile: Option<String>,
+}
+
+// What one makefile (and everything it inherits) sets
+#[derive(Default)]
+struct Scope {
+    values: HashMap<&'static str, String>,
+    conditional: BTreeSet<&'static str>,
+    // Any variable assigned so far, for expanding $(VAR) references
+    variables: HashMap<String, String>,
+}
+
+pub fn product_identity(
+    tree_path: &Path,
+    android_products: Option<&Path>,
+    device_mk: Option<&Path>,
+    warnings: &mut Vec<Warning>,
+) -> ProductIdentity {
+    let mut identity = ProductIdentity::default();
+
+    // PRODUCT_MAKEFILES := $(LOCAL_DIR)/lineage_raphael.mk, or
+    // lineage_raphael:$(LOCAL_DIR)/lineage_raphael.mk; without any, the
+    // tree's device.mk is the best guess
+    let mut makefiles: Vec<PathBuf> = Vec::new();
+    let mut own = Scope::default();
+    if let Some(android_products) = android_products {
+        match fsread::read_to_string(android_products) {
+            Ok(content) => {
+                let dir = android_products.parent().unwrap_or(tree_path);
+                for (_, statement) in statements(&content) {
+                    let Some(assignment) = makefile::parse_assignment(&statement) else {
+                        continue;
+                    };
+                    if assignment.name == "PRODUCT_MAKEFILES" {
+                        for entry in assignment.value.split_whitespace() {
+                            let path = match entry.split_once(':') {
+                                Some((name, path)) if !name.contains('$') => path,
+                                _ => entry,
+                            };
+                            if let Some(path) = resolve(tree_path, dir, path, &own.variables) {
+                                makefiles.push(path);
+                            }
+                        }
+                    } else {
+                        assign(&mut own, assignment, dir, false);
+                    }
+                }
+            }
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, "AndroidProducts.mk", e.to_string())),
+        }
+    }
+    if makefiles.is_empty() {
+        makefiles.extend(device_mk.map(Path::to_path_buf));
+    }
+
+    // The first product makefile that sets anything is the product;
+    // AndroidProducts.mk's own assignments fill in what it doesn't
+    let mut visited = BTreeSet::new();
+    for makefile in makefiles {
+        let scope = evaluate(tree_path, &makefile, 0, &mut visited, warnings);
+        if !scope.values.is_empty() {
+            identity.makefile = Some(relative(tree_path, &makefile));
+            inherit(&mut own, scope);
+            break;
+        }
+    }
+
+    for (_, key) in PRODUCT_VARIABLES {
+        if let Some(value) = own.values.remove(key) {
+            identity.values.push((key, value));
+            if own.conditional.contains(key) {
+                identity.conditional.push(key);
+            }
+        }
+    }
+    identity
+}
+
+fn evaluate(
+    tree_path: &Path,
+    path: &Path,
+    depth: usize,
+    visited: &mut BTreeSet<PathBuf>,
+    warnings: &mut Vec<Warning>,
+) -> Scope {
+    let mut scope = Scope::default();
+    if depth > MAX_INCLUDE_DEPTH || !visited.insert(path.to_path_buf()) {
+        return scope;
+    }
+    let content = match fsread::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            warnings.push(Warning::new(WarningKind::UnreadableFile, relative(tree_path, path), e.to_string()));
+            return scope;
+        }
+    };
+    let dir = path.parent().unwrap_or(tree_path);
+
+    // inherit-product values only apply where this makefile sets nothing,
+    // wherever the call is; include is read in place
+    let mut inherited: Vec<Scope> = Vec::new();
+    let mut conditional_depth = 0usize;
+    for (_, statement) in statements(&content) {
+        let first = statement.split_whitespace().next().unwrap_or_default();
+        match first {
+            "ifeq" | "ifneq" | "ifdef" | "ifndef" => {
+                conditional_depth += 1;
+                continue;
+            }
+            "endif" => {
+                conditional_depth = conditional_depth.saturating_sub(1);
+                continue;
+            }
+            "else" => continue,
+            "include" | "-include" | "sinclude" => {
+                let target = statement[first.len()..].trim();
+                if let Some(included) = resolve(tree_path, dir, target, &scope.variables) {
+                    let included = evaluate(tree_path, &included, depth + 1, visited, warnings);
+                    scope.variables.extend(included.variables);
+                    for (key, value) in included.values {
+                        set(&mut scope, key, value, included.conditional.contains(key) || conditional_depth > 0);
+                    }
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        // $(call inherit-product, $(LOCAL_PATH)/device.mk) and the
+        // -if-exists variant
+        if let Some(call) = statement.strip_prefix("$(call ")
+            && let Some((function, argument)) = call.strip_suffix(')').unwrap_or(call).split_once(',')
+            && function.trim().starts_with("inherit-product")
+        {
+            if let Some(child) = resolve(tree_path, dir, argument.trim(), &scope.variables) {
+                inherited.push(evaluate(tree_path, &child, depth + 1, visited, warnings));
+            }
+            continue;
+        }
+
+        if let Some(assignment) = makefile::parse_assignment(&statement) {
+            assign(&mut scope, assignment, dir, conditional_depth > 0);
+        }
+    }
+
+    for child in inherited {
+        inherit(&mut scope, child);
+    }
+    scope
+}
+
+fn assign(scope: &mut Scope, assignment: makefile::Assignment<'_>, dir: &Path, conditional: bool) {
+    let makefile::Assignment { name, op, value } = assignment;
+    // LOCAL_PATH := $(call my-dir)
+    let value = if value.starts_with("$(call my-dir)") {
+        dir.to_string_lossy().to_string()
+    } else {
+        expand(value, dir, &scope.variables)
+    };
+    let value = match (op, scope.variables.get(name)) {
+        (AssignOp::Conditional, Some(_)) => return,
+        (AssignOp::Append, Some(current)) => format!("{} {}", current, value).trim().to_string(),
+        _ => value,
+    };
+    scope.variables.insert(name.to_string(), value.clone());
+    if let Some((_, key)) = PRODUCT_VARIABLES.iter().find(|(variable, _)| *variable == name) {
+        set(scope, key, value, conditional);
+    }
+}
+
+// An unconditional value replaces anything; a conditional one only fills a
+// gap, so the first branch's value stands in for the whole block
+fn set(scope: &mut Scope, key: &'static str, value: String, conditional: bool) {
+    if conditional {
+        if scope.values.contains_key(key) {
+            return;
+        }
+        scope.conditional.insert(key);
+    } else {
+        scope.conditional.remove(key);
+    }
+    scope.values.insert(key, value);
+}
+
+fn inherit(scope: &mut Scope, child: Scope) {
+    for (key, value) in child.values {
+        if !scope.values.contains_key(key) {
+            if child.conditional.contains(key) {
+                scope.conditional.insert(key);
+            }
+            scope.values.insert(key, value);
+        }
+    }
+}
+
+// $(VAR) and ${VAR} references to variables set so far, and to the
+// makefile's own directory; anything else is left as written
+fn expand(value: &str, dir: &Path, variables: &HashMap<String, String>) -> String {
+    makefile::expand(value, &|name| {
+        if DIR_VARIABLES.contains(&name) && !variables.contains_key(name) {
+            Some(dir.to_string_lossy().to_string())
+        } else {
+            variables.get(name).cloned()
+        }
+    })
+}
+
+// A makefile path as written to a file in the tree, if it's there.
+// Paths from the source root (device/xiaomi/raphael/lineage.mk) resolve
+// when the tree is that directory.
+fn resolve(tree_path: &Path, dir: &Path, target: &str, variables: &HashMap<String, String>) -> Option<PathBuf> {
+    let target = expand(target, dir, variables);
+    if target.contains('$') {
+        return None;
+    }
+    let target = Path::new(&target);
+    let candidates = [dir.join(target), tree_path.join(target)];
+    if let Some(found) = candidates.into_iter().find(|path| path.is_file()) {
+        return Some(found);
+    }
+
+    let tree = std::fs::canonicalize(tree_path).unwrap_or_else(|_| tree_path.to_path_buf());
+    let components: Vec<_> = target.components().collect();
+    (1..components.len()).find_map(|split| {
+        let prefix: PathBuf = components[..split].iter().collect();
+        let rest: PathBuf = components[split..].iter().collect();
+        let path = tree.join(rest);
+        (tree.ends_with(&prefix) && path.is_file()).then_some(path)
+    })
+}
+
+fn relative(tree_path: &Path, path: &Path) -> String {
+    path.strip_prefix(tree_path).unwrap_or(path).to_string_lossy().to_string()
+}
@@ -0,0 +1,37 @@
+use std::time::{Duration, Instant};
+
+// Wall-clock time per analysis phase, printed with --profile
+#[derive(Debug, Default)]
+pub struct Profiler {
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Profiler {
+    pub fn time<T>(&mut self, phase: &'static str, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        self.record(phase, started);
+        result
+    }
+
+    // For phases that span more than one expression
+    pub fn record(&mut self, phase: &'static str, started: Instant) {
+        self.phases.push((phase, started.elapsed()));
+    }
+
+    // Phase/time table on stderr, so it never mixes with report output
+    pub fn print(&self) {
+        let width = self.phases.iter().map(|(phase, _)| phase.len()).max().unwrap_or(0).max("Total".len());
+        let total: Duration = self.phases.iter().map(|(_, elapsed)| *elapsed).sum();
+
+        eprintln!("\n=== Profile ===");
+        for (phase, elapsed) in &self.phases {
+            eprintln!("  {:<width$}  {:>10.3} ms", phase, millis(*elapsed), width = width);
+        }
+        eprintln!("  {:<width$}  {:>10.3} ms", "Total", millis(total), width = width);
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
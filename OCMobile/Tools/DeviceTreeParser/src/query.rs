@@ -0,0 +1,304 @@
+// Single-property lookups on a HardwareReport, shared by --assert and
+// anything else that addresses report values by name.
+//
+// Grammar:
+//
+//   path  = field [ "." key ]
+//   field = a report field name: structure_valid, device_info, drivers, ...
+//   key   = everything after the first ".", taken verbatim
+//
+// Only the first '.' separates, so keys may contain dots, spaces, slashes
+// and parentheses: key_files.BoardConfig.mk, drivers.WiFi Driver,
+// drivers.GPU/Platform, drivers.HAL (Hardware Abstraction Layer),
+// vendor_flags.persist.vendor.radio.foo. Fields and keys are matched
+// exactly (case-sensitive).
+//
+// A field alone gives the whole field (a map's size, a list's items); with
+// a key it gives one entry or sub-field. Unknown fields and keys give None.
+
+use crate::HardwareReport;
+use crate::gsi;
+
+// A value addressed by a path, borrowed from the report
+#[derive(Debug, PartialEq)]
+pub enum Value<'a> {
+    Bool(bool),
+    Str(&'a str),
+    // Items in report order (driver entries sorted and deduped)
+    List(Vec<&'a str>),
+    // A keyed field addressed without a key: its number of entries
+    Map(usize),
+}
+
+impl Value<'_> {
+    pub fn is_present(&self) -> bool {
+        match self {
+            Value::Bool(b) => *b,
+            Value::Str(s) => !s.is_empty(),
+            Value::List(items) => !items.is_empty(),
+            Value::Map(len) => *len > 0,
+        }
+    }
+
+    pub fn count(&self) -> Option<usize> {
+        match self {
+            Value::List(items) => Some(items.len()),
+            Value::Map(len) => Some(*len),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Value::Bool(b) => b.to_string(),
+            Value::Str(s) => format!("\"{}\"", s),
+            Value::List(items) => format!("[{}]", items.join(", ")),
+            Value::Map(len) => format!("{} entries", len),
+        }
+    }
+}
+
+impl HardwareReport {
+    // The value at `path`, e.g. get("device_info.vendor"),
+    // get("drivers.WiFi Driver"), get("structure_valid")
+    pub fn get(&self, path: &str) -> Option<Value<'_>> {
+        lookup(self, path)
+    }
+}
+
+fn lookup<'a>(report: &'a HardwareReport, path: &str) -> Option<Value<'a>> {
+    let (field, key) = match path.split_once('.') {
+        Some((field, key)) => (field, Some(key)),
+        None => (path, None),
+    };
+
+    match (field, key) {
+        ("structure_valid", None) => Some(Value::Bool(report.structure_valid)),
+        ("recursive", None) => Some(Value::Bool(report.max_depth != Some(0))),
+        ("device_info", None) => Some(Value::Map(report.device_info.len())),
+        ("device_info", Some(key)) => report.device_info.get(key).map(|v| Value::Str(v)),
+        ("key_files", None) => Some(Value::Map(report.key_files.len())),
+        ("key_files", Some(key)) => report.key_files.get(key).map(|found| Value::Bool(*found)),
+        ("key_dirs", None) => Some(Value::Map(report.key_dirs.len())),
+        ("key_dirs", Some(key)) => report.key_dirs.get(key).map(|found| Value::Bool(*found)),
+        ("drivers", None) => Some(Value::Map(report.drivers.len())),
+        ("drivers", Some(category)) => report.drivers.get(category).map(|list| {
+            let mut entries: Vec<&str> = list.iter().map(|s| s.as_str()).collect();
+            entries.sort();
+            entries.dedup();
+            Value::List(entries)
+        }),
+        ("wifi", None) => Some(Value::Map(report.wifi.fields().len())),
+        ("wifi", Some(key)) => match key {
+            "chipset" => report.wifi.chipset.as_deref().map(Value::Str),
+            "supplicant_version" => report.wifi.supplicant_version.as_deref().map(Value::Str),
+            "hostapd_driver" => report.wifi.hostapd_driver.as_deref().map(Value::Str),
+            "driver_module" => report.wifi.driver_module.as_deref().map(Value::Str),
+            "bands" => Some(Value::List(report.wifi.configs.bands())),
+            "country_codes" => Some(Value::List(report.wifi.configs.country_codes())),
+            "ap_standards" => Some(Value::List(report.wifi.configs.ap_standards())),
+            "config_files" => Some(Value::List(report.wifi.configs.files.iter().map(|f| f.file.as_str()).collect())),
+            _ => match report.wifi.driver_vars.get(key) {
+                Some(value) => Some(Value::Str(value)),
+                // Any driver INI/hostapd/supplicant setting, e.g. wifi.gDot11Mode
+                None => report.wifi.configs.get(key).map(|(value, _)| Value::Str(value)),
+            },
+        },
+        ("treble", Some("vndk_version")) => report.treble.vndk_version.as_deref().map(Value::Str),
+        ("treble", Some("compatible")) => Some(Value::Bool(report.treble.is_compatible())),
+        ("treble", Some("vndk_dirs")) => Some(Value::List(report.treble.vndk_dirs.iter().map(|s| s.as_str()).collect())),
+        ("gsi", None) => {
+            Some(Value::Bool(gsi::likely_to_boot(&gsi::checklist(&report.treble, &report.boot, &report.gsi))))
+        }
+        ("gsi", Some("target_fcm_version")) => report.gsi.target_fcm().map(|(level, _)| Value::Str(level)),
+        ("apex", None) => Some(Value::List(report.apex.modules.iter().map(|m| m.name.as_str()).collect())),
+        ("linker_namespaces", None) => Some(Value::List(
+            report.linker_namespaces.public_libraries.iter().flat_map(|l| &l.libraries).map(|s| s.as_str()).collect(),
+        )),
+        ("linker_namespaces", Some(section)) => report.linker_namespaces.configs.iter().find_map(|config| {
+            let section = config.sections.iter().find(|s| s.name == section)?;
+            Some(Value::List(section.namespaces.iter().map(|n| n.name.as_str()).collect()))
+        }),
+        ("merge", Some("inherited_sections")) => report.merge.as_ref().map(|merge| {
+            Value::List(merge.inherited_sections.iter().map(|s| s.as_str()).collect())
+        }),
+        ("apex", Some("prebuilt")) => Some(Value::List(
+            report.apex.modules.iter().filter(|m| m.prebuilt).map(|m| m.name.as_str()).collect(),
+        )),
+        ("boot", Some("scheme")) => Some(Value::Str(report.boot.scheme())),
+        ("partitions", None) => Some(Value::List(
+            report.partition_sizes.partitions.iter().map(|p| p.name.as_str()).collect(),
+        )),
+        ("partitions", Some(name)) => report.partition_sizes.get(name).and_then(|p| p.size.as_deref()).map(Value::Str),
+        ("encryption", None) => report.encryption.mode().map(|mode| Value::Str(mode.name())),
+        ("encryption", Some("metadata")) => Some(Value::Bool(
+            report.encryption.data.iter().any(|data| data.metadata_cipher.is_some()),
+        )),
+        ("boot", Some("ab_partitions")) => Some(Value::List(report.boot.ab_partitions.iter().map(|s| s.as_str()).collect())),
+        ("modem", Some("versions")) => Some(Value::List(
+            report.modem.firmware.iter().filter_map(|f| f.version.as_deref()).collect(),
+        )),
+        ("modem", Some("firmware")) => Some(Value::List(report.modem.firmware.iter().map(|f| f.file.as_str()).collect())),
+        ("telephony", None) => Some(Value::Map(report.telephony.properties.len() + report.telephony.resources.len())),
+        ("telephony", Some("ril_class")) => report.telephony.ril_class().map(Value::Str),
+        ("telephony", Some(name)) => {
+            let telephony = &report.telephony;
+            telephony.properties.get(name).or_else(|| telephony.resources.get(name)).map(|v| Value::Str(&v.value))
+        }
+        ("module_consistency", Some("unreferenced")) => Some(Value::List(
+            report.module_consistency.unreferenced.iter().map(|s| s.as_str()).collect(),
+        )),
+        ("module_consistency", Some("missing")) => Some(Value::List(
+            report.module_consistency.missing.iter().map(|m| m.name.as_str()).collect(),
+        )),
+        ("kernel", Some("source")) => report.kernel.source.as_deref().map(Value::Str),
+        ("kernel", Some("version")) => report.kernel.version.as_deref().map(Value::Str),
+        ("kernel", Some("defconfigs")) => Some(Value::List(report.kernel.defconfigs.iter().map(|s| s.as_str()).collect())),
+        ("dts_sources", None) | ("dts_sources", Some("files")) => Some(Value::List(
+            report.dts_sources.files.iter().map(|s| s.as_str()).collect(),
+        )),
+        ("dts_sources", Some("unused_includes")) => Some(Value::List(
+            report.dts_sources.unused_includes().into_iter().map(|s| s.as_str()).collect(),
+        )),
+        ("aliases", None) => Some(Value::Map(report.dts_sources.aliases().len())),
+        ("aliases", Some(name)) => report
+            .dts_sources
+            .aliases()
+            .into_iter()
+            .find(|alias| alias.name == name)
+            .map(|alias| Value::Str(alias.target.unwrap_or(alias.value))),
+        ("chosen", None) => report.dts_sources.chosen().map(|chosen| Value::Map(chosen.properties.len())),
+        ("chosen", Some(property)) => {
+            report.dts_sources.chosen().and_then(|chosen| chosen.display_value(property)).map(Value::Str)
+        }
+        ("dtb_overlays", None) => Some(Value::List(
+            report.dtb_overlays.overlays.iter().map(|o| o.file.as_str()).collect(),
+        )),
+        ("warnings", None) => Some(Value::Map(report.warnings.len())),
+        ("panels", None) => Some(Value::List(report.panels.iter().map(|p| p.display_name()).collect())),
+        ("touchscreens", None) => Some(Value::List(report.touchscreens.iter().map(|t| t.vendor.as_str()).collect())),
+        ("key_layouts", None) => Some(Value::List(
+            report.key_layouts.files.iter().map(|l| l.file.as_str()).collect(),
+        )),
+        ("power_tree", None) => Some(Value::List(report.power_tree.iter().map(|c| c.node.as_str()).collect())),
+        ("battery", Some("controllers")) => Some(Value::List(
+            report.battery.controllers.iter().map(|c| c.binding.as_str()).collect(),
+        )),
+        ("battery", Some("profiles")) => Some(Value::List(
+            report.battery.profiles.iter().filter_map(|p| p.battery_type.as_deref()).collect(),
+        )),
+        ("features", None) => Some(Value::List(
+            report.declared_features.available().iter().map(|f| f.name.as_str()).collect(),
+        )),
+        ("features", Some("missing_hals")) => Some(Value::List(
+            report.declared_features.missing_hals(&report.hals).into_iter().map(|(_, family)| family).collect(),
+        )),
+        ("mixer_paths", None) => Some(Value::List(
+            report.mixer_paths.files.iter().flat_map(|f| &f.paths).map(|p| p.name.as_str()).collect(),
+        )),
+        ("mixer_paths", Some("variants")) => Some(Value::List(
+            report.mixer_paths.files.iter().map(|f| f.variant_name()).collect(),
+        )),
+        ("usb", None) => Some(Value::List(report.usb.functions())),
+        ("usb", Some("default")) => Some(Value::List(report.usb.default_functions())),
+        ("usb", Some("adb")) => report.usb.adb_by_default().map(Value::Bool),
+        ("nfc", None) => Some(Value::List(report.nfc.devices.iter().map(|d| d.vendor.as_str()).collect())),
+        ("nfc", Some("config_files")) => Some(Value::List(
+            report.nfc.config_files.iter().map(|c| c.file.as_str()).collect(),
+        )),
+        ("perf", None) => Some(Value::List(
+            report.perf_tuning.boosts.iter().filter_map(|boost| boost.name.as_deref()).collect(),
+        )),
+        ("perf", Some("targets")) => Some(Value::List(report.perf_tuning.targets())),
+        ("cpu_topology", None) => Some(Value::List(
+            report.cpu_topology.groups.keys().map(|name| name.as_str()).collect(),
+        )),
+        ("vendor_flags", None) => Some(Value::Map(report.vendor_flags.flags.len())),
+        ("vendor_flags", Some(name)) => report.vendor_flags.flags.get(name).map(|flag| Value::Str(&flag.value)),
+        ("camera", None) => Some(Value::List(report.camera.sensors.iter().map(|s| s.model.as_str()).collect())),
+        ("camera", Some("sensor_modules")) => Some(Value::List(
+            report.camera.sensor_libs.iter().map(|s| s.as_str()).collect(),
+        )),
+        ("haptics", None) => Some(Value::List(report.haptics.controllers.iter().map(|c| c.vendor.as_str()).collect())),
+        ("biometrics", None) => Some(Value::List(
+            report.biometrics.sensors.iter().map(|sensor| sensor.vendor.as_str()).collect(),
+        )),
+        ("biometrics", Some("kinds")) => Some(Value::List(
+            report.biometrics.sensors.iter().map(|sensor| sensor.kind.as_str()).collect(),
+        )),
+        ("boot_sequence", None) => Some(Value::Map(report.boot_sequence.triggers.len())),
+        ("boot_sequence", Some(trigger)) => report
+            .boot_sequence
+            .triggers
+            .iter()
+            .any(|t| t.name == trigger)
+            .then(|| Value::List(report.boot_sequence.services_on(trigger))),
+        ("drm", None) => Some(Value::List(report.drm.hal_packages.iter().map(|p| p.as_str()).collect())),
+        ("drm", Some("l1")) => report.drm.level().number().map(|level| Value::Bool(level == 1)),
+        ("tee", None) => report.tee.tee_type().map(|kind| Value::Str(kind.as_str())),
+        ("tee", Some("keymaster")) => {
+            Some(Value::List(report.tee.keymaster_packages.iter().map(|p| p.as_str()).collect()))
+        }
+        ("gpu", None) => Some(Value::List(report.gpu.bindings.iter().filter_map(|b| b.model.as_deref()).collect())),
+        ("gpu", Some("platform")) => report.gpu.platform.as_ref().map(|var| Value::Str(var.value.as_str())),
+        ("gpu", Some("expected")) => report.gpu.expected_gpu().map(Value::Str),
+        ("leds", None) => Some(Value::List(report.leds.leds.iter().map(|led| led.label.as_str()).collect())),
+        ("leds", Some("backlights")) => Some(Value::List(
+            report.leds.backlights.iter().map(|backlight| backlight.node.as_str()).collect(),
+        )),
+        ("regulators", None) => Some(Value::List(
+            report.regulators.regulators.iter().map(|r| r.display_name()).collect(),
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::category;
+
+    fn report() -> HardwareReport {
+        let mut report = HardwareReport::default();
+        for (category, entries) in [
+            (category::GPU, vec!["msmnile"]),
+            (category::HAL, vec!["android.hardware.power@1.3", "android.hardware.light@2.0"]),
+            (category::WIFI, vec!["qcwcn", "wlan.ko", "qcwcn"]),
+        ] {
+            report.drivers.insert(category.to_string(), entries.into_iter().map(String::from).collect());
+        }
+        report.key_files.insert("BoardConfig.mk".to_string(), true);
+        report.key_files.insert("system.prop".to_string(), false);
+        report
+    }
+
+    #[test]
+    fn category_names_with_slashes_and_spaces_are_keys() {
+        let report = report();
+        assert_eq!(report.get("drivers.GPU/Platform"), Some(Value::List(vec!["msmnile"])));
+        assert_eq!(
+            report.get("drivers.HAL (Hardware Abstraction Layer)"),
+            Some(Value::List(vec!["android.hardware.light@2.0", "android.hardware.power@1.3"]))
+        );
+        // Sorted and deduped
+        assert_eq!(report.get("drivers.WiFi Driver"), Some(Value::List(vec!["qcwcn", "wlan.ko"])));
+        assert_eq!(report.get("drivers"), Some(Value::Map(3)));
+    }
+
+    #[test]
+    fn only_the_first_dot_separates() {
+        let report = report();
+        assert_eq!(report.get("key_files.BoardConfig.mk"), Some(Value::Bool(true)));
+        assert_eq!(report.get("key_files.system.prop"), Some(Value::Bool(false)));
+    }
+
+    #[test]
+    fn unknown_fields_and_keys_give_none() {
+        let report = report();
+        assert_eq!(report.get("drivers.Modem Driver"), None);
+        assert_eq!(report.get("drivers.gpu/platform"), None);
+        assert_eq!(report.get("key_files.BoardConfig"), None);
+        assert_eq!(report.get("no_such_field"), None);
+        assert_eq!(report.get("structure_valid.extra"), None);
+    }
+}
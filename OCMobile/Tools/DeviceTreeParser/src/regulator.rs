@@ -0,0 +1,224 @@
+use crate::dts::{DtsNode, DtsSources};
+
+// Fixed regulators are GPIO/always-on rails described on their own;
+// everything else is supplied by a PMIC (RPMh, SPMI, I2C PMICs, ...)
+const FIXED_COMPATIBLES: [&str; 2] = ["regulator-fixed", "regulator-gpio"];
+
+#[derive(Debug, Default)]
+pub struct Regulator {
+    pub node: String,
+    pub name: Option<String>,
+    pub compatible: Vec<String>,
+    pub fixed: bool,
+    // Nearest ancestor below the root with a compatible, i.e. the PMIC or
+    // RPMh block
+    pub provider: Option<String>,
+    pub min_microvolt: Option<u64>,
+    pub max_microvolt: Option<u64>,
+    pub always_on: bool,
+    pub locations: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct PowerDomain {
+    pub node: String,
+    pub compatible: Vec<String>,
+    pub locations: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Regulators {
+    pub regulators: Vec<Regulator>,
+    // Nodes providing power domains (#power-domain-cells)
+    pub power_domains: Vec<PowerDomain>,
+}
+
+// A `*-supply = <&label>` reference and, if the target is itself supplied
+// (vin-supply, parent-supply, ...), the rest of the chain
+#[derive(Debug)]
+pub struct SupplyLink {
+    pub property: String,
+    // The label as written
+    pub reference: String,
+    // Resolved node path; None when no node carries the label
+    pub target: Option<String>,
+    // regulator-name of the target
+    pub name: Option<String>,
+    pub parents: Vec<SupplyLink>,
+    // The target already appears higher up this chain
+    pub cycle: bool,
+}
+
+// A node drawing power that no other node draws from, i.e. the top of a
+// device -> regulator -> parent regulator chain
+#[derive(Debug)]
+pub struct PowerConsumer {
+    pub node: String,
+    pub supplies: Vec<SupplyLink>,
+}
+
+impl SupplyLink {
+    // "pm8150_s4 (/rsc/regulator-pm8150-s4)", or "&ldo5 (unresolved)"
+    pub fn describe(&self) -> String {
+        match &self.target {
+            Some(target) => format!("{} ({})", self.name.as_deref().unwrap_or(&self.reference), target),
+            None => format!("&{} (unresolved)", self.reference),
+        }
+    }
+}
+
+impl Regulator {
+    // regulator-name, falling back to the node name
+    pub fn display_name(&self) -> &str {
+        self.name
+            .as_deref()
+            .unwrap_or_else(|| self.node.rsplit('/').next().unwrap_or(&self.node))
+    }
+
+    pub fn voltage_range(&self) -> Option<String> {
+        let volts = |uv: u64| format!("{}V", uv as f64 / 1_000_000.0);
+        match (self.min_microvolt, self.max_microvolt) {
+            (Some(min), Some(max)) if min == max => Some(volts(min)),
+            (Some(min), Some(max)) => Some(format!("{} - {}", volts(min), volts(max))),
+            (Some(min), None) => Some(format!(">= {}", volts(min))),
+            (None, Some(max)) => Some(format!("<= {}", volts(max))),
+            (None, None) => None,
+        }
+    }
+}
+
+impl Regulators {
+    pub fn is_empty(&self) -> bool {
+        self.regulators.is_empty() && self.power_domains.is_empty()
+    }
+
+    pub fn fixed_count(&self) -> usize {
+        self.regulators.iter().filter(|r| r.fixed).count()
+    }
+
+    pub fn always_on_count(&self) -> usize {
+        self.regulators.iter().filter(|r| r.always_on).count()
+    }
+}
+
+pub fn find_regulators(sources: &DtsSources) -> Regulators {
+    let mut found = Regulators::default();
+
+    for node in sources.nodes.values() {
+        if node.properties.contains_key("#power-domain-cells") {
+            found.power_domains.push(PowerDomain {
+                node: node.path.clone(),
+                compatible: compatibles(node),
+                locations: node.locations.clone(),
+            });
+        }
+
+        if !is_regulator(sources, node) {
+            continue;
+        }
+
+        let compatible = compatibles(node);
+        let fixed = compatible.iter().any(|c| FIXED_COMPATIBLES.contains(&c.as_str()));
+        found.regulators.push(Regulator {
+            node: node.path.clone(),
+            name: node.string("regulator-name").map(|s| s.to_string()),
+            fixed,
+            provider: if fixed { None } else { provider(sources, node) },
+            compatible,
+            min_microvolt: node.cells("regulator-min-microvolt").first().copied(),
+            max_microvolt: node.cells("regulator-max-microvolt").first().copied(),
+            always_on: node.properties.contains_key("regulator-always-on"),
+            locations: node.locations.clone(),
+        });
+    }
+
+    found
+}
+
+pub fn build_power_tree(sources: &DtsSources) -> Vec<PowerConsumer> {
+    let supplied: Vec<&DtsNode> = sources.nodes.values().filter(|node| !supply_properties(node).is_empty()).collect();
+
+    // Anything another node draws from sits below that node in the tree
+    let mut targets = Vec::new();
+    for node in &supplied {
+        for property in supply_properties(node) {
+            for reference in node.phandles(property) {
+                if let Some(target) = sources.resolve_phandle(reference) {
+                    targets.push(target.path.as_str());
+                }
+            }
+        }
+    }
+
+    supplied
+        .into_iter()
+        .filter(|node| !targets.contains(&node.path.as_str()))
+        .map(|node| PowerConsumer {
+            node: node.path.clone(),
+            supplies: supply_links(sources, node, &mut vec![node.path.clone()]),
+        })
+        .collect()
+}
+
+fn supply_links(sources: &DtsSources, node: &DtsNode, chain: &mut Vec<String>) -> Vec<SupplyLink> {
+    let mut links = Vec::new();
+    for property in supply_properties(node) {
+        for reference in node.phandles(property) {
+            let target = sources.resolve_phandle(reference);
+            let cycle = target.is_some_and(|t| chain.contains(&t.path));
+            let parents = match target {
+                Some(target) if !cycle => {
+                    chain.push(target.path.clone());
+                    let parents = supply_links(sources, target, chain);
+                    chain.pop();
+                    parents
+                }
+                _ => Vec::new(),
+            };
+            links.push(SupplyLink {
+                property: property.to_string(),
+                reference: reference.to_string(),
+                target: target.map(|t| t.path.clone()),
+                name: target.and_then(|t| t.string("regulator-name")).map(|s| s.to_string()),
+                parents,
+                cycle,
+            });
+        }
+    }
+    links
+}
+
+fn supply_properties(node: &DtsNode) -> Vec<&str> {
+    node.properties.keys().map(|k| k.as_str()).filter(|k| k.ends_with("-supply")).collect()
+}
+
+fn is_regulator(sources: &DtsSources, node: &DtsNode) -> bool {
+    if node.properties.contains_key("regulator-name") {
+        return true;
+    }
+
+    // PMIC regulator blocks (qcom,rpmh-vrm-regulator, ...) are compatible
+    // with "regulator" too; count their rails rather than the block itself
+    node.is_compatible_with("regulator")
+        && !sources
+            .descendants(node)
+            .any(|child| child.properties.contains_key("regulator-name"))
+}
+
+fn provider(sources: &DtsSources, node: &DtsNode) -> Option<String> {
+    let mut path = node.path.as_str();
+    while let Some((parent, _)) = path.rsplit_once('/') {
+        if parent.is_empty() {
+            return None;
+        }
+        if let Some(compatible) = sources.nodes.get(parent).and_then(|p| p.string("compatible")) {
+            return Some(compatible.to_string());
+        }
+        path = parent;
+    }
+    None
+}
+
+fn compatibles(node: &DtsNode) -> Vec<String> {
+    node.strings("compatible").into_iter().map(|s| s.to_string()).collect()
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use sha2::{Digest, Sha256};
+use crate::error::AnalyzeError;
+
+// The temporary clone of this run, if any. Kept here rather than in an RAII
+// guard because the error paths leave through process::exit, which skips
+// destructors; fail() and the end of main both call remove_scratch().
+static SCRATCH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// Shallow-clones --git-url at --git-ref (a branch or tag; the default branch
+// when None) and returns the checkout. With a cache directory the clone is
+// kept there under a key derived from URL+ref and reused by later runs;
+// otherwise it goes to the temp dir and is removed when the run ends.
+pub fn clone(url: &str, git_ref: Option<&str>, cache: Option<&Path>) -> Result<PathBuf, AnalyzeError> {
+    // git would take these as options (--upload-pack=<command> runs it)
+    if url.starts_with('-') {
+        return Err(AnalyzeError::Git(format!("'{}' is not a repository URL", url)));
+    }
+    if let Some(git_ref) = git_ref.filter(|r| r.starts_with('-')) {
+        return Err(AnalyzeError::Git(format!("'{}' is not a branch or tag", git_ref)));
+    }
+
+    // Device info comes from the last two path components, so the checkout
+    // is laid out as <vendor>/<device> where the repository name allows
+    let layout = checkout_layout(&repository_name(url));
+    let name = layout.file_name().map_or("tree".into(), |n| n.to_string_lossy().to_string());
+
+    let checkout = match cache {
+        Some(cache) => {
+            let checkout = cache.join(cache_key(url, git_ref)).join(&layout);
+            if checkout.is_dir() {
+                return Ok(checkout);
+            }
+            checkout
+        }
+        None => {
+            let scratch = std::env::temp_dir().join(format!("DeviceTreeParser-clone-{}", std::process::id()));
+            *SCRATCH.lock().unwrap() = Some(scratch.clone());
+            scratch.join(&layout)
+        }
+    };
+
+    // Clone next to the final location and rename, so an interrupted clone
+    // never looks like a cached one
+    let partial = checkout.with_file_name(format!("{}.partial", name));
+    let _ = fs::remove_dir_all(&partial);
+    if let Some(parent) = partial.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AnalyzeError::Git(format!("couldn't create '{}': {}", parent.display(), e)))?;
+    }
+
+    let mut command = Command::new("git");
+    command.args(["clone", "--quiet", "--depth", "1", "--single-branch"]);
+    if let Some(git_ref) = git_ref {
+        command.args(["--branch", git_ref]);
+    }
+    command.arg("--").arg(url).arg(&partial);
+
+    let output = command.output().map_err(|e| match e.kind() {
+        ErrorKind::NotFound => {
+            AnalyzeError::Git("git is not installed or not on PATH (needed for --git-url)".to_string())
+        }
+        _ => AnalyzeError::Git(format!("failed to run git: {}", e)),
+    })?;
+    if !output.status.success() {
+        let _ = fs::remove_dir_all(&partial);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let at = git_ref.map(|r| format!(" at '{}'", r)).unwrap_or_default();
+        return Err(AnalyzeError::Git(format!("cloning '{}'{} failed: {}", url, at, stderr.trim())));
+    }
+
+    fs::rename(&partial, &checkout)
+        .map_err(|e| AnalyzeError::Git(format!("couldn't move the clone to '{}': {}", checkout.display(), e)))?;
+    Ok(checkout)
+}
+
+// Removes this run's temporary clone; cached clones are left alone
+pub fn remove_scratch() {
+    if let Some(scratch) = SCRATCH.lock().unwrap().take() {
+        let _ = fs::remove_dir_all(scratch);
+    }
+}
+
+// "https://github.com/x/device_y_z.git" -> "device_y_z"
+fn repository_name(url: &str) -> String {
+    let last = url.trim_end_matches('/').rsplit(['/', ':']).next().unwrap_or("");
+    let name = last.strip_suffix(".git").unwrap_or(last);
+    if name.is_empty() { "tree".to_string() } else { name.to_string() }
+}
+
+// "android_device_xiaomi_raphael"/"device_xiaomi_raphael" -> xiaomi/raphael,
+// anything else is used as is
+fn checkout_layout(name: &str) -> PathBuf {
+    let rest = name.strip_prefix("android_").unwrap_or(name);
+    match rest.strip_prefix("device_").and_then(|rest| rest.split_once('_')) {
+        Some((vendor, device)) if !vendor.is_empty() && !device.is_empty() => Path::new(vendor).join(device),
+        _ => PathBuf::from(name),
+    }
+}
+
+fn cache_key(url: &str, git_ref: Option<&str>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(url.trim_end_matches('/').as_bytes());
+    hasher.update(b"\n");
+    hasher.update(git_ref.unwrap_or("HEAD").as_bytes());
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
@@ -0,0 +1,1477 @@
+// JSON rendering of a HardwareReport for --format json. Keys mirror the
+// report's field names; maps are emitted in sorted order so output is
+// stable between runs.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use json::JsonValue;
+use crate::{DriverSort, HardwareReport, OutputEncoding};
+use crate::category;
+use crate::drm::WidevineLevel;
+use crate::encoding;
+use crate::gsi;
+use crate::module_consistency::DeclaredModule;
+use crate::regulator::SupplyLink;
+
+// Bumped whenever a key is renamed, removed or changes meaning, so --load
+// can tell a report it may misread
+pub const SCHEMA_VERSION: u32 = 1;
+
+pub fn report_to_json(report: &HardwareReport) -> JsonValue {
+    let mut root = head_to_json(report);
+
+    let lists = sorted_drivers(&report.drivers);
+    let mut drivers = JsonValue::new_object();
+    for category in json_categories(report) {
+        drivers[category] = lists.get(category).cloned().unwrap_or_default().into();
+    }
+    root["drivers"] = drivers;
+
+    for (key, value) in tail_to_json(report).entries() {
+        root[key] = value.clone();
+    }
+    root
+}
+
+// --format json, written as it's built: everything but the driver lists is
+// small, so those are serialized one category at a time rather than as part
+// of a single document. The output is exactly report_to_json(..).pretty(2).
+pub fn write_json(report: &HardwareReport, out: &mut impl Write) -> io::Result<()> {
+    write!(out, "{{")?;
+    let mut first = true;
+    for (key, value) in head_to_json(report).entries() {
+        write_entry(out, &mut first, key, value, 1)?;
+    }
+
+    // "drivers" is bracketed by hand so only one category is held at a time
+    write_key(out, &mut first, "drivers", 1)?;
+    let categories = json_categories(report);
+    if categories.is_empty() {
+        write!(out, "{{}}")?;
+    } else {
+        write!(out, "{{")?;
+        let mut first_category = true;
+        for category in categories {
+            let mut entries = report.drivers.get(category).cloned().unwrap_or_default();
+            entries.sort();
+            entries.dedup();
+            write_entry(out, &mut first_category, category, &entries.into(), 2)?;
+        }
+        write!(out, "\n  }}")?;
+    }
+
+    for (key, value) in tail_to_json(report).entries() {
+        write_entry(out, &mut first, key, value, 1)?;
+    }
+    writeln!(out, "\n}}")
+}
+
+// `"key": ` on a new line at the given depth, after a comma unless first
+fn write_key(out: &mut impl Write, first: &mut bool, key: &str, depth: usize) -> io::Result<()> {
+    let separator = if *first { "" } else { "," };
+    *first = false;
+    write!(out, "{}\n{}{}: ", separator, "  ".repeat(depth), json::stringify(key))
+}
+
+// A nested value pretty-printed on its own, re-indented to sit at depth
+fn write_entry(out: &mut impl Write, first: &mut bool, key: &str, value: &JsonValue, depth: usize) -> io::Result<()> {
+    write_key(out, first, key, depth)?;
+    write!(out, "{}", value.pretty(2).replace('\n', &format!("\n{}", "  ".repeat(depth))))
+}
+
+// Fields before "drivers"
+fn head_to_json(report: &HardwareReport) -> JsonValue {
+    let mut root = JsonValue::new_object();
+
+    root["schema_version"] = SCHEMA_VERSION.into();
+    root["tree_path"] = report.tree_path.as_str().into();
+    root["structure_valid"] = report.structure_valid.into();
+    root["device_info"] = string_map(&report.device_info);
+    root["key_files"] = bool_map(&report.key_files);
+    root["key_dirs"] = bool_map(&report.key_dirs);
+    let mut on_disk = JsonValue::new_object();
+    for (name, found_as) in &report.key_names_on_disk {
+        on_disk[name.as_str()] = found_as.as_str().into();
+    }
+    root["key_names_on_disk"] = on_disk;
+    root["drivers_scanned"] = report.drivers_scanned.into();
+    root["max_depth"] = report.max_depth.into();
+    root["recursive"] = (report.max_depth != Some(0)).into();
+    root["max_entries_per_category"] = report.max_entries.into();
+    // Every known category, with whether the scan looked for it; "drivers"
+    // has an empty array for the attempted ones that came up empty
+    let counts = driver_counts(report);
+    let mut categories = JsonValue::new_object();
+    for category in report.category_map.known() {
+        let mut entry = JsonValue::new_object();
+        entry["attempted"] = report.attempted_categories.contains(category).into();
+        entry["truncated"] = report.truncated_categories.contains(category).into();
+        entry["count"] = counts.iter().find(|(name, _)| *name == category).map_or(0, |(_, count)| *count).into();
+        categories[category] = entry;
+    }
+    root["driver_categories"] = categories;
+    root
+}
+
+// Fields after "drivers"
+fn tail_to_json(report: &HardwareReport) -> JsonValue {
+    let mut root = JsonValue::new_object();
+
+    let mut hals = JsonValue::new_array();
+    for hal in &report.hals {
+        let mut entry = JsonValue::new_object();
+        entry["family"] = hal.family.as_str().into();
+        entry["transports"] = hal.transports().iter().map(|t| t.as_str()).collect::<Vec<_>>().into();
+        let mut packages = JsonValue::new_array();
+        for (transport, package) in &hal.packages {
+            let mut package_entry = JsonValue::new_object();
+            package_entry["name"] = package.as_str().into();
+            package_entry["transport"] = transport.as_str().into();
+            let _ = packages.push(package_entry);
+        }
+        entry["packages"] = packages;
+        let _ = hals.push(entry);
+    }
+    root["hals"] = hals;
+
+    let wifi = &report.wifi;
+    let mut wifi_json = JsonValue::new_object();
+    wifi_json["chipset"] = wifi.chipset.clone().into();
+    wifi_json["supplicant_version"] = wifi.supplicant_version.clone().into();
+    wifi_json["hostapd_driver"] = wifi.hostapd_driver.clone().into();
+    wifi_json["driver_module"] = wifi.driver_module.clone().into();
+    let mut driver_vars = JsonValue::new_object();
+    for (name, value) in &wifi.driver_vars {
+        driver_vars[name.as_str()] = value.as_str().into();
+    }
+    wifi_json["driver_vars"] = driver_vars;
+    wifi_json["bands"] = wifi.configs.bands().into();
+    wifi_json["country_codes"] = wifi.configs.country_codes().into();
+    wifi_json["ap_standards"] = wifi.configs.ap_standards().into();
+    let mut config_files = JsonValue::new_array();
+    for config in &wifi.configs.files {
+        let mut entry = JsonValue::new_object();
+        entry["file"] = config.file.as_str().into();
+        entry["kind"] = config.kind.as_str().into();
+        let mut settings = JsonValue::new_object();
+        for setting in &config.settings {
+            settings[setting.key.as_str()] = setting.value.as_str().into();
+        }
+        entry["settings"] = settings;
+        let _ = config_files.push(entry);
+    }
+    wifi_json["config_files"] = config_files;
+    root["wifi"] = wifi_json;
+
+    let treble = &report.treble;
+    let mut treble_json = JsonValue::new_object();
+    treble_json["vndk_version"] = treble.vndk_version.clone().into();
+    treble_json["compatible"] = treble.is_compatible().into();
+    let mut flags = JsonValue::new_object();
+    for (name, value) in &treble.flags {
+        flags[name.as_str()] = value.as_str().into();
+    }
+    treble_json["flags"] = flags;
+    treble_json["vndk_dirs"] = treble.vndk_dirs.clone().into();
+    root["treble"] = treble_json;
+
+    let checks = gsi::checklist(&report.treble, &report.boot, &report.gsi);
+    let mut gsi_json = JsonValue::new_object();
+    gsi_json["likely_to_boot"] = gsi::likely_to_boot(&checks).into();
+    gsi_json["target_fcm_version"] = report.gsi.target_fcm().map(|(level, _)| level).into();
+    gsi_json["target_fcm_source"] = report.gsi.target_fcm().map(|(_, file)| file).into();
+    let shipping_api_level = report.gsi.vars.get("PRODUCT_SHIPPING_API_LEVEL");
+    gsi_json["shipping_api_level"] = shipping_api_level.map(|var| var.value.as_str()).into();
+    let mut checks_json = JsonValue::new_array();
+    for check in &checks {
+        let mut entry = JsonValue::new_object();
+        entry["name"] = check.name.into();
+        entry["passed"] = check.passed.into();
+        entry["required"] = check.required.into();
+        entry["evidence"] = check.evidence.as_str().into();
+        let _ = checks_json.push(entry);
+    }
+    gsi_json["checks"] = checks_json;
+    gsi_json["manifests"] = report.gsi.files().into();
+    root["gsi"] = gsi_json;
+
+    let mut apex = JsonValue::new_array();
+    for module in &report.apex.modules {
+        let mut entry = JsonValue::new_object();
+        entry["name"] = module.name.as_str().into();
+        entry["source"] = if module.prebuilt { "prebuilt" } else { "source" }.into();
+        entry["compressed"] = module.compressed.into();
+        entry["packaged"] = module.packaged.into();
+        entry["files"] = module.files.clone().into();
+        let _ = apex.push(entry);
+    }
+    root["apex"] = apex;
+
+    let linker = &report.linker_namespaces;
+    let mut linker_json = JsonValue::new_object();
+    let mut public = JsonValue::new_object();
+    for list in &linker.public_libraries {
+        public[list.file.as_str()] = list.libraries.clone().into();
+    }
+    linker_json["public_libraries"] = public;
+    let mut configs = JsonValue::new_object();
+    for config in &linker.configs {
+        let mut sections = JsonValue::new_array();
+        for section in &config.sections {
+            let mut namespaces = JsonValue::new_array();
+            for namespace in &section.namespaces {
+                let mut entry = JsonValue::new_object();
+                entry["name"] = namespace.name.as_str().into();
+                entry["isolated"] = namespace.isolated.into();
+                entry["visible"] = namespace.visible.into();
+                entry["search_paths"] = namespace.search_paths.clone().into();
+                entry["permitted_paths"] = namespace.permitted_paths.clone().into();
+                let mut links = JsonValue::new_array();
+                for link in &namespace.links {
+                    let mut link_json = JsonValue::new_object();
+                    link_json["target"] = link.target.as_str().into();
+                    link_json["shared_libs"] = link.shared_libs.clone().into();
+                    link_json["allow_all_shared_libs"] = link.allow_all.into();
+                    let _ = links.push(link_json);
+                }
+                entry["links"] = links;
+                let _ = namespaces.push(entry);
+            }
+            let mut section_json = JsonValue::new_object();
+            section_json["name"] = section.name.as_str().into();
+            section_json["dirs"] = section.dirs.clone().into();
+            section_json["namespaces"] = namespaces;
+            let _ = sections.push(section_json);
+        }
+        configs[config.file.as_str()] = sections;
+    }
+    linker_json["configs"] = configs;
+    root["linker_namespaces"] = linker_json;
+
+    let boot = &report.boot;
+    let mut boot_json = JsonValue::new_object();
+    boot_json["scheme"] = boot.scheme().into();
+    boot_json["ab_updater"] = boot.ab_updater.into();
+    boot_json["recovery"] = boot.recovery.map(|r| r.name()).into();
+    boot_json["ab_partitions"] = boot.ab_partitions.clone().into();
+    let mut boot_vars = JsonValue::new_object();
+    for (name, value) in &boot.vars {
+        boot_vars[name.as_str()] = value.as_str().into();
+    }
+    boot_json["vars"] = boot_vars;
+    root["boot"] = boot_json;
+
+    let sizes = &report.partition_sizes;
+    let mut sizes_json = JsonValue::new_object();
+    sizes_json["flash_block_size"] = sizes.flash_block_size.clone().into();
+    let mut partitions = JsonValue::new_array();
+    for partition in &sizes.partitions {
+        let mut entry = JsonValue::new_object();
+        entry["name"] = partition.name.as_str().into();
+        entry["size"] = partition.size.clone().into();
+        entry["bytes"] = partition.bytes.into();
+        entry["fs_type"] = partition.fs_type.clone().into();
+        entry["unresolved"] = partition.unresolved().into();
+        entry["location"] = partition.location.as_str().into();
+        let _ = partitions.push(entry);
+    }
+    sizes_json["partitions"] = partitions;
+    root["partition_sizes"] = sizes_json;
+
+    let encryption = &report.encryption;
+    let mut encryption_json = JsonValue::new_object();
+    encryption_json["mode"] = encryption.mode().map(|mode| mode.name()).into();
+    let mut data_json = JsonValue::new_array();
+    for data in &encryption.data {
+        let mut entry = JsonValue::new_object();
+        entry["location"] = data.location.as_str().into();
+        entry["mode"] = data.mode.name().into();
+        entry["fs_type"] = data.fs_type.as_str().into();
+        entry["contents"] = data.contents.clone().into();
+        entry["filenames"] = data.filenames.clone().into();
+        entry["policy"] = data.policy.clone().into();
+        entry["options"] = data.options.clone().into();
+        entry["metadata_encryption"] = data.metadata_cipher.clone().into();
+        entry["key_directory"] = data.key_directory.clone().into();
+        entry["flags"] = data.flags.clone().into();
+        let _ = data_json.push(entry);
+    }
+    encryption_json["data"] = data_json;
+    encryption_json["metadata_mounts"] = encryption.metadata_mounts.clone().into();
+    encryption_json["fstab_files"] = encryption.fstab_files.clone().into();
+    let mut vars = JsonValue::new_object();
+    for (name, value) in &encryption.vars {
+        vars[name.as_str()] = value.as_str().into();
+    }
+    encryption_json["vars"] = vars;
+    root["encryption"] = encryption_json;
+
+    let mut kernel = JsonValue::new_object();
+    kernel["source"] = report.kernel.source.clone().into();
+    kernel["defconfigs"] = report.kernel.defconfigs.clone().into();
+    kernel["prebuilt_image"] = report.kernel.prebuilt_image.clone().into();
+    kernel["version"] = report.kernel.version.clone().into();
+    root["kernel"] = kernel;
+
+    let modules = &report.module_consistency;
+    let module_json = |module: &DeclaredModule| {
+        let mut entry = JsonValue::new_object();
+        entry["name"] = module.name.as_str().into();
+        entry["variable"] = module.variable.as_str().into();
+        entry["location"] = module.location.as_str().into();
+        entry
+    };
+    let mut consistency = JsonValue::new_object();
+    consistency["declared"] = modules.declared.iter().map(module_json).collect::<Vec<_>>().into();
+    consistency["wildcard_dirs"] = modules.wildcard_dirs.clone().into();
+    consistency["prebuilt"] = modules.prebuilt.clone().into();
+    consistency["unreferenced"] = modules.unreferenced.clone().into();
+    consistency["missing"] = modules.missing.iter().map(module_json).collect::<Vec<_>>().into();
+    root["module_consistency"] = consistency;
+
+    let mut modem = JsonValue::new_object();
+    let mut firmware = JsonValue::new_array();
+    for image in &report.modem.firmware {
+        let mut entry = JsonValue::new_object();
+        entry["file"] = image.file.as_str().into();
+        entry["version"] = image.version.clone().into();
+        let _ = firmware.push(entry);
+    }
+    modem["firmware"] = firmware;
+    modem["required_baseband"] = report.modem.required_baseband.clone().into();
+    modem["no_radio_image"] = report.modem.no_radio_image().into();
+    let mut modem_vars = JsonValue::new_object();
+    for (name, value) in &report.modem.vars {
+        modem_vars[name.as_str()] = value.as_str().into();
+    }
+    modem["vars"] = modem_vars;
+    root["modem"] = modem;
+
+    let sources = &report.dts_sources;
+    let mut dts = JsonValue::new_object();
+    dts["files"] = sources.files.clone().into();
+    let mut includes = JsonValue::new_object();
+    for (file, included) in &sources.includes {
+        includes[file.as_str()] = included.clone().into();
+    }
+    dts["includes"] = includes;
+    dts["unused_includes"] = sources.unused_includes().into_iter().cloned().collect::<Vec<_>>().into();
+    root["dts_sources"] = dts;
+
+    let mut aliases = JsonValue::new_object();
+    for alias in sources.aliases() {
+        let mut entry = JsonValue::new_object();
+        entry["value"] = alias.value.into();
+        entry["target"] = alias.target.into();
+        entry["location"] = alias.location.into();
+        aliases[alias.name] = entry;
+    }
+    root["aliases"] = aliases;
+
+    let mut chosen = JsonValue::new_object();
+    if let Some(node) = sources.chosen() {
+        let mut properties = JsonValue::new_object();
+        for name in node.properties.keys() {
+            properties[name.as_str()] = node.display_value(name).into();
+        }
+        chosen["properties"] = properties;
+        chosen["bootargs"] = node.display_value("bootargs").into();
+        chosen["stdout_path"] = node.display_value("stdout-path").into();
+    }
+    if let Some((target, options)) = sources.stdout_path() {
+        chosen["console_node"] = target.into();
+        chosen["console_options"] = options.into();
+    }
+    root["chosen"] = chosen;
+
+    let overlays = &report.dtb_overlays;
+    let mut dtbo = JsonValue::new_object();
+    dtbo["build_mode"] = overlays.build_mode.map(|mode| mode.name()).into();
+    let mut board_vars = JsonValue::new_object();
+    for (name, value) in &overlays.board_vars {
+        board_vars[name.as_str()] = value.as_str().into();
+    }
+    dtbo["board_vars"] = board_vars;
+    dtbo["prebuilts"] = overlays.prebuilts.clone().into();
+    dtbo["config_files"] = overlays.config_files.clone().into();
+    let mut entries = JsonValue::new_array();
+    for overlay in &overlays.overlays {
+        let mut entry = JsonValue::new_object();
+        entry["file"] = overlay.file.as_str().into();
+        entry["index"] = overlay.index.into();
+        entry["id"] = overlay.id.clone().into();
+        entry["rev"] = overlay.rev.clone().into();
+        entry["location"] = overlay.location.as_str().into();
+        let _ = entries.push(entry);
+    }
+    dtbo["overlays"] = entries;
+    root["dtb_overlays"] = dtbo;
+
+    let mut panels = JsonValue::new_array();
+    for panel in &report.panels {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = panel.node.as_str().into();
+        entry["name"] = panel.name.clone().into();
+        entry["compatible"] = panel.compatible.clone().into();
+        entry["width"] = panel.width.into();
+        entry["height"] = panel.height.into();
+        entry["refresh_rates"] = panel.refresh_rates.clone().into();
+        entry["locations"] = panel.locations.clone().into();
+        let _ = panels.push(entry);
+    }
+    root["panels"] = panels;
+
+    let camera = &report.camera;
+    let mut camera_json = JsonValue::new_object();
+    let mut sensors = JsonValue::new_array();
+    for sensor in &camera.sensors {
+        let mut entry = JsonValue::new_object();
+        entry["model"] = sensor.model.as_str().into();
+        entry["nodes"] = sensor.nodes.clone().into();
+        entry["files"] = sensor.files.clone().into();
+        let _ = sensors.push(entry);
+    }
+    camera_json["sensors"] = sensors;
+    camera_json["unnamed_nodes"] = camera.unnamed_nodes.clone().into();
+    let mut configs = JsonValue::new_array();
+    for config in &camera.config_files {
+        let mut entry = JsonValue::new_object();
+        entry["file"] = config.file.as_str().into();
+        entry["sensors"] = config.sensors.clone().into();
+        let mut settings = JsonValue::new_object();
+        for (name, value) in &config.settings {
+            settings[name.as_str()] = value.as_str().into();
+        }
+        entry["settings"] = settings;
+        let _ = configs.push(entry);
+    }
+    camera_json["config_files"] = configs;
+    camera_json["sensor_modules"] = camera.sensor_libs.clone().into();
+    camera_json["hal_packages"] = camera.hal_packages.clone().into();
+    root["camera"] = camera_json;
+
+    let mut touchscreens = JsonValue::new_array();
+    for touchscreen in &report.touchscreens {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = touchscreen.node.as_str().into();
+        entry["vendor"] = touchscreen.vendor.as_str().into();
+        entry["compatible"] = touchscreen.compatible.clone().into();
+        entry["firmware_name"] = touchscreen.firmware_name.clone().into();
+        entry["firmware_files"] = touchscreen.firmware_files.clone().into();
+        entry["locations"] = touchscreen.locations.clone().into();
+        let _ = touchscreens.push(entry);
+    }
+    root["touchscreens"] = touchscreens;
+
+    let mut haptics = JsonValue::new_object();
+    let mut controllers = JsonValue::new_array();
+    for controller in &report.haptics.controllers {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = controller.node.as_str().into();
+        entry["vendor"] = controller.vendor.as_str().into();
+        entry["compatible"] = controller.compatible.clone().into();
+        entry["hal_packages"] = controller.hal_packages.clone().into();
+        entry["locations"] = controller.locations.clone().into();
+        let _ = controllers.push(entry);
+    }
+    haptics["controllers"] = controllers;
+    haptics["hal_packages"] = report.haptics.hal_packages.clone().into();
+    haptics["config_files"] = report.haptics.config_files.clone().into();
+    root["haptics"] = haptics;
+
+    let biometrics = &report.biometrics;
+    let mut biometrics_json = JsonValue::new_object();
+    let mut sensors = JsonValue::new_array();
+    for sensor in &biometrics.sensors {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = sensor.node.as_str().into();
+        entry["vendor"] = sensor.vendor.as_str().into();
+        entry["kind"] = sensor.kind.as_str().into();
+        entry["compatible"] = sensor.compatible.clone().into();
+        entry["hal_packages"] = sensor.hal_packages.clone().into();
+        entry["locations"] = sensor.locations.clone().into();
+        let _ = sensors.push(entry);
+    }
+    biometrics_json["sensors"] = sensors;
+    biometrics_json["hal_packages"] = biometrics.hal_packages.clone().into();
+    let mut hints = JsonValue::new_array();
+    for hint in &biometrics.under_display_hints {
+        let mut entry = JsonValue::new_object();
+        entry["text"] = hint.text.as_str().into();
+        entry["location"] = hint.location.as_str().into();
+        let _ = hints.push(entry);
+    }
+    biometrics_json["under_display_hints"] = hints;
+    biometrics_json["config_files"] = biometrics.config_files.clone().into();
+    root["biometrics"] = biometrics_json;
+
+    let drm = &report.drm;
+    let mut drm_json = JsonValue::new_object();
+    let level = drm.level();
+    drm_json["widevine_level"] = level.number().into();
+    drm_json["level_source"] = match level {
+        WidevineLevel::Configured(_) => "board_config".into(),
+        WidevineLevel::Likely(_) => "inferred".into(),
+        WidevineLevel::Unknown => JsonValue::Null,
+    };
+    drm_json["level_reason"] = drm.level_reason().into();
+    drm_json["hal_packages"] = drm.hal_packages.clone().into();
+    drm_json["widevine_hals"] = drm.widevine_hals().into();
+    let mut flags = JsonValue::new_object();
+    for flag in &drm.board_flags {
+        flags[flag.name.as_str()] = flag.value.as_str().into();
+    }
+    drm_json["board_flags"] = flags;
+    for (key, blobs) in [("libraries", &drm.libraries), ("trusted_apps", &drm.trusted_apps)] {
+        let mut entries = JsonValue::new_array();
+        for blob in blobs {
+            let mut entry = JsonValue::new_object();
+            entry["name"] = blob.name.as_str().into();
+            entry["path"] = blob.value.as_str().into();
+            entry["location"] = blob.location.as_str().into();
+            let _ = entries.push(entry);
+        }
+        drm_json[key] = entries;
+    }
+    root["drm"] = drm_json;
+
+    let tee = &report.tee;
+    let mut tee_json = JsonValue::new_object();
+    tee_json["type"] = tee.tee_type().map(|kind| kind.as_str()).into();
+    tee_json["type_source"] = tee.tee_source().into();
+    tee_json["types"] = tee.tee_types().iter().map(|kind| kind.as_str()).collect::<Vec<_>>().into();
+    tee_json["keymaster_version"] = tee.keymaster_version().into();
+    tee_json["keymaster_packages"] = tee.keymaster_packages.clone().into();
+    tee_json["gatekeeper_packages"] = tee.gatekeeper_packages.clone().into();
+    tee_json["tee_packages"] = tee.tee_packages.clone().into();
+    let mut flags = JsonValue::new_object();
+    for flag in &tee.board_flags {
+        flags[flag.name.as_str()] = flag.value.as_str().into();
+    }
+    tee_json["board_flags"] = flags;
+    let mut bindings = JsonValue::new_array();
+    for binding in &tee.bindings {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = binding.node.as_str().into();
+        entry["compatible"] = binding.compatible.as_str().into();
+        entry["tee"] = binding.tee.as_str().into();
+        entry["keymaster_packages"] = binding.keymaster_packages.clone().into();
+        entry["locations"] = binding.locations.clone().into();
+        let _ = bindings.push(entry);
+    }
+    tee_json["bindings"] = bindings;
+    root["tee"] = tee_json;
+
+    let gpu = &report.gpu;
+    let mut gpu_json = JsonValue::new_object();
+    let model = gpu.model();
+    gpu_json["model"] = model.as_ref().map(|(model, _)| model.as_str()).into();
+    gpu_json["model_source"] = model.as_ref().map(|(_, source)| source.as_str()).into();
+    gpu_json["platform"] = gpu.platform.as_ref().map(|var| var.value.as_str()).into();
+    gpu_json["platform_gpu"] = gpu.platform_gpu.as_ref().map(|var| var.value.as_str()).into();
+    gpu_json["expected_gpu"] = gpu.expected_gpu().into();
+    gpu_json["matches_platform"] = match (gpu.expected_gpu(), gpu.bindings.iter().any(|b| b.model.is_some())) {
+        (Some(_), true) => gpu.platform_mismatch().is_none().into(),
+        _ => JsonValue::Null,
+    };
+    gpu_json["gralloc_packages"] = gpu.gralloc_packages.clone().into();
+    gpu_json["mapper_packages"] = gpu.mapper_packages.clone().into();
+    gpu_json["composer_packages"] = gpu.composer_packages.clone().into();
+    gpu_json["driver_packages"] = gpu.driver_packages.clone().into();
+    let mut bindings = JsonValue::new_array();
+    for binding in &gpu.bindings {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = binding.node.as_str().into();
+        entry["compatible"] = binding.compatible.as_str().into();
+        entry["model"] = binding.model.clone().into();
+        entry["locations"] = binding.locations.clone().into();
+        let _ = bindings.push(entry);
+    }
+    gpu_json["bindings"] = bindings;
+    root["gpu"] = gpu_json;
+
+    let mut leds = JsonValue::new_object();
+    let mut entries = JsonValue::new_array();
+    for led in &report.leds.leds {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = led.node.as_str().into();
+        entry["label"] = led.label.as_str().into();
+        entry["default_trigger"] = led.default_trigger.clone().into();
+        entry["controller"] = led.controller.clone().into();
+        entry["location"] = led.location.as_str().into();
+        let _ = entries.push(entry);
+    }
+    leds["leds"] = entries;
+    let mut backlights = JsonValue::new_array();
+    for backlight in &report.leds.backlights {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = backlight.node.as_str().into();
+        entry["compatible"] = backlight.compatible.clone().into();
+        entry["location"] = backlight.location.as_str().into();
+        let _ = backlights.push(entry);
+    }
+    leds["backlights"] = backlights;
+    leds["hal_packages"] = report.leds.hal_packages.clone().into();
+    let mut sysfs_commands = JsonValue::new_array();
+    for command in &report.leds.sysfs_commands {
+        let mut entry = JsonValue::new_object();
+        entry["command"] = command.command.as_str().into();
+        entry["path"] = command.path.as_str().into();
+        entry["location"] = command.location.as_str().into();
+        let _ = sysfs_commands.push(entry);
+    }
+    leds["sysfs_commands"] = sysfs_commands;
+    leds["undefined_sysfs_leds"] = report.leds.undefined_sysfs_leds().into();
+    root["leds"] = leds;
+
+    let mut nfc = JsonValue::new_object();
+    let mut devices = JsonValue::new_array();
+    for device in &report.nfc.devices {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = device.node.as_str().into();
+        entry["kind"] = device.kind.as_str().into();
+        entry["vendor"] = device.vendor.as_str().into();
+        entry["compatible"] = device.compatible.clone().into();
+        entry["vendor_config"] = device.vendor_config.clone().into();
+        entry["locations"] = device.locations.clone().into();
+        let _ = devices.push(entry);
+    }
+    nfc["devices"] = devices;
+    nfc["packages"] = report.nfc.hal_packages.clone().into();
+    let mut config_files = JsonValue::new_array();
+    for config in &report.nfc.config_files {
+        let mut entry = JsonValue::new_object();
+        entry["file"] = config.file.as_str().into();
+        let mut settings = JsonValue::new_object();
+        for (key, value) in &config.settings {
+            settings[key.as_str()] = value.as_str().into();
+        }
+        entry["settings"] = settings;
+        let _ = config_files.push(entry);
+    }
+    nfc["config_files"] = config_files;
+    nfc["has_nci_config"] = report.nfc.has_nci_config().into();
+    root["nfc"] = nfc;
+
+    let usb = &report.usb;
+    let mut usb_json = JsonValue::new_object();
+    usb_json["default_config"] = usb.default_setting().map(|setting| setting.value.clone()).into();
+    usb_json["default_functions"] = usb.default_functions().into();
+    usb_json["adb_by_default"] = usb.adb_by_default().into();
+    usb_json["adb_secure"] = usb.adb_secure.clone().into();
+    usb_json["functions"] = usb.functions().into();
+    let mut settings = JsonValue::new_array();
+    for setting in &usb.settings {
+        let mut entry = JsonValue::new_object();
+        entry["property"] = setting.property.as_str().into();
+        entry["value"] = setting.value.as_str().into();
+        entry["trigger"] = setting.trigger.clone().into();
+        entry["location"] = setting.location.as_str().into();
+        let _ = settings.push(entry);
+    }
+    usb_json["settings"] = settings;
+    usb_json["compositions"] = usb.compositions.clone().into();
+    let mut board_flags = JsonValue::new_object();
+    for flag in &usb.board_flags {
+        board_flags[flag.name.as_str()] = flag.value.as_str().into();
+    }
+    usb_json["board_flags"] = board_flags;
+    root["usb"] = usb_json;
+
+    let mut regulators = JsonValue::new_array();
+    for regulator in &report.regulators.regulators {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = regulator.node.as_str().into();
+        entry["name"] = regulator.name.clone().into();
+        entry["compatible"] = regulator.compatible.clone().into();
+        entry["fixed"] = regulator.fixed.into();
+        entry["provider"] = regulator.provider.clone().into();
+        entry["min_microvolt"] = regulator.min_microvolt.into();
+        entry["max_microvolt"] = regulator.max_microvolt.into();
+        entry["always_on"] = regulator.always_on.into();
+        entry["locations"] = regulator.locations.clone().into();
+        let _ = regulators.push(entry);
+    }
+    root["regulators"] = regulators;
+
+    let mut power_domains = JsonValue::new_array();
+    for domain in &report.regulators.power_domains {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = domain.node.as_str().into();
+        entry["compatible"] = domain.compatible.clone().into();
+        entry["locations"] = domain.locations.clone().into();
+        let _ = power_domains.push(entry);
+    }
+    root["power_domains"] = power_domains;
+
+    let mut power_tree = JsonValue::new_array();
+    for consumer in &report.power_tree {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = consumer.node.as_str().into();
+        entry["supplies"] = supply_links(&consumer.supplies);
+        let _ = power_tree.push(entry);
+    }
+    root["power_tree"] = power_tree;
+
+    let mut battery = JsonValue::new_object();
+    let mut controllers = JsonValue::new_array();
+    for controller in &report.battery.controllers {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = controller.node.as_str().into();
+        entry["kind"] = controller.kind.as_str().into();
+        entry["binding"] = controller.binding.as_str().into();
+        entry["description"] = controller.description.into();
+        entry["locations"] = controller.locations.clone().into();
+        let _ = controllers.push(entry);
+    }
+    battery["controllers"] = controllers;
+    let mut profiles = JsonValue::new_array();
+    for profile in &report.battery.profiles {
+        let mut entry = JsonValue::new_object();
+        entry["node"] = profile.node.as_str().into();
+        entry["battery_type"] = profile.battery_type.clone().into();
+        entry["capacity_mah"] = profile.capacity_mah.into();
+        entry["chemistry"] = profile.chemistry.clone().into();
+        entry["max_microvolt"] = profile.max_microvolt.into();
+        entry["locations"] = profile.locations.clone().into();
+        let _ = profiles.push(entry);
+    }
+    battery["profiles"] = profiles;
+    let mut profile_files = JsonValue::new_array();
+    for file in &report.battery.profile_files {
+        let mut entry = JsonValue::new_object();
+        entry["file"] = file.file.as_str().into();
+        entry["capacity_mah"] = file.capacity_mah.into();
+        let _ = profile_files.push(entry);
+    }
+    battery["profile_files"] = profile_files;
+    root["battery"] = battery;
+
+    let mut copied = JsonValue::new_array();
+    for entry in &report.copied_files.entries {
+        let mut entry_json = JsonValue::new_object();
+        entry_json["source"] = entry.source.as_str().into();
+        entry_json["destination"] = entry.destination.as_str().into();
+        entry_json["partition"] = entry.partition().into();
+        entry_json["in_tree"] = entry.in_tree.into();
+        entry_json["location"] = entry.location.as_str().into();
+        let _ = copied.push(entry_json);
+    }
+    root["copied_files"] = copied;
+
+    let mut declared_features = JsonValue::new_object();
+    let mut features = JsonValue::new_array();
+    for feature in &report.declared_features.features {
+        let mut entry = JsonValue::new_object();
+        entry["name"] = feature.name.as_str().into();
+        entry["version"] = feature.version.clone().into();
+        entry["unavailable"] = feature.unavailable.into();
+        entry["from_file_name"] = feature.from_file_name.into();
+        entry["location"] = feature.location.as_str().into();
+        let _ = features.push(entry);
+    }
+    declared_features["features"] = features;
+    declared_features["files"] = report.declared_features.files.clone().into();
+    let mut missing_hals = JsonValue::new_array();
+    for (feature, family) in report.declared_features.missing_hals(&report.hals) {
+        let mut entry = JsonValue::new_object();
+        entry["feature"] = feature.into();
+        entry["hal"] = format!("android.hardware.{}", family).into();
+        let _ = missing_hals.push(entry);
+    }
+    declared_features["missing_hals"] = missing_hals;
+    root["declared_features"] = declared_features;
+
+    let media = &report.media_profiles;
+    let mut media_json = JsonValue::new_object();
+    media_json["files"] = media.files.clone().into();
+    let mut profiles = JsonValue::new_array();
+    for profile in &media.profiles {
+        let mut entry = JsonValue::new_object();
+        entry["camera_id"] = profile.camera_id.clone().into();
+        entry["quality"] = profile.quality.as_str().into();
+        entry["file_format"] = profile.file_format.clone().into();
+        entry["video"] = match &profile.video {
+            Some(video) => {
+                let mut video_json = JsonValue::new_object();
+                video_json["codec"] = video.codec.as_str().into();
+                video_json["width"] = video.width.into();
+                video_json["height"] = video.height.into();
+                video_json["frame_rate"] = video.frame_rate.into();
+                video_json["bit_rate"] = video.bit_rate.into();
+                video_json
+            }
+            None => JsonValue::Null,
+        };
+        entry["audio"] = match &profile.audio {
+            Some(audio) => {
+                let mut audio_json = JsonValue::new_object();
+                audio_json["codec"] = audio.codec.as_str().into();
+                audio_json["bit_rate"] = audio.bit_rate.into();
+                audio_json["sample_rate"] = audio.sample_rate.into();
+                audio_json["channels"] = audio.channels.into();
+                audio_json
+            }
+            None => JsonValue::Null,
+        };
+        entry["file"] = profile.file.as_str().into();
+        let _ = profiles.push(entry);
+    }
+    media_json["profiles"] = profiles;
+    root["media_profiles"] = media_json;
+
+    let mut mixer_files = JsonValue::new_array();
+    for mixer_file in &report.mixer_paths.files {
+        let mut file_json = JsonValue::new_object();
+        file_json["file"] = mixer_file.file.as_str().into();
+        file_json["variant"] = mixer_file.variant.clone().into();
+        file_json["default_controls"] = mixer_file.defaults.len().into();
+        let mut paths = JsonValue::new_array();
+        for path in &mixer_file.paths {
+            let mut path_json = JsonValue::new_object();
+            path_json["name"] = path.name.as_str().into();
+            let mut controls = JsonValue::new_array();
+            for control in &path.controls {
+                let mut control_json = JsonValue::new_object();
+                control_json["name"] = control.name.as_str().into();
+                control_json["value"] = control.value.as_str().into();
+                let _ = controls.push(control_json);
+            }
+            path_json["controls"] = controls;
+            path_json["includes"] = path.includes.clone().into();
+            let _ = paths.push(path_json);
+        }
+        file_json["paths"] = paths;
+        file_json["key_paths"] =
+            mixer_file.key_paths().iter().map(|path| path.name.clone()).collect::<Vec<_>>().into();
+        let mut unresolved = JsonValue::new_array();
+        for (path, include) in mixer_file.unresolved_includes() {
+            let mut entry = JsonValue::new_object();
+            entry["path"] = path.into();
+            entry["include"] = include.into();
+            let _ = unresolved.push(entry);
+        }
+        file_json["unresolved_includes"] = unresolved;
+        let _ = mixer_files.push(file_json);
+    }
+    root["mixer_paths"] = mixer_files;
+
+    let mut key_layouts = JsonValue::new_array();
+    for layout in &report.key_layouts.files {
+        let mut layout_json = JsonValue::new_object();
+        layout_json["file"] = layout.file.as_str().into();
+        let mut keys = JsonValue::new_array();
+        for key in &layout.keys {
+            let mut key_json = JsonValue::new_object();
+            key_json["code"] = key.code.clone().into();
+            key_json["name"] = key.name.as_str().into();
+            key_json["flags"] = key.flags.clone().into();
+            let _ = keys.push(key_json);
+        }
+        layout_json["keys"] = keys;
+        let _ = key_layouts.push(layout_json);
+    }
+    root["key_layouts"] = key_layouts;
+
+    let carrier = &report.carrier_config;
+    let mut carrier_json = JsonValue::new_object();
+    carrier_json["apn_files"] = carrier.apn_files.clone().into();
+    carrier_json["apns"] = carrier.apns.clone().into();
+    carrier_json["apns_omitted"] = carrier.apns_omitted.into();
+    carrier_json["config_files"] = carrier.config_files.clone().into();
+    carrier_json["carrier_ids"] = carrier.carrier_ids.clone().into();
+    carrier_json["carrier_ids_omitted"] = carrier.carrier_ids_omitted.into();
+    carrier_json["carrier_list_blobs"] = carrier.carrier_list_blobs.clone().into();
+    root["carrier_config"] = carrier_json;
+
+    let telephony = &report.telephony;
+    let mut telephony_json = JsonValue::new_object();
+    match telephony.sim_count() {
+        Some((count, source)) => {
+            telephony_json["sim_slots"] = count.into();
+            telephony_json["sim_slots_source"] = source.into();
+        }
+        None => telephony_json["sim_slots"] = JsonValue::Null,
+    }
+    telephony_json["ril_class"] = telephony.ril_class().into();
+    telephony_json["default_network_modes"] = telephony.default_network_modes().into();
+    telephony_json["radio_families"] = telephony.radio_families().into();
+    telephony_json["overlay_files"] = telephony.overlay_files.clone().into();
+    for (key, values) in [("properties", &telephony.properties), ("resources", &telephony.resources)] {
+        let mut entries = JsonValue::new_object();
+        for (name, value) in values {
+            let mut entry = JsonValue::new_object();
+            entry["value"] = value.value.as_str().into();
+            entry["location"] = value.location.as_str().into();
+            entries[name.as_str()] = entry;
+        }
+        telephony_json[key] = entries;
+    }
+    root["telephony"] = telephony_json;
+
+    let power = &report.power;
+    let mut power_json = JsonValue::new_object();
+    power_json["hint_files"] = power.hint_files.clone().into();
+    power_json["hint_nodes"] = power.hint_nodes.clone().into();
+    power_json["hints"] = power.hints.clone().into();
+    power_json["profile_files"] = power.profile_files.clone().into();
+    let mut profile = JsonValue::new_object();
+    for (component, value) in &power.profile {
+        profile[component.as_str()] = value.as_str().into();
+    }
+    power_json["profile"] = profile;
+    root["power"] = power_json;
+
+    let perf = &report.perf_tuning;
+    let mut perf_json = JsonValue::new_object();
+    let mut perf_files = JsonValue::new_array();
+    for perf_file in &perf.files {
+        let mut file_json = JsonValue::new_object();
+        file_json["file"] = perf_file.file.as_str().into();
+        file_json["boosts"] = perf_file.boosts.into();
+        file_json["properties"] = perf_file.properties.into();
+        let _ = perf_files.push(file_json);
+    }
+    perf_json["files"] = perf_files;
+    let mut boosts = JsonValue::new_array();
+    for boost in &perf.boosts {
+        let mut boost_json = JsonValue::new_object();
+        boost_json["id"] = boost.id.as_str().into();
+        boost_json["name"] = boost.name.clone().into();
+        boost_json["type"] = boost.kind.clone().into();
+        boost_json["enabled"] = boost.enabled.into();
+        boost_json["timeout"] = boost.timeout.clone().into();
+        boost_json["target"] = boost.target.clone().into();
+        boost_json["resolution"] = boost.resolution.clone().into();
+        let mut resources = JsonValue::new_array();
+        for (opcode, value) in &boost.resources {
+            let mut resource = JsonValue::new_object();
+            resource["opcode"] = opcode.as_str().into();
+            resource["value"] = value.as_str().into();
+            let _ = resources.push(resource);
+        }
+        boost_json["resources"] = resources;
+        boost_json["file"] = boost.file.as_str().into();
+        let _ = boosts.push(boost_json);
+    }
+    perf_json["boosts"] = boosts;
+    let mut properties = JsonValue::new_array();
+    for property in &perf.properties {
+        let mut property_json = JsonValue::new_object();
+        property_json["name"] = property.name.as_str().into();
+        property_json["value"] = property.value.as_str().into();
+        property_json["target"] = property.target.clone().into();
+        property_json["file"] = property.file.as_str().into();
+        let _ = properties.push(property_json);
+    }
+    perf_json["properties"] = properties;
+    perf_json["targets"] = perf.targets().into();
+    perf_json["opcodes"] = perf.opcodes().into();
+    root["perf_tuning"] = perf_json;
+
+    let topology = &report.cpu_topology;
+    let mut topology_json = JsonValue::new_object();
+    let mut groups = JsonValue::new_object();
+    for (name, group) in &topology.groups {
+        let mut entry = JsonValue::new_object();
+        entry["cpus"] = group.cpus.clone().into();
+        entry["cores"] = group.cores.clone().into();
+        entry["profiles"] = group.profiles.clone().into();
+        entry["locations"] = group.locations.clone().into();
+        groups[name.as_str()] = entry;
+    }
+    topology_json["cpuset_groups"] = groups;
+    let mut clusters = JsonValue::new_array();
+    for (cores, cpusets) in topology.clusters() {
+        let mut entry = JsonValue::new_object();
+        entry["cores"] = cores.into();
+        entry["cpusets"] = cpusets.into();
+        let _ = clusters.push(entry);
+    }
+    topology_json["core_groups"] = clusters;
+    let mut profiles = JsonValue::new_array();
+    for profile in &topology.profiles {
+        let mut entry = JsonValue::new_object();
+        entry["name"] = profile.name.as_str().into();
+        entry["cpuset"] = profile.cpuset.clone().into();
+        entry["file"] = profile.file.as_str().into();
+        let _ = profiles.push(entry);
+    }
+    topology_json["task_profiles"] = profiles;
+    topology_json["files"] = topology.files.clone().into();
+    root["cpu_topology"] = topology_json;
+
+    let mut vendor_flags = JsonValue::new_object();
+    for (group, flags) in report.vendor_flags.groups() {
+        let mut group_json = JsonValue::new_object();
+        for (name, flag) in flags {
+            let mut entry = JsonValue::new_object();
+            entry["value"] = flag.value.as_str().into();
+            entry["location"] = flag.location.as_str().into();
+            entry["source"] = flag.source.label().into();
+            entry["overridden"] = flag.overridden.clone().into();
+            group_json[name] = entry;
+        }
+        vendor_flags[group.as_str()] = group_json;
+    }
+    root["vendor_flags"] = vendor_flags;
+
+    let setup = &report.filesystem_setup;
+    let mut setup_json = JsonValue::new_object();
+    setup_json["rc_files"] = setup.rc_files.clone().into();
+    let mut mounts = JsonValue::new_array();
+    for mount in &setup.mounts {
+        let mut entry = JsonValue::new_object();
+        entry["type"] = mount.fs_type.as_str().into();
+        entry["device"] = mount.device.as_str().into();
+        entry["target"] = mount.target.as_str().into();
+        entry["options"] = mount.options.clone().into();
+        entry["trigger"] = mount.trigger.as_str().into();
+        entry["location"] = mount.location.as_str().into();
+        let _ = mounts.push(entry);
+    }
+    setup_json["mounts"] = mounts;
+    let mut directories = JsonValue::new_array();
+    for dir in &setup.directories {
+        let mut entry = JsonValue::new_object();
+        entry["path"] = dir.path.as_str().into();
+        entry["mode"] = dir.mode.clone().into();
+        entry["owner"] = dir.owner.clone().into();
+        entry["group"] = dir.group.clone().into();
+        entry["trigger"] = dir.trigger.as_str().into();
+        entry["location"] = dir.location.as_str().into();
+        let _ = directories.push(entry);
+    }
+    setup_json["directories"] = directories;
+    let mut symlinks = JsonValue::new_array();
+    for symlink in &setup.symlinks {
+        let mut entry = JsonValue::new_object();
+        entry["link"] = symlink.link.as_str().into();
+        entry["target"] = symlink.target.as_str().into();
+        entry["trigger"] = symlink.trigger.as_str().into();
+        entry["location"] = symlink.location.as_str().into();
+        let _ = symlinks.push(entry);
+    }
+    setup_json["symlinks"] = symlinks;
+    setup_json["properties"] = setup.properties.iter().cloned().collect::<Vec<_>>().into();
+    root["filesystem_setup"] = setup_json;
+
+    let boot = &report.boot_sequence;
+    let mut boot_json = JsonValue::new_object();
+    boot_json["rc_files"] = boot.rc_files.clone().into();
+    let mut services = JsonValue::new_array();
+    for service in &boot.services {
+        let mut entry = JsonValue::new_object();
+        entry["name"] = service.name.as_str().into();
+        entry["classes"] = service.classes.clone().into();
+        entry["disabled"] = service.disabled.into();
+        entry["oneshot"] = service.oneshot.into();
+        entry["location"] = service.location.as_str().into();
+        let _ = services.push(entry);
+    }
+    boot_json["services"] = services;
+    let mut triggers = JsonValue::new_array();
+    for trigger in &boot.triggers {
+        let mut entry = JsonValue::new_object();
+        entry["trigger"] = trigger.name.as_str().into();
+        let mut starts = JsonValue::new_array();
+        for start in &trigger.starts {
+            let mut start_json = JsonValue::new_object();
+            start_json["command"] = start.command.as_str().into();
+            start_json["target"] = start.target.as_str().into();
+            start_json["services"] = boot.started_by(start).into();
+            start_json["location"] = start.location.as_str().into();
+            let _ = starts.push(start_json);
+        }
+        entry["starts"] = starts;
+        entry["services"] = boot.services_on(&trigger.name).into();
+        let _ = triggers.push(entry);
+    }
+    boot_json["triggers"] = triggers;
+    boot_json["not_started"] = boot.unstarted().iter().map(|service| service.name.as_str()).collect::<Vec<_>>().into();
+    let mut imports = JsonValue::new_array();
+    for import in &boot.imports {
+        let mut entry = JsonValue::new_object();
+        entry["path"] = import.path.as_str().into();
+        entry["resolved"] = import.resolved.clone().into();
+        entry["location"] = import.location.as_str().into();
+        let _ = imports.push(entry);
+    }
+    boot_json["imports"] = imports;
+    root["boot_sequence"] = boot_json;
+
+    let mut hashes = JsonValue::new_object();
+    for (file, sha256) in &report.file_hashes {
+        hashes[file.as_str()] = sha256.as_str().into();
+    }
+    root["file_hashes"] = hashes;
+
+    if let Some(merge) = &report.merge {
+        let mut merge_json = JsonValue::new_object();
+        merge_json["base_path"] = merge.base_path.as_str().into();
+        merge_json["inherited_sections"] = merge.inherited_sections.clone().into();
+        let mut entries = JsonValue::new_object();
+        for (category, inherited) in &merge.inherited_entries {
+            entries[category.as_str()] = inherited.clone().into();
+        }
+        merge_json["inherited_entries"] = entries;
+        merge_json["inherited_values"] = merge.inherited_values.clone().into();
+        root["merge"] = merge_json;
+    }
+
+    let mut warnings = JsonValue::new_array();
+    for warning in &report.warnings {
+        let mut entry = JsonValue::new_object();
+        entry["kind"] = warning.kind.name().into();
+        entry["file"] = warning.file.as_str().into();
+        entry["message"] = warning.message.as_str().into();
+        let _ = warnings.push(entry);
+    }
+    root["warnings"] = warnings;
+
+    root
+}
+
+// --export-json-combined: one entry per analyzed tree, with the tree path
+// and device name alongside the full report so a whole device family can be
+// loaded in one read
+pub fn combined_to_json(reports: &[&HardwareReport]) -> JsonValue {
+    let mut entries = JsonValue::new_array();
+    for report in reports {
+        let mut entry = JsonValue::new_object();
+        entry["tree_path"] = report.tree_path.as_str().into();
+        entry["device"] = report.device_info.get("device").cloned().into();
+        entry["vendor"] = report.device_info.get("vendor").cloned().into();
+        entry["report"] = report_to_json(report);
+        let _ = entries.push(entry);
+    }
+    entries
+}
+
+// --export-split: <dir>/<category>.json per driver category (attempted but
+// empty ones included) and a summary.json that maps each category back to
+// its file. Returns the number of category files written.
+pub fn write_split(report: &HardwareReport, dir: &Path, encoding: OutputEncoding) -> io::Result<usize> {
+    let files = split_files(report);
+    fs::create_dir_all(dir)?;
+    for file in &files {
+        encoding::write(&dir.join(&file.name), &file.contents, encoding)?;
+    }
+    // Not counting summary.json
+    Ok(files.len() - 1)
+}
+
+// One file of an --export-split directory
+pub struct SplitFile {
+    pub name: String,
+    // Driver entries, or categories for summary.json
+    pub entries: usize,
+    pub contents: String,
+}
+
+// The category files --export-split writes, then summary.json. Categories
+// whose names collide get a numeric suffix in category order
+// (GPUPlatform.json, GPUPlatform_2.json, ...). Names are compared ignoring
+// case, as "WiFi" and "Wifi" would overwrite each other on a
+// case-insensitive filesystem.
+pub fn split_files(report: &HardwareReport) -> Vec<SplitFile> {
+    let mut lists = sorted_drivers(&report.drivers);
+    for category in unfilled_categories(report) {
+        lists.insert(category, Vec::new());
+    }
+
+    let mut taken = BTreeSet::from(["summary.json".to_string()]);
+    let mut files: Vec<(String, &str)> = Vec::new();
+    for category in lists.keys() {
+        let name = split_file_name(category);
+        let stem = name.trim_end_matches(".json");
+        let mut file = name.clone();
+        let mut suffix = 2;
+        while !taken.insert(file.to_lowercase()) {
+            file = format!("{}_{}.json", stem, suffix);
+            suffix += 1;
+        }
+        files.push((file, category));
+    }
+
+    let mut split = Vec::new();
+    let mut categories = JsonValue::new_object();
+    for (file, category) in &files {
+        let entries = &lists[category];
+        let mut json = JsonValue::new_object();
+        json["category"] = (*category).into();
+        json["attempted"] = report.attempted_categories.contains(*category).into();
+        json["truncated"] = report.truncated_categories.contains(*category).into();
+        json["entries"] = entries.clone().into();
+        split.push(SplitFile { name: file.clone(), entries: entries.len(), contents: json.pretty(2) });
+
+        let mut summary_entry = JsonValue::new_object();
+        summary_entry["file"] = file.as_str().into();
+        summary_entry["count"] = entries.len().into();
+        categories[*category] = summary_entry;
+    }
+
+    let mut summary = JsonValue::new_object();
+    summary["tree_path"] = report.tree_path.as_str().into();
+    summary["structure_valid"] = report.structure_valid.into();
+    summary["device_info"] = string_map(&report.device_info);
+    summary["drivers_scanned"] = report.drivers_scanned.into();
+    summary["recursive"] = (report.max_depth != Some(0)).into();
+    summary["categories"] = categories;
+    split.push(SplitFile { name: "summary.json".to_string(), entries: files.len(), contents: summary.pretty(2) });
+
+    split
+}
+
+// Category name -> file name: spaces become underscores, ASCII letters,
+// digits, '-', '_' and '.' are kept and everything else ('/', parentheses,
+// ...) is dropped, e.g. "GPU/Platform" -> GPUPlatform.json. The mapping
+// isn't reversible on its own (and split_files may add a suffix);
+// summary.json records it.
+pub fn split_file_name(category: &str) -> String {
+    let mut name: String = category
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('_'),
+            c if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') => Some(c),
+            _ => None,
+        })
+        .collect();
+    // Leading dots would hide the file; "summary" is taken
+    name = name.trim_start_matches('.').to_string();
+    if name.is_empty() || name == "summary" {
+        name.insert_str(0, "category_");
+    }
+    format!("{}.json", name)
+}
+
+fn supply_links(links: &[SupplyLink]) -> JsonValue {
+    let mut array = JsonValue::new_array();
+    for link in links {
+        let mut entry = JsonValue::new_object();
+        entry["property"] = link.property.as_str().into();
+        entry["reference"] = link.reference.as_str().into();
+        entry["target"] = link.target.clone().into();
+        entry["name"] = link.name.clone().into();
+        entry["cycle"] = link.cycle.into();
+        entry["supplies"] = supply_links(&link.parents);
+        let _ = array.push(entry);
+    }
+    array
+}
+
+// Deduped entry count per driver category for --count-only, in --sort order
+pub fn driver_counts(report: &HardwareReport) -> Vec<(&str, usize)> {
+    let mut categories: Vec<&str> = report.drivers.keys().map(|c| c.as_str()).collect();
+    sort_categories(&mut categories, report);
+    categories.into_iter().map(|category| (category, unique_count(&report.drivers[category]))).collect()
+}
+
+pub fn counts_to_json(counts: &[(&str, usize)]) -> JsonValue {
+    let mut categories = JsonValue::new_object();
+    for (category, count) in counts {
+        categories[*category] = (*count).into();
+    }
+
+    let mut root = JsonValue::new_object();
+    root["categories"] = categories;
+    root["total_categories"] = counts.len().into();
+    root["total_entries"] = counts.iter().map(|(_, count)| count).sum::<usize>().into();
+    root
+}
+
+// --format summary-json: one flat object of headline numbers for metric
+// ingestion. The keys never change between runs: every known category has
+// a "count.<category>" key, 0 when the scan found nothing (or didn't run).
+pub fn summary_to_json(report: &HardwareReport) -> JsonValue {
+    let counts = driver_counts(report);
+    let mut root = JsonValue::new_object();
+    root["tool_version"] = env!("CARGO_PKG_VERSION").into();
+    root["device"] = report.device_info.get("device").cloned().into();
+    root["vendor"] = report.device_info.get("vendor").cloned().into();
+    root["structure"] = crate::structure_level(report).into();
+    for category in report.category_map.known() {
+        let count = counts.iter().find(|(name, _)| *name == category).map_or(0, |(_, count)| *count);
+        root[format!("count.{}", category).as_str()] = count.into();
+    }
+    root["total_drivers"] = counts.iter().map(|(_, count)| count).sum::<usize>().into();
+    root
+}
+
+// --only-missing: {"missing_files": [...], "missing_dirs": [...]}
+pub fn missing_to_json(files: &[&str], dirs: &[&str]) -> JsonValue {
+    let mut root = JsonValue::new_object();
+    root["missing_files"] = files.to_vec().into();
+    root["missing_dirs"] = dirs.to_vec().into();
+    root
+}
+
+// --format ndjson: a {"type": "metadata"} line with the tree path, structure
+// status and device info, then one {"type": "driver", category, name, source,
+// file} line per driver entry, written as soon as each is built. Bindings
+// found in several files get one line per file.
+pub fn write_ndjson(report: &HardwareReport, out: &mut impl Write) -> io::Result<()> {
+    let mut metadata = JsonValue::new_object();
+    metadata["type"] = "metadata".into();
+    metadata["tree_path"] = report.tree_path.as_str().into();
+    metadata["structure_valid"] = report.structure_valid.into();
+    metadata["device_info"] = string_map(&report.device_info);
+    writeln!(out, "{}", metadata.dump())?;
+
+    let lists = sorted_drivers(&report.drivers);
+    let mut categories: Vec<&str> = lists.keys().copied().collect();
+    sort_categories(&mut categories, report);
+    for category in categories {
+        let entries = &lists[category];
+        let (source, file) = driver_source(report, category);
+        for entry in entries {
+            // "qcom,foo (in a.dts, b.dtsi)"
+            let (name, files) = match entry.strip_suffix(')').and_then(|e| e.split_once(" (in ")) {
+                Some((name, files)) => {
+                    let paths = files.split(", ").flat_map(|file| binding_paths(report, name, file));
+                    (name.to_string(), paths.map(Some).collect())
+                }
+                None => (entry.clone(), vec![file.map(str::to_string)]),
+            };
+            for file in files {
+                let mut line = JsonValue::new_object();
+                line["type"] = "driver".into();
+                line["category"] = category.into();
+                line["name"] = name.as_str().into();
+                line["source"] = source.into();
+                line["file"] = file.into();
+                writeln!(out, "{}", line.dump())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Bindings only name the file they're in ("a.dts"); find its path relative
+// to the tree, using the parsed nodes to tell same-named files apart. Falls
+// back to the name when that doesn't settle it.
+fn binding_paths(report: &HardwareReport, compatible: &str, file_name: &str) -> Vec<String> {
+    let sources = &report.dts_sources;
+    let candidates: Vec<&String> =
+        sources.files.iter().filter(|file| Path::new(file).file_name().is_some_and(|name| name == file_name)).collect();
+    if candidates.len() == 1 {
+        return vec![candidates[0].clone()];
+    }
+    let defined_in = |file: &str| {
+        sources.nodes.values().any(|node| {
+            let location = node.property_locations.get("compatible").and_then(|at| at.rsplit_once(':'));
+            node.strings("compatible").contains(&compatible) && location.is_some_and(|(at, _)| at == file)
+        })
+    };
+    let paths: Vec<String> = candidates.into_iter().filter(|file| defined_in(file)).cloned().collect();
+    if paths.is_empty() { vec![file_name.to_string()] } else { paths }
+}
+
+// --count-only with --format ndjson: one {"type": "count"} line per category
+pub fn write_counts_ndjson(counts: &[(&str, usize)], out: &mut impl Write) -> io::Result<()> {
+    for (category, count) in counts {
+        let mut line = JsonValue::new_object();
+        line["type"] = "count".into();
+        line["category"] = (*category).into();
+        line["count"] = (*count).into();
+        writeln!(out, "{}", line.dump())?;
+    }
+    Ok(())
+}
+
+// category::source for a category as reported, i.e. after --category-map
+fn driver_source(report: &HardwareReport, category: &str) -> (&'static str, Option<&'static str>) {
+    category::source(report.category_map.original(category))
+}
+
+// The keys of "drivers": found categories plus attempted-but-empty ones, in
+// --sort order
+fn json_categories(report: &HardwareReport) -> Vec<&str> {
+    let mut categories: Vec<&str> = report.drivers.keys().map(|c| c.as_str()).collect();
+    categories.extend(unfilled_categories(report));
+    sort_categories(&mut categories, report);
+    categories
+}
+
+// Orders categories for --sort. Everything is sorted by name first and the
+// other orders are stable sorts on top, so ties stay alphabetical.
+pub fn sort_categories(categories: &mut [&str], report: &HardwareReport) {
+    categories.sort();
+    match report.driver_sort {
+        DriverSort::Name => {}
+        DriverSort::Source => categories.sort_by_key(|category| driver_source(report, category).0),
+        DriverSort::File => categories.sort_by_key(|category| {
+            let file = driver_source(report, category).1;
+            (file.is_none(), file)
+        }),
+        DriverSort::Count => categories.sort_by_key(|category| {
+            Reverse(report.drivers.get(*category).map_or(0, |entries| unique_count(entries)))
+        }),
+    }
+}
+
+fn unique_count(entries: &[String]) -> usize {
+    let mut unique: Vec<&String> = entries.iter().collect();
+    unique.sort();
+    unique.dedup();
+    unique.len()
+}
+
+// Attempted categories with nothing in report.drivers
+fn unfilled_categories(report: &HardwareReport) -> impl Iterator<Item = &str> {
+    report
+        .attempted_categories
+        .iter()
+        .map(|category| category.as_str())
+        .filter(|category| !report.drivers.contains_key(*category))
+}
+
+// Categories in name order with their entries sorted and deduped
+fn sorted_drivers(drivers: &HashMap<String, Vec<String>>) -> BTreeMap<&str, Vec<String>> {
+    drivers
+        .iter()
+        .map(|(category, entries)| {
+            let mut entries = entries.clone();
+            entries.sort();
+            entries.dedup();
+            (category.as_str(), entries)
+        })
+        .collect()
+}
+
+fn string_map(map: &HashMap<String, String>) -> JsonValue {
+    let mut object = JsonValue::new_object();
+    for (key, value) in map.iter().collect::<BTreeMap<_, _>>() {
+        object[key.as_str()] = value.as_str().into();
+    }
+    object
+}
+
+fn bool_map(map: &HashMap<String, bool>) -> JsonValue {
+    let mut object = JsonValue::new_object();
+    for (key, value) in map.iter().collect::<BTreeMap<_, _>>() {
+        object[key.as_str()] = (*value).into();
+    }
+    object
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_names_differing_in_case_get_a_suffix() {
+        let mut report = HardwareReport::default();
+        for category in ["WiFi Driver", "Wifi Driver", "WiFi/Driver", "Summary"] {
+            report.drivers.insert(category.to_string(), vec!["entry".to_string()]);
+        }
+        let names: Vec<String> = split_files(&report).into_iter().map(|file| file.name).collect();
+        assert_eq!(
+            names,
+            ["Summary_2.json", "WiFi_Driver.json", "WiFiDriver.json", "Wifi_Driver_2.json", "summary.json"]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+// TOML rendering of a HardwareReport for --format toml/--export-toml. The
+// document is the --format json one translated key for key: objects become
+// [tables], arrays of objects [[arrays of tables]], so "drivers" is a table
+// with one array per category.
+//
+// TOML has no null, so keys whose JSON value is null (unset optional
+// fields) are left out; empty arrays and tables are kept, so a missing key
+// always means "not set" and never "empty". Nulls inside arrays are dropped.
+
+use std::io::{self, Write};
+use json::JsonValue;
+use crate::HardwareReport;
+use crate::report_json;
+
+pub fn write_toml(report: &HardwareReport, out: &mut impl Write) -> io::Result<()> {
+    let json = report_json::report_to_json(report);
+    write_table(out, &[], &json)
+}
+
+// The key = value lines of a table, then its subtables and arrays of tables,
+// which TOML requires to come after every plain key
+fn write_table(out: &mut impl Write, path: &[String], table: &JsonValue) -> io::Result<()> {
+    for (key, value) in table.entries() {
+        if value.is_null() || value.is_object() || is_table_array(value) {
+            continue;
+        }
+        writeln!(out, "{} = {}", quote_key(key), inline(value))?;
+    }
+
+    for (key, value) in table.entries() {
+        let mut child = path.to_vec();
+        child.push(quote_key(key));
+        if value.is_object() {
+            writeln!(out, "\n[{}]", child.join("."))?;
+            write_table(out, &child, value)?;
+        } else if is_table_array(value) {
+            for member in value.members() {
+                writeln!(out, "\n[[{}]]", child.join("."))?;
+                write_table(out, &child, member)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Non-empty arrays of objects only; an empty array stays an inline []
+fn is_table_array(value: &JsonValue) -> bool {
+    value.is_array() && !value.is_empty() && value.members().all(JsonValue::is_object)
+}
+
+// A value on the right of '=': arrays and objects nested inside other
+// arrays are written inline
+fn inline(value: &JsonValue) -> String {
+    if value.is_array() {
+        let members: Vec<String> = value.members().filter(|member| !member.is_null()).map(inline).collect();
+        return format!("[{}]", members.join(", "));
+    }
+    if value.is_object() {
+        let entries: Vec<String> = value
+            .entries()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| format!("{} = {}", quote_key(key), inline(value)))
+            .collect();
+        return if entries.is_empty() { "{}".to_string() } else { format!("{{ {} }}", entries.join(", ")) };
+    }
+    match value.as_str() {
+        Some(string) => quote(string),
+        // Numbers and booleans are spelled the same in JSON and TOML
+        None => value.dump(),
+    }
+}
+
+// Bare keys are A-Za-z0-9_-; anything else ("HAL (Hardware Abstraction
+// Layer)", "device_info.codename" style keys) is quoted
+fn quote_key(key: &str) -> String {
+    let bare = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare { key.to_string() } else { quote(key) }
+}
+
+fn quote(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+    use crate::{AnalysisOptions, Profiler};
+
+    // Parsing the TOML gives back the --format json document minus its
+    // nulls. Category keys have spaces, slashes and parentheses, and
+    // key_files keys have dots, so each needs quoting.
+    #[test]
+    fn toml_parses_back_to_the_json_document() {
+        let tree = Scratch::new(
+            "toml",
+            &[
+                (
+                    "device.mk",
+                    "PRODUCT_MODEL := Widget \"Pro\" C:\\x\tedition\n\
+                     PRODUCT_PACKAGES += android.hardware.light@2.0-service libEGL_adreno\n",
+                ),
+                ("BoardConfig.mk", "TARGET_BOARD_PLATFORM := msmnile\nBOARD_HAVE_BLUETOOTH := true\n"),
+                (
+                    "arch/arm64/boot/dts/board.dts",
+                    "/ { model = \"board\"; gpu { compatible = \"qcom,adreno-640.1\"; }; };\n",
+                ),
+            ],
+        );
+        let options = AnalysisOptions { scan_drivers: true, ..Default::default() };
+        let tree_path = tree.path().to_string_lossy();
+        let report =
+            crate::detect_android_device_tree_structure(&tree_path, &options, &mut Profiler::default()).unwrap();
+
+        let mut out = Vec::new();
+        write_toml(&report, &mut out).unwrap();
+        let parsed: toml::Value = toml::from_str(&String::from_utf8(out).unwrap()).unwrap();
+
+        let json = report_json::report_to_json(&report);
+        for key in ["GPU/Platform", "HAL (Hardware Abstraction Layer)", "WiFi Driver"] {
+            assert!(json["driver_categories"].has_key(key), "{}", key);
+        }
+        assert!(json["key_files"].has_key("BoardConfig.mk"));
+        let expected: serde_json::Value = serde_json::from_str(&without_nulls(&json).dump()).unwrap();
+        assert_eq!(serde_json::to_value(parsed).unwrap(), expected);
+    }
+
+    fn without_nulls(value: &JsonValue) -> JsonValue {
+        if value.is_array() {
+            return JsonValue::Array(value.members().filter(|member| !member.is_null()).map(without_nulls).collect());
+        }
+        if value.is_object() {
+            let mut object = JsonValue::new_object();
+            for (key, value) in value.entries().filter(|(_, value)| !value.is_null()) {
+                object[key] = without_nulls(value);
+            }
+            return object;
+        }
+        value.clone()
+    }
+}
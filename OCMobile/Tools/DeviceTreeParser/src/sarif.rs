@@ -0,0 +1,231 @@
+// --format sarif: the report's warnings, plus any missing --fail-if-missing
+// files, as a SARIF 2.1.0 log for code-scanning tools. Every WarningKind is
+// a rule; locations are relative to the tree root (SRCROOT).
+
+use std::path::Path;
+use json::JsonValue;
+use crate::HardwareReport;
+use crate::warning::{Warning, WarningKind};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+pub fn report_to_sarif(report: &HardwareReport, missing_files: &[String]) -> JsonValue {
+    let mut rules = JsonValue::new_array();
+    for kind in WarningKind::ALL {
+        let (level, description) = rule(kind);
+        let mut entry = JsonValue::new_object();
+        entry["id"] = kind.name().into();
+        entry["shortDescription"] = text(description);
+        let mut configuration = JsonValue::new_object();
+        configuration["level"] = level.into();
+        entry["defaultConfiguration"] = configuration;
+        let _ = rules.push(entry);
+    }
+
+    let mut driver = JsonValue::new_object();
+    driver["name"] = "DeviceTreeParser".into();
+    driver["version"] = env!("CARGO_PKG_VERSION").into();
+    driver["rules"] = rules;
+    let mut tool = JsonValue::new_object();
+    tool["driver"] = driver;
+
+    let mut results = JsonValue::new_array();
+    for warning in &report.warnings {
+        let _ = results.push(result(warning));
+    }
+    for file in missing_files {
+        let warning = Warning::new(WarningKind::MissingRequiredFile, file.as_str(), "required by --fail-if-missing");
+        let _ = results.push(result(&warning));
+    }
+
+    let mut root = JsonValue::new_object();
+    root["uri"] = root_uri(&report.tree_path).into();
+    let mut base_ids = JsonValue::new_object();
+    base_ids["SRCROOT"] = root;
+
+    let mut run = JsonValue::new_object();
+    run["tool"] = tool;
+    run["originalUriBaseIds"] = base_ids;
+    run["results"] = results;
+
+    let mut log = JsonValue::new_object();
+    log["$schema"] = SCHEMA.into();
+    log["version"] = "2.1.0".into();
+    log["runs"] = vec![run].into();
+    log
+}
+
+// SARIF level and rule description of each warning kind. Missing required
+// files and DTS that doesn't parse make the analysis incomplete, so they're
+// errors; the rest are warnings.
+fn rule(kind: WarningKind) -> (&'static str, &'static str) {
+    match kind {
+        WarningKind::UnreadableFile => ("warning", "A file in the tree couldn't be read"),
+        WarningKind::MalformedDts => ("error", "A .dts/.dtsi file has a syntax error"),
+        WarningKind::UnresolvedInclude => ("warning", "A DTS #include or /include/ doesn't resolve to a file in the tree"),
+        WarningKind::MissingRequiredFile => ("error", "A file the tree is expected to have is missing"),
+        WarningKind::MissingCopySource => ("warning", "A PRODUCT_COPY_FILES source doesn't exist in the tree"),
+        WarningKind::CellMismatch => ("warning", "A reg property doesn't match #address-cells/#size-cells"),
+        WarningKind::UnreferencedModule => ("warning", "A prebuilt .ko isn't listed in BOARD_*KERNEL_MODULES"),
+        WarningKind::MissingModule => ("warning", "A BOARD_*KERNEL_MODULES entry has no matching .ko in the tree"),
+        WarningKind::DuplicateCompatible => ("warning", "A board compatible is claimed by more than one DTS file"),
+        WarningKind::CircularImport => ("warning", "An init script's imports lead back to itself"),
+        WarningKind::TruncatedCategory => ("note", "A driver category hit --max-entries-per-category and was cut short"),
+    }
+}
+
+fn result(warning: &Warning) -> JsonValue {
+    let (path, line) = split_location(&warning.file);
+
+    let mut artifact = JsonValue::new_object();
+    artifact["uri"] = encode_uri(path).into();
+    artifact["uriBaseId"] = "SRCROOT".into();
+    let mut physical = JsonValue::new_object();
+    physical["artifactLocation"] = artifact;
+    if let Some(line) = line {
+        let mut region = JsonValue::new_object();
+        region["startLine"] = line.into();
+        physical["region"] = region;
+    }
+    let mut location = JsonValue::new_object();
+    location["physicalLocation"] = physical;
+
+    let mut entry = JsonValue::new_object();
+    entry["ruleId"] = warning.kind.name().into();
+    entry["ruleIndex"] = WarningKind::ALL.iter().position(|kind| *kind == warning.kind).unwrap_or(0).into();
+    entry["level"] = rule(warning.kind).0.into();
+    entry["message"] = text(&warning.message);
+    entry["locations"] = vec![location].into();
+    entry
+}
+
+fn text(message: &str) -> JsonValue {
+    let mut object = JsonValue::new_object();
+    object["text"] = message.into();
+    object
+}
+
+// "arch/arm64/boot/dts/x.dts:12" -> (path, Some(12)); SARIF lines start at 1
+fn split_location(file: &str) -> (&str, Option<usize>) {
+    match file.rsplit_once(':') {
+        Some((path, line)) if !path.is_empty() => match line.parse::<usize>() {
+            Ok(line) if line > 0 => (path, Some(line)),
+            _ => (file, None),
+        },
+        _ => (file, None),
+    }
+}
+
+// file:// URI of the tree root, with a trailing slash as SARIF requires of
+// base URIs
+fn root_uri(tree_path: &str) -> String {
+    let path = Path::new(tree_path);
+    let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let mut uri = format!("file://{}", encode_uri(&absolute.to_string_lossy().replace('\\', "/")));
+    if !uri.ends_with('/') {
+        uri.push('/');
+    }
+    uri
+}
+
+// Percent-encodes everything but unreserved characters and '/'
+fn encode_uri(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The OASIS SARIF 2.1.0 schema (draft-07)
+    const SARIF_SCHEMA: &str = include_str!("../tests/sarif-schema-2.1.0.json");
+
+    // One warning of every kind, with and without a line, plus a missing file
+    #[test]
+    fn document_validates_against_the_sarif_schema() {
+        let warnings = WarningKind::ALL.iter().enumerate().map(|(index, kind)| {
+            let file = match index % 2 {
+                0 => format!("dir with space/file{}.dts:{}", index, index + 1),
+                _ => "BoardConfig.mk".to_string(),
+            };
+            Warning::new(*kind, file, format!("message {}", index))
+        });
+        let report = HardwareReport { tree_path: ".".to_string(), warnings: warnings.collect(), ..Default::default() };
+        let log = report_to_sarif(&report, &["system.prop".to_string()]);
+
+        // The published run.language pattern has a stray ']', which
+        // ECMAScript reads as a literal but the regex crate rejects; escape it
+        let language = "^[a-zA-Z]{2}|^[a-zA-Z]{2}-[a-zA-Z]{2}]?$";
+        assert!(SARIF_SCHEMA.contains(language));
+        let schema = SARIF_SCHEMA.replace(language, "^[a-zA-Z]{2}|^[a-zA-Z]{2}-[a-zA-Z]{2}\\\\]?$");
+        let schema: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        let validator = jsonschema::validator_for(&schema).unwrap();
+        let document: serde_json::Value = serde_json::from_str(&log.dump()).unwrap();
+        let errors: Vec<String> =
+            validator.iter_errors(&document).map(|e| format!("{} at {}", e, e.instance_path())).collect();
+        assert!(errors.is_empty(), "{:#?}", errors);
+        // ...and the schema does catch a result without its required message
+        let mut broken = document.clone();
+        broken["runs"][0]["results"][0].as_object_mut().unwrap().remove("message");
+        assert!(!validator.is_valid(&broken));
+
+        // Every result's ruleIndex points at the rule its ruleId names
+        let run = &log["runs"][0];
+        let rules = &run["tool"]["driver"]["rules"];
+        assert_eq!(run["results"].len(), WarningKind::ALL.len() + 1);
+        for result in run["results"].members() {
+            let index = result["ruleIndex"].as_usize().unwrap();
+            assert_eq!(rules[index]["id"], result["ruleId"]);
+            assert_eq!(rules[index]["defaultConfiguration"]["level"], result["level"]);
+        }
+    }
+
+    #[test]
+    fn document_has_a_rule_per_kind_and_located_results() {
+        let report = HardwareReport {
+            tree_path: "/nonexistent/device tree".to_string(),
+            warnings: vec![Warning::new(WarningKind::MalformedDts, "arch/arm64/boot/dts/x.dts:12", "syntax error")],
+            ..Default::default()
+        };
+        let log = report_to_sarif(&report, &["system.prop".to_string()]);
+
+        assert_eq!(log["version"], "2.1.0");
+        assert_eq!(log["$schema"], SCHEMA);
+        assert_eq!(log["runs"].len(), 1);
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "DeviceTreeParser");
+        assert_eq!(run["originalUriBaseIds"]["SRCROOT"]["uri"], "file:///nonexistent/device%20tree/");
+
+        let rules = &run["tool"]["driver"]["rules"];
+        assert_eq!(rules.len(), WarningKind::ALL.len());
+        for (index, kind) in WarningKind::ALL.iter().enumerate() {
+            assert_eq!(rules[index]["id"], kind.name());
+        }
+
+        let results = &run["results"];
+        assert_eq!(results.len(), 2);
+        let dts = &results[0];
+        assert_eq!(dts["ruleId"], WarningKind::MalformedDts.name());
+        assert_eq!(rules[dts["ruleIndex"].as_usize().unwrap()]["id"], dts["ruleId"]);
+        assert_eq!(dts["level"], "error");
+        assert_eq!(dts["message"]["text"], "syntax error");
+        let physical = &dts["locations"][0]["physicalLocation"];
+        assert_eq!(physical["artifactLocation"]["uri"], "arch/arm64/boot/dts/x.dts");
+        assert_eq!(physical["artifactLocation"]["uriBaseId"], "SRCROOT");
+        assert_eq!(physical["region"]["startLine"], 12);
+
+        // --fail-if-missing files come last, without a region
+        let missing = &results[1];
+        assert_eq!(missing["ruleId"], WarningKind::MissingRequiredFile.name());
+        assert_eq!(missing["locations"][0]["physicalLocation"]["artifactLocation"]["uri"], "system.prop");
+        assert!(missing["locations"][0]["physicalLocation"]["region"].is_null());
+    }
+}
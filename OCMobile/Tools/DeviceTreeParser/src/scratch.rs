@@ -0,0 +1,34 @@
+// Throwaway directory trees for unit tests, removed again when dropped
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub struct Scratch {
+    path: PathBuf,
+}
+
+impl Scratch {
+    // A fresh directory holding each (relative path, contents) file; the
+    // name keeps tests running in parallel apart
+    pub fn new(name: &str, files: &[(&str, &str)]) -> Scratch {
+        let path = std::env::temp_dir().join(format!("DeviceTreeParser-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        for (file, contents) in files {
+            let file = path.join(file);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, contents).unwrap();
+        }
+        Scratch { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,133 @@
+// --selftest: runs the scanners over a small tree built from fixtures
+// embedded in the binary and checks what each one extracts, so a broken
+// build shows up without needing a real device tree at hand.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::category;
+use crate::encoding::{self, UTF16LE_BOM, UTF8_BOM};
+use crate::profile::Profiler;
+use crate::symbols::Marker;
+use crate::vendor_flags::FlagSource;
+use crate::{AnalysisOptions, HardwareReport, OutputEncoding};
+
+// Path in the scratch tree -> contents
+const FIXTURES: [(&str, &str); 5] = [
+    ("BoardConfig.mk", include_str!("selftest/BoardConfig.mk")),
+    ("device.mk", include_str!("selftest/device.mk")),
+    ("system.prop", include_str!("selftest/system.prop")),
+    ("arch/arm64/boot/dts/selftest.dts", include_str!("selftest/selftest.dts")),
+    ("rootdir/etc/init.selftest.rc", include_str!("selftest/init.selftest.rc")),
+];
+
+struct Check {
+    parser: &'static str,
+    what: &'static str,
+    expected: usize,
+    actual: usize,
+}
+
+// Prints one line per check and returns how many failed
+pub fn run() -> usize {
+    let scratch = std::env::temp_dir().join(format!("DeviceTreeParser-selftest-{}", std::process::id()));
+    let result = write_fixtures(&scratch).map(|()| {
+        let options = AnalysisOptions { scan_drivers: true, ..Default::default() };
+        crate::detect_android_device_tree_structure(&scratch.to_string_lossy(), &options, &mut Profiler::default())
+    });
+    let _ = fs::remove_dir_all(&scratch);
+
+    println!("=== Self-Test ===");
+    let report = match result {
+        Ok(Ok(report)) => report,
+        Ok(Err(e)) => {
+            println!("  {} analysis: {}", Marker::Fail, e);
+            return 1;
+        }
+        Err(e) => {
+            println!("  {} fixtures: couldn't write {}: {}", Marker::Fail, scratch.display(), e);
+            return 1;
+        }
+    };
+
+    let checks = checks(&report);
+    let failed = checks.iter().filter(|check| check.actual != check.expected).count();
+    for check in &checks {
+        if check.actual == check.expected {
+            println!("  {} {}: {} ({})", Marker::Ok, check.parser, check.what, check.actual);
+        } else {
+            println!(
+                "  {} {}: {}: expected {}, got {}",
+                Marker::Fail,
+                check.parser,
+                check.what,
+                check.expected,
+                check.actual
+            );
+        }
+    }
+    println!("\nPassed {} of {} checks", checks.len() - failed, checks.len());
+    failed
+}
+
+fn write_fixtures(scratch: &Path) -> io::Result<()> {
+    for (path, content) in FIXTURES {
+        let path = scratch.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, content)?;
+    }
+    Ok(())
+}
+
+fn checks(report: &HardwareReport) -> Vec<Check> {
+    let drivers = |category: &str| report.drivers.get(category).map_or(0, |entries| entries.len());
+    let check = |parser, what, expected, actual| Check { parser, what, expected, actual };
+    // persist.vendor.selftest.flag is overridden again by system.prop
+    let build_overrides =
+        report.vendor_flags.flags.values().filter(|flag| flag.source == FlagSource::BuildOverride).count();
+
+    // --output-encoding, checked on the plist export
+    let utf8_bom = plist(report, OutputEncoding::Utf8Bom);
+    let utf16 = plist(report, OutputEncoding::Utf16le);
+    let utf16_declared = "<?xml version=\"1.0\" encoding=\"UTF-16\"?>"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<u8>>();
+    let declares_utf16 = utf16.get(UTF16LE_BOM.len()..).is_some_and(|rest| rest.starts_with(&utf16_declared));
+
+    vec![
+        check("BoardConfig.mk", "kernel modules", 3, drivers(category::KERNEL_MODULES)),
+        check("BoardConfig.mk", "Wi-Fi settings", 2, report.wifi.fields().len()),
+        check("BoardConfig.mk", "Bluetooth entries", 1, drivers(category::BLUETOOTH)),
+        check("BoardConfig.mk", "kernel defconfigs", 1, report.kernel.defconfigs.len()),
+        check("BoardConfig.mk", "A/B partitions", 3, report.boot.ab_partitions.len()),
+        check("BoardConfig.mk", "radio variables", 1, report.modem.vars.len()),
+        check("device.mk", "HAL families", 2, report.hals.len()),
+        check("device.mk", "copied files", 2, report.copied_files.entries.len()),
+        check("device.mk", "Treble flags", 2, report.treble.flags.len()),
+        check("DTS", "bindings", 3, drivers(category::DEVICE_TREE_BINDINGS)),
+        check("DTS", "NFC controllers", 1, report.nfc.devices.len()),
+        check("DTS", "charger controllers", 1, report.battery.controllers.len()),
+        check("init.rc", "mounts", 1, report.filesystem_setup.mounts.len()),
+        check("init.rc", "directories", 1, report.filesystem_setup.directories.len()),
+        check("init.rc", "symlinks", 1, report.filesystem_setup.symlinks.len()),
+        check("init.rc", "cpuset groups", 2, report.cpu_topology.groups.len()),
+        check("init.rc", "services started on boot", 2, report.boot_sequence.services_on("boot").len()),
+        check("device.mk", "property overrides", 1, build_overrides),
+        check("prop", "vendor flags", 3, report.vendor_flags.flags.len()),
+        check("export", "UTF-8 BOMs", 1, usize::from(utf8_bom.starts_with(&UTF8_BOM))),
+        check("export", "UTF-16LE BOMs", 1, usize::from(utf16.starts_with(&UTF16LE_BOM))),
+        check("export", "UTF-16 declarations", 1, usize::from(declares_utf16)),
+    ]
+}
+
+// The plist export as it would be written to disk
+fn plist(report: &HardwareReport, encoding: OutputEncoding) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    if crate::write_plist(report, &mut buffer, encoding).is_err() {
+        return Vec::new();
+    }
+    encoding::encode(&String::from_utf8_lossy(&buffer), encoding)
+}
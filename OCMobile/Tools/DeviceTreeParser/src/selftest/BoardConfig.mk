@@ -0,0 +1,15 @@
+# --selftest fixture: the BoardConfig.mk variables the scanners look at
+TARGET_BOARD_PLATFORM := msmnile
+TARGET_KERNEL_SOURCE := kernel/selftest/sm8150
+TARGET_KERNEL_CONFIG := vendor/selftest_defconfig
+
+BOARD_VENDOR_KERNEL_MODULES := wlan.ko audio_apr.ko
+	BOARD_VENDOR_KERNEL_MODULES   +=	touch.ko
+
+BOARD_WLAN_DEVICE := qcwcn
+WPA_SUPPLICANT_VERSION := VER_0_8_X
+BOARD_HAVE_BLUETOOTH := true
+
+BOARD_VNDK_VERSION := current
+AB_OTA_PARTITIONS += boot system vendor
+TARGET_NO_RADIOIMAGE := true
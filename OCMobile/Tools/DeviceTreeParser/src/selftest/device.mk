@@ -0,0 +1,14 @@
+# --selftest fixture: HAL packages, copied files and property overrides
+PRODUCT_FULL_TREBLE_OVERRIDE := true
+
+PRODUCT_PROPERTY_OVERRIDES += \
+    persist.vendor.selftest.flag=1 \
+    ro.vendor.selftest.override=true
+
+PRODUCT_PACKAGES += android.hardware.power@1.3-service
+PRODUCT_PACKAGES += android.hardware.power-service.pixel-libperfmgr
+PRODUCT_PACKAGES += android.hardware.nfc@1.2-service
+
+PRODUCT_COPY_FILES += \
+    $(LOCAL_PATH)/system.prop:$(TARGET_COPY_OUT_VENDOR)/etc/selftest.prop \
+    $(LOCAL_PATH)/rootdir/etc/init.selftest.rc:$(TARGET_COPY_OUT_VENDOR)/etc/init/hw/init.selftest.rc
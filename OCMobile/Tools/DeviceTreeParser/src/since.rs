@@ -0,0 +1,43 @@
+use std::path::Path;
+use std::process::Command;
+use crate::error::AnalyzeError;
+
+// Files changed between <git_ref> and the working copy, relative to the
+// tree directory. Files outside the tree are left out by --relative.
+pub fn changed_files(tree_path: &Path, git_ref: &str) -> Result<Vec<String>, AnalyzeError> {
+    // A leading '-' would be read as an option (--output=<file> writes
+    // anywhere); no branch, tag or revision can start with one
+    if git_ref.starts_with('-') {
+        return Err(AnalyzeError::Git(format!("'{}' is not a git revision", git_ref)));
+    }
+
+    // Outside a work tree git diff falls back to --no-index and prints its
+    // usage, so check up front to give a clear error instead
+    let inside = Command::new("git")
+        .arg("-C")
+        .arg(tree_path)
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .output()
+        .map_err(|e| AnalyzeError::Git(format!("failed to run git: {}", e)))?;
+    if !inside.status.success() {
+        return Err(AnalyzeError::NotAGitRepository(tree_path.display().to_string()));
+    }
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(tree_path)
+        .args(["diff", "--name-only", "--relative", "--end-of-options", git_ref, "--"])
+        .output()
+        .map_err(|e| AnalyzeError::Git(format!("failed to run git: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AnalyzeError::Git(format!("git diff against '{}' failed: {}", git_ref, stderr.trim())));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
@@ -0,0 +1,118 @@
+// --symbols/--color-theme: how the text report draws its status markers.
+// Set once before any output and read by every display function, like
+// fsread's retry count, so it doesn't have to be threaded through them.
+//
+// Colors are only used when stdout is a terminal and NO_COLOR isn't set;
+// --symbols defaults to unicode unless the locale isn't UTF-8.
+
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+use crate::{ColorTheme, SymbolSet};
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy)]
+struct Style {
+    symbols: SymbolSet,
+    // None when output isn't colored
+    theme: Option<ColorTheme>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Marker {
+    Ok,
+    Fail,
+    Warn,
+    Bullet,
+    Arrow,
+    // Where --compact cut a line short
+    Ellipsis,
+}
+
+pub fn init(symbols: Option<SymbolSet>, theme: ColorTheme) {
+    let symbols = symbols.unwrap_or(if utf8_locale() { SymbolSet::Unicode } else { SymbolSet::Ascii });
+    let theme = color_enabled().then_some(theme);
+    let _ = STYLE.set(Style { symbols, theme });
+}
+
+fn style() -> Style {
+    *STYLE.get_or_init(|| Style { symbols: SymbolSet::Unicode, theme: None })
+}
+
+impl Marker {
+    // The marker without color, for stderr and other non-report output
+    pub fn symbol(self) -> &'static str {
+        match (style().symbols, self) {
+            (SymbolSet::Unicode, Marker::Ok) => "✓",
+            (SymbolSet::Unicode, Marker::Fail) => "✗",
+            (SymbolSet::Unicode, Marker::Warn) => "⚠",
+            (SymbolSet::Unicode, Marker::Bullet) => "•",
+            (SymbolSet::Unicode, Marker::Arrow) => "→",
+            (SymbolSet::Unicode, Marker::Ellipsis) => "…",
+            (SymbolSet::Ascii, Marker::Ok) => "[OK]",
+            (SymbolSet::Ascii, Marker::Fail) => "[--]",
+            (SymbolSet::Ascii, Marker::Warn) => "[!!]",
+            (SymbolSet::Ascii, Marker::Bullet) => "*",
+            (SymbolSet::Ascii, Marker::Arrow) => "->",
+            (SymbolSet::Ascii, Marker::Ellipsis) => "...",
+        }
+    }
+
+    // ANSI SGR color of a status marker. The colorblind palette is
+    // Okabe-Ito blue/vermillion/yellow, which stay apart under red-green
+    // color blindness.
+    fn color(self, theme: ColorTheme) -> Option<&'static str> {
+        match (theme, self) {
+            (_, Marker::Bullet | Marker::Arrow | Marker::Ellipsis) => None,
+            (ColorTheme::Default, Marker::Ok) => Some("32"),
+            (ColorTheme::Default, Marker::Fail) => Some("31"),
+            (ColorTheme::Default, Marker::Warn) => Some("33"),
+            (ColorTheme::Colorblind, Marker::Ok) => Some("38;5;32"),
+            (ColorTheme::Colorblind, Marker::Fail) => Some("38;5;166"),
+            (ColorTheme::Colorblind, Marker::Warn) => Some("38;5;221"),
+        }
+    }
+}
+
+impl fmt::Display for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match style().theme.and_then(|theme| self.color(theme)) {
+            Some(color) => write!(f, "\x1b[{}m{}\x1b[0m", color, self.symbol()),
+            None => f.write_str(self.symbol()),
+        }
+    }
+}
+
+// The first of LC_ALL, LC_CTYPE and LANG that is set decides, as in
+// setlocale(); unset means the C locale, which is ASCII
+fn utf8_locale() -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+    for name in ["LC_ALL", "LC_CTYPE", "LANG"] {
+        if let Ok(value) = env::var(name)
+            && !value.is_empty()
+        {
+            let value = value.to_ascii_lowercase();
+            return value.contains("utf-8") || value.contains("utf8");
+        }
+    }
+    false
+}
+
+// Columns --compact lines are cut to: $COLUMNS, else 80 on a terminal.
+// None when stdout is piped, so nothing is cut.
+pub fn terminal_width() -> Option<usize> {
+    match env::var("COLUMNS").ok().and_then(|columns| columns.trim().parse().ok()) {
+        Some(columns) if columns > 0 => Some(columns),
+        _ => io::stdout().is_terminal().then_some(80),
+    }
+}
+
+fn color_enabled() -> bool {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let dumb = env::var("TERM").is_ok_and(|term| term == "dumb");
+    io::stdout().is_terminal() && !no_color && !dumb
+}
@@ -0,0 +1,300 @@
+use std::path::Path;
+use crate::dts::DtsSources;
+use crate::fsread;
+use crate::makefile::{self, statements};
+use crate::walk::WalkOptions;
+
+// Compatible fragments of the secure-world drivers, and the TEE each means
+const TEE_COMPATIBLES: [(&str, TeeType); 5] = [
+    ("qcom,qseecom", TeeType::Qsee),
+    ("qcom,smcinvoke", TeeType::Qsee),
+    ("android,trusty", TeeType::Trusty),
+    ("trustonic,mobicore", TeeType::Trustonic),
+    ("linaro,optee", TeeType::OpTee),
+];
+
+// Words in package names and makefile variables (split on '_', '.', '-',
+// '@') that tie them to one TEE
+const TEE_WORDS: [(&str, TeeType); 8] = [
+    ("qseecom", TeeType::Qsee),
+    ("qseecomd", TeeType::Qsee),
+    ("trusty", TeeType::Trusty),
+    ("storageproxyd", TeeType::Trusty),
+    ("trustonic", TeeType::Trustonic),
+    ("mobicore", TeeType::Trustonic),
+    ("mcdriverdaemon", TeeType::Trustonic),
+    ("optee", TeeType::OpTee),
+];
+
+// Keymaster/gatekeeper builds named after the SoC vendor rather than the
+// TEE (android.hardware.keymaster@4.1-service.qti)
+const VENDOR_BUILDS: [(&str, TeeType); 1] = [("qti", TeeType::Qsee)];
+
+// Makefile variables mentioning any of these words are reported as TEE flags
+const FLAG_WORDS: [&str; 9] =
+    ["TRUSTY", "QSEECOM", "TEE", "TRUSTONIC", "MOBICORE", "OPTEE", "KEYMASTER", "KEYMINT", "GATEKEEPER"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TeeType {
+    Trusty,
+    // Qualcomm Secure Execution Environment, reached through QSEECom
+    Qsee,
+    // Trustonic Kinibi (MobiCore), common on MediaTek and Exynos
+    Trustonic,
+    OpTee,
+}
+
+impl TeeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TeeType::Trusty => "Trusty",
+            TeeType::Qsee => "QSEE",
+            TeeType::Trustonic => "Trustonic",
+            TeeType::OpTee => "OP-TEE",
+        }
+    }
+}
+
+// The trusted execution environment a tree is built for, as seen from its
+// board flags, TEE/keymaster/gatekeeper packages and secure-world DTS nodes
+#[derive(Debug, Default)]
+pub struct TeeInfo {
+    pub board_flags: Vec<Flag>,
+    // keymaster and KeyMint PRODUCT_PACKAGES entries
+    pub keymaster_packages: Vec<String>,
+    pub gatekeeper_packages: Vec<String>,
+    // TEE daemons and client libraries (qseecomd, storageproxyd, ...)
+    pub tee_packages: Vec<String>,
+    pub bindings: Vec<TeeBinding>,
+}
+
+#[derive(Debug)]
+pub struct Flag {
+    pub name: String,
+    pub value: String,
+    pub location: String,
+    // Set inside an ifeq/ifdef block, which isn't evaluated
+    pub conditional: bool,
+}
+
+#[derive(Debug)]
+pub struct TeeBinding {
+    pub node: String,
+    pub compatible: String,
+    pub tee: TeeType,
+    // Keymaster packages built for this TEE, or generic ones (see correlate)
+    pub keymaster_packages: Vec<String>,
+    pub locations: Vec<String>,
+}
+
+impl TeeInfo {
+    pub fn is_empty(&self) -> bool {
+        self.board_flags.is_empty()
+            && self.keymaster_packages.is_empty()
+            && self.gatekeeper_packages.is_empty()
+            && self.tee_packages.is_empty()
+            && self.bindings.is_empty()
+    }
+
+    // Every TEE something in the tree points to, sorted
+    pub fn tee_types(&self) -> Vec<TeeType> {
+        let flags = self.board_flags.iter().filter(|flag| is_enabled(&flag.value)).filter_map(|f| tee_of(&f.name));
+        let packages = self.tee_packages.iter().chain(&self.keymaster_packages).filter_map(|package| hal_tee(package));
+        let mut types: Vec<TeeType> = flags.chain(packages).chain(self.bindings.iter().map(|b| b.tee)).collect();
+        types.sort();
+        types.dedup();
+        types
+    }
+
+    // The TEE the tree is built for: BOARD_USES_TRUSTY and the like first,
+    // then the DTS, then flags only set inside ifeq blocks, then package
+    // names
+    pub fn tee_type(&self) -> Option<TeeType> {
+        self.enabled_flags(false)
+            .find_map(|flag| tee_of(&flag.name))
+            .or_else(|| self.bindings.first().map(|binding| binding.tee))
+            .or_else(|| self.enabled_flags(true).find_map(|flag| tee_of(&flag.name)))
+            .or_else(|| self.tee_packages.iter().chain(&self.keymaster_packages).find_map(|p| hal_tee(p)))
+    }
+
+    // What tee_type() was decided from
+    pub fn tee_source(&self) -> Option<String> {
+        let tee = self.tee_type()?;
+        if let Some(flag) = self.enabled_flags(false).find(|flag| tee_of(&flag.name) == Some(tee)) {
+            return Some(format!("{} = {}", flag.name, flag.value));
+        }
+        if let Some(binding) = self.bindings.iter().find(|binding| binding.tee == tee) {
+            return Some(format!("{} binding", binding.compatible));
+        }
+        if let Some(flag) = self.enabled_flags(true).find(|flag| tee_of(&flag.name) == Some(tee)) {
+            return Some(format!("{} = {} (conditional)", flag.name, flag.value));
+        }
+        self.tee_packages
+            .iter()
+            .chain(&self.keymaster_packages)
+            .find(|package| hal_tee(package) == Some(tee))
+            .map(|package| format!("package {}", package))
+    }
+
+    fn enabled_flags(&self, conditional: bool) -> impl Iterator<Item = &Flag> {
+        self.board_flags.iter().filter(move |flag| flag.conditional == conditional && is_enabled(&flag.value))
+    }
+
+    // The newest keymaster interface among the packages: "Keymaster 4.1
+    // (HIDL)", or "KeyMint (AIDL)" which supersedes every HIDL version
+    pub fn keymaster_version(&self) -> Option<String> {
+        if self.keymaster_packages.iter().any(|package| package.contains("keymint")) {
+            return Some("KeyMint (AIDL)".to_string());
+        }
+        self.keymaster_packages
+            .iter()
+            .filter_map(|package| package.split_once("keymaster@"))
+            .filter_map(|(_, rest)| rest.split(['-', ':']).next())
+            .max_by(|a, b| version_key(a).cmp(&version_key(b)))
+            .map(|version| format!("Keymaster {} (HIDL)", version))
+            .or_else(|| (!self.keymaster_packages.is_empty()).then(|| "unknown".to_string()))
+    }
+}
+
+pub fn scan_tee(
+    sources: &DtsSources,
+    device_mk: Option<&Path>,
+    board_config: Option<&Path>,
+    walk_options: &WalkOptions,
+) -> TeeInfo {
+    let mut tee = TeeInfo::default();
+
+    for node in sources.nodes.values() {
+        for compatible in node.strings("compatible") {
+            if let Some((_, kind)) = TEE_COMPATIBLES.iter().find(|(marker, _)| compatible.starts_with(marker)) {
+                tee.bindings.push(TeeBinding {
+                    node: node.path.clone(),
+                    compatible: compatible.to_string(),
+                    tee: *kind,
+                    keymaster_packages: Vec::new(),
+                    locations: node.locations.clone(),
+                });
+                break;
+            }
+        }
+    }
+
+    // Unreadable makefiles are already reported by the driver scan
+    for makefile in [device_mk, board_config].into_iter().flatten() {
+        if !walk_options.includes_file(makefile) {
+            continue;
+        }
+        let Ok(content) = fsread::read_to_string(makefile) else {
+            continue;
+        };
+        let file_name = makefile.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let mut conditional_depth = 0usize;
+        for (line, statement) in statements(&content) {
+            match statement.split_whitespace().next().unwrap_or_default() {
+                "ifeq" | "ifneq" | "ifdef" | "ifndef" => conditional_depth += 1,
+                "endif" => conditional_depth = conditional_depth.saturating_sub(1),
+                _ => {}
+            }
+            let Some(makefile::Assignment { name, value, .. }) = makefile::parse_assignment(&statement) else {
+                continue;
+            };
+            if name == "PRODUCT_PACKAGES" {
+                for package in value.split_whitespace() {
+                    add_package(&mut tee, package);
+                }
+            } else if name.split('_').any(|word| FLAG_WORDS.contains(&word)) {
+                tee.board_flags.push(Flag {
+                    name: name.to_string(),
+                    value: value.trim().to_string(),
+                    location: format!("{}:{}", file_name, line),
+                    conditional: conditional_depth > 0,
+                });
+            }
+        }
+    }
+
+    correlate(&mut tee);
+    tee
+}
+
+fn add_package(tee: &mut TeeInfo, package: &str) {
+    let lower = package.to_lowercase();
+    let list = if lower.contains("keymaster") || lower.contains("keymint") {
+        &mut tee.keymaster_packages
+    } else if lower.contains("gatekeeper") {
+        &mut tee.gatekeeper_packages
+    } else if tee_of(package).is_some() {
+        &mut tee.tee_packages
+    } else {
+        return;
+    };
+    if !list.iter().any(|p| p == package) {
+        list.push(package.to_string());
+    }
+}
+
+// A keymaster package belongs to a secure-world binding when it's built
+// for that TEE (android.hardware.keymaster@4.1-service.qti for QSEECom), or
+// when its name doesn't tie it to any TEE at all
+fn correlate(tee: &mut TeeInfo) {
+    for binding in &mut tee.bindings {
+        binding.keymaster_packages = tee
+            .keymaster_packages
+            .iter()
+            .filter(|package| hal_tee(package).is_none_or(|kind| kind == binding.tee))
+            .cloned()
+            .collect();
+    }
+}
+
+fn tee_of(name: &str) -> Option<TeeType> {
+    find_word(name, &TEE_WORDS)
+}
+
+// The TEE a keymaster/gatekeeper build is for
+fn hal_tee(package: &str) -> Option<TeeType> {
+    tee_of(package).or_else(|| find_word(package, &VENDOR_BUILDS))
+}
+
+fn find_word(name: &str, words: &[(&str, TeeType)]) -> Option<TeeType> {
+    let lower = name.to_lowercase();
+    lower
+        .split(['_', '.', '-', '@'])
+        .find_map(|word| words.iter().find(|(marker, _)| *marker == word).map(|(_, kind)| *kind))
+}
+
+fn is_enabled(value: &str) -> bool {
+    !matches!(value.trim(), "" | "false" | "0")
+}
+
+// "4.1" -> [4, 1], so 4.10 sorts after 4.9
+fn version_key(version: &str) -> Vec<u32> {
+    version.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    fn scan(board_config: &str) -> TeeInfo {
+        let tree = Scratch::new("tee", &[("BoardConfig.mk", board_config)]);
+        let path = tree.path().join("BoardConfig.mk");
+        scan_tee(&DtsSources::default(), None, Some(&path), &WalkOptions::default())
+    }
+
+    #[test]
+    fn a_conditional_flag_is_only_a_fallback() {
+        let tee = scan("ifeq ($(TARGET_USES_TRUSTY),true)\nBOARD_USES_TRUSTY := true\nendif\n");
+        assert!(tee.board_flags[0].conditional);
+        assert_eq!(tee.tee_type(), Some(TeeType::Trusty));
+        assert_eq!(tee.tee_source().as_deref(), Some("BOARD_USES_TRUSTY = true (conditional)"));
+
+        // An unconditional flag for another TEE outranks it
+        let tee = scan("ifdef TRUSTY\n  BOARD_USES_TRUSTY := true\nendif\nBOARD_USES_QSEECOM := true\n");
+        assert!(!tee.board_flags[1].conditional);
+        assert_eq!(tee.tee_type(), Some(TeeType::Qsee));
+        assert_eq!(tee.tee_source().as_deref(), Some("BOARD_USES_QSEECOM = true"));
+        assert_eq!(tee.tee_types(), [TeeType::Trusty, TeeType::Qsee]);
+    }
+}
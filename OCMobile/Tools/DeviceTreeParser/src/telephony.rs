@@ -0,0 +1,364 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use xml::reader::{EventReader, XmlEvent};
+use crate::fsread;
+use crate::makefile::{parse_assignment, statements};
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Framework resources in overlay config.xml that configure telephony
+const RESOURCE_NAMES: [&str; 6] = [
+    "config_num_physical_slots",
+    "config_voice_capable",
+    "config_sms_capable",
+    "networkAttributes",
+    "radioAttributes",
+    "config_device_respects_hold_carrier_config",
+];
+const RESOURCE_PREFIXES: [&str; 4] = ["config_telephony", "config_mobile", "config_device_volte", "config_device_vt"];
+
+// Properties that pick the SIM layout, the RIL and the default network mode
+const PROPERTY_NAMES: [&str; 6] = [
+    "persist.radio.multisim.config",
+    "persist.vendor.radio.multisim.config",
+    "ro.multisim.simslotcount",
+    "rild.libpath",
+    "vendor.rild.libpath",
+    "ro.radio.noril",
+];
+const PROPERTY_PREFIXES: [&str; 2] = ["ro.telephony.", "telephony."];
+
+// RILConstants NETWORK_MODE_* by value
+const NETWORK_MODES: [&str; 34] = [
+    "GSM/WCDMA (WCDMA preferred)",
+    "GSM only",
+    "WCDMA only",
+    "GSM/WCDMA (auto)",
+    "CDMA/EvDo (auto)",
+    "CDMA only",
+    "EvDo only",
+    "Global (GSM/WCDMA/CDMA/EvDo)",
+    "LTE/CDMA/EvDo",
+    "LTE/GSM/WCDMA",
+    "LTE/CDMA/EvDo/GSM/WCDMA",
+    "LTE only",
+    "LTE/WCDMA",
+    "TD-SCDMA only",
+    "TD-SCDMA/WCDMA",
+    "LTE/TD-SCDMA",
+    "TD-SCDMA/GSM",
+    "LTE/TD-SCDMA/GSM",
+    "TD-SCDMA/GSM/WCDMA",
+    "LTE/TD-SCDMA/WCDMA",
+    "LTE/TD-SCDMA/GSM/WCDMA",
+    "TD-SCDMA/CDMA/EvDo/GSM/WCDMA",
+    "LTE/TD-SCDMA/CDMA/EvDo/GSM/WCDMA",
+    "NR only",
+    "NR/LTE",
+    "NR/LTE/CDMA/EvDo",
+    "NR/LTE/GSM/WCDMA",
+    "NR/LTE/CDMA/EvDo/GSM/WCDMA",
+    "NR/LTE/WCDMA",
+    "NR/LTE/TD-SCDMA",
+    "NR/LTE/TD-SCDMA/GSM",
+    "NR/LTE/TD-SCDMA/WCDMA",
+    "NR/LTE/TD-SCDMA/GSM/WCDMA",
+    "NR/LTE/TD-SCDMA/CDMA/EvDo/GSM/WCDMA",
+];
+
+// Telephony resources from overlay config.xml files plus the ril/multisim
+// properties set by prop files and PRODUCT_PROPERTY_OVERRIDES. Anything not
+// set falls back to the framework defaults: one SIM slot, the stock RIL class.
+#[derive(Debug, Default)]
+pub struct TelephonyInfo {
+    pub overlay_files: Vec<String>,
+    // Resource name -> value; array items are joined with ", "
+    pub resources: BTreeMap<String, TelephonyValue>,
+    pub properties: BTreeMap<String, TelephonyValue>,
+}
+
+#[derive(Debug)]
+pub struct TelephonyValue {
+    pub value: String,
+    // file:line for properties; overlay resources give just the file
+    pub location: String,
+}
+
+impl TelephonyInfo {
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty() && self.properties.is_empty()
+    }
+
+    fn property(&self, name: &str) -> Option<&str> {
+        self.properties.get(name).map(|property| property.value.as_str())
+    }
+
+    // SIM count and the setting it came from: the multisim config (ssss,
+    // dsds, dsda, tsts) wins over the slot count resource
+    pub fn sim_count(&self) -> Option<(usize, &'static str)> {
+        for name in ["persist.vendor.radio.multisim.config", "persist.radio.multisim.config"] {
+            let count = match self.property(name) {
+                Some("ssss") => 1,
+                Some("dsds") | Some("dsda") => 2,
+                Some("tsts") => 3,
+                _ => continue,
+            };
+            return Some((count, name));
+        }
+        for name in ["ro.multisim.simslotcount", "ro.telephony.sim_slots.count"] {
+            if let Some(count) = self.property(name).and_then(|value| value.parse().ok()) {
+                return Some((count, name));
+            }
+        }
+        let slots = self.resources.get("config_num_physical_slots")?;
+        Some((slots.value.parse().ok()?, "config_num_physical_slots"))
+    }
+
+    pub fn ril_class(&self) -> Option<&str> {
+        self.property("ro.telephony.ril_class")
+    }
+
+    // ro.telephony.default_network, one mode per SIM: "22,20" ->
+    // ["LTE/TD-SCDMA/CDMA/EvDo/GSM/WCDMA (22)", "LTE/TD-SCDMA/GSM/WCDMA (20)"]
+    pub fn default_network_modes(&self) -> Vec<String> {
+        let Some(value) = self.property("ro.telephony.default_network") else {
+            return Vec::new();
+        };
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|mode| !mode.is_empty())
+            .map(|mode| match mode.parse::<usize>().ok().and_then(|index| NETWORK_MODES.get(index)) {
+                Some(name) => format!("{} ({})", name, mode),
+                None => format!("unknown ({})", mode),
+            })
+            .collect()
+    }
+
+    // CDMA and/or GSM family radios, going by the default network modes and
+    // the LTE-on-CDMA flag
+    pub fn radio_families(&self) -> Vec<&'static str> {
+        let modes = self.default_network_modes();
+        let mut families = Vec::new();
+        let cdma_flag = self.property("telephony.lteOnCdmaDevice") == Some("1");
+        if cdma_flag || modes.iter().any(|mode| mode.contains("CDMA/") || mode.starts_with("CDMA")) {
+            families.push("CDMA");
+        }
+        if modes.iter().any(|mode| mode.contains("GSM") || mode.contains("WCDMA")) {
+            families.push("GSM");
+        }
+        families
+    }
+}
+
+pub fn scan_telephony(
+    tree_path: &Path,
+    device_mk: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> TelephonyInfo {
+    let mut telephony = TelephonyInfo::default();
+
+    // Resource overlays live under overlay*/ directories
+    // (overlay/frameworks/base/core/res/res/values/config.xml)
+    let mut overlays: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name == "config.xml", &mut overlays);
+    overlays.sort();
+    for file in overlays {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        if !relative.split('/').any(|component| component.contains("overlay")) {
+            continue;
+        }
+        match parse_overlay(&file, &relative, &mut telephony.resources) {
+            Ok(true) => telephony.overlay_files.push(relative),
+            Ok(false) => {}
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+        }
+    }
+
+    // PRODUCT_PROPERTY_OVERRIDES go into build.prop ahead of the prop files,
+    // so they're read first and the prop files win. Unreadable makefiles are
+    // already reported by the driver and vendor flag scans.
+    let mut makefiles: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name.ends_with(".mk"), &mut makefiles);
+    makefiles.sort_by_key(|file| (Some(file.as_path()) != device_mk, file.clone()));
+    for file in &makefiles {
+        let Ok(content) = fsread::read_to_string(file) else {
+            continue;
+        };
+        let relative = file.strip_prefix(tree_path).unwrap_or(file).to_string_lossy().to_string();
+        for (line, statement) in statements(&content) {
+            let Some(assignment) = parse_assignment(&statement) else {
+                continue;
+            };
+            if assignment.name != "PRODUCT_PROPERTY_OVERRIDES" && assignment.name != "PRODUCT_VENDOR_PROPERTIES" {
+                continue;
+            }
+            for (name, value) in assignment.value.split_whitespace().filter_map(|pair| pair.split_once('=')) {
+                set_property(&mut telephony, name, value.trim_matches('"'), format!("{}:{}", relative, line));
+            }
+        }
+    }
+
+    // Unreadable prop files are already reported by the vendor flag scan
+    let mut prop_files: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name.ends_with(".prop"), &mut prop_files);
+    prop_files.sort();
+    for file in &prop_files {
+        let Ok(content) = fsread::read_to_string(file) else {
+            continue;
+        };
+        let relative = file.strip_prefix(tree_path).unwrap_or(file).to_string_lossy().to_string();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some((name, value)) = line.split_once('=') {
+                set_property(&mut telephony, name.trim(), value.trim(), format!("{}:{}", relative, index + 1));
+            }
+        }
+    }
+
+    telephony
+}
+
+fn set_property(telephony: &mut TelephonyInfo, name: &str, value: &str, location: String) {
+    let wanted = PROPERTY_NAMES.contains(&name) || PROPERTY_PREFIXES.iter().any(|prefix| name.starts_with(prefix));
+    if wanted {
+        telephony.properties.insert(name.to_string(), TelephonyValue { value: value.to_string(), location });
+    }
+}
+
+fn is_telephony_resource(name: &str) -> bool {
+    RESOURCE_NAMES.contains(&name) || RESOURCE_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+// <integer name="config_num_physical_slots">2</integer>,
+// <string-array name="networkAttributes"><item>mobile,0,0,0,-1,true</item>...
+// Returns whether the file set any telephony resource.
+fn parse_overlay(path: &Path, relative: &str, resources: &mut BTreeMap<String, TelephonyValue>) -> io::Result<bool> {
+    let file = fsread::open(path)?;
+
+    let mut found = false;
+    // The resource being read and the items collected so far
+    let mut current: Option<(String, Vec<String>)> = None;
+    let mut text = String::new();
+    let mut parser = EventReader::new(BufReader::new(file));
+    loop {
+        match parser.next() {
+            Ok(XmlEvent::StartElement { name, attributes, .. }) => {
+                text.clear();
+                if name.local_name == "item" {
+                    continue;
+                }
+                let resource = attributes.iter().find(|a| a.name.local_name == "name").map(|a| a.value.clone());
+                if let Some(resource) = resource
+                    && is_telephony_resource(&resource)
+                {
+                    current = Some((resource, Vec::new()));
+                }
+            }
+            Ok(XmlEvent::Characters(chars)) | Ok(XmlEvent::CData(chars)) => text.push_str(&chars),
+            Ok(XmlEvent::EndElement { name }) => {
+                let Some((_, values)) = current.as_mut() else {
+                    continue;
+                };
+                let value = text.trim().trim_matches('"').to_string();
+                text.clear();
+                if name.local_name == "item" {
+                    values.push(value);
+                    continue;
+                }
+                let (resource, mut values) = current.take().unwrap();
+                if values.is_empty() && !value.is_empty() {
+                    values.push(value);
+                }
+                let location = relative.to_string();
+                resources.insert(resource, TelephonyValue { value: values.join(", "), location });
+                found = true;
+            }
+            Ok(XmlEvent::EndDocument) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    const CONFIG_XML: &str = r#"<resources>
+    <integer name="config_num_physical_slots">2</integer>
+    <bool name="config_device_volte_available">true</bool>
+    <string-array name="networkAttributes">
+        <item>"mobile,0,0,0,-1,true"</item>
+        <item>"wifi,1,1,1,-1,true"</item>
+    </string-array>
+    <bool name="config_showNavigationBar">false</bool>
+</resources>
+"#;
+
+    fn scan(name: &str, files: &[(&str, &str)]) -> TelephonyInfo {
+        let tree = Scratch::new(name, files);
+        let device_mk = tree.path().join("device.mk");
+        let mut warnings = Vec::new();
+        let telephony = scan_telephony(tree.path(), Some(&device_mk), &WalkOptions::default(), &mut warnings);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        telephony
+    }
+
+    #[test]
+    fn overlay_resources_and_radio_props_are_read() {
+        let telephony = scan(
+            "telephony",
+            &[
+                ("overlay/frameworks/base/core/res/res/values/config.xml", CONFIG_XML),
+                // Not in an overlay directory
+                (
+                    "res/values/config.xml",
+                    r#"<resources><integer name="config_num_physical_slots">3</integer></resources>"#,
+                ),
+                (
+                    "device.mk",
+                    "PRODUCT_PROPERTY_OVERRIDES += \\\n\
+                         ro.telephony.default_network=22,20 \\\n\
+                         ro.telephony.ril_class=MyRIL\n",
+                ),
+                ("system.prop", "# radio\npersist.vendor.radio.multisim.config=dsds\nro.telephony.ril_class=QcomRIL\n"),
+            ],
+        );
+
+        assert_eq!(telephony.overlay_files, ["overlay/frameworks/base/core/res/res/values/config.xml"]);
+        assert_eq!(telephony.resources["config_num_physical_slots"].value, "2");
+        assert_eq!(telephony.resources["networkAttributes"].value, "mobile,0,0,0,-1,true, wifi,1,1,1,-1,true");
+        assert!(!telephony.resources.contains_key("config_showNavigationBar"));
+
+        assert_eq!(telephony.sim_count(), Some((2, "persist.vendor.radio.multisim.config")));
+        // Prop files are read after PRODUCT_PROPERTY_OVERRIDES and win
+        assert_eq!(telephony.ril_class(), Some("QcomRIL"));
+        assert_eq!(telephony.properties["ro.telephony.ril_class"].location, "system.prop:3");
+        assert_eq!(
+            telephony.default_network_modes(),
+            ["LTE/TD-SCDMA/CDMA/EvDo/GSM/WCDMA (22)", "LTE/TD-SCDMA/GSM/WCDMA (20)"]
+        );
+        assert_eq!(telephony.radio_families(), ["CDMA", "GSM"]);
+    }
+
+    #[test]
+    fn sim_count_falls_back_to_the_slot_resource() {
+        let telephony = scan("telephony-slots", &[("overlay/values/config.xml", CONFIG_XML)]);
+        assert_eq!(telephony.sim_count(), Some((2, "config_num_physical_slots")));
+        assert_eq!(telephony.ril_class(), None);
+        assert!(telephony.radio_families().is_empty());
+
+        // No telephony overlay or props at all
+        let telephony = scan("telephony-none", &[("device.mk", "PRODUCT_PACKAGES += Dialer\n")]);
+        assert!(telephony.is_empty());
+        assert_eq!(telephony.sim_count(), None);
+    }
+}
@@ -0,0 +1,26 @@
+use std::path::Path;
+use handlebars::Handlebars;
+use crate::fsread;
+use crate::HardwareReport;
+use crate::report_json;
+
+// Renders the report through a user-supplied handlebars template. The
+// template context is the same document --format json prints, so any
+// field documented there can be used, e.g. {{device_info.vendor}} or
+// {{#each drivers}}{{@key}}: {{len this}}{{/each}}.
+pub fn render(template_path: &Path, report: &HardwareReport) -> Result<String, String> {
+    let source = fsread::read_to_string(template_path)
+        .map_err(|e| format!("cannot read template '{}': {}", template_path.display(), e))?;
+
+    let context: serde_json::Value = serde_json::from_str(&report_json::report_to_json(report).dump())
+        .map_err(|e| format!("cannot build template context: {}", e))?;
+
+    let mut handlebars = Handlebars::new();
+    // Reports are plain text, not HTML
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars
+        .register_template_string("report", source)
+        .map_err(|e| format!("invalid template '{}': {}", template_path.display(), e))?;
+
+    handlebars.render("report", &context).map_err(|e| e.to_string())
+}
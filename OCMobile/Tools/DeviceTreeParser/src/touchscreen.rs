@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+use crate::dts::{DtsNode, DtsSources};
+use crate::walk::{self, WalkOptions};
+
+// Touch controller vendors as they appear in compatible strings, with the
+// names their firmware blobs usually carry
+const TOUCH_VENDORS: [(&str, &[&str]); 10] = [
+    ("focaltech", &["focaltech", "fts"]),
+    ("goodix", &["goodix", "gt9", "gt1", "gtp"]),
+    ("synaptics", &["synaptics", "syna"]),
+    ("novatek", &["novatek", "nvt"]),
+    ("himax", &["himax", "hx"]),
+    ("atmel", &["atmel", "mxt"]),
+    ("elan", &["elan", "ektf"]),
+    ("ilitek", &["ilitek", "ili"]),
+    ("st", &["stm", "fts"]),
+    ("sec", &["sec_ts", "sec-ts"]),
+];
+
+const FIRMWARE_EXTENSIONS: [&str; 3] = [".bin", ".img", ".fw"];
+
+#[derive(Debug, Default)]
+pub struct Touchscreen {
+    pub node: String,
+    pub vendor: String,
+    pub compatible: Vec<String>,
+    // *fw-name/firmware-name property, if the DTS names its blob
+    pub firmware_name: Option<String>,
+    // Blobs under firmware/ matching the DTS name or the vendor
+    pub firmware_files: Vec<String>,
+    pub locations: Vec<String>,
+}
+
+pub fn find_touchscreens(tree_path: &Path, sources: &DtsSources, walk_options: &WalkOptions) -> Vec<Touchscreen> {
+    let mut touchscreens: Vec<Touchscreen> = Vec::new();
+
+    for node in sources.nodes.values() {
+        let Some(vendor) = touch_vendor(node) else {
+            continue;
+        };
+        touchscreens.push(Touchscreen {
+            node: node.path.clone(),
+            vendor: vendor.to_string(),
+            compatible: node.strings("compatible").into_iter().map(|s| s.to_string()).collect(),
+            firmware_name: firmware_property(node),
+            locations: node.locations.clone(),
+            ..Default::default()
+        });
+    }
+
+    if touchscreens.is_empty() {
+        return touchscreens;
+    }
+
+    let firmware = find_firmware(tree_path, walk_options);
+    for touchscreen in &mut touchscreens {
+        let keywords = TOUCH_VENDORS
+            .iter()
+            .find(|(vendor, _)| *vendor == touchscreen.vendor)
+            .map_or(&[][..], |(_, keywords)| *keywords);
+
+        for (relative, name) in &firmware {
+            let named = touchscreen.firmware_name.as_deref().is_some_and(|fw| name == &fw.to_lowercase());
+            if named || keywords.iter().any(|keyword| name.contains(keyword)) {
+                touchscreen.firmware_files.push(relative.clone());
+            }
+        }
+    }
+
+    touchscreens
+}
+
+// Controller part numbers and driver names that mark a compatible as touch
+const TOUCH_MARKERS: [&str; 15] = [
+    "touch", "_ts", "-ts", ",fts", "gt9", "gt1x", "mxt", "nt36", "hx83", "ft5", "ft8", "ekth", "rmi4", "dsx", "tcm",
+];
+
+// Vendor prefix of a touch controller node, e.g. "goodix,gt9896" -> goodix
+fn touch_vendor(node: &DtsNode) -> Option<&str> {
+    let compatibles = node.strings("compatible");
+    let is_touch = node.name().contains("touch")
+        || compatibles.iter().any(|c| TOUCH_MARKERS.iter().any(|marker| c.contains(marker)));
+    if !is_touch {
+        return None;
+    }
+
+    let vendors: Vec<&str> = compatibles.iter().filter_map(|c| c.split_once(',').map(|(vendor, _)| vendor)).collect();
+    vendors
+        .iter()
+        .find(|vendor| TOUCH_VENDORS.iter().any(|(known, _)| known == *vendor))
+        .or(vendors.first())
+        .copied()
+}
+
+fn firmware_property(node: &DtsNode) -> Option<String> {
+    node.properties
+        .keys()
+        .find(|property| property.ends_with("fw-name") || property.ends_with("firmware-name"))
+        .and_then(|property| node.string(property))
+        .map(|s| s.to_string())
+}
+
+// (path relative to the tree, lowercased file name) of every blob in a
+// firmware/ directory
+fn find_firmware(tree_path: &Path, walk_options: &WalkOptions) -> Vec<(String, String)> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| FIRMWARE_EXTENSIONS.iter().any(|ext| name.ends_with(ext)),
+        &mut files,
+    );
+    files.sort();
+
+    files
+        .into_iter()
+        .filter_map(|file| {
+            let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_path_buf();
+            let in_firmware = relative.parent()?.components().any(|c| c.as_os_str() == "firmware");
+            let name = relative.file_name()?.to_string_lossy().to_lowercase();
+            in_firmware.then(|| (relative.to_string_lossy().to_string(), name))
+        })
+        .collect()
+}
@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::makefile;
+use crate::walk::{self, EntryKind, WalkOptions};
+
+// Makefile variables that describe Treble/VNDK support
+const TREBLE_PREFIXES: [&str; 5] = [
+    "BOARD_VNDK_",
+    "PRODUCT_TREBLE_",
+    "PRODUCT_FULL_TREBLE",
+    "PRODUCT_USE_VNDK",
+    "PRODUCT_EXTRA_VNDK_VERSIONS",
+];
+
+// Directories holding VNDK libraries or snapshots
+const VNDK_DIRS: [&str; 2] = ["vndk", "vndk-sp"];
+
+// BOARD_VNDK_VERSION, PRODUCT_TREBLE_*/PRODUCT_FULL_TREBLE* flags and any
+// vndk/vndk-sp directories, i.e. what decides whether a GSI will boot
+#[derive(Debug, Default)]
+pub struct TrebleInfo {
+    pub vndk_version: Option<String>,
+    // Every matched variable, BOARD_VNDK_VERSION included
+    pub flags: BTreeMap<String, String>,
+    // Relative to the tree
+    pub vndk_dirs: Vec<String>,
+}
+
+impl TrebleInfo {
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty() && self.vndk_dirs.is_empty()
+    }
+
+    // Records `line` if it sets one of the Treble/VNDK variables
+    pub fn parse_variable(&mut self, line: &str) {
+        let Some((name, value)) = makefile::split_assignment(line) else {
+            return;
+        };
+        if !TREBLE_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+            return;
+        }
+
+        let value = value.trim_matches('"').to_string();
+        if name == "BOARD_VNDK_VERSION" && !value.is_empty() {
+            self.vndk_version = Some(value.clone());
+        }
+        self.flags.insert(name.to_string(), value);
+    }
+
+    // An explicit PRODUCT_FULL_TREBLE(_OVERRIDE) wins; otherwise Treble
+    // linker namespaces or a VNDK version imply a Treble build
+    pub fn is_compatible(&self) -> bool {
+        for name in ["PRODUCT_FULL_TREBLE_OVERRIDE", "PRODUCT_FULL_TREBLE"] {
+            if let Some(value) = self.flags.get(name) {
+                return value == "true";
+            }
+        }
+        self.flags.get("PRODUCT_TREBLE_LINKER_NAMESPACES").is_some_and(|value| value == "true")
+            || self.vndk_version.is_some()
+    }
+}
+
+pub fn find_vndk_dirs(tree_path: &Path, walk_options: &WalkOptions) -> Vec<String> {
+    let mut found = Vec::new();
+    scan_for_vndk_dirs(tree_path, tree_path, walk_options, 0, &mut found);
+    found.sort();
+    found
+}
+
+fn scan_for_vndk_dirs(tree_path: &Path, path: &Path, walk_options: &WalkOptions, depth: usize, found: &mut Vec<String>) {
+    if !walk_options.within_depth(depth) {
+        return;
+    }
+
+    for (entry_path, kind) in walk::read_dir(path, walk_options) {
+        if kind != EntryKind::Dir {
+            continue;
+        }
+        let dir_name = entry_path.file_name().unwrap_or_default().to_string_lossy();
+        if VNDK_DIRS.contains(&dir_name.as_ref()) {
+            let relative = entry_path.strip_prefix(tree_path).unwrap_or(&entry_path);
+            found.push(relative.display().to_string());
+        }
+        scan_for_vndk_dirs(tree_path, &entry_path, walk_options, depth + 1, found);
+    }
+}
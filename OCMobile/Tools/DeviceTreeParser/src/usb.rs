@@ -0,0 +1,215 @@
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::makefile::{self, statements};
+use crate::walk::{self, WalkOptions};
+
+// Properties that pick the gadget's function set; persist.* survives reboots
+// and is what a build defaults to
+const CONFIG_PROPERTIES: [&str; 2] = ["persist.sys.usb.config", "sys.usb.config"];
+
+// init triggers a boot always passes through, as opposed to property
+// triggers and charger mode
+const BOOT_TRIGGERS: [&str; 6] = ["early-init", "init", "fs", "post-fs", "post-fs-data", "boot"];
+
+// USB gadget setup: the sys.usb.config values init scripts, .prop files and
+// device.mk set, the compositions init.*.usb.rc knows how to configure and
+// BoardConfig's BOARD_USB_* flags
+#[derive(Debug, Default)]
+pub struct UsbInfo {
+    pub settings: Vec<UsbSetting>,
+    // Function sets from "on property:sys.usb.config=..." triggers, in
+    // file order
+    pub compositions: Vec<String>,
+    pub board_flags: Vec<BoardFlag>,
+    // ro.adb.secure, where set (0 = no authorization prompt)
+    pub adb_secure: Option<String>,
+    // .rc and .prop files any of the above came from
+    pub files: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct UsbSetting {
+    pub property: String,
+    // "mtp,adb", "none", ...
+    pub value: String,
+    // The init trigger a setprop runs under; None for .prop files and
+    // device.mk
+    pub trigger: Option<String>,
+    pub location: String,
+}
+
+#[derive(Debug)]
+pub struct BoardFlag {
+    pub name: String,
+    pub value: String,
+    pub location: String,
+}
+
+impl UsbInfo {
+    pub fn is_empty(&self) -> bool {
+        self.settings.is_empty() && self.compositions.is_empty() && self.board_flags.is_empty()
+    }
+
+    // What the gadget comes up with: the last persist.sys.usb.config set
+    // outside init, else the first sys.usb.config setprop on a boot trigger
+    pub fn default_setting(&self) -> Option<&UsbSetting> {
+        self.settings
+            .iter()
+            .rev()
+            .find(|setting| setting.trigger.is_none() && setting.property == "persist.sys.usb.config")
+            .or_else(|| {
+                self.settings.iter().find(|setting| {
+                    setting.trigger.as_deref().is_some_and(|trigger| BOOT_TRIGGERS.contains(&trigger))
+                })
+            })
+    }
+
+    pub fn default_functions(&self) -> Vec<&str> {
+        self.default_setting().map(|setting| split_functions(&setting.value)).unwrap_or_default()
+    }
+
+    // None when no default configuration was found
+    pub fn adb_by_default(&self) -> Option<bool> {
+        self.default_setting().map(|setting| split_functions(&setting.value).contains(&"adb"))
+    }
+
+    // Every function any setting or composition names, sorted
+    pub fn functions(&self) -> Vec<&str> {
+        let values = self.settings.iter().map(|setting| setting.value.as_str());
+        let compositions = self.compositions.iter().map(|composition| composition.as_str());
+        let mut all: Vec<&str> = values.chain(compositions).flat_map(split_functions).collect();
+        all.sort();
+        all.dedup();
+        all
+    }
+}
+
+// "mtp,adb" -> [mtp, adb]; "none" and unexpanded ${...} values name nothing
+fn split_functions(value: &str) -> Vec<&str> {
+    if value.contains("${") {
+        return Vec::new();
+    }
+    value.split(',').map(str::trim).filter(|function| !function.is_empty() && *function != "none").collect()
+}
+
+pub fn scan_usb(
+    tree_path: &Path,
+    device_mk: Option<&Path>,
+    board_config: Option<&Path>,
+    walk_options: &WalkOptions,
+) -> UsbInfo {
+    let mut usb = UsbInfo::default();
+
+    // Unreadable .rc files are already reported by the vendor flag scan,
+    // and makefiles by the driver scan
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(
+        tree_path,
+        walk_options,
+        0,
+        &|name| (name.ends_with(".rc") && !name.starts_with("ueventd")) || name.ends_with(".prop"),
+        &mut files,
+    );
+    files.sort();
+    for file in files {
+        let Ok(content) = fsread::read_to_string(&file) else {
+            continue;
+        };
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let before = usb.settings.len() + usb.compositions.len();
+        if relative.ends_with(".prop") {
+            parse_prop(&content, &relative, &mut usb);
+        } else {
+            parse_rc(&content, &relative, &mut usb);
+        }
+        if usb.settings.len() + usb.compositions.len() > before {
+            usb.files.push(relative);
+        }
+    }
+
+    for makefile in [device_mk, board_config].into_iter().flatten() {
+        if !walk_options.includes_file(makefile) {
+            continue;
+        }
+        let Ok(content) = fsread::read_to_string(makefile) else {
+            continue;
+        };
+        let file_name = makefile.file_name().unwrap_or_default().to_string_lossy().to_string();
+        for (line, statement) in statements(&content) {
+            let location = format!("{}:{}", file_name, line);
+            let Some(makefile::Assignment { name, value, .. }) = makefile::parse_assignment(&statement) else {
+                continue;
+            };
+            if name.starts_with("BOARD_USB_") {
+                usb.board_flags.push(BoardFlag {
+                    name: name.to_string(),
+                    value: value.trim().to_string(),
+                    location,
+                });
+                continue;
+            }
+            // PRODUCT_PROPERTY_OVERRIDES += persist.sys.usb.config=mtp,adb
+            for assignment in value.split_whitespace() {
+                property(assignment, &location, None, &mut usb);
+            }
+        }
+    }
+
+    usb
+}
+
+// key=value lines
+fn parse_prop(content: &str, file: &str, usb: &mut UsbInfo) {
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if !line.starts_with('#') {
+            property(line, &format!("{}:{}", file, index + 1), None, usb);
+        }
+    }
+}
+
+// setprop commands under "on" sections, and the property:sys.usb.config
+// triggers of the compositions the gadget can be switched to
+fn parse_rc(content: &str, file: &str, usb: &mut UsbInfo) {
+    let mut trigger: Option<String> = None;
+    for (index, line) in content.lines().enumerate() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first().copied() {
+            Some("on") => {
+                trigger = Some(tokens[1..].join(" "));
+                for condition in &tokens[1..] {
+                    if let Some(value) = condition.strip_prefix("property:sys.usb.config=")
+                        && !split_functions(value).is_empty()
+                        && !usb.compositions.iter().any(|c| c == value)
+                    {
+                        usb.compositions.push(value.to_string());
+                    }
+                }
+            }
+            Some("service") | Some("import") => trigger = None,
+            Some("setprop") if tokens.len() >= 3 => {
+                let location = format!("{}:{}", file, index + 1);
+                property(&format!("{}={}", tokens[1], tokens[2]), &location, trigger.as_deref(), usb);
+            }
+            _ => {}
+        }
+    }
+}
+
+// One name=value pair, kept when it's a USB config or ro.adb.secure
+fn property(assignment: &str, location: &str, trigger: Option<&str>, usb: &mut UsbInfo) {
+    let Some((name, value)) = assignment.split_once('=') else {
+        return;
+    };
+    let (name, value) = (name.trim(), value.trim().trim_matches('"'));
+    if name == "ro.adb.secure" {
+        usb.adb_secure = Some(value.to_string());
+    } else if CONFIG_PROPERTIES.contains(&name) {
+        usb.settings.push(UsbSetting {
+            property: name.to_string(),
+            value: value.to_string(),
+            trigger: trigger.map(str::to_string),
+            location: location.to_string(),
+        });
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::makefile::{parse_assignment, statements, AssignOp};
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Property namespaces vendors put their feature toggles under
+const FLAG_PREFIXES: [&str; 2] = ["persist.vendor.", "ro.vendor."];
+
+// persist.vendor.*/ro.vendor.* properties from PRODUCT_PROPERTY_OVERRIDES,
+// prop files and init.rc setprop lines. The overrides are written to
+// build.prop ahead of the tree's prop files, and prop files are loaded before
+// init runs any setprop, so they're read in that order and the last write
+// wins, as on the device.
+#[derive(Debug, Default)]
+pub struct VendorFlags {
+    // Property name -> final value
+    pub flags: BTreeMap<String, VendorFlag>,
+    // Files that set at least one flag
+    pub files: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct VendorFlag {
+    pub value: String,
+    // Where the winning value was set
+    pub location: String,
+    pub source: FlagSource,
+    // Earlier writes it overrides, as "value (file:line)"
+    pub overridden: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlagSource {
+    // PRODUCT_PROPERTY_OVERRIDES in a product makefile
+    BuildOverride,
+    PropFile,
+    InitRc,
+}
+
+impl FlagSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            FlagSource::BuildOverride => "build override",
+            FlagSource::PropFile => "prop file",
+            FlagSource::InitRc => "init.rc",
+        }
+    }
+}
+
+impl VendorFlags {
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+
+    // "persist.vendor.radio" -> its flags; properties with nothing after the
+    // vendor component ("ro.vendor.foo") stay under "ro.vendor"
+    pub fn groups(&self) -> BTreeMap<String, Vec<(&str, &VendorFlag)>> {
+        let mut groups: BTreeMap<String, Vec<(&str, &VendorFlag)>> = BTreeMap::new();
+        for (name, flag) in &self.flags {
+            let parts: Vec<&str> = name.split('.').collect();
+            let group = if parts.len() > 3 { parts[..3].join(".") } else { parts[..2].join(".") };
+            groups.entry(group).or_default().push((name.as_str(), flag));
+        }
+        groups
+    }
+}
+
+pub fn scan_vendor_flags(
+    tree_path: &Path,
+    device_mk: Option<&Path>,
+    walk_options: &WalkOptions,
+    warnings: &mut Vec<Warning>,
+) -> VendorFlags {
+    let mut vendor_flags = VendorFlags::default();
+
+    // device.mk first, then the makefiles it usually inherits (vendor_prop.mk, ...)
+    let mut makefiles: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name.ends_with(".mk"), &mut makefiles);
+    makefiles.sort_by_key(|file| (Some(file.as_path()) != device_mk, file.clone()));
+    for file in &makefiles {
+        let relative = file.strip_prefix(tree_path).unwrap_or(file).to_string_lossy().to_string();
+        let content = match fsread::read_to_string(file) {
+            Ok(content) => content,
+            // Unreadable device.mk is already reported by the driver scan
+            Err(_) if Some(file.as_path()) == device_mk => continue,
+            Err(e) => {
+                warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+                continue;
+            }
+        };
+
+        let overrides = property_overrides(&content);
+        for (line, name, value) in &overrides {
+            set_flag(&mut vendor_flags, name, value, format!("{}:{}", relative, line), FlagSource::BuildOverride);
+        }
+        if !overrides.is_empty() {
+            vendor_flags.files.push(relative);
+        }
+    }
+
+    let mut prop_files: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name.ends_with(".prop"), &mut prop_files);
+    prop_files.sort();
+
+    let mut rc_files: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| name.ends_with(".rc"), &mut rc_files);
+    rc_files.sort();
+
+    for (file, is_rc) in prop_files.iter().map(|f| (f, false)).chain(rc_files.iter().map(|f| (f, true))) {
+        let relative = file.strip_prefix(tree_path).unwrap_or(file).to_string_lossy().to_string();
+        let content = match fsread::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) => {
+                warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string()));
+                continue;
+            }
+        };
+
+        let mut contributed = false;
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            let assignment = if is_rc { parse_setprop(line) } else { parse_prop(line) };
+            let Some((name, value)) = assignment else {
+                continue;
+            };
+            if !FLAG_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+                continue;
+            }
+            let source = if is_rc { FlagSource::InitRc } else { FlagSource::PropFile };
+            set_flag(&mut vendor_flags, name, value, format!("{}:{}", relative, index + 1), source);
+            contributed = true;
+        }
+        if contributed {
+            vendor_flags.files.push(relative);
+        }
+    }
+
+    vendor_flags
+}
+
+fn set_flag(vendor_flags: &mut VendorFlags, name: &str, value: &str, location: String, source: FlagSource) {
+    match vendor_flags.flags.get_mut(name) {
+        Some(flag) => {
+            if flag.value != value {
+                flag.overridden.push(format!("{} ({})", flag.value, flag.location));
+            }
+            flag.value = value.to_string();
+            flag.location = location;
+            flag.source = source;
+        }
+        None => {
+            vendor_flags.flags.insert(
+                name.to_string(),
+                VendorFlag { value: value.to_string(), location, source, overridden: Vec::new() },
+            );
+        }
+    }
+}
+
+// Final (line, name, value) entries of PRODUCT_PROPERTY_OVERRIDES in one
+// makefile, only the vendor flag namespaces kept. += appends, := and =
+// replace what the file assigned so far, and ?= only applies while the
+// variable is still empty.
+fn property_overrides(content: &str) -> Vec<(usize, String, String)> {
+    let mut overrides: Vec<(usize, String, String)> = Vec::new();
+    let mut assigned = false;
+    for (line, statement) in statements(content) {
+        let Some(assignment) = parse_assignment(&statement) else {
+            continue;
+        };
+        if assignment.name != "PRODUCT_PROPERTY_OVERRIDES" {
+            continue;
+        }
+        match assignment.op {
+            AssignOp::Append => {}
+            AssignOp::Simple | AssignOp::Recursive => overrides.clear(),
+            AssignOp::Conditional if assigned => continue,
+            AssignOp::Conditional => {}
+        }
+        assigned = true;
+        for pair in assignment.value.split_whitespace() {
+            let Some((name, value)) = pair.split_once('=') else {
+                continue;
+            };
+            if FLAG_PREFIXES.iter().any(|prefix| name.starts_with(prefix)) {
+                overrides.push((line, name.to_string(), value.trim_matches('"').to_string()));
+            }
+        }
+    }
+    overrides
+}
+
+// name=value, as in system.prop/vendor.prop
+fn parse_prop(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with('#') {
+        return None;
+    }
+    let (name, value) = line.split_once('=')?;
+    Some((name.trim(), value.trim()))
+}
+
+// "setprop persist.vendor.foo 1" inside an init.rc action
+fn parse_setprop(line: &str) -> Option<(&str, &str)> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    match tokens.as_slice() {
+        ["setprop", name, value, ..] => Some((name, value.trim_matches('"'))),
+        ["setprop", name] => Some((name, "")),
+        _ => None,
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, DirEntry};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Controls how far the recursive scanners descend and whether they cross
+// symlinks. Depth is counted from the tree root: files directly in the root
+// are at depth 0, so a max_depth of 0 only looks at the top level.
+#[derive(Debug, Clone, Default)]
+pub struct WalkOptions {
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    // With --since, only these files (joined onto the tree path) are parsed
+    pub only_files: Option<Arc<BTreeSet<PathBuf>>>,
+    // With --tree -, the tree is enumerated from this listing instead of
+    // read_dir; files are still read from disk on demand
+    pub listing: Option<Arc<Listing>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Dir,
+    Skip,
+}
+
+impl WalkOptions {
+    // Whether the contents of a directory at `depth` should be examined
+    pub fn within_depth(&self, depth: usize) -> bool {
+        self.max_depth.is_none_or(|max| depth <= max)
+    }
+
+    // Whether a file the analysis reads directly (not through a walk) is in
+    // scope
+    pub fn includes_file(&self, path: &Path) -> bool {
+        self.only_files.as_ref().is_none_or(|files| files.contains(path))
+    }
+
+    // Like Path::is_file/is_dir, but answered from the listing when there is one
+    pub fn is_file(&self, path: &Path) -> bool {
+        match &self.listing {
+            Some(listing) => listing.kind(path) == Some(EntryKind::File),
+            None => path.is_file(),
+        }
+    }
+
+    pub fn is_dir(&self, path: &Path) -> bool {
+        match &self.listing {
+            Some(listing) => listing.kind(path) == Some(EntryKind::Dir),
+            None => path.is_dir(),
+        }
+    }
+
+    pub fn entry_kind(&self, entry: &DirEntry) -> EntryKind {
+        match self.file_type_kind(entry) {
+            EntryKind::File if !self.includes_file(&entry.path()) => EntryKind::Skip,
+            kind => kind,
+        }
+    }
+
+    fn file_type_kind(&self, entry: &DirEntry) -> EntryKind {
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => return EntryKind::Skip,
+        };
+
+        if !file_type.is_symlink() {
+            if file_type.is_dir() {
+                return EntryKind::Dir;
+            }
+            return if file_type.is_file() { EntryKind::File } else { EntryKind::Skip };
+        }
+
+        if !self.follow_symlinks {
+            return EntryKind::Skip;
+        }
+
+        let entry_path = entry.path();
+        match fs::metadata(&entry_path) {
+            Ok(metadata) if metadata.is_file() => EntryKind::File,
+            Ok(metadata) if metadata.is_dir() && !links_to_ancestor(&entry_path) => EntryKind::Dir,
+            _ => EntryKind::Skip,
+        }
+    }
+}
+
+// Newline-separated file paths relative to the tree root, as read from
+// stdin with --tree - (e.g. the output of git ls-files). Blank lines and
+// lines starting with '#' are ignored, a leading "./" is dropped, and
+// absolute paths or paths with ".." are skipped. Directories are implied
+// by the files below them; a line ending in '/' names an empty one.
+#[derive(Debug, Default)]
+pub struct Listing {
+    // Directory -> its immediate children
+    children: BTreeMap<PathBuf, Vec<(PathBuf, EntryKind)>>,
+    kinds: BTreeMap<PathBuf, EntryKind>,
+}
+
+impl Listing {
+    pub fn parse(root: &Path, text: &str) -> Listing {
+        let mut listing = Listing::default();
+        listing.kinds.insert(root.to_path_buf(), EntryKind::Dir);
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let relative = Path::new(line.strip_prefix("./").unwrap_or(line));
+            if relative.is_absolute() || relative.components().any(|c| c == std::path::Component::ParentDir) {
+                continue;
+            }
+
+            let kind = if line.ends_with('/') { EntryKind::Dir } else { EntryKind::File };
+            listing.insert(root, root.join(relative), kind);
+        }
+
+        for entries in listing.children.values_mut() {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+        }
+        listing
+    }
+
+    pub fn kind(&self, path: &Path) -> Option<EntryKind> {
+        self.kinds.get(path).copied()
+    }
+
+    pub fn children(&self, dir: &Path) -> &[(PathBuf, EntryKind)] {
+        self.children.get(dir).map_or(&[], |entries| entries.as_slice())
+    }
+
+    // Adds `path` and every directory between it and the root
+    fn insert(&mut self, root: &Path, path: PathBuf, kind: EntryKind) {
+        if path == root || self.kinds.contains_key(&path) {
+            return;
+        }
+        let Some(parent) = path.parent().map(|p| p.to_path_buf()) else {
+            return;
+        };
+
+        self.kinds.insert(path.clone(), kind);
+        self.children.entry(parent.clone()).or_default().push((path, kind));
+        self.insert(root, parent, EntryKind::Dir);
+    }
+}
+
+// Immediate children of `path` and what to do with them, from the listing
+// when there is one
+pub fn read_dir(path: &Path, walk_options: &WalkOptions) -> Vec<(PathBuf, EntryKind)> {
+    if let Some(listing) = &walk_options.listing {
+        return listing
+            .children(path)
+            .iter()
+            .map(|(entry_path, kind)| match kind {
+                EntryKind::File if !walk_options.includes_file(entry_path) => (entry_path.clone(), EntryKind::Skip),
+                _ => (entry_path.clone(), *kind),
+            })
+            .collect();
+    }
+
+    match fs::read_dir(path) {
+        Ok(entries) => entries.flatten().map(|entry| (entry.path(), walk_options.entry_kind(&entry))).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+// `relative` below `root` if it names a file in the tree. With
+// `ignore_case`, a component with no exact match may match case-insensitively.
+pub fn resolve_file(root: &Path, relative: &str, walk_options: &WalkOptions, ignore_case: bool) -> Option<PathBuf> {
+    let mut current = root.to_path_buf();
+    for component in Path::new(relative).components() {
+        let exact = current.join(component);
+        current = if walk_options.is_file(&exact) || walk_options.is_dir(&exact) {
+            exact
+        } else if ignore_case {
+            let name = component.as_os_str().to_string_lossy();
+            read_dir(&current, walk_options).into_iter().map(|(entry_path, _)| entry_path).find(|entry_path| {
+                entry_path.file_name().is_some_and(|entry_name| entry_name.to_string_lossy().eq_ignore_ascii_case(&name))
+            })?
+        } else {
+            return None;
+        };
+    }
+    walk_options.is_file(&current).then_some(current)
+}
+
+// A symlink pointing back at one of its own parents (e.g. vendor -> ..)
+// would otherwise be followed forever
+fn links_to_ancestor(link: &Path) -> bool {
+    let target = match fs::canonicalize(link) {
+        Ok(target) => target,
+        Err(_) => return true,
+    };
+
+    match link.parent().map(fs::canonicalize) {
+        Some(Ok(parent)) => parent.starts_with(&target),
+        _ => true,
+    }
+}
+
+// Collects every file below `path` whose name satisfies `matches`
+pub fn find_files(
+    path: &Path,
+    walk_options: &WalkOptions,
+    depth: usize,
+    matches: &dyn Fn(&str) -> bool,
+    found: &mut Vec<PathBuf>,
+) {
+    if !walk_options.within_depth(depth) {
+        return;
+    }
+
+    for (entry_path, kind) in read_dir(path, walk_options) {
+        match kind {
+            EntryKind::File => {
+                let file_name = entry_path.file_name().unwrap_or_default().to_string_lossy();
+                if matches(&file_name) {
+                    found.push(entry_path);
+                }
+            }
+            EntryKind::Dir => find_files(&entry_path, walk_options, depth + 1, matches, found),
+            EntryKind::Skip => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scratch::Scratch;
+
+    fn find_dts(root: &Path, walk_options: &WalkOptions) -> Vec<String> {
+        let mut found = Vec::new();
+        find_files(root, walk_options, 0, &|name| name.ends_with(".dts"), &mut found);
+        let mut found: Vec<String> =
+            found.iter().map(|file| file.strip_prefix(root).unwrap().to_string_lossy().to_string()).collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn max_depth_limits_the_walk() {
+        let tree = Scratch::new("max-depth", &[("top.dts", ""), ("a/one.dts", ""), ("a/b/two.dts", "")]);
+
+        let unlimited = find_dts(tree.path(), &WalkOptions::default());
+        assert_eq!(unlimited, ["a/b/two.dts", "a/one.dts", "top.dts"]);
+
+        let top_level = WalkOptions { max_depth: Some(0), ..Default::default() };
+        assert_eq!(find_dts(tree.path(), &top_level), ["top.dts"]);
+
+        let one_level = WalkOptions { max_depth: Some(1), ..Default::default() };
+        assert_eq!(find_dts(tree.path(), &one_level), ["a/one.dts", "top.dts"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_only_followed_on_request() {
+        let tree = Scratch::new("follow-symlinks", &[("real/board.dts", "")]);
+        std::os::unix::fs::symlink(tree.path().join("real"), tree.path().join("linked")).unwrap();
+        // Would loop forever if followed
+        std::os::unix::fs::symlink("..", tree.path().join("real/parent")).unwrap();
+
+        assert_eq!(find_dts(tree.path(), &WalkOptions::default()), ["real/board.dts"]);
+
+        let follow = WalkOptions { follow_symlinks: true, ..Default::default() };
+        assert_eq!(find_dts(tree.path(), &follow), ["linked/board.dts", "real/board.dts"]);
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+// Anything questionable found while analyzing a tree. Warnings never stop
+// the analysis; --warnings-as-errors turns them into a failing exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    UnreadableFile,
+    MalformedDts,
+    UnresolvedInclude,
+    MissingRequiredFile,
+    MissingCopySource,
+    CellMismatch,
+    UnreferencedModule,
+    MissingModule,
+    DuplicateCompatible,
+    CircularImport,
+    TruncatedCategory,
+}
+
+impl WarningKind {
+    pub const ALL: [WarningKind; 11] = [
+        WarningKind::UnreadableFile,
+        WarningKind::MalformedDts,
+        WarningKind::UnresolvedInclude,
+        WarningKind::MissingRequiredFile,
+        WarningKind::MissingCopySource,
+        WarningKind::CellMismatch,
+        WarningKind::UnreferencedModule,
+        WarningKind::MissingModule,
+        WarningKind::DuplicateCompatible,
+        WarningKind::CircularImport,
+        WarningKind::TruncatedCategory,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningKind::UnreadableFile => "unreadable-file",
+            WarningKind::MalformedDts => "malformed-dts",
+            WarningKind::UnresolvedInclude => "unresolved-include",
+            WarningKind::MissingRequiredFile => "missing-required-file",
+            WarningKind::MissingCopySource => "missing-copy-source",
+            WarningKind::CellMismatch => "cell-mismatch",
+            WarningKind::UnreferencedModule => "unreferenced-module",
+            WarningKind::MissingModule => "missing-module",
+            WarningKind::DuplicateCompatible => "duplicate-compatible",
+            WarningKind::CircularImport => "circular-import",
+            WarningKind::TruncatedCategory => "truncated-category",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    // Path relative to the tree root (with ":line" where known)
+    pub file: String,
+    pub message: String,
+}
+
+impl Warning {
+    pub fn new(kind: WarningKind, file: impl Into<String>, message: impl Into<String>) -> Self {
+        Warning {
+            kind,
+            file: file.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.kind.name(), self.file, self.message)
+    }
+}
@@ -0,0 +1,213 @@
+use std::path::{Path, PathBuf};
+use crate::fsread;
+use crate::walk::{self, WalkOptions};
+use crate::warning::{Warning, WarningKind};
+
+// Secrets hostapd/wpa_supplicant configs may carry; reported as set, never
+// with their value
+const SECRET_KEYS: [&str; 5] = ["wpa_passphrase", "wpa_psk", "sae_password", "psk", "password"];
+
+// hostapd flags that enable an 802.11 amendment on the AP
+const AP_STANDARDS: [(&str, &str); 4] =
+    [("ieee80211n", "802.11n"), ("ieee80211ac", "802.11ac"), ("ieee80211ax", "802.11ax"), ("ieee80211be", "802.11be")];
+
+// Settings shown in the WiFi section, with their labels. Everything else is
+// kept for JSON/plist and --assert.
+const KEY_SETTINGS: [(&str, &str); 16] = [
+    ("BandCapability", "Band Capability"),
+    ("gDot11Mode", "802.11 Mode"),
+    ("gChannelBondingMode24GHz", "2.4 GHz Channel Bonding"),
+    ("gChannelBondingMode5GHz", "5 GHz Channel Bonding"),
+    ("gVhtChannelWidth", "VHT Channel Width"),
+    ("gEnable11dSupport", "802.11d"),
+    ("gEnableDFSChnlScan", "DFS Channel Scan"),
+    ("gSoftApMaxPeers", "SoftAP Max Peers"),
+    ("hw_mode", "AP Band (hw_mode)"),
+    ("channel", "AP Channel"),
+    ("max_num_sta", "AP Max Stations"),
+    ("wpa_key_mgmt", "AP Key Management"),
+    ("driver", "Driver"),
+    ("driver_param", "Driver Params"),
+    ("p2p_no_group_iface", "P2P Without Group Interface"),
+    ("p2p_go_ht40", "P2P GO HT40"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiConfigKind {
+    // WCNSS_qcom_cfg.ini and other gKey=value driver INIs
+    DriverIni,
+    Hostapd,
+    // wpa_supplicant/p2p_supplicant overlays
+    Supplicant,
+}
+
+impl WifiConfigKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WifiConfigKind::DriverIni => "driver-ini",
+            WifiConfigKind::Hostapd => "hostapd",
+            WifiConfigKind::Supplicant => "supplicant",
+        }
+    }
+
+    fn of(name: &str) -> Option<WifiConfigKind> {
+        let lower = name.to_lowercase();
+        if lower.ends_with(".ini") && (lower.starts_with("wcnss") || lower.ends_with("_cfg.ini")) {
+            Some(WifiConfigKind::DriverIni)
+        } else if lower.starts_with("hostapd") && lower.ends_with(".conf") {
+            Some(WifiConfigKind::Hostapd)
+        } else if lower.ends_with("_supplicant.conf") || lower.ends_with("_supplicant_overlay.conf") {
+            Some(WifiConfigKind::Supplicant)
+        } else {
+            None
+        }
+    }
+}
+
+// WiFi driver, hostapd and supplicant config files: band support, country
+// code and AP capabilities the BoardConfig variables don't show
+#[derive(Debug, Default)]
+pub struct WifiConfigs {
+    pub files: Vec<WifiConfigFile>,
+}
+
+#[derive(Debug)]
+pub struct WifiConfigFile {
+    pub file: String,
+    pub kind: WifiConfigKind,
+    // In file order; a key set twice keeps both, the last one wins
+    pub settings: Vec<WifiSetting>,
+}
+
+#[derive(Debug)]
+pub struct WifiSetting {
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+impl WifiConfigs {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    // The last value of a key across all files, with its file:line
+    pub fn get(&self, key: &str) -> Option<(&str, String)> {
+        self.files.iter().rev().find_map(|file| {
+            let setting = file.settings.iter().rev().find(|setting| setting.key == key)?;
+            Some((setting.value.as_str(), format!("{}:{}", file.file, setting.line)))
+        })
+    }
+
+    // (label, value, file:line) for the settings the text report shows
+    pub fn key_settings(&self) -> Vec<(&'static str, &str, String)> {
+        KEY_SETTINGS
+            .iter()
+            .filter_map(|(key, label)| self.get(key).map(|(value, location)| (*label, value, location)))
+            .collect()
+    }
+
+    // Country codes from hostapd country_code, supplicant country= and the
+    // driver INI's *CountryCode keys
+    pub fn country_codes(&self) -> Vec<&str> {
+        let mut codes: Vec<&str> = self
+            .files
+            .iter()
+            .flat_map(|file| &file.settings)
+            .filter(|setting| {
+                setting.key == "country_code"
+                    || setting.key == "country"
+                    || (setting.key.ends_with("CountryCode") && !setting.key.contains("Priority"))
+            })
+            .map(|setting| setting.value.as_str())
+            .collect();
+        codes.sort();
+        codes.dedup();
+        codes
+    }
+
+    // Bands from the driver INI's BandCapability (0 = all, 1 = 2.4 GHz,
+    // 2 = 5 GHz) and hostapd's hw_mode
+    pub fn bands(&self) -> Vec<&'static str> {
+        let mut bands = Vec::new();
+        match self.get("BandCapability").map(|(value, _)| value) {
+            Some("0") => bands.extend(["2.4 GHz", "5 GHz"]),
+            Some("1") => bands.push("2.4 GHz"),
+            Some("2") => bands.push("5 GHz"),
+            _ => {}
+        }
+        let hw_modes = self.files.iter().flat_map(|file| &file.settings).filter(|setting| setting.key == "hw_mode");
+        for hw_mode in hw_modes {
+            let band = match hw_mode.value.as_str() {
+                "a" => "5 GHz",
+                "b" | "g" => "2.4 GHz",
+                "ad" => "60 GHz",
+                _ => continue,
+            };
+            if !bands.contains(&band) {
+                bands.push(band);
+            }
+        }
+        bands
+    }
+
+    // 802.11 amendments hostapd enables for the AP
+    pub fn ap_standards(&self) -> Vec<&'static str> {
+        AP_STANDARDS
+            .into_iter()
+            .filter(|(key, _)| self.get(key).is_some_and(|(value, _)| value == "1"))
+            .map(|(_, standard)| standard)
+            .collect()
+    }
+}
+
+pub fn scan_wifi_configs(tree_path: &Path, walk_options: &WalkOptions, warnings: &mut Vec<Warning>) -> WifiConfigs {
+    let mut configs = WifiConfigs::default();
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    walk::find_files(tree_path, walk_options, 0, &|name| WifiConfigKind::of(name).is_some(), &mut files);
+    files.sort();
+    for file in files {
+        let relative = file.strip_prefix(tree_path).unwrap_or(&file).to_string_lossy().to_string();
+        let Some(kind) = file.file_name().and_then(|name| WifiConfigKind::of(&name.to_string_lossy())) else {
+            continue;
+        };
+        match fsread::read_to_string(&file) {
+            Ok(content) => {
+                configs.files.push(WifiConfigFile { file: relative, kind, settings: parse_settings(&content) })
+            }
+            Err(e) => warnings.push(Warning::new(WarningKind::UnreadableFile, relative, e.to_string())),
+        }
+    }
+
+    configs
+}
+
+// key=value lines. '#' and ';' start comments; INI [sections], the "END"
+// marker WCNSS_qcom_cfg.ini ends with and supplicant network={...} blocks
+// are skipped.
+fn parse_settings(content: &str) -> Vec<WifiSetting> {
+    let mut settings = Vec::new();
+    let mut in_block = false;
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') || line.starts_with(';') || line.starts_with('[') {
+            continue;
+        }
+        if in_block {
+            in_block = !line.starts_with('}');
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if value.starts_with('{') {
+            in_block = !value.ends_with('}');
+            continue;
+        }
+        let value = if SECRET_KEYS.contains(&key) { "(set)" } else { value.trim_matches('"') };
+        settings.push(WifiSetting { key: key.to_string(), value: value.to_string(), line: index + 1 });
+    }
+    settings
+}